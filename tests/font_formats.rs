@@ -0,0 +1,214 @@
+use lester::PdfDocument;
+use std::sync::Arc;
+use victor::fonts::{Font, FontError, NamedInstance, OutlineSegment, VariationAxis};
+use victor::pdf::Document;
+use victor::primitives::{point, Length, Point, Rect, Size, TextRun};
+use victor::text::ShapedSegment;
+
+include_fonts! {
+    VICTOR_TEST_CFF: "fonts/victor-test/VictorTest-CFF.otf",
+    VICTOR_TEST_VARIABLE: "fonts/victor-test/VictorTest-Variable.ttf",
+}
+
+static VICTOR_TEST_COLLECTION: &[u8] =
+    include_bytes!("fonts/victor-test/VictorTest-Collection.ttc");
+
+#[test]
+fn cff_outlines() {
+    let font = &*VICTOR_TEST_CFF;
+    let x = font.glyph_id('X').unwrap();
+    assert_eq!(
+        rounded(font.glyph_outline(x).unwrap()),
+        [
+            OutlineSegment::MoveTo(point(0., -0.2)),
+            OutlineSegment::LineTo(point(1., -0.2)),
+            OutlineSegment::LineTo(point(1., 0.8)),
+            OutlineSegment::LineTo(point(0., 0.8)),
+            OutlineSegment::Close,
+        ]
+    );
+    assert_eq!(
+        font.glyph_bounds(x).unwrap(),
+        Some(Rect::new(point(0., -0.2), Size::new(1., 1.)))
+    );
+
+    // Curves from local and global subroutines, with an extra final operand for the last one
+    let o = font.glyph_id('O').unwrap();
+    assert_eq!(
+        rounded(font.glyph_outline(o).unwrap()),
+        [
+            OutlineSegment::MoveTo(point(0.05, 0.35)),
+            OutlineSegment::CubicCurveTo(point(0.05, 0.16), point(0.11, 0.), point(0.25, 0.)),
+            OutlineSegment::CubicCurveTo(point(0.39, 0.), point(0.45, 0.16), point(0.45, 0.35)),
+            OutlineSegment::CubicCurveTo(point(0.45, 0.54), point(0.39, 0.7), point(0.25, 0.7)),
+            OutlineSegment::CubicCurveTo(point(0.11, 0.7), point(0.05, 0.54), point(0.06, 0.35)),
+            OutlineSegment::Close,
+            OutlineSegment::MoveTo(point(0.15, 0.35)),
+            OutlineSegment::CubicCurveTo(point(0.2, 0.35), point(0.25, 0.25), point(0.275, 0.2)),
+            OutlineSegment::Close,
+        ]
+    );
+    let bounds = font.glyph_bounds(o).unwrap().unwrap();
+    assert_eq!(
+        [
+            round(bounds.min_x()),
+            round(bounds.min_y()),
+            round(bounds.max_x()),
+            round(bounds.max_y())
+        ],
+        [0.05, 0., 0.45, 0.7]
+    );
+
+    let space = font.glyph_id(' ').unwrap();
+    assert_eq!(font.glyph_outline(space).unwrap(), []);
+    assert_eq!(font.glyph_bounds(space).unwrap(), None);
+}
+
+#[test]
+fn collections() {
+    let faces = Font::parse_collection(VICTOR_TEST_COLLECTION).unwrap();
+    let subfamilies: Vec<_> = faces.iter().map(|face| face.subfamily_name()).collect();
+    assert_eq!(subfamilies, [Some("Regular"), Some("Wide")]);
+    // Faces share the data of the whole collection
+    assert_eq!(faces[0].bytes().len(), VICTOR_TEST_COLLECTION.len());
+    assert_eq!(faces[0].bytes().as_ptr(), faces[1].bytes().as_ptr());
+
+    let wide = Font::parse_with_index(VICTOR_TEST_COLLECTION, 1).unwrap();
+    assert_eq!(wide.subfamily_name(), Some("Wide"));
+    match Font::parse_with_index(VICTOR_TEST_COLLECTION, 2) {
+        Err(FontError::NoSuchFace) => {}
+        result => panic!(
+            "{:?}",
+            result.map(|face| face.subfamily_name().map(String::from))
+        ),
+    }
+
+    // The face is extracted into a standalone font
+    let sfnt = wide.sfnt_bytes().unwrap();
+    assert_eq!(sfnt[..4], [0, 1, 0, 0]);
+    let standalone = Font::parse(sfnt.to_vec()).unwrap();
+    assert_eq!(standalone.subfamily_name(), Some("Wide"));
+
+    let mut doc = Document::new();
+    doc.add_page(Size::new(50., 20.))
+        .show_text(&TextRun {
+            segment: &ShapedSegment::naive_shape("X", wide.clone()).unwrap(),
+            font_size: Length::new(15.),
+            origin: point(0., 15.),
+        })
+        .unwrap();
+    let pdf_bytes = doc.write_to_pdf_bytes();
+    let pdf = String::from_utf8_lossy(&pdf_bytes);
+    assert!(pdf.contains("/Subtype /CIDFontType2 /BaseFont /VictorTestCollection-Wide"));
+    // `X` is wider in this face than in the first one
+    assert!(pdf.contains("/W [0 [500 1000 250]]"));
+    assert!(pdf_bytes.windows(sfnt.len()).any(|window| *window == *sfnt));
+
+    let doc = PdfDocument::from_bytes(&pdf_bytes).unwrap();
+    let pages: Vec<_> = doc.pages().collect();
+    assert_eq!(pages[0].text().to_str().unwrap(), "X");
+}
+
+#[test]
+fn variable_fonts() {
+    let font = &*VICTOR_TEST_VARIABLE;
+    assert_eq!(
+        font.variation_axes(),
+        [VariationAxis {
+            tag: *b"wght",
+            min_value: 100.,
+            default_value: 400.,
+            max_value: 900.,
+            hidden: false,
+            name: Some("Weight".into()),
+        }]
+    );
+    assert_eq!(
+        font.named_instances(),
+        [
+            NamedInstance {
+                subfamily_name: Some("Regular".into()),
+                postscript_name: Some("VictorTestVariable-Regular".into()),
+                coordinates: vec![400.],
+            },
+            NamedInstance {
+                subfamily_name: Some("Bold".into()),
+                postscript_name: Some("VictorTestVariable-Bold".into()),
+                coordinates: vec![700.],
+            },
+        ]
+    );
+
+    // The default instance is the font itself, other instances are cached
+    assert!(Arc::ptr_eq(&font.instantiate(&[]).unwrap(), font));
+    assert!(Arc::ptr_eq(
+        &font.instantiate(&[(*b"wght", 400.)]).unwrap(),
+        font
+    ));
+    let bold = font.instantiate(&[(*b"wght", 700.)]).unwrap();
+    assert!(Arc::ptr_eq(
+        &font.instantiate(&[(*b"wght", 700.)]).unwrap(),
+        &bold
+    ));
+
+    // The right edge of `X` moves by 200 units at the maximum weight, its advance by 300.
+    // 700 is normalized to 0.6, which `avar` maps to 0.8.
+    // Values out of the axis’ range are clamped.
+    for &(weight, right, postscript_name, advance) in &[
+        (400., 0.5, "VictorTestVariable-Regular", 600),
+        (550., 0.59, "VictorTestVariable-Regular_550wght", 735),
+        (700., 0.66, "VictorTestVariable-Bold", 840),
+        (2000., 0.7, "VictorTestVariable-Regular_900wght", 900),
+    ] {
+        let instance = font.instantiate(&[(*b"wght", weight)]).unwrap();
+        let x = instance.glyph_id('X').unwrap();
+        assert_eq!(
+            rounded(instance.glyph_outline(x).unwrap()),
+            [
+                OutlineSegment::MoveTo(point(0.1, 0.)),
+                OutlineSegment::LineTo(point(0.1, 0.7)),
+                OutlineSegment::LineTo(point(right, 0.7)),
+                OutlineSegment::LineTo(point(right, 0.)),
+                OutlineSegment::Close,
+            ]
+        );
+        let bounds = instance.glyph_bounds(x).unwrap().unwrap();
+        assert_eq!(round(bounds.max_x()), right);
+
+        let mut doc = Document::new();
+        doc.add_page(Size::new(50., 20.))
+            .show_text(&TextRun {
+                segment: &ShapedSegment::naive_shape("X", instance.clone()).unwrap(),
+                font_size: Length::new(15.),
+                origin: point(0., 15.),
+            })
+            .unwrap();
+        let pdf_bytes = doc.write_to_pdf_bytes();
+        let pdf = String::from_utf8_lossy(&pdf_bytes);
+        assert!(pdf.contains(&format!("/BaseFont /{} ", postscript_name)));
+        assert!(pdf.contains(&format!("/W [0 [500 {} 250]]", advance)));
+    }
+}
+
+/// Remove floating point noise, to 1/1000 em
+fn round(value: f32) -> f32 {
+    (value * 1000.).round() / 1000.
+}
+
+fn rounded(outline: Vec<OutlineSegment>) -> Vec<OutlineSegment> {
+    let p = |p: Point<_>| point(round(p.x), round(p.y));
+    outline
+        .into_iter()
+        .map(|segment| match segment {
+            OutlineSegment::MoveTo(to) => OutlineSegment::MoveTo(p(to)),
+            OutlineSegment::LineTo(to) => OutlineSegment::LineTo(p(to)),
+            OutlineSegment::QuadraticCurveTo(control, to) => {
+                OutlineSegment::QuadraticCurveTo(p(control), p(to))
+            }
+            OutlineSegment::CubicCurveTo(control1, control2, to) => {
+                OutlineSegment::CubicCurveTo(p(control1), p(control2), p(to))
+            }
+            OutlineSegment::Close => OutlineSegment::Close,
+        })
+        .collect()
+}
@@ -0,0 +1,480 @@
+#!/usr/bin/env python3
+"""Generate the Victor Test fonts.
+
+These are minimal fonts for features that the other testing fonts don’t have.
+Run this script in its directory to regenerate them.
+"""
+
+import struct
+
+UNITS_PER_EM = 1000
+
+
+def u16(*values):
+    return struct.pack(">%dH" % len(values), *values)
+
+
+def i16(*values):
+    return struct.pack(">%dh" % len(values), *values)
+
+
+def u32(*values):
+    return struct.pack(">%dI" % len(values), *values)
+
+
+def fixed(value):
+    return struct.pack(">i", round(value * 65536))
+
+
+def f2dot14(value):
+    return struct.pack(">h", round(value * 16384))
+
+
+def checksum(data):
+    data += b"\0" * (-len(data) % 4)
+    return sum(struct.unpack(">%dI" % (len(data) // 4), data)) & 0xFFFFFFFF
+
+
+def pad(data):
+    return data + b"\0" * (-len(data) % 4)
+
+
+def offset_table(scaler_type, tags, offsets, tables):
+    count = len(tags)
+    entry_selector = count.bit_length() - 1
+    search_range = 16 << entry_selector
+    data = u32(scaler_type) + u16(count, search_range, entry_selector, count * 16 - search_range)
+    for tag in sorted(tags):
+        table = tables[tag]
+        data += tag + u32(checksum(table), offsets[tag], len(table))
+    return data
+
+
+def sfnt(scaler_type, tables):
+    """Serialize a font file, with `checkSumAdjustment` in `head` set"""
+    tags = sorted(tables)
+    offset = 12 + 16 * len(tags)
+    offsets = {}
+    for tag in tags:
+        offsets[tag] = offset
+        offset += len(pad(tables[tag]))
+    data = offset_table(scaler_type, tags, offsets, tables)
+    data += b"".join(pad(tables[tag]) for tag in tags)
+    adjustment = (0xB1B0AFBA - checksum(data)) & 0xFFFFFFFF
+    head = offsets[b"head"]
+    return data[: head + 8] + u32(adjustment) + data[head + 12 :]
+
+
+def head(bbox, long_loca=False):
+    return (
+        u32(0x00010000, 0x00010000, 0, 0x5F0F3CF5)
+        + u16(0x000B, UNITS_PER_EM)
+        + b"\0" * 16  # created and modified dates
+        + i16(*bbox)
+        + u16(0, 8)  # macStyle, lowestRecPPEM
+        + i16(2, 1 if long_loca else 0, 0)
+    )
+
+
+def hhea(advances, glyph_count):
+    return u32(0x00010000) + i16(
+        800, -200, 0, max(advances), 0, 0, max(advances), 1, 0, 0, 0, 0, 0, 0, 0, glyph_count
+    )
+
+
+def hmtx(metrics):
+    return b"".join(u16(advance) + i16(lsb) for advance, lsb in metrics)
+
+
+def os2(weight_class=400):
+    return (
+        u16(4)
+        + i16(500, weight_class, 5, 0)  # avg width, weight, width, fsType
+        + i16(650, 600, 0, 75, 650, 600, 0, 350, 50, 300, 0)  # sub/superscript, strikeout
+        + b"\0" * 10  # panose
+        + u32(1, 0, 0, 0)  # Unicode ranges: Basic Latin
+        + b"VICT"
+        + u16(0x0040, 0x20, 0x58)  # fsSelection REGULAR, first and last char index
+        + i16(800, -200, 0)
+        + u16(800, 200)
+        + u32(1, 0)  # code page ranges: Latin 1
+        + i16(500, 700)  # x height, cap height
+        + u16(0, 0x20, 1)
+    )
+
+
+def post():
+    return u32(0x00030000, 0) + i16(-100, 50) + u32(0, 0, 0, 0, 0)
+
+
+def name(names):
+    """`names` is a dict from name ID to string, for the Windows platform in US English"""
+    records = b""
+    strings = b""
+    for name_id, string in sorted(names.items()):
+        encoded = string.encode("utf-16-be")
+        records += u16(3, 1, 0x0409, name_id, len(encoded), len(strings))
+        strings += encoded
+    return u16(0, len(names), 6 + len(records)) + records + strings
+
+
+def cmap(mapping):
+    """A format 4 subtable with one segment per character"""
+    segments = sorted(mapping.items()) + [(0xFFFF, 0)]
+    count = len(segments)
+    entry_selector = count.bit_length() - 1
+    search_range = 2 << entry_selector
+    subtable = (
+        u16(4, 16 + 8 * count, 0, 2 * count, search_range, entry_selector)
+        + u16(2 * count - search_range)
+        + u16(*[code for code, _ in segments])
+        + u16(0)
+        + u16(*[code for code, _ in segments])
+        + u16(*[(glyph_id - code) % 0x10000 if code != 0xFFFF else 1 for code, glyph_id in segments])
+        + u16(*[0] * count)
+    )
+    return u16(0, 1) + u16(3, 1) + u32(12) + subtable
+
+
+def collection(faces):
+    """Serialize a font collection. Faces are dicts of tables, which may be shared."""
+    header_size = 12 + 4 * len(faces)
+    offset_tables_size = sum(12 + 16 * len(tables) for tables in faces)
+    offset = header_size + offset_tables_size
+    data_by_id = {}
+    offsets = []
+    for tables in faces:
+        face_offsets = {}
+        for tag in sorted(tables):
+            table = tables[tag]
+            if id(table) not in data_by_id:
+                data_by_id[id(table)] = (offset, table)
+                offset += len(pad(table))
+            face_offsets[tag] = data_by_id[id(table)][0]
+        offsets.append(face_offsets)
+    data = b"ttcf" + u32(0x00010000, len(faces))
+    face_offset = header_size
+    for tables in faces:
+        data += u32(face_offset)
+        face_offset += 12 + 16 * len(tables)
+    for tables, face_offsets in zip(faces, offsets):
+        data += offset_table(0x00010000, list(tables), face_offsets, tables)
+    for table_offset, table in sorted(data_by_id.values(), key=lambda entry: entry[0]):
+        data += pad(table)
+    return data
+
+
+# ~~~~ TrueType ~~~~
+
+
+def simple_glyph(points):
+    """A `glyf` entry with one contour of on-curve points"""
+    xs = [x for x, _ in points]
+    ys = [y for _, y in points]
+    bbox = [min(xs), min(ys), max(xs), max(ys)]
+    deltas = lambda values: i16(*[b - a for a, b in zip([0] + values, values)])
+    return (
+        i16(1, *bbox)
+        + u16(len(points) - 1, 0)  # end point of the contour, no instructions
+        + bytes([0x01] * len(points))  # on curve, 16-bit coordinates
+        + deltas(xs)
+        + deltas(ys)
+    )
+
+
+def glyf_and_loca(glyphs):
+    """With short offsets"""
+    glyf = b""
+    loca = [0]
+    for glyph in glyphs:
+        glyf += glyph + b"\0" * (len(glyph) % 2)
+        loca.append(len(glyf) // 2)
+    return glyf, u16(*loca)
+
+
+def maxp_truetype(glyph_count, max_points):
+    return u32(0x00010000) + u16(glyph_count, max_points, 1, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0)
+
+
+# `.notdef` and space are empty, `X` is a rectangle.
+X_POINTS = [(100, 0), (100, 700), (500, 700), (500, 0)]
+
+
+def truetype_tables(names, advances):
+    glyf, loca = glyf_and_loca([b"", simple_glyph(X_POINTS), b""])
+    return {
+        b"OS/2": os2(),
+        b"cmap": cmap({ord("X"): 1, ord(" "): 2}),
+        b"glyf": glyf,
+        b"head": head([100, 0, 500, 700]),
+        b"hhea": hhea(advances, len(advances)),
+        b"hmtx": hmtx(zip(advances, [0, 100, 0])),
+        b"loca": loca,
+        b"maxp": maxp_truetype(len(advances), len(X_POINTS)),
+        b"name": name(names),
+        b"post": post(),
+    }
+
+
+def font_names(family, subfamily, postscript_name):
+    return {1: family, 2: subfamily, 3: postscript_name, 4: family + " " + subfamily, 6: postscript_name}
+
+
+def collection_font():
+    """Victor Test Collection: two faces that share all tables except `name`, `hhea`, and `hmtx`
+
+    The advance width of `X` is 600 in the Regular face and 1000 in the Wide one.
+    """
+    regular = truetype_tables(
+        font_names("Victor Test Collection", "Regular", "VictorTestCollection-Regular"),
+        [500, 600, 250],
+    )
+    wide = truetype_tables(
+        font_names("Victor Test Collection", "Wide", "VictorTestCollection-Wide"),
+        [500, 1000, 250],
+    )
+    for tag in [b"OS/2", b"cmap", b"glyf", b"head", b"loca", b"maxp", b"post"]:
+        wide[tag] = regular[tag]
+    return collection([regular, wide])
+
+
+
+# ~~~~ Variations ~~~~
+
+
+def fvar(axes, instances):
+    """`axes` are `(tag, min, default, max, name ID)`,
+    `instances` are `(subfamily name ID, PostScript name ID, coordinates)`
+    """
+    data = u16(1, 0, 16, 2, len(axes), 20, len(instances), 4 + 4 * len(axes) + 2)
+    for tag, min_value, default_value, max_value, name_id in axes:
+        data += tag + fixed(min_value) + fixed(default_value) + fixed(max_value) + u16(0, name_id)
+    for subfamily_name_id, postscript_name_id, coordinates in instances:
+        data += u16(subfamily_name_id, 0)
+        data += b"".join(fixed(value) for value in coordinates)
+        data += u16(postscript_name_id)
+    return data
+
+
+def avar(segment_maps):
+    data = u16(1, 0, 0, len(segment_maps))
+    for segment_map in segment_maps:
+        data += u16(len(segment_map))
+        data += b"".join(f2dot14(a) + f2dot14(b) for a, b in segment_map)
+    return data
+
+
+def gvar(shared_tuple, glyph_variations):
+    """One axis. `glyph_variations` has for each glyph `None` or a single variation
+    with the shared tuple as its peak: `(point numbers, x deltas, y deltas)`,
+    with point numbers below 128 and 16-bit deltas.
+    """
+    glyph_data = []
+    for variation in glyph_variations:
+        if variation is None:
+            glyph_data.append(b"")
+            continue
+        points, x_deltas, y_deltas = variation
+        count = len(points)
+        differences = [b - a for a, b in zip([0] + points, points)]
+        serialized = bytes([count, count - 1] + differences)
+        serialized += bytes([0x40 | (count - 1)]) + i16(*x_deltas)
+        serialized += bytes([0x40 | (count - 1)]) + i16(*y_deltas)
+        private_point_numbers = 0x2000
+        data = u16(1, 8) + u16(len(serialized), private_point_numbers) + serialized
+        glyph_data.append(data + b"\0" * (len(data) % 2))
+    offsets = [0]
+    for data in glyph_data:
+        offsets.append(offsets[-1] + len(data) // 2)
+    header_size = 20 + 2 * len(offsets)
+    return (
+        u16(1, 0, 1, 1) + u32(header_size) + u16(len(glyph_variations), 0)
+        + u32(header_size + 2) + u16(*offsets)
+        + f2dot14(shared_tuple)
+        + b"".join(glyph_data)
+    )
+
+
+def hvar(region, advance_deltas):
+    """One axis, one region `(start, peak, end)`, and a 16-bit delta for each glyph"""
+    region_list = u16(1, 1) + b"".join(f2dot14(value) for value in region)
+    item_data = u16(len(advance_deltas), 1, 1, 0) + i16(*advance_deltas)
+    store = u16(1) + u32(12) + u16(1) + u32(12 + len(region_list)) + region_list + item_data
+    return u16(1, 0) + u32(20, 0, 0, 0) + store
+
+
+def variable_font():
+    """Victor Test Variable: a TrueType variable font with a weight axis from 100 to 900
+
+    At the maximum weight, the right edge of `X` moves by 200 units
+    and its advance width increases by 300 units.
+    `avar` maps 650 (normalized 0.5) to 0.75, so the Bold instance (700) is at 0.8.
+    """
+    names = font_names("Victor Test Variable", "Regular", "VictorTestVariable-Regular")
+    names.update({256: "Weight", 257: "Bold", 258: "VictorTestVariable-Bold"})
+    tables = truetype_tables(names, [500, 600, 250])
+    tables[b"fvar"] = fvar([(b"wght", 100, 400, 900, 256)], [(2, 6, [400]), (257, 258, [700])])
+    tables[b"avar"] = avar([[(-1, -1), (0, 0), (0.5, 0.75), (1, 1)]])
+    # Points 2 and 3 are the right edge, 3 is inferred. Point 5 is the advance phantom point.
+    tables[b"gvar"] = gvar(1, [None, ([0, 2, 5], [0, 200, 300], [0, 0, 0]), None])
+    tables[b"HVAR"] = hvar((0, 1, 1), [0, 300, 0])
+    return sfnt(0x00010000, tables)
+
+
+# ~~~~ Compact Font Format ~~~~
+
+
+def cff_index(objects, offset_size=None):
+    if not objects:
+        return u16(0)
+    offsets = [1]
+    for obj in objects:
+        offsets.append(offsets[-1] + len(obj))
+    if offset_size is None:
+        offset_size = (offsets[-1].bit_length() + 7) // 8
+    data = u16(len(objects)) + bytes([offset_size])
+    for offset in offsets:
+        data += offset.to_bytes(offset_size, "big")
+    return data + b"".join(objects)
+
+
+def cff_integer(value, charstring):
+    if -107 <= value <= 107:
+        return bytes([value + 139])
+    if 108 <= value <= 1131:
+        value -= 108
+        return bytes([247 + (value >> 8), value & 0xFF])
+    if -1131 <= value <= -108:
+        value = -value - 108
+        return bytes([251 + (value >> 8), value & 0xFF])
+    if charstring or not -32768 <= value <= 32767:
+        raise ValueError(value)
+    return bytes([28]) + i16(value)
+
+
+def dict_offset(value):
+    """A 5-byte integer, so that DICT sizes don’t depend on offsets"""
+    return bytes([29]) + struct.pack(">i", value)
+
+
+CHARSTRING_OPERATORS = {
+    "hstemhm": [18],
+    "hintmask": [19],
+    "rmoveto": [21],
+    "hlineto": [6],
+    "rlineto": [5],
+    "vhcurveto": [30],
+    "hvcurveto": [31],
+    "callsubr": [10],
+    "callgsubr": [29],
+    "return": [11],
+    "endchar": [14],
+}
+
+
+def charstring(program):
+    """`program` is a list of integers, operator names, and raw `bytes` for hint masks"""
+    data = b""
+    for token in program:
+        if isinstance(token, int):
+            data += cff_integer(token, charstring=True)
+        elif isinstance(token, bytes):
+            data += token
+        else:
+            data += bytes(CHARSTRING_OPERATORS[token])
+    return data
+
+
+def cff(font_name, charset, charstrings, local_subroutines, global_subroutines):
+    """A name-keyed CFF table. `charset` has the SID of each glyph except `.notdef`."""
+    private = lambda subrs_offset: (
+        cff_integer(0, False) + bytes([20])  # defaultWidthX
+        + cff_integer(0, False) + bytes([21])  # nominalWidthX
+        + dict_offset(subrs_offset) + bytes([19])  # Subrs, from the start of Private
+    )
+    top_dict = lambda charset_offset, charstrings_offset, private_size, private_offset: (
+        dict_offset(charset_offset) + bytes([15])
+        + dict_offset(charstrings_offset) + bytes([17])
+        + dict_offset(private_size) + dict_offset(private_offset) + bytes([18])
+    )
+    header = bytes([1, 0, 4, 4])
+    names = cff_index([font_name.encode("ascii")])
+    top_dict_size = len(cff_index([top_dict(0, 0, 0, 0)]))
+    strings = cff_index([])
+    global_subrs = cff_index([charstring(s) for s in global_subroutines])
+    charset_offset = len(header) + len(names) + top_dict_size + len(strings) + len(global_subrs)
+    charset_data = bytes([0]) + u16(*charset)
+    charstrings_offset = charset_offset + len(charset_data)
+    charstrings_data = cff_index([charstring(s) for s in charstrings])
+    private_offset = charstrings_offset + len(charstrings_data)
+    private_size = len(private(0))
+    local_subrs = cff_index([charstring(s) for s in local_subroutines])
+    top = cff_index([top_dict(charset_offset, charstrings_offset, private_size, private_offset)])
+    return (
+        header + names + top + strings + global_subrs + charset_data + charstrings_data
+        + private(private_size) + local_subrs
+    )
+
+
+def cff_font():
+    """Victor Test CFF: an OpenType font with `CFF ` outlines
+
+    * `X` is a square that fills the em box, like in Ahem.
+    * `O` uses hint masks, local and global subroutines (with more than 1240 global ones,
+      so with a different bias), and `vhcurveto` and `hvcurveto` with an extra final operand.
+    """
+    glyph_x = [0, -200, "rmoveto", 1000, 1000, -1000, "hlineto", "endchar"]
+    glyph_o = [
+        # Advance width, then 5 horizontal stems
+        500, 0, 20, 160, 20, 160, 20, 160, 20, 160, 20, "hstemhm",
+        # 4 implicit vertical stems, for 9 stems and a 2-byte mask
+        50, 20, 100, 20, 100, 20, 100, 20, "hintmask", bytes([0xFF, 0x80]),
+        50, 350, "rmoveto",
+        -107, "callsubr",
+        -1131, "callgsubr",
+        "hintmask", bytes([0xF0, 0x00]),
+        90, 0, "rmoveto",
+        50, 50, -100, -50, 25, "hvcurveto",
+        "endchar",
+    ]
+    local_subroutines = [[-190, 60, -160, 140, 140, 60, 160, 190, "vhcurveto", "return"]]
+    global_subroutines = [[190, -60, 160, -140, -140, -60, -160, -190, 10, "vhcurveto", "return"]]
+    global_subroutines += [["return"]] * 1239
+    notdef = [500, "endchar"]
+    space = [250, "endchar"]
+    # Standard strings: X, O, space
+    charset = [57, 48, 1]
+    advances = [500, 1000, 500, 250]
+    tables = {
+        b"CFF ": cff(
+            "VictorTest-CFF",
+            charset,
+            [notdef, glyph_x, glyph_o, space],
+            local_subroutines,
+            global_subroutines,
+        ),
+        b"OS/2": os2(),
+        b"cmap": cmap({ord("X"): 1, ord("O"): 2, ord(" "): 3}),
+        b"head": head([0, -200, 1000, 800]),
+        b"hhea": hhea(advances, len(advances)),
+        b"hmtx": hmtx(zip(advances, [0, 0, 50, 0])),
+        b"maxp": u32(0x00005000) + u16(len(advances)),
+        b"name": name({
+            1: "Victor Test CFF",
+            2: "Regular",
+            3: "Victor Test CFF",
+            4: "Victor Test CFF",
+            6: "VictorTest-CFF",
+        }),
+        b"post": post(),
+    }
+    return sfnt(0x4F54544F, tables)
+
+
+if __name__ == "__main__":
+    with open("VictorTest-CFF.otf", "wb") as f:
+        f.write(cff_font())
+    with open("VictorTest-Collection.ttc", "wb") as f:
+        f.write(collection_font())
+    with open("VictorTest-Variable.ttf", "wb") as f:
+        f.write(variable_font())
@@ -6,4 +6,6 @@ extern crate lester;
 extern crate victor;
 
 mod alice;
+mod font_formats;
 mod pdf;
+mod web_fonts;
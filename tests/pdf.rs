@@ -9,7 +9,11 @@ use victor::text::ShapedSegment;
 
 include_fonts! {
     AHEM: "fonts/ahem/ahem.ttf",
+    AHEM_COLR: "fonts/ahem/ahem-colr.ttf",
     NOTO: "fonts/noto/NotoSansLinearB-Regular.ttf",
+    NOTO_EMOJI_CBDT: "fonts/noto/NotoColorEmoji-CBDT-subset.ttf",
+    NOTO_EMOJI_SBIX: "fonts/noto/NotoColorEmoji-sbix-subset.ttf",
+    VICTOR_TEST_CFF: "fonts/victor-test/VictorTest-CFF.otf",
 }
 
 fn doc() -> Result<Vec<u8>, FontError> {
@@ -130,3 +134,84 @@ fn pdf() {
 
     assert!(pdf_bytes == include_bytes!("expected.pdf").as_ref());
 }
+
+#[test]
+fn color_glyphs() {
+    let mut doc = Document::new();
+    doc.add_page(Size::new(5., 5.))
+        .set_color(&RGBA(0., 0., 1., 1.))
+        .show_text(&TextRun {
+            segment: &ShapedSegment::naive_shape("X", AHEM_COLR.clone()).unwrap(),
+            font_size: Length::new(5.),
+            origin: point(0., 4.),
+        })
+        .unwrap();
+    // At 109px, one pixel of these bitmaps is one CSS pixel
+    doc.add_page(Size::new(280., 140.))
+        .show_text(&TextRun {
+            segment: &ShapedSegment::naive_shape("💁", NOTO_EMOJI_CBDT.clone()).unwrap(),
+            font_size: Length::new(109.),
+            origin: point(0., 110.),
+        })
+        .unwrap()
+        .show_text(&TextRun {
+            segment: &ShapedSegment::naive_shape("😀", NOTO_EMOJI_SBIX.clone()).unwrap(),
+            font_size: Length::new(109.),
+            origin: point(140., 110.),
+        })
+        .unwrap();
+    let pdf_bytes = doc.write_to_pdf_bytes();
+
+    let doc = PdfDocument::from_bytes(&pdf_bytes).unwrap();
+    let pages: Vec<_> = doc.pages().collect();
+    assert_eq!(pages[0].text().to_str().unwrap(), "X");
+    let mut surface = pages[0].render().unwrap();
+    const RED_: u32 = 0xFFFF_0000;
+    const BLUE: u32 = 0xFF00_00FF;
+    #[rustfmt::skip]
+    assert_pixels_eq!(
+        surface.pixels().buffer,
+        &[
+            RED_, RED_, RED_, RED_, RED_,
+            RED_, RED_, RED_, RED_, RED_,
+            RED_, RED_, RED_, RED_, RED_,
+            RED_, RED_, RED_, RED_, RED_,
+            BLUE, BLUE, BLUE, BLUE, BLUE,
+        ]
+    );
+
+    let text = pages[1].text();
+    let text = text.to_str().unwrap();
+    assert!(text.contains('💁') && text.contains('😀'), "{:?}", text);
+    let mut surface = pages[1].render().unwrap();
+    let pixels = surface.pixels();
+    // Brown hair and yellow forehead, in areas of a uniform colour in the bitmaps
+    assert_eq!(pixels.buffer[31 * 280 + 50], 0xFF6D_4C41);
+    assert_eq!(pixels.buffer[33 * 280 + 140 + 68], 0xFFFE_E22F);
+}
+
+#[test]
+fn opentype_cff() {
+    let mut doc = Document::new();
+    doc.add_page(Size::new(5., 5.))
+        .set_color(&RGBA(0., 0., 1., 1.))
+        .show_text(&TextRun {
+            segment: &ShapedSegment::naive_shape("X", VICTOR_TEST_CFF.clone()).unwrap(),
+            font_size: Length::new(5.),
+            origin: point(0., 4.),
+        })
+        .unwrap();
+    let pdf_bytes = doc.write_to_pdf_bytes();
+    let pdf = String::from_utf8_lossy(&pdf_bytes);
+    assert!(pdf.contains("/Subtype /CIDFontType0 /BaseFont /VictorTest-CFF"));
+    assert!(pdf.contains("/FontFile3 "));
+    assert!(pdf.contains("<< /Subtype /OpenType /Length "));
+
+    let doc = PdfDocument::from_bytes(&pdf_bytes).unwrap();
+    let pages: Vec<_> = doc.pages().collect();
+    assert_eq!(pages[0].text().to_str().unwrap(), "X");
+    // Like in Ahem, the glyph is a square that fills the em box
+    let mut surface = pages[0].render().unwrap();
+    const BLUE: u32 = 0xFF00_00FF;
+    assert_pixels_eq!(surface.pixels().buffer, &[BLUE; 25][..]);
+}
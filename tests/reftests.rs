@@ -4,6 +4,11 @@ use std::io::Write;
 use std::path::{Path, PathBuf};
 
 fn main() {
+    // For `hyphens: auto` in `lang=en` content: the example patterns from Liang’s thesis
+    victor::hyphenation::register(
+        "en",
+        victor::hyphenation::Patterns::parse("hy3ph he2n hena4 hen5at 1na n2at 1tio 2io o2n"),
+    );
     let args: Vec<_> = env::args().skip(1).collect();
     let base = reftests_dir();
     for_each_file_in(base.clone(), &mut |path| {
@@ -0,0 +1,75 @@
+use lester::PdfDocument;
+use victor::fonts::{EmbeddingPermissions, OutlineSegment};
+use victor::pdf::Document;
+use victor::primitives::{point, Length, Rect, Size, TextRun};
+use victor::text::ShapedSegment;
+
+include_fonts! {
+    AHEM_WOFF: "fonts/ahem/ahem.woff",
+    OPEN_SANS_WOFF2: "fonts/open-sans/OpenSans-Regular.woff2",
+}
+
+#[test]
+fn web_fonts() {
+    let mut doc = Document::new();
+    doc.add_page(Size::new(140., 50.))
+        .show_text(&TextRun {
+            segment: &ShapedSegment::naive_shape("pÉX", AHEM_WOFF.clone()).unwrap(),
+            font_size: Length::new(15.),
+            origin: point(10., 20.),
+        })
+        .unwrap()
+        .show_text(&TextRun {
+            segment: &ShapedSegment::naive_shape("Têst", OPEN_SANS_WOFF2.clone()).unwrap(),
+            font_size: Length::new(15.),
+            origin: point(10., 40.),
+        })
+        .unwrap();
+    let pdf_bytes = doc.write_to_pdf_bytes();
+
+    let doc = PdfDocument::from_bytes(&pdf_bytes).unwrap();
+    let pages: Vec<_> = doc.pages().collect();
+    assert_eq!(pages[0].text().to_str().unwrap(), "pÉX\nTêst");
+}
+
+#[test]
+fn names_and_metrics() {
+    assert_eq!(OPEN_SANS_WOFF2.family_name(), Some("Open Sans"));
+    assert_eq!(OPEN_SANS_WOFF2.subfamily_name(), Some("Regular"));
+    assert_eq!(OPEN_SANS_WOFF2.weight_class(), 400);
+    assert_eq!(OPEN_SANS_WOFF2.width_class(), 5);
+    assert_eq!(
+        OPEN_SANS_WOFF2.embedding_permissions(),
+        EmbeddingPermissions::Installable
+    );
+    assert_eq!(AHEM_WOFF.family_name(), Some("Ahem"));
+}
+
+#[test]
+fn glyph_outlines() {
+    let x = AHEM_WOFF.glyph_id('X').unwrap();
+    assert_eq!(
+        AHEM_WOFF.glyph_outline(x).unwrap(),
+        [
+            OutlineSegment::MoveTo(point(0., 0.8)),
+            OutlineSegment::LineTo(point(1., 0.8)),
+            OutlineSegment::LineTo(point(1., -0.2)),
+            OutlineSegment::LineTo(point(0., -0.2)),
+            OutlineSegment::Close,
+        ]
+    );
+    assert_eq!(
+        AHEM_WOFF.glyph_bounds(x).unwrap(),
+        Some(Rect::new(point(0., -0.2), Size::new(1., 1.)))
+    );
+
+    let space = OPEN_SANS_WOFF2.glyph_id(' ').unwrap();
+    assert_eq!(OPEN_SANS_WOFF2.glyph_outline(space).unwrap(), []);
+    assert_eq!(OPEN_SANS_WOFF2.glyph_bounds(space).unwrap(), None);
+
+    // Tight bounds around the curves of 'o', below the baseline for overshoot
+    let o = OPEN_SANS_WOFF2.glyph_id('o').unwrap();
+    let bounds = OPEN_SANS_WOFF2.glyph_bounds(o).unwrap().unwrap();
+    assert!(bounds.min_y() < 0. && bounds.min_y() > -0.02);
+    assert!(bounds.max_y() > 0.5 && bounds.max_y() < 0.6);
+}
@@ -0,0 +1,525 @@
+//! Glyph outlines from Compact Font Format data, with Type 2 charstrings.
+//!
+//! https://adobe-type-tools.github.io/font-tech-notes/pdfs/5176.CFF.pdf
+//! https://adobe-type-tools.github.io/font-tech-notes/pdfs/5177.Type2.pdf
+
+use crate::fonts::outlines::OutlineBuilder;
+use crate::fonts::parsing::Reader;
+use crate::fonts::FontError;
+
+/// An array of variable-length objects
+struct Index<'a> {
+    count: usize,
+    offset_size: u8,
+    offsets: &'a [u8],
+    data: &'a [u8],
+}
+
+impl<'a> Index<'a> {
+    /// Parse an INDEX, and advance `reader` to after it.
+    fn parse(reader: &mut Reader<'a>) -> Result<Self, FontError> {
+        let count = usize::from(reader.u16()?);
+        if count == 0 {
+            return Ok(Index {
+                count,
+                offset_size: 1,
+                offsets: &[],
+                data: &[],
+            });
+        }
+        let offset_size = reader.u8()?;
+        if !(1..=4).contains(&offset_size) {
+            return Err(FontError::InvalidCompactFontFormat);
+        }
+        let offsets = reader.take((count + 1) * usize::from(offset_size))?;
+        let mut index = Index {
+            count,
+            offset_size,
+            offsets,
+            data: &[],
+        };
+        // Offsets are relative to the byte before the object data.
+        let data_length = index.offset(count)?.saturating_sub(1);
+        index.data = reader.take(data_length)?;
+        Ok(index)
+    }
+
+    fn offset(&self, i: usize) -> Result<usize, FontError> {
+        let size = usize::from(self.offset_size);
+        let bytes = self
+            .offsets
+            .get(i * size..(i + 1) * size)
+            .ok_or(FontError::InvalidCompactFontFormat)?;
+        Ok(bytes
+            .iter()
+            .fold(0, |offset, &byte| (offset << 8) | usize::from(byte)))
+    }
+
+    fn get(&self, i: usize) -> Result<&'a [u8], FontError> {
+        if i >= self.count {
+            return Err(FontError::InvalidCompactFontFormat);
+        }
+        let start = self.offset(i)?.saturating_sub(1);
+        let end = self.offset(i + 1)?.saturating_sub(1);
+        self.data
+            .get(start..end)
+            .ok_or(FontError::InvalidCompactFontFormat)
+    }
+}
+
+/// The operands of each operator in a DICT, with two-byte operators as `1200 + second byte`.
+fn parse_dict(data: &[u8]) -> Result<Vec<(u16, Vec<f32>)>, FontError> {
+    let mut entries = Vec::new();
+    let mut operands = Vec::new();
+    let mut reader = Reader::new(data);
+    while let Ok(b0) = reader.u8() {
+        match b0 {
+            0..=21 => {
+                let operator = if b0 == 12 {
+                    1200 + u16::from(reader.u8()?)
+                } else {
+                    u16::from(b0)
+                };
+                entries.push((operator, std::mem::take(&mut operands)))
+            }
+            28 => operands.push(f32::from(reader.i16()?)),
+            29 => operands.push(reader.i32()? as f32),
+            30 => {
+                // A real number, as nibbles. Only the integer part is used here.
+                let mut value = 0.;
+                let mut negative = false;
+                let mut integer_part = true;
+                'nibbles: loop {
+                    let byte = reader.u8()?;
+                    for &nibble in &[byte >> 4, byte & 0xF] {
+                        match nibble {
+                            0..=9 if integer_part => value = value * 10. + f32::from(nibble),
+                            0xE => negative = true,
+                            0xF => break 'nibbles,
+                            _ => integer_part = false,
+                        }
+                    }
+                }
+                operands.push(if negative { -value } else { value })
+            }
+            _ => operands.push(f32::from(short_integer(b0, &mut reader)?)),
+        }
+    }
+    Ok(entries)
+}
+
+/// An integer encoded in one or two bytes, shared by DICT data and charstrings
+fn short_integer(b0: u8, reader: &mut Reader) -> Result<i16, FontError> {
+    let b0 = i16::from(b0);
+    Ok(match b0 {
+        32..=246 => b0 - 139,
+        247..=250 => (b0 - 247) * 256 + i16::from(reader.u8()?) + 108,
+        251..=254 => -(b0 - 251) * 256 - i16::from(reader.u8()?) - 108,
+        _ => return Err(FontError::InvalidCompactFontFormat),
+    })
+}
+
+fn dict_operand(dict: &[(u16, Vec<f32>)], operator: u16, index: usize) -> Option<usize> {
+    dict.iter()
+        .find(|&&(op, _)| op == operator)
+        .and_then(|(_, operands)| operands.get(index))
+        .map(|&value| value as usize)
+}
+
+// Top DICT and Private DICT operators
+const CHARSTRINGS: u16 = 17;
+const PRIVATE: u16 = 18;
+const SUBRS: u16 = 19;
+const FD_ARRAY: u16 = 1236;
+const FD_SELECT: u16 = 1237;
+
+/// The local subroutines for a Private DICT of `size` bytes at `offset` from the start of `cff`
+fn local_subroutines(cff: &[u8], size: usize, offset: usize) -> Result<Index, FontError> {
+    let private_dict = cff
+        .get(offset..offset + size)
+        .ok_or(FontError::InvalidCompactFontFormat)?;
+    match dict_operand(&parse_dict(private_dict)?, SUBRS, 0) {
+        Some(subrs_offset) => Index::parse(&mut Reader::at(cff, offset + subrs_offset)?),
+        None => Index::parse(&mut Reader::new(&[0, 0])),
+    }
+}
+
+/// The index of the Font DICT used by a glyph in a CID-keyed font
+fn font_dict_index(cff: &[u8], fd_select_offset: usize, glyph_id: u16) -> Result<usize, FontError> {
+    let mut reader = Reader::at(cff, fd_select_offset)?;
+    match reader.u8()? {
+        0 => {
+            reader.skip(usize::from(glyph_id))?;
+            Ok(usize::from(reader.u8()?))
+        }
+        3 => {
+            let range_count = reader.u16()?;
+            let mut first = reader.u16()?;
+            for _ in 0..range_count {
+                let font_dict = reader.u8()?;
+                let next_first = reader.u16()?;
+                if first <= glyph_id && glyph_id < next_first {
+                    return Ok(usize::from(font_dict));
+                }
+                first = next_first
+            }
+            Err(FontError::InvalidCompactFontFormat)
+        }
+        _ => Err(FontError::InvalidCompactFontFormat),
+    }
+}
+
+pub(in crate::fonts) fn glyph_outline(
+    cff: &[u8],
+    glyph_id: u16,
+    builder: &mut OutlineBuilder,
+) -> Result<(), FontError> {
+    let header_size = *cff.get(2).ok_or(FontError::InvalidCompactFontFormat)?;
+    let mut reader = Reader::at(cff, usize::from(header_size))?;
+    let _names = Index::parse(&mut reader)?;
+    let top_dicts = Index::parse(&mut reader)?;
+    let _strings = Index::parse(&mut reader)?;
+    let global_subroutines = Index::parse(&mut reader)?;
+
+    let top_dict = parse_dict(top_dicts.get(0)?)?;
+    let charstrings_offset =
+        dict_operand(&top_dict, CHARSTRINGS, 0).ok_or(FontError::InvalidCompactFontFormat)?;
+    let charstrings = Index::parse(&mut Reader::at(cff, charstrings_offset)?)?;
+
+    let private_dict = match (
+        dict_operand(&top_dict, FD_ARRAY, 0),
+        dict_operand(&top_dict, FD_SELECT, 0),
+    ) {
+        (Some(fd_array_offset), Some(fd_select_offset)) => {
+            // CID-keyed font
+            let font_dicts = Index::parse(&mut Reader::at(cff, fd_array_offset)?)?;
+            let font_dict = font_dicts.get(font_dict_index(cff, fd_select_offset, glyph_id)?)?;
+            parse_dict(font_dict)?
+        }
+        _ => top_dict,
+    };
+    let local_subroutines = match (
+        dict_operand(&private_dict, PRIVATE, 0),
+        dict_operand(&private_dict, PRIVATE, 1),
+    ) {
+        (Some(size), Some(offset)) => local_subroutines(cff, size, offset)?,
+        _ => Index::parse(&mut Reader::new(&[0, 0]))?,
+    };
+
+    let mut interpreter = CharstringInterpreter {
+        builder,
+        global_subroutines: &global_subroutines,
+        local_subroutines: &local_subroutines,
+        stack: Vec::with_capacity(48),
+        x: 0.,
+        y: 0.,
+        stem_count: 0,
+        width_parsed: false,
+        contour_open: false,
+    };
+    interpreter.run(charstrings.get(usize::from(glyph_id))?, 0)?;
+    interpreter.close_contour();
+    Ok(())
+}
+
+struct CharstringInterpreter<'a, 'b> {
+    builder: &'b mut OutlineBuilder,
+    global_subroutines: &'b Index<'a>,
+    local_subroutines: &'b Index<'a>,
+    stack: Vec<f32>,
+    x: f32,
+    y: f32,
+    stem_count: usize,
+
+    /// The first stack-clearing operator may have the advance width as an extra first operand.
+    width_parsed: bool,
+
+    contour_open: bool,
+}
+
+/// What to do after a charstring or subroutine ends
+enum End {
+    Return,
+    EndChar,
+}
+
+impl<'a, 'b> CharstringInterpreter<'a, 'b> {
+    fn run(&mut self, charstring: &[u8], depth: u32) -> Result<End, FontError> {
+        const MAX_SUBROUTINE_DEPTH: u32 = 10;
+        if depth > MAX_SUBROUTINE_DEPTH {
+            return Err(FontError::InvalidCompactFontFormat);
+        }
+        let mut reader = Reader::new(charstring);
+        while let Ok(b0) = reader.u8() {
+            match b0 {
+                // hstem, vstem, hstemhm, vstemhm
+                1 | 3 | 18 | 23 => {
+                    self.take_width(self.stack.len() % 2 == 1);
+                    self.stem_count += self.stack.len() / 2;
+                    self.stack.clear()
+                }
+                // hintmask, cntrmask
+                19 | 20 => {
+                    // Operands are for an implicit vstem
+                    self.take_width(self.stack.len() % 2 == 1);
+                    self.stem_count += self.stack.len() / 2;
+                    self.stack.clear();
+                    reader.skip((self.stem_count + 7) / 8)?
+                }
+                // rmoveto
+                21 => {
+                    self.take_width(self.stack.len() > 2);
+                    let dx = self.operand(0)?;
+                    let dy = self.operand(1)?;
+                    self.move_to(dx, dy)
+                }
+                // hmoveto
+                22 => {
+                    self.take_width(self.stack.len() > 1);
+                    let dx = self.operand(0)?;
+                    self.move_to(dx, 0.)
+                }
+                // vmoveto
+                4 => {
+                    self.take_width(self.stack.len() > 1);
+                    let dy = self.operand(0)?;
+                    self.move_to(0., dy)
+                }
+                // rlineto
+                5 => {
+                    for pair in self.stack.chunks(2) {
+                        if let [dx, dy] = *pair {
+                            self.x += dx;
+                            self.y += dy;
+                            self.builder.line_to(self.x, self.y)
+                        }
+                    }
+                    self.stack.clear()
+                }
+                // hlineto, vlineto: alternating horizontal and vertical lines
+                6 | 7 => {
+                    let mut horizontal = b0 == 6;
+                    for &delta in &self.stack {
+                        if horizontal {
+                            self.x += delta
+                        } else {
+                            self.y += delta
+                        }
+                        self.builder.line_to(self.x, self.y);
+                        horizontal = !horizontal
+                    }
+                    self.stack.clear()
+                }
+                // rrcurveto
+                8 => {
+                    let stack = std::mem::take(&mut self.stack);
+                    for curve in stack.chunks(6) {
+                        if let [dxa, dya, dxb, dyb, dxc, dyc] = *curve {
+                            self.curve(dxa, dya, dxb, dyb, dxc, dyc)
+                        }
+                    }
+                }
+                // hhcurveto, vvcurveto
+                27 | 26 => {
+                    let stack = std::mem::take(&mut self.stack);
+                    let (mut first, curves) = if stack.len() % 4 == 1 {
+                        (stack[0], &stack[1..])
+                    } else {
+                        (0., &stack[..])
+                    };
+                    for curve in curves.chunks(4) {
+                        if let [a, dxb, dyb, c] = *curve {
+                            if b0 == 27 {
+                                self.curve(a, first, dxb, dyb, c, 0.)
+                            } else {
+                                self.curve(first, a, dxb, dyb, 0., c)
+                            }
+                        }
+                        first = 0.
+                    }
+                }
+                // hvcurveto, vhcurveto: curves alternately starting horizontal and vertical
+                31 | 30 => {
+                    let stack = std::mem::take(&mut self.stack);
+                    let mut horizontal = b0 == 31;
+                    let mut curves = stack.chunks_exact(4).peekable();
+                    let remainder = stack.chunks_exact(4).remainder();
+                    while let Some(curve) = curves.next() {
+                        // The last curve may have an extra operand, for its otherwise zero delta
+                        let last = if curves.peek().is_none() {
+                            remainder.first().cloned().unwrap_or(0.)
+                        } else {
+                            0.
+                        };
+                        if let [a, dxb, dyb, c] = *curve {
+                            if horizontal {
+                                self.curve(a, 0., dxb, dyb, last, c)
+                            } else {
+                                self.curve(0., a, dxb, dyb, c, last)
+                            }
+                        }
+                        horizontal = !horizontal
+                    }
+                }
+                // rcurveline
+                24 => {
+                    let stack = std::mem::take(&mut self.stack);
+                    let line_start = stack.len().saturating_sub(2);
+                    for curve in stack[..line_start].chunks(6) {
+                        if let [dxa, dya, dxb, dyb, dxc, dyc] = *curve {
+                            self.curve(dxa, dya, dxb, dyb, dxc, dyc)
+                        }
+                    }
+                    if let [dx, dy] = stack[line_start..] {
+                        self.x += dx;
+                        self.y += dy;
+                        self.builder.line_to(self.x, self.y)
+                    }
+                }
+                // rlinecurve
+                25 => {
+                    let stack = std::mem::take(&mut self.stack);
+                    let curve_start = stack.len().saturating_sub(6);
+                    for pair in stack[..curve_start].chunks(2) {
+                        if let [dx, dy] = *pair {
+                            self.x += dx;
+                            self.y += dy;
+                            self.builder.line_to(self.x, self.y)
+                        }
+                    }
+                    if let [dxa, dya, dxb, dyb, dxc, dyc] = stack[curve_start..] {
+                        self.curve(dxa, dya, dxb, dyb, dxc, dyc)
+                    }
+                }
+                // callsubr, callgsubr
+                10 | 29 => {
+                    let subroutines = if b0 == 10 {
+                        self.local_subroutines
+                    } else {
+                        self.global_subroutines
+                    };
+                    let index = self
+                        .stack
+                        .pop()
+                        .ok_or(FontError::InvalidCompactFontFormat)?;
+                    let index = index as i32 + subroutine_bias(subroutines.count);
+                    if index < 0 {
+                        return Err(FontError::InvalidCompactFontFormat);
+                    }
+                    let subroutine = subroutines.get(index as usize)?;
+                    if let End::EndChar = self.run(subroutine, depth + 1)? {
+                        return Ok(End::EndChar);
+                    }
+                }
+                // return
+                11 => return Ok(End::Return),
+                // endchar
+                14 => {
+                    // Four extra operands are for the deprecated `seac` accented characters,
+                    // which are not supported.
+                    self.take_width(self.stack.len() == 1 || self.stack.len() == 5);
+                    self.stack.clear();
+                    return Ok(End::EndChar);
+                }
+                12 => {
+                    let b1 = reader.u8()?;
+                    let stack = std::mem::take(&mut self.stack);
+                    self.flex(b1, &stack)
+                }
+                28 => self.stack.push(f32::from(reader.i16()?)),
+                255 => self.stack.push(reader.i32()? as f32 / 65536.),
+                32..=254 => self.stack.push(f32::from(short_integer(b0, &mut reader)?)),
+                // Reserved
+                _ => self.stack.clear(),
+            }
+        }
+        Ok(End::Return)
+    }
+
+    /// `flex`, `hflex`, `hflex1`, and `flex1`: two curves which may be rendered as a line.
+    /// Other two-byte operators are deprecated arithmetic operators, ignored here.
+    fn flex(&mut self, operator: u8, stack: &[f32]) {
+        match (operator, stack) {
+            (35, &[dx1, dy1, dx2, dy2, dx3, dy3, dx4, dy4, dx5, dy5, dx6, dy6, _flex_depth]) => {
+                self.curve(dx1, dy1, dx2, dy2, dx3, dy3);
+                self.curve(dx4, dy4, dx5, dy5, dx6, dy6)
+            }
+            (34, &[dx1, dx2, dy2, dx3, dx4, dx5, dx6]) => {
+                self.curve(dx1, 0., dx2, dy2, dx3, 0.);
+                self.curve(dx4, 0., dx5, -dy2, dx6, 0.)
+            }
+            (36, &[dx1, dy1, dx2, dy2, dx3, dx4, dx5, dy5, dx6]) => {
+                let start_y = self.y;
+                self.curve(dx1, dy1, dx2, dy2, dx3, 0.);
+                let dy6 = start_y - (self.y + dy5);
+                self.curve(dx4, 0., dx5, dy5, dx6, dy6)
+            }
+            (37, &[dx1, dy1, dx2, dy2, dx3, dy3, dx4, dy4, dx5, dy5, d6]) => {
+                let dx = dx1 + dx2 + dx3 + dx4 + dx5;
+                let dy = dy1 + dy2 + dy3 + dy4 + dy5;
+                let (dx6, dy6) = if dx.abs() > dy.abs() {
+                    (d6, -dy)
+                } else {
+                    (-dx, d6)
+                };
+                self.curve(dx1, dy1, dx2, dy2, dx3, dy3);
+                self.curve(dx4, dy4, dx5, dy5, dx6, dy6)
+            }
+            _ => {}
+        }
+    }
+
+    fn take_width(&mut self, has_width: bool) {
+        if !self.width_parsed {
+            self.width_parsed = true;
+            if has_width && !self.stack.is_empty() {
+                self.stack.remove(0);
+            }
+        }
+    }
+
+    fn operand(&self, index: usize) -> Result<f32, FontError> {
+        self.stack
+            .get(index)
+            .cloned()
+            .ok_or(FontError::InvalidCompactFontFormat)
+    }
+
+    fn move_to(&mut self, dx: f32, dy: f32) {
+        self.stack.clear();
+        self.close_contour();
+        self.x += dx;
+        self.y += dy;
+        self.builder.move_to(self.x, self.y);
+        self.contour_open = true
+    }
+
+    fn close_contour(&mut self) {
+        if self.contour_open {
+            self.builder.close();
+            self.contour_open = false
+        }
+    }
+
+    /// A cubic curve, with each point relative to the previous one
+    #[allow(clippy::too_many_arguments)]
+    fn curve(&mut self, dxa: f32, dya: f32, dxb: f32, dyb: f32, dxc: f32, dyc: f32) {
+        let a = (self.x + dxa, self.y + dya);
+        let b = (a.0 + dxb, a.1 + dyb);
+        let c = (b.0 + dxc, b.1 + dyc);
+        self.builder.cubic_curve_to(a, b, c);
+        self.x = c.0;
+        self.y = c.1
+    }
+}
+
+/// Subroutine numbers are stored minus this bias, to use more one-byte numbers.
+fn subroutine_bias(count: usize) -> i32 {
+    if count < 1240 {
+        107
+    } else if count < 33900 {
+        1131
+    } else {
+        32768
+    }
+}
@@ -1,14 +1,44 @@
 use crate::fonts::parsing::{binary_search, Position, Slice};
 use crate::fonts::tables::*;
-use crate::fonts::{FontError, GlyphId};
+use crate::fonts::{decode_mac_roman, encode_mac_roman, FontError, GlyphId};
 use std::char;
 use std::cmp::Ordering;
 
-pub(in crate::fonts) enum Cmap {
+pub(in crate::fonts) struct Cmap {
+    subtable: Subtable,
+    encoding: Encoding,
+
+    /// Format 14, for variation sequences
+    variation_sequences: Option<Format14>,
+}
+
+enum Subtable {
+    Format0(Position<u8>),
     Format4(Format4),
+    Format6(Format6),
+    /// Also used for format 13, with `many_to_one` set
     Format12(Format12),
 }
 
+/// How the character codes of a subtable relate to Unicode
+#[derive(Copy, Clone)]
+enum Encoding {
+    Unicode,
+
+    /// Characters are in the U+F020 to U+F0FF private use range,
+    /// but are typically used through the corresponding Latin-1 code points.
+    Symbol,
+
+    MacintoshRoman,
+}
+
+/// The glyph for a variation sequence
+pub(in crate::fonts) enum VariationGlyph {
+    /// The sequence is supported with the glyph for the base character
+    Default,
+    NonDefault(u16),
+}
+
 impl Cmap {
     pub(in crate::fonts) fn parse(
         bytes: &[u8],
@@ -19,32 +49,117 @@ impl Cmap {
             cmap_header.followed_by::<CmapEncodingRecord>(),
             cmap_header.num_tables().read_from(bytes)?,
         );
-        // Entries are sorted by (platform, encoding).
-        // Iterator in reverse order to prefer (3, 10) over (3, 1).
-        for record in cmap_records.into_iter().rev() {
+        const UNICODE: u16 = 0;
+        const MACINTOSH: u16 = 1;
+        const MICROSOFT: u16 = 3;
+        const UNICODE_VARIATION_SEQUENCES: u16 = 5;
+        const MACINTOSH_ROMAN: u16 = 0;
+        const MICROSOFT_SYMBOL: u16 = 0;
+        const MICROSOFT_UNICODE_USC2: u16 = 1;
+        const MICROSOFT_UNICODE_USC4: u16 = 10;
+        const BYTE_ENCODING_TABLE: u16 = 0;
+        const SEGMENT_MAPPING_TO_DELTA_VALUES: u16 = 4;
+        const TRIMMED_TABLE_MAPPING: u16 = 6;
+        const SEGMENTED_COVERAGE: u16 = 12;
+        const MANY_TO_ONE_RANGE_MAPPINGS: u16 = 13;
+        const UNICODE_VARIATION_SEQUENCES_FORMAT: u16 = 14;
+
+        // (rank, encoding, format, subtable), lower ranks are preferred:
+        // full Unicode coverage, then the BMP, then legacy encodings.
+        let mut best: Option<(u8, Encoding, u16, Position<u16>)> = None;
+        let mut variation_sequences = None;
+        for record in cmap_records {
             let subtable = cmap_header.offset_bytes(record.subtable_offset().read_from(bytes)?);
             let format = subtable.read_from(bytes)?;
-            const MICROSOFT: u16 = 3;
-            const UNICODE_USC2: u16 = 1;
-            const UNICODE_USC4: u16 = 10;
-            const SEGMENT_MAPPING_TO_DELTA_VALUES: u16 = 4;
-            const SEGMENTED_COVERAGE: u16 = 12;
-            match (
+            let (rank, encoding) = match (
                 record.platform_id().read_from(bytes)?,
                 record.encoding_id().read_from(bytes)?,
                 format,
             ) {
-                (MICROSOFT, UNICODE_USC2, SEGMENT_MAPPING_TO_DELTA_VALUES) => {
-                    return Ok(Cmap::Format4(Format4::parse(bytes, subtable.cast())?))
+                (UNICODE, UNICODE_VARIATION_SEQUENCES, UNICODE_VARIATION_SEQUENCES_FORMAT) => {
+                    variation_sequences = Some(Format14::parse(bytes, subtable)?);
+                    continue;
                 }
-                (MICROSOFT, UNICODE_USC4, SEGMENTED_COVERAGE) => {
-                    return Ok(Cmap::Format12(Format12::parse(bytes, subtable.cast())?))
+                // Encoding IDs of the Unicode platform only say which version of Unicode
+                // and which range of code points are covered, which the format implies.
+                (MICROSOFT, MICROSOFT_UNICODE_USC4, SEGMENTED_COVERAGE)
+                | (UNICODE, _, SEGMENTED_COVERAGE) => (0, Encoding::Unicode),
+                (MICROSOFT, MICROSOFT_UNICODE_USC4, MANY_TO_ONE_RANGE_MAPPINGS)
+                | (UNICODE, _, MANY_TO_ONE_RANGE_MAPPINGS) => (1, Encoding::Unicode),
+                (MICROSOFT, MICROSOFT_UNICODE_USC2, SEGMENT_MAPPING_TO_DELTA_VALUES)
+                | (MICROSOFT, MICROSOFT_UNICODE_USC2, TRIMMED_TABLE_MAPPING)
+                | (UNICODE, _, SEGMENT_MAPPING_TO_DELTA_VALUES)
+                | (UNICODE, _, TRIMMED_TABLE_MAPPING) => (2, Encoding::Unicode),
+                (MICROSOFT, MICROSOFT_SYMBOL, SEGMENT_MAPPING_TO_DELTA_VALUES)
+                | (MICROSOFT, MICROSOFT_SYMBOL, TRIMMED_TABLE_MAPPING) => (3, Encoding::Symbol),
+                (MACINTOSH, MACINTOSH_ROMAN, BYTE_ENCODING_TABLE)
+                | (MACINTOSH, MACINTOSH_ROMAN, SEGMENT_MAPPING_TO_DELTA_VALUES)
+                | (MACINTOSH, MACINTOSH_ROMAN, TRIMMED_TABLE_MAPPING) => {
+                    (4, Encoding::MacintoshRoman)
                 }
-                _ => {}
+                _ => continue,
+            };
+            if best.map_or(true, |(best_rank, ..)| rank < best_rank) {
+                best = Some((rank, encoding, format, subtable))
             }
         }
 
-        Err(FontError::NoSupportedCmap)
+        let (_, encoding, format, subtable) = best.ok_or(FontError::NoSupportedCmap)?;
+        let subtable = match format {
+            BYTE_ENCODING_TABLE => {
+                Subtable::Format0(subtable.cast::<CmapFormat0Header>().followed_by())
+            }
+            SEGMENT_MAPPING_TO_DELTA_VALUES => {
+                Subtable::Format4(Format4::parse(bytes, subtable.cast())?)
+            }
+            TRIMMED_TABLE_MAPPING => Subtable::Format6(Format6::parse(bytes, subtable.cast())?),
+            _ => Subtable::Format12(Format12::parse(
+                bytes,
+                subtable.cast(),
+                format == MANY_TO_ONE_RANGE_MAPPINGS,
+            )?),
+        };
+        Ok(Cmap {
+            subtable,
+            encoding,
+            variation_sequences,
+        })
+    }
+
+    /// The glyph ID for a code point, if it is mapped
+    pub(in crate::fonts) fn get(
+        &self,
+        bytes: &[u8],
+        code_point: u32,
+    ) -> Result<Option<u16>, FontError> {
+        match self.encoding {
+            Encoding::Unicode => self.subtable.get(bytes, code_point),
+            Encoding::Symbol => match self.subtable.get(bytes, code_point)? {
+                None if (0x20..=0xFF).contains(&code_point) => {
+                    self.subtable.get(bytes, 0xF000 + code_point)
+                }
+                result => Ok(result),
+            },
+            Encoding::MacintoshRoman => match char::from_u32(code_point).and_then(encode_mac_roman)
+            {
+                Some(byte) => self.subtable.get(bytes, u32::from(byte)),
+                None => Ok(None),
+            },
+        }
+    }
+
+    /// The glyph for a base character followed by a variation selector,
+    /// if this font supports that sequence
+    pub(in crate::fonts) fn get_variation(
+        &self,
+        bytes: &[u8],
+        code_point: u32,
+        selector: u32,
+    ) -> Result<Option<VariationGlyph>, FontError> {
+        match self.variation_sequences {
+            Some(ref table) => table.get(bytes, code_point, selector),
+            None => Ok(None),
+        }
     }
 
     pub(in crate::fonts) fn each_code_point<F>(
@@ -55,17 +170,63 @@ impl Cmap {
     where
         F: FnMut(char, GlyphId),
     {
-        let f = move |code_point, glyph_id| {
+        let encoding = self.encoding;
+        let mut translated = |code, glyph_id| {
             if glyph_id != 0 {
+                let ch = match encoding {
+                    Encoding::Unicode => char::from_u32(code),
+                    Encoding::Symbol if (0xF020..=0xF0FF).contains(&code) => {
+                        char::from_u32(code - 0xF000)
+                    }
+                    Encoding::Symbol => char::from_u32(code),
+                    Encoding::MacintoshRoman if code <= 0xFF => Some(decode_mac_roman(code as u8)),
+                    Encoding::MacintoshRoman => None,
+                };
                 // Ignore any mapping for surrogate code points
-                if let Some(ch) = char::from_u32(code_point) {
+                if let Some(ch) = ch {
                     f(ch, GlyphId(glyph_id));
                 }
             }
         };
+        match self.subtable {
+            Subtable::Format0(glyph_ids) => {
+                let glyph_ids = Slice::<u8>::new(glyph_ids, 256_u32).read_from(bytes)?;
+                for (code, &glyph_id) in glyph_ids.iter().enumerate() {
+                    translated(code as u32, u16::from(glyph_id))
+                }
+            }
+            Subtable::Format4(ref table) => table.each_code_point(bytes, &mut translated)?,
+            Subtable::Format6(ref table) => table.each_code_point(bytes, &mut translated)?,
+            Subtable::Format12(ref table) => table.each_code_point(bytes, &mut translated)?,
+        }
+        // Glyphs only used for a variation sequence represent their base character.
+        // Format 14 always has Unicode code points, whatever the encoding of the other subtable.
+        if let Some(ref table) = self.variation_sequences {
+            table.each_non_default_mapping(bytes, |code, glyph_id| {
+                if glyph_id != 0 {
+                    if let Some(ch) = char::from_u32(code) {
+                        f(ch, GlyphId(glyph_id))
+                    }
+                }
+            })?
+        }
+        Ok(())
+    }
+}
+
+impl Subtable {
+    fn get(&self, bytes: &[u8], code: u32) -> Result<Option<u16>, FontError> {
         match *self {
-            Cmap::Format4(ref table) => table.each_code_point(bytes, f),
-            Cmap::Format12(ref table) => table.each_code_point(bytes, f),
+            Subtable::Format0(glyph_ids) => {
+                let glyph_ids = Slice::<u8>::new(glyph_ids, 256_u32).read_from(bytes)?;
+                Ok(match glyph_ids.get(code as usize) {
+                    Some(&glyph_id) if glyph_id != 0 => Some(u16::from(glyph_id)),
+                    _ => None,
+                })
+            }
+            Subtable::Format4(ref table) => table.get(bytes, code),
+            Subtable::Format6(ref table) => table.get(bytes, code),
+            Subtable::Format12(ref table) => table.get(bytes, code),
         }
     }
 }
@@ -179,20 +340,66 @@ impl Format4 {
     }
 }
 
+pub(in crate::fonts) struct Format6 {
+    first_code: u32,
+    glyph_ids: Slice<u16>,
+}
+
+impl Format6 {
+    fn parse(
+        bytes: &[u8],
+        encoding_header: Position<CmapFormat6Header>,
+    ) -> Result<Self, FontError> {
+        Ok(Format6 {
+            first_code: u32::from(encoding_header.first_code().read_from(bytes)?),
+            glyph_ids: Slice::new(
+                encoding_header.followed_by(),
+                encoding_header.entry_count().read_from(bytes)?,
+            ),
+        })
+    }
+
+    fn get(&self, bytes: &[u8], code_point: u32) -> Result<Option<u16>, FontError> {
+        match code_point.checked_sub(self.first_code) {
+            Some(index) if index < self.glyph_ids.count() => {
+                let glyph_id = self.glyph_ids.get_unchecked(index).read_from(bytes)?;
+                Ok(if glyph_id != 0 { Some(glyph_id) } else { None })
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn each_code_point<F>(&self, bytes: &[u8], mut f: F) -> Result<(), FontError>
+    where
+        F: FnMut(u32, u16),
+    {
+        for (index, glyph_id) in self.glyph_ids.into_iter().enumerate() {
+            f(self.first_code + index as u32, glyph_id.read_from(bytes)?)
+        }
+        Ok(())
+    }
+}
+
+/// Format 12, or format 13 which has the same layout
 pub(in crate::fonts) struct Format12 {
     groups: Slice<CmapFormat12Group>,
+
+    /// For format 13, all code points of a group map to the same glyph
+    many_to_one: bool,
 }
 
 impl Format12 {
     fn parse(
         bytes: &[u8],
         encoding_header: Position<CmapFormat12Header>,
+        many_to_one: bool,
     ) -> Result<Self, FontError> {
         Ok(Format12 {
             groups: Slice::new(
                 encoding_header.followed_by(),
                 encoding_header.num_groups().read_from(bytes)?,
             ),
+            many_to_one,
         })
     }
 
@@ -215,7 +422,7 @@ impl Format12 {
         if let Some(index) = result? {
             let group = self.groups.get_unchecked(index);
             let id32 = group.start_glyph_id().read_from(bytes)?
-                + (code_point - group.start_char_code().read_from(bytes)?);
+                + self.offset_in_group(code_point - group.start_char_code().read_from(bytes)?);
             // Glyph IDs are 16 bits in PDF.
             // For now, pretend that glyphs with larger IDs are missing.
             // FIXME: Maybe this will be unnecessary with PDF font subsetting?
@@ -237,7 +444,7 @@ impl Format12 {
             let start_glyph_id = group.start_glyph_id().read_from(bytes)?;
             let mut code_point = start_code;
             loop {
-                let glyph_id = (code_point - start_code) + start_glyph_id;
+                let glyph_id = self.offset_in_group(code_point - start_code) + start_glyph_id;
                 f(code_point, glyph_id as u16);
 
                 if code_point == end_code {
@@ -248,4 +455,328 @@ impl Format12 {
         }
         Ok(())
     }
+
+    fn offset_in_group(&self, offset: u32) -> u32 {
+        if self.many_to_one {
+            0
+        } else {
+            offset
+        }
+    }
+}
+
+/// https://docs.microsoft.com/en-us/typography/opentype/spec/cmap#format-14-unicode-variation-sequences
+///
+/// Records in this subtable are not aligned, they are read with `Reader`.
+pub(in crate::fonts) struct Format14 {
+    /// The start of the subtable, that offsets are relative to
+    start: Position<()>,
+    record_count: u32,
+}
+
+const VARIATION_SELECTOR_RECORD_SIZE: u32 = 11;
+
+impl Format14 {
+    fn parse(bytes: &[u8], subtable: Position<u16>) -> Result<Self, FontError> {
+        let start = subtable.cast();
+        let mut reader = subtable.reader(bytes)?;
+        reader.skip(6)?; // format and length
+        Ok(Format14 {
+            start,
+            record_count: reader.u32()?,
+        })
+    }
+
+    /// The variation selector, default UVS offset, and non-default UVS offset of a record
+    fn record(&self, bytes: &[u8], index: u32) -> Result<(u32, u32, u32), FontError> {
+        let mut reader = self
+            .start
+            .offset_bytes::<(), _>(10 + index * VARIATION_SELECTOR_RECORD_SIZE)
+            .reader(bytes)?;
+        Ok((reader.u24()?, reader.u32()?, reader.u32()?))
+    }
+
+    fn get(
+        &self,
+        bytes: &[u8],
+        code_point: u32,
+        selector: u32,
+    ) -> Result<Option<VariationGlyph>, FontError> {
+        let index = binary_search(self.record_count, |index| {
+            Ok(self.record(bytes, index)?.0.cmp(&selector))
+        })?;
+        let (_, default_offset, non_default_offset) = match index {
+            Some(index) => self.record(bytes, index)?,
+            None => return Ok(None),
+        };
+
+        if default_offset != 0 {
+            // Ranges of code points, 4 bytes each
+            let mut reader = self
+                .start
+                .offset_bytes::<(), _>(default_offset)
+                .reader(bytes)?;
+            let count = reader.u32()?;
+            let range = |index: u32| {
+                let mut reader = reader.at_offset(index as usize * 4)?;
+                Ok((reader.u24()?, u32::from(reader.u8()?)))
+            };
+            let found = binary_search(count, |index| {
+                let (start, additional_count) = range(index)?;
+                Ok(if code_point < start {
+                    Ordering::Greater
+                } else if code_point > start + additional_count {
+                    Ordering::Less
+                } else {
+                    Ordering::Equal
+                })
+            })?;
+            if found.is_some() {
+                return Ok(Some(VariationGlyph::Default));
+            }
+        }
+
+        if non_default_offset != 0 {
+            // Mappings from a code point to a glyph, 5 bytes each
+            let mut reader = self
+                .start
+                .offset_bytes::<(), _>(non_default_offset)
+                .reader(bytes)?;
+            let count = reader.u32()?;
+            let found = binary_search(count, |index| {
+                Ok(reader
+                    .at_offset(index as usize * 5)?
+                    .u24()?
+                    .cmp(&code_point))
+            })?;
+            if let Some(index) = found {
+                let mut mapping = reader.at_offset(index as usize * 5 + 3)?;
+                return Ok(Some(VariationGlyph::NonDefault(mapping.u16()?)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn each_non_default_mapping<F>(&self, bytes: &[u8], mut f: F) -> Result<(), FontError>
+    where
+        F: FnMut(u32, u16),
+    {
+        for index in 0..self.record_count {
+            let (_, _, non_default_offset) = self.record(bytes, index)?;
+            if non_default_offset == 0 {
+                continue;
+            }
+            let mut reader = self
+                .start
+                .offset_bytes::<(), _>(non_default_offset)
+                .reader(bytes)?;
+            for _ in 0..reader.u32()? {
+                let code_point = reader.u24()?;
+                f(code_point, reader.u16()?)
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fonts::types::Tag;
+    use crate::fonts::write_sfnt;
+
+    /// A font with only a `cmap` table, with `(platform ID, encoding ID, subtable)` records
+    fn parse(records: Vec<(u16, u16, Vec<u8>)>) -> (Vec<u8>, Cmap) {
+        let mut table = u16s(&[0, records.len() as u16]);
+        let mut offset = 4 + 8 * records.len() as u32;
+        for (platform_id, encoding_id, subtable) in &records {
+            table.extend(u16s(&[*platform_id, *encoding_id]));
+            table.extend(u32s(&[offset]));
+            offset += subtable.len() as u32
+        }
+        for (_, _, subtable) in &records {
+            table.extend(subtable)
+        }
+        let bytes = write_sfnt(0x0001_0000, 0, vec![(Tag(*b"cmap"), table)]);
+        let table_directory = Slice::new(
+            Position::<OffsetSubtable>::initial().followed_by::<TableDirectoryEntry>(),
+            1_u16,
+        );
+        let cmap = Cmap::parse(&bytes, table_directory).unwrap();
+        (bytes, cmap)
+    }
+
+    fn u16s(values: &[u16]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|v| v.to_be_bytes().to_vec())
+            .collect()
+    }
+
+    fn u32s(values: &[u32]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|v| v.to_be_bytes().to_vec())
+            .collect()
+    }
+
+    fn u24(value: u32) -> Vec<u8> {
+        value.to_be_bytes()[1..].to_vec()
+    }
+
+    fn format0(mappings: &[(u8, u8)]) -> Vec<u8> {
+        let mut glyph_ids = [0; 256];
+        for &(code, glyph_id) in mappings {
+            glyph_ids[usize::from(code)] = glyph_id
+        }
+        let mut subtable = u16s(&[0, 262, 0]);
+        subtable.extend_from_slice(&glyph_ids);
+        subtable
+    }
+
+    fn format6(first_code: u16, glyph_ids: &[u16]) -> Vec<u8> {
+        let count = glyph_ids.len() as u16;
+        let mut subtable = u16s(&[6, 10 + 2 * count, 0, first_code, count]);
+        subtable.extend(u16s(glyph_ids));
+        subtable
+    }
+
+    /// Format 12 or 13, with `(start code, end code, glyph ID)` groups
+    fn format12(format: u16, groups: &[(u32, u32, u32)]) -> Vec<u8> {
+        let count = groups.len() as u32;
+        let mut subtable = u16s(&[format, 0]);
+        subtable.extend(u32s(&[16 + 12 * count, 0, count]));
+        for &(start, end, glyph_id) in groups {
+            subtable.extend(u32s(&[start, end, glyph_id]))
+        }
+        subtable
+    }
+
+    /// With a single variation selector, default UVS ranges of `(start, additional count)`
+    /// and non-default UVS mappings of `(code point, glyph ID)`
+    fn format14(selector: u32, default: &[(u32, u8)], non_default: &[(u32, u16)]) -> Vec<u8> {
+        let header_size = 10 + VARIATION_SELECTOR_RECORD_SIZE;
+        let default_size = 4 + 4 * default.len() as u32;
+        let non_default_size = 4 + 5 * non_default.len() as u32;
+        let mut subtable = u16s(&[14]);
+        subtable.extend(u32s(&[header_size + default_size + non_default_size, 1]));
+        subtable.extend(u24(selector));
+        subtable.extend(u32s(&[header_size, header_size + default_size]));
+        subtable.extend(u32s(&[default.len() as u32]));
+        for &(start, additional_count) in default {
+            subtable.extend(u24(start));
+            subtable.push(additional_count)
+        }
+        subtable.extend(u32s(&[non_default.len() as u32]));
+        for &(code_point, glyph_id) in non_default {
+            subtable.extend(u24(code_point));
+            subtable.extend(u16s(&[glyph_id]))
+        }
+        subtable
+    }
+
+    fn get(bytes: &[u8], cmap: &Cmap, ch: char) -> Option<u16> {
+        cmap.get(bytes, ch as u32).unwrap()
+    }
+
+    fn code_points(bytes: &[u8], cmap: &Cmap) -> Vec<(char, u16)> {
+        let mut code_points = Vec::new();
+        cmap.each_code_point(bytes, |ch, glyph_id| code_points.push((ch, glyph_id.0)))
+            .unwrap();
+        code_points
+    }
+
+    #[test]
+    fn format_0_macintosh_roman() {
+        let (bytes, cmap) = parse(vec![(1, 0, format0(&[(b'A', 5), (0x8A, 6)]))]);
+        assert_eq!(get(&bytes, &cmap, 'A'), Some(5));
+        assert_eq!(get(&bytes, &cmap, 'ä'), Some(6));
+        assert_eq!(get(&bytes, &cmap, '\u{8A}'), None);
+        assert_eq!(code_points(&bytes, &cmap), [('A', 5), ('ä', 6)]);
+    }
+
+    #[test]
+    fn format_6_unicode_platform() {
+        let (bytes, cmap) = parse(vec![(0, 3, format6(0x41, &[7, 0, 8]))]);
+        assert_eq!(get(&bytes, &cmap, 'A'), Some(7));
+        assert_eq!(get(&bytes, &cmap, 'B'), None);
+        assert_eq!(get(&bytes, &cmap, 'C'), Some(8));
+        assert_eq!(get(&bytes, &cmap, 'D'), None);
+        assert_eq!(code_points(&bytes, &cmap), [('A', 7), ('C', 8)]);
+    }
+
+    #[test]
+    fn format_12_preferred() {
+        let (bytes, cmap) = parse(vec![
+            (0, 3, format6(0x41, &[7])),
+            (
+                3,
+                10,
+                format12(12, &[(0x41, 0x42, 3), (0x1F600, 0x1F600, 9)]),
+            ),
+        ]);
+        assert_eq!(get(&bytes, &cmap, 'A'), Some(3));
+        assert_eq!(get(&bytes, &cmap, 'B'), Some(4));
+        assert_eq!(get(&bytes, &cmap, '😀'), Some(9));
+        assert_eq!(code_points(&bytes, &cmap), [('A', 3), ('B', 4), ('😀', 9)]);
+    }
+
+    #[test]
+    fn format_13_many_to_one() {
+        let (bytes, cmap) = parse(vec![(0, 6, format12(13, &[(0x4E00, 0x4E02, 9)]))]);
+        assert_eq!(get(&bytes, &cmap, '\u{4E00}'), Some(9));
+        assert_eq!(get(&bytes, &cmap, '\u{4E02}'), Some(9));
+        assert_eq!(get(&bytes, &cmap, '\u{4E03}'), None);
+        assert_eq!(
+            code_points(&bytes, &cmap),
+            [('\u{4E00}', 9), ('\u{4E01}', 9), ('\u{4E02}', 9)]
+        );
+    }
+
+    #[test]
+    fn symbol_remapping() {
+        let (bytes, cmap) = parse(vec![(3, 0, format6(0xF041, &[10]))]);
+        assert_eq!(get(&bytes, &cmap, 'A'), Some(10));
+        assert_eq!(get(&bytes, &cmap, '\u{F041}'), Some(10));
+        assert_eq!(get(&bytes, &cmap, 'B'), None);
+        assert_eq!(code_points(&bytes, &cmap), [('A', 10)]);
+    }
+
+    #[test]
+    fn variation_sequences() {
+        let (bytes, cmap) = parse(vec![
+            (
+                0,
+                5,
+                format14(0xFE00, &[(0x41, 1)], &[(0xE4, 12), (0x4E00, 13)]),
+            ),
+            (1, 0, format0(&[(b'A', 5), (b'B', 7), (0x8A, 6)])),
+        ]);
+        let variation = |code_point, selector| cmap.get_variation(&bytes, code_point, selector);
+        assert!(matches!(
+            variation(0x41, 0xFE00),
+            Ok(Some(VariationGlyph::Default))
+        ));
+        assert!(matches!(
+            variation(0x42, 0xFE00),
+            Ok(Some(VariationGlyph::Default))
+        ));
+        assert!(matches!(variation(0x43, 0xFE00), Ok(None)));
+        assert!(matches!(
+            variation(0xE4, 0xFE00),
+            Ok(Some(VariationGlyph::NonDefault(12)))
+        ));
+        assert!(matches!(
+            variation(0x4E00, 0xFE00),
+            Ok(Some(VariationGlyph::NonDefault(13)))
+        ));
+        assert!(matches!(variation(0x41, 0xFE01), Ok(None)));
+
+        // Code points of format 14 are Unicode, not Mac Roman like the other subtable
+        assert_eq!(
+            code_points(&bytes, &cmap),
+            [('A', 5), ('B', 7), ('ä', 6), ('ä', 12), ('\u{4E00}', 13)]
+        );
+    }
 }
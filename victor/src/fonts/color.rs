@@ -0,0 +1,344 @@
+//! Colour glyphs: layers of glyphs from `COLR` and `CPAL`,
+//! and bitmaps from `CBDT` and `CBLC` or `sbix`.
+//!
+//! These tables contain unaligned records, they are read with `Reader`.
+
+use crate::fonts::parsing::{binary_search, Reader};
+use crate::fonts::types::Tag;
+use crate::fonts::{Em, Font, FontError, GlyphId};
+use crate::primitives::{point, Rect, Size, RGBA};
+
+/// Which table colour glyphs are taken from, in order of preference
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(in crate::fonts) enum ColorFormat {
+    /// https://docs.microsoft.com/en-us/typography/opentype/spec/colr
+    Layers,
+
+    /// https://docs.microsoft.com/en-us/typography/opentype/spec/cbdt
+    ColorBitmaps,
+
+    /// https://docs.microsoft.com/en-us/typography/opentype/spec/sbix
+    StandardBitmaps,
+}
+
+impl ColorFormat {
+    pub(in crate::fonts) fn detect(has_table: impl Fn(Tag) -> bool) -> Option<Self> {
+        if has_table(Tag(*b"COLR")) && has_table(Tag(*b"CPAL")) {
+            Some(ColorFormat::Layers)
+        } else if has_table(Tag(*b"CBDT")) && has_table(Tag(*b"CBLC")) {
+            Some(ColorFormat::ColorBitmaps)
+        } else if has_table(Tag(*b"sbix")) {
+            Some(ColorFormat::StandardBitmaps)
+        } else {
+            None
+        }
+    }
+}
+
+/// How to paint a glyph in colour, instead of filling its outline with the text colour
+pub(crate) enum ColorGlyph<'a> {
+    /// Glyphs of the same font to paint on top of each other, from bottom to top
+    Layers(Vec<ColorLayer>),
+
+    /// An image in PNG format
+    Bitmap(GlyphBitmap<'a>),
+}
+
+pub(crate) struct ColorLayer {
+    pub glyph_id: GlyphId,
+
+    /// `None` for the current text colour
+    pub color: Option<RGBA>,
+}
+
+pub(crate) struct GlyphBitmap<'a> {
+    pub png: &'a [u8],
+
+    /// Where to paint the image, relative to the glyph origin with the y axis pointing up.
+    pub bounds: Rect<Em>,
+}
+
+impl Font {
+    /// Whether some glyphs of this font may be painted in colour
+    pub(crate) fn has_color_glyphs(&self) -> bool {
+        self.color_format.is_some()
+    }
+
+    /// The colour version of a glyph, if this font has one
+    pub(crate) fn color_glyph(&self, glyph_id: GlyphId) -> Result<Option<ColorGlyph>, FontError> {
+        let bytes: &[u8] = &self.bytes;
+        let table_directory = self.table_directory()?;
+        let table = |tag: &[u8; 4]| table_directory.find_table_bytes(bytes, Tag(*tag));
+        Ok(match self.color_format {
+            None => None,
+            Some(ColorFormat::Layers) => {
+                color_layers(table(b"COLR")?, table(b"CPAL")?, glyph_id)?.map(ColorGlyph::Layers)
+            }
+            Some(ColorFormat::ColorBitmaps) => {
+                color_bitmap(table(b"CBLC")?, table(b"CBDT")?, glyph_id)?.map(ColorGlyph::Bitmap)
+            }
+            Some(ColorFormat::StandardBitmaps) => {
+                standard_bitmap(table(b"sbix")?, self.glyph_count, glyph_id, 0)?
+                    .map(ColorGlyph::Bitmap)
+            }
+        })
+    }
+}
+
+/// The layers of a glyph in a `COLR` version 0 table, with colours from the first `CPAL` palette
+fn color_layers(
+    colr: &[u8],
+    cpal: &[u8],
+    glyph_id: GlyphId,
+) -> Result<Option<Vec<ColorLayer>>, FontError> {
+    let mut header = Reader::new(colr);
+    header.skip(2)?; // version
+    let base_glyph_count = header.u16()?;
+    let base_glyphs_offset = header.u32()? as usize;
+    let layers_offset = header.u32()? as usize;
+
+    // Base glyph records are 6 bytes, sorted by glyph ID
+    let base_glyph = |index: u32| Reader::at(colr, base_glyphs_offset + index as usize * 6);
+    let found = binary_search(u32::from(base_glyph_count), |index| {
+        Ok(base_glyph(index)?.u16()?.cmp(&glyph_id.0))
+    })?;
+    let mut record = match found {
+        Some(index) => base_glyph(index)?,
+        None => return Ok(None),
+    };
+    record.skip(2)?; // glyph ID
+    let first_layer = record.u16()? as usize;
+    let layer_count = record.u16()? as usize;
+
+    let mut palette = Reader::new(cpal);
+    palette.skip(2)?; // version
+    let palette_entry_count = palette.u16()?;
+    palette.skip(4)?; // number of palettes and of color records
+    let color_records_offset = palette.u32()? as usize;
+    let first_color_record = palette.u16()? as usize; // of the first palette
+
+    const TEXT_COLOR: u16 = 0xFFFF;
+    let mut layers = Vec::with_capacity(layer_count);
+    let mut reader = Reader::at(colr, layers_offset + first_layer * 4)?;
+    for _ in 0..layer_count {
+        let glyph_id = GlyphId(reader.u16()?);
+        let palette_index = reader.u16()?;
+        let color = if palette_index == TEXT_COLOR || palette_index >= palette_entry_count {
+            None
+        } else {
+            // Blue, green, red, alpha
+            let mut record = Reader::at(
+                cpal,
+                color_records_offset + (first_color_record + palette_index as usize) * 4,
+            )?;
+            let (b, g, r, a) = (record.u8()?, record.u8()?, record.u8()?, record.u8()?);
+            let channel = |value: u8| f32::from(value) / 255.;
+            Some(RGBA(channel(r), channel(g), channel(b), channel(a)))
+        };
+        layers.push(ColorLayer { glyph_id, color })
+    }
+    Ok(Some(layers))
+}
+
+/// A PNG bitmap from the `CBDT` table, in the strike with the most pixels per em
+fn color_bitmap<'a>(
+    cblc: &[u8],
+    cbdt: &'a [u8],
+    glyph_id: GlyphId,
+) -> Result<Option<GlyphBitmap<'a>>, FontError> {
+    let mut header = Reader::new(cblc);
+    header.skip(4)?; // version
+    let strike_count = header.u32()?;
+
+    // Bitmap size records are 48 bytes
+    let mut best_strike = None;
+    for index in 0..strike_count {
+        let strike = Reader::at(cblc, 8 + index as usize * 48)?;
+        let mut glyph_range = strike.at_offset(40)?;
+        let (start_glyph, end_glyph) = (glyph_range.u16()?, glyph_range.u16()?);
+        let (ppem_x, ppem_y) = (glyph_range.u8()?, glyph_range.u8()?);
+        let is_better = match best_strike {
+            Some((_, _, best_ppem_y)) => ppem_y > best_ppem_y,
+            None => true,
+        };
+        if (start_glyph..=end_glyph).contains(&glyph_id.0) && is_better {
+            best_strike = Some((strike, ppem_x, ppem_y))
+        }
+    }
+    let (mut strike, ppem_x, ppem_y) = match best_strike {
+        Some(best_strike) => best_strike,
+        None => return Ok(None),
+    };
+    let index_subtables_offset = strike.u32()? as usize;
+    strike.skip(4)?; // index tables size
+    let index_subtable_count = strike.u32()?;
+
+    for index in 0..index_subtable_count {
+        let mut record = Reader::at(cblc, index_subtables_offset + index as usize * 8)?;
+        let (first_glyph, last_glyph) = (record.u16()?, record.u16()?);
+        if !(first_glyph..=last_glyph).contains(&glyph_id.0) {
+            continue;
+        }
+        let mut subtable = Reader::at(cblc, index_subtables_offset + record.u32()? as usize)?;
+        let index_format = subtable.u16()?;
+        let image_format = subtable.u16()?;
+        let image_data_offset = subtable.u32()? as usize;
+        let glyph_index = usize::from(glyph_id.0 - first_glyph);
+
+        // For index formats 2 and 5, all glyphs have the same size and metrics
+        let mut shared_metrics = None;
+        let glyph_offset = match index_format {
+            // Offsets of each glyph and of the end of the last one, 4 or 2 bytes each
+            1 | 3 => {
+                let (start, end) = if index_format == 1 {
+                    let mut offsets = subtable.at_offset(glyph_index * 4)?;
+                    (offsets.u32()? as usize, offsets.u32()? as usize)
+                } else {
+                    let mut offsets = subtable.at_offset(glyph_index * 2)?;
+                    (usize::from(offsets.u16()?), usize::from(offsets.u16()?))
+                };
+                if end <= start {
+                    return Ok(None);
+                }
+                start
+            }
+            2 | 5 => {
+                let image_size = subtable.u32()? as usize;
+                shared_metrics = Some(subtable.take(8)?);
+                if index_format == 5 {
+                    let glyph_count = subtable.u32()?;
+                    let found = binary_search(glyph_count, |index| {
+                        Ok(subtable
+                            .at_offset(index as usize * 2)?
+                            .u16()?
+                            .cmp(&glyph_id.0))
+                    })?;
+                    match found {
+                        Some(index) => image_size * index as usize,
+                        None => return Ok(None),
+                    }
+                } else {
+                    image_size * glyph_index
+                }
+            }
+            4 => {
+                let glyph_count = subtable.u32()?;
+                // Glyph ID and offset pairs, sorted by glyph ID
+                let found = binary_search(glyph_count, |index| {
+                    Ok(subtable
+                        .at_offset(index as usize * 4)?
+                        .u16()?
+                        .cmp(&glyph_id.0))
+                })?;
+                match found {
+                    Some(index) => usize::from(subtable.at_offset(index as usize * 4 + 2)?.u16()?),
+                    None => return Ok(None),
+                }
+            }
+            _ => return Ok(None),
+        };
+
+        let mut data = Reader::at(cbdt, image_data_offset + glyph_offset)?;
+        let metrics = match image_format {
+            // Small glyph metrics
+            17 => data.take(5)?,
+            // Big glyph metrics, with vertical metrics after horizontal ones
+            18 => data.take(8)?,
+            19 => match shared_metrics {
+                Some(metrics) => metrics,
+                None => return Ok(None),
+            },
+            // Uncompressed or monochrome bitmaps are not supported
+            _ => return Ok(None),
+        };
+        let mut metrics = Reader::new(metrics);
+        let height = f32::from(metrics.u8()?);
+        let width = f32::from(metrics.u8()?);
+        let bearing_x = f32::from(metrics.i8()?);
+        let bearing_y = f32::from(metrics.i8()?);
+        let length = data.u32()? as usize;
+        let (ppem_x, ppem_y) = (f32::from(ppem_x), f32::from(ppem_y));
+        return Ok(Some(GlyphBitmap {
+            png: data.take(length)?,
+            bounds: Rect::new(
+                point(bearing_x / ppem_x, (bearing_y - height) / ppem_y),
+                Size::new(width / ppem_x, height / ppem_y),
+            ),
+        }));
+    }
+    Ok(None)
+}
+
+/// A PNG bitmap from the `sbix` table, in the strike with the most pixels per em
+fn standard_bitmap(
+    sbix: &[u8],
+    glyph_count: u16,
+    glyph_id: GlyphId,
+    depth: u32,
+) -> Result<Option<GlyphBitmap>, FontError> {
+    if glyph_id.0 >= glyph_count {
+        return Ok(None);
+    }
+    let mut header = Reader::new(sbix);
+    header.skip(4)?; // version and flags
+    let strike_count = header.u32()?;
+
+    // The strike with the most pixels per em that has data for this glyph
+    let mut best = None;
+    for _ in 0..strike_count {
+        let strike_offset = header.u32()? as usize;
+        let mut strike = Reader::at(sbix, strike_offset)?;
+        let ppem = strike.u16()?;
+        strike.skip(2)?; // pixels per inch
+        let mut offsets = strike.at_offset(usize::from(glyph_id.0) * 4)?;
+        let start = offsets.u32()? as usize;
+        let end = offsets.u32()? as usize;
+        let is_better = match best {
+            Some((best_ppem, _, _)) => ppem > best_ppem,
+            None => true,
+        };
+        if end > start && is_better {
+            best = Some((ppem, strike_offset + start, end - start))
+        }
+    }
+    let (ppem, data_offset, length) = match best {
+        Some(best) => best,
+        None => return Ok(None),
+    };
+    let mut data = Reader::new(Reader::at(sbix, data_offset)?.take(length)?);
+    let origin_x = f32::from(data.i16()?);
+    let origin_y = f32::from(data.i16()?);
+    let graphic_type = data.take(4)?;
+    let rest = data.take(length.saturating_sub(8))?;
+    match graphic_type {
+        b"png " => {}
+        b"dupe" if depth == 0 => {
+            let original = GlyphId(Reader::new(rest).u16()?);
+            return standard_bitmap(sbix, glyph_count, original, depth + 1);
+        }
+        // JPEG, TIFF, and masks are not supported
+        _ => return Ok(None),
+    }
+    let (width, height) = match png_size(rest) {
+        Some(size) => size,
+        None => return Ok(None),
+    };
+    let ppem = f32::from(ppem);
+    Ok(Some(GlyphBitmap {
+        png: rest,
+        bounds: Rect::new(
+            point(origin_x / ppem, origin_y / ppem),
+            Size::new(width as f32 / ppem, height as f32 / ppem),
+        ),
+    }))
+}
+
+/// The width and height from the header of a PNG image
+fn png_size(png: &[u8]) -> Option<(u32, u32)> {
+    // The 8-byte signature, then the length and type of the `IHDR` chunk
+    let mut header = Reader::at(png, 16).ok()?;
+    if png.get(12..16) != Some(b"IHDR") {
+        return None;
+    }
+    Some((header.u32().ok()?, header.u32().ok()?))
+}
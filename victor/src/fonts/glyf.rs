@@ -0,0 +1,351 @@
+//! https://docs.microsoft.com/en-us/typography/opentype/spec/glyf
+
+use crate::fonts::parsing::Reader;
+use crate::fonts::FontError;
+
+// Simple glyph flags
+const ON_CURVE_POINT: u8 = 0x01;
+const X_SHORT_VECTOR: u8 = 0x02;
+const Y_SHORT_VECTOR: u8 = 0x04;
+const REPEAT_FLAG: u8 = 0x08;
+const X_IS_SAME_OR_POSITIVE_X_SHORT_VECTOR: u8 = 0x10;
+const Y_IS_SAME_OR_POSITIVE_Y_SHORT_VECTOR: u8 = 0x20;
+const OVERLAP_SIMPLE: u8 = 0x40;
+
+// Composite glyph flags
+const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+pub(in crate::fonts) const ARGS_ARE_XY_VALUES: u16 = 0x0002;
+const WE_HAVE_A_SCALE: u16 = 0x0008;
+const MORE_COMPONENTS: u16 = 0x0020;
+const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+const WE_HAVE_INSTRUCTIONS: u16 = 0x0100;
+
+/// The data for one glyph in the `glyf` table, as located by the `loca` table
+pub(in crate::fonts) fn glyph_data<'a>(
+    glyf: &'a [u8],
+    loca: &[u8],
+    long_loca: bool,
+    glyph_id: u16,
+) -> Result<&'a [u8], FontError> {
+    let location = |index: usize| -> Result<usize, FontError> {
+        Ok(if long_loca {
+            Reader::at(loca, index * 4)?.u32()? as usize
+        } else {
+            Reader::at(loca, index * 2)?.u16()? as usize * 2
+        })
+    };
+    let index = usize::from(glyph_id);
+    glyf.get(location(index)?..location(index + 1)?)
+        .ok_or(FontError::OffsetPlusLengthBeyondEof)
+}
+
+#[derive(Clone, Copy)]
+pub(in crate::fonts) struct Point {
+    pub x: i16,
+    pub y: i16,
+    pub on_curve: bool,
+}
+
+/// The data for one glyph in the `glyf` table
+pub(in crate::fonts) enum Glyph<'a> {
+    /// No outline, for example for a space
+    Empty,
+    Simple(SimpleGlyph<'a>),
+    Composite(CompositeGlyph<'a>),
+}
+
+pub(in crate::fonts) struct SimpleGlyph<'a> {
+    /// The index of the last point of each contour
+    pub end_points: Vec<u16>,
+    pub instructions: &'a [u8],
+    pub points: Vec<Point>,
+    pub overlap: bool,
+}
+
+pub(in crate::fonts) struct CompositeGlyph<'a> {
+    pub components: Vec<Component<'a>>,
+    pub instructions: Option<&'a [u8]>,
+}
+
+pub(in crate::fonts) struct Component<'a> {
+    pub flags: u16,
+    pub glyph_id: u16,
+
+    /// An offset if `flags` contains `ARGS_ARE_XY_VALUES`, otherwise point numbers to match
+    pub arguments: (i32, i32),
+
+    /// A scale, two scales, or a 2×2 matrix of F2Dot14 numbers, depending on `flags`
+    pub transform: &'a [u8],
+}
+
+impl<'a> Glyph<'a> {
+    pub(in crate::fonts) fn parse(data: &'a [u8]) -> Result<Self, FontError> {
+        if data.is_empty() {
+            return Ok(Glyph::Empty);
+        }
+        let mut reader = Reader::new(data);
+        let contour_count = reader.i16()?;
+        // The bounding box is recomputed when writing
+        reader.skip(8)?;
+        if contour_count >= 0 {
+            SimpleGlyph::parse(contour_count as u16, &mut reader).map(Glyph::Simple)
+        } else {
+            CompositeGlyph::parse(&mut reader).map(Glyph::Composite)
+        }
+    }
+
+    /// Write this glyph with the given bounding box, as `[x_min, y_min, x_max, y_max]`.
+    pub(in crate::fonts) fn write(&self, bbox: [i16; 4], glyf: &mut Vec<u8>) {
+        let contour_count = match self {
+            Glyph::Empty => return,
+            Glyph::Simple(glyph) => glyph.end_points.len() as i16,
+            Glyph::Composite(_) => -1,
+        };
+        glyf.extend_from_slice(&contour_count.to_be_bytes());
+        for &value in &bbox {
+            glyf.extend_from_slice(&value.to_be_bytes());
+        }
+        match self {
+            Glyph::Empty => {}
+            Glyph::Simple(glyph) => glyph.write_after_header(glyf),
+            Glyph::Composite(glyph) => glyph.write_after_header(glyf),
+        }
+    }
+}
+
+impl<'a> SimpleGlyph<'a> {
+    fn parse(contour_count: u16, reader: &mut Reader<'a>) -> Result<Self, FontError> {
+        let mut end_points = Vec::with_capacity(contour_count as usize);
+        for _ in 0..contour_count {
+            end_points.push(reader.u16()?)
+        }
+        let point_count = end_points.last().map_or(0, |&last| last as usize + 1);
+        let instruction_length = reader.u16()?;
+        let instructions = reader.take(instruction_length as usize)?;
+
+        let mut flags = Vec::with_capacity(point_count);
+        while flags.len() < point_count {
+            let flag = reader.u8()?;
+            flags.push(flag);
+            if flag & REPEAT_FLAG != 0 {
+                for _ in 0..reader.u8()? {
+                    flags.push(flag)
+                }
+            }
+        }
+        flags.truncate(point_count);
+
+        fn decode(
+            flags: &[u8],
+            short: u8,
+            same_or_positive: u8,
+            reader: &mut Reader,
+        ) -> Result<Vec<i16>, FontError> {
+            let mut value: i16 = 0;
+            flags
+                .iter()
+                .map(|&flag| {
+                    let delta = if flag & short != 0 {
+                        let delta = i16::from(reader.u8()?);
+                        if flag & same_or_positive != 0 {
+                            delta
+                        } else {
+                            -delta
+                        }
+                    } else if flag & same_or_positive != 0 {
+                        0
+                    } else {
+                        reader.i16()?
+                    };
+                    value = value.wrapping_add(delta);
+                    Ok(value)
+                })
+                .collect()
+        }
+        let xs = decode(
+            &flags,
+            X_SHORT_VECTOR,
+            X_IS_SAME_OR_POSITIVE_X_SHORT_VECTOR,
+            reader,
+        )?;
+        let ys = decode(
+            &flags,
+            Y_SHORT_VECTOR,
+            Y_IS_SAME_OR_POSITIVE_Y_SHORT_VECTOR,
+            reader,
+        )?;
+        let points = flags
+            .iter()
+            .zip(xs.into_iter().zip(ys))
+            .map(|(&flag, (x, y))| Point {
+                x,
+                y,
+                on_curve: flag & ON_CURVE_POINT != 0,
+            })
+            .collect();
+        Ok(SimpleGlyph {
+            end_points,
+            instructions,
+            points,
+            overlap: flags
+                .first()
+                .map_or(false, |&flag| flag & OVERLAP_SIMPLE != 0),
+        })
+    }
+
+    fn write_after_header(&self, glyf: &mut Vec<u8>) {
+        for end_point in &self.end_points {
+            glyf.extend_from_slice(&end_point.to_be_bytes());
+        }
+        glyf.extend_from_slice(&(self.instructions.len() as u16).to_be_bytes());
+        glyf.extend_from_slice(self.instructions);
+        encode_simple_glyph_points(&self.points, self.overlap, glyf)
+    }
+}
+
+impl<'a> CompositeGlyph<'a> {
+    fn parse(reader: &mut Reader<'a>) -> Result<Self, FontError> {
+        let mut components = Vec::new();
+        let mut have_instructions = false;
+        loop {
+            let flags = reader.u16()?;
+            let glyph_id = reader.u16()?;
+            let xy_values = flags & ARGS_ARE_XY_VALUES != 0;
+            let arguments = match (flags & ARG_1_AND_2_ARE_WORDS != 0, xy_values) {
+                (true, true) => (i32::from(reader.i16()?), i32::from(reader.i16()?)),
+                (true, false) => (i32::from(reader.u16()?), i32::from(reader.u16()?)),
+                (false, true) => (i32::from(reader.i8()?), i32::from(reader.i8()?)),
+                (false, false) => (i32::from(reader.u8()?), i32::from(reader.u8()?)),
+            };
+            let transform_length = if flags & WE_HAVE_A_SCALE != 0 {
+                2
+            } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+                4
+            } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+                8
+            } else {
+                0
+            };
+            components.push(Component {
+                flags,
+                glyph_id,
+                arguments,
+                transform: reader.take(transform_length)?,
+            });
+            have_instructions |= flags & WE_HAVE_INSTRUCTIONS != 0;
+            if flags & MORE_COMPONENTS == 0 {
+                break;
+            }
+        }
+        let instructions = if have_instructions {
+            let length = reader.u16()?;
+            Some(reader.take(length as usize)?)
+        } else {
+            None
+        };
+        Ok(CompositeGlyph {
+            components,
+            instructions,
+        })
+    }
+
+    fn write_after_header(&self, glyf: &mut Vec<u8>) {
+        for (i, component) in self.components.iter().enumerate() {
+            // Always use words, since variations may have moved the offsets out of `i8` range
+            let mut flags = (component.flags | ARG_1_AND_2_ARE_WORDS) & !MORE_COMPONENTS;
+            if i + 1 < self.components.len() {
+                flags |= MORE_COMPONENTS
+            }
+            glyf.extend_from_slice(&flags.to_be_bytes());
+            glyf.extend_from_slice(&component.glyph_id.to_be_bytes());
+            let (arg1, arg2) = component.arguments;
+            glyf.extend_from_slice(&(arg1 as i16).to_be_bytes());
+            glyf.extend_from_slice(&(arg2 as i16).to_be_bytes());
+            glyf.extend_from_slice(component.transform);
+        }
+        if let Some(instructions) = self.instructions {
+            glyf.extend_from_slice(&(instructions.len() as u16).to_be_bytes());
+            glyf.extend_from_slice(instructions);
+        }
+    }
+}
+
+impl<'a> Component<'a> {
+    /// The 2×2 transformation matrix `[xx, xy, yx, yy]`, applied before the offset
+    pub(in crate::fonts) fn matrix(&self) -> [f32; 4] {
+        let f2dot14 = |i: usize| {
+            f32::from(i16::from_be_bytes([
+                self.transform[i * 2],
+                self.transform[i * 2 + 1],
+            ])) / 16384.
+        };
+        match self.transform.len() {
+            2 => [f2dot14(0), 0., 0., f2dot14(0)],
+            4 => [f2dot14(0), 0., 0., f2dot14(1)],
+            8 => [f2dot14(0), f2dot14(1), f2dot14(2), f2dot14(3)],
+            _ => [1., 0., 0., 1.],
+        }
+    }
+}
+
+/// https://docs.microsoft.com/en-us/typography/opentype/spec/glyf#simple-glyph-description
+pub(in crate::fonts) fn encode_simple_glyph_points(
+    points: &[Point],
+    overlap: bool,
+    glyf: &mut Vec<u8>,
+) {
+    fn encode(
+        delta: i32,
+        short: u8,
+        same_or_positive: u8,
+        flag: &mut u8,
+        coordinates: &mut Vec<u8>,
+    ) {
+        if delta == 0 {
+            *flag |= same_or_positive
+        } else if -256 < delta && delta < 256 {
+            *flag |= short;
+            if delta > 0 {
+                *flag |= same_or_positive;
+                coordinates.push(delta as u8)
+            } else {
+                coordinates.push(-delta as u8)
+            }
+        } else {
+            coordinates.extend_from_slice(&(delta as i16).to_be_bytes())
+        }
+    }
+
+    let mut flags = Vec::with_capacity(points.len());
+    let mut x_coordinates = Vec::new();
+    let mut y_coordinates = Vec::new();
+    let (mut previous_x, mut previous_y) = (0_i32, 0_i32);
+    for (i, point) in points.iter().enumerate() {
+        let mut flag = if point.on_curve { ON_CURVE_POINT } else { 0 };
+        if overlap && i == 0 {
+            flag |= OVERLAP_SIMPLE
+        }
+        let (x, y) = (i32::from(point.x), i32::from(point.y));
+        encode(
+            x - previous_x,
+            X_SHORT_VECTOR,
+            X_IS_SAME_OR_POSITIVE_X_SHORT_VECTOR,
+            &mut flag,
+            &mut x_coordinates,
+        );
+        encode(
+            y - previous_y,
+            Y_SHORT_VECTOR,
+            Y_IS_SAME_OR_POSITIVE_Y_SHORT_VECTOR,
+            &mut flag,
+            &mut y_coordinates,
+        );
+        flags.push(flag);
+        previous_x = x;
+        previous_y = y;
+    }
+    glyf.extend_from_slice(&flags);
+    glyf.extend_from_slice(&x_coordinates);
+    glyf.extend_from_slice(&y_coordinates);
+}
@@ -0,0 +1,158 @@
+//! https://docs.microsoft.com/en-us/typography/opentype/spec/gsub
+
+use crate::fonts::parsing::{Position, Slice};
+use crate::fonts::tables::*;
+use crate::fonts::types::Tag;
+use crate::fonts::{FontError, GlyphId};
+
+/// Collect the single substitutions of the lookups for a given feature, in any script.
+///
+/// The result is sorted by input glyph.
+/// Other lookup types are ignored.
+pub(in crate::fonts) fn single_substitutions(
+    bytes: &[u8],
+    table_directory: Slice<TableDirectoryEntry>,
+    feature: Tag,
+) -> Result<Vec<(GlyphId, GlyphId)>, FontError> {
+    let header = match table_directory.find_table::<GlyphSubstitutionHeader>(bytes) {
+        Ok(header) => header,
+        Err(FontError::MissingTable) => return Ok(Vec::new()),
+        Err(error) => return Err(error),
+    };
+    let feature_list: Position<FeatureListHeader> =
+        header.offset_bytes(header.feature_list_offset().read_from(bytes)?);
+    let lookup_list: Position<LookupListHeader> =
+        header.offset_bytes(header.lookup_list_offset().read_from(bytes)?);
+    let feature_records = Slice::new(
+        feature_list.followed_by::<FeatureRecord>(),
+        feature_list.feature_count().read_from(bytes)?,
+    );
+    let lookup_offsets = Slice::new(
+        lookup_list.followed_by::<u16>(),
+        lookup_list.lookup_count().read_from(bytes)?,
+    );
+
+    let mut lookup_indices = Vec::new();
+    for record in feature_records {
+        if record.feature_tag().read_from(bytes)? != feature {
+            continue;
+        }
+        let feature_table: Position<FeatureTableHeader> =
+            feature_list.offset_bytes(record.feature_offset().read_from(bytes)?);
+        let indices = Slice::new(
+            feature_table.followed_by::<u16>(),
+            feature_table.lookup_index_count().read_from(bytes)?,
+        );
+        for index in indices {
+            lookup_indices.push(u32::from(index.read_from(bytes)?))
+        }
+    }
+    // The same lookups are typically listed for each script
+    lookup_indices.sort();
+    lookup_indices.dedup();
+
+    let mut substitutions = Vec::new();
+    for index in lookup_indices {
+        if index >= lookup_offsets.count() {
+            continue;
+        }
+        let lookup: Position<LookupTableHeader> =
+            lookup_list.offset_bytes(lookup_offsets.get_unchecked(index).read_from(bytes)?);
+        let subtable_offsets = Slice::new(
+            lookup.followed_by::<u16>(),
+            lookup.sub_table_count().read_from(bytes)?,
+        );
+        for subtable_offset in subtable_offsets {
+            let mut lookup_type = lookup.lookup_type().read_from(bytes)?;
+            let mut subtable: Position<()> = lookup.offset_bytes(subtable_offset.read_from(bytes)?);
+            const SINGLE: u16 = 1;
+            const EXTENSION: u16 = 7;
+            if lookup_type == EXTENSION {
+                let extension = subtable.cast::<ExtensionSubstitution>();
+                lookup_type = extension.extension_lookup_type().read_from(bytes)?;
+                subtable = subtable.offset_bytes(extension.extension_offset().read_from(bytes)?);
+            }
+            if lookup_type == SINGLE {
+                single_substitution(bytes, subtable, &mut substitutions)?
+            }
+        }
+    }
+    // Stable sort: when a glyph is covered more than once, the first lookup wins
+    substitutions.sort_by_key(|&(from, _)| from);
+    substitutions.dedup_by_key(|&mut (from, _)| from);
+    Ok(substitutions)
+}
+
+fn single_substitution(
+    bytes: &[u8],
+    subtable: Position<()>,
+    substitutions: &mut Vec<(GlyphId, GlyphId)>,
+) -> Result<(), FontError> {
+    match subtable.cast::<u16>().read_from(bytes)? {
+        1 => {
+            let subtable = subtable.cast::<SingleSubstitutionFormat1>();
+            let delta = subtable.delta_glyph_id().read_from(bytes)?;
+            let coverage = subtable.offset_bytes(subtable.coverage_offset().read_from(bytes)?);
+            each_covered_glyph(bytes, coverage, |glyph, _| {
+                // “Addition of deltaGlyphID is modulo 65536.”
+                substitutions.push((GlyphId(glyph), GlyphId(glyph.wrapping_add(delta as u16))));
+                Ok(())
+            })
+        }
+        2 => {
+            let subtable = subtable.cast::<SingleSubstitutionFormat2>();
+            let substitutes = Slice::new(
+                subtable.followed_by::<u16>(),
+                subtable.glyph_count().read_from(bytes)?,
+            );
+            let coverage = subtable.offset_bytes(subtable.coverage_offset().read_from(bytes)?);
+            each_covered_glyph(bytes, coverage, |glyph, coverage_index| {
+                if coverage_index < substitutes.count() {
+                    let substitute = substitutes.get_unchecked(coverage_index).read_from(bytes)?;
+                    substitutions.push((GlyphId(glyph), GlyphId(substitute)))
+                }
+                Ok(())
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// https://docs.microsoft.com/en-us/typography/opentype/spec/chapter2#coverage-table
+fn each_covered_glyph<F>(bytes: &[u8], coverage: Position<()>, mut f: F) -> Result<(), FontError>
+where
+    F: FnMut(u16, u32) -> Result<(), FontError>,
+{
+    match coverage.cast::<u16>().read_from(bytes)? {
+        1 => {
+            let coverage = coverage.cast::<CoverageFormat1>();
+            let glyphs = Slice::new(
+                coverage.followed_by::<u16>(),
+                coverage.glyph_count().read_from(bytes)?,
+            );
+            for (coverage_index, glyph) in glyphs.into_iter().enumerate() {
+                f(glyph.read_from(bytes)?, coverage_index as u32)?
+            }
+        }
+        2 => {
+            let coverage = coverage.cast::<CoverageFormat2>();
+            let ranges = Slice::new(
+                coverage.followed_by::<CoverageRangeRecord>(),
+                coverage.range_count().read_from(bytes)?,
+            );
+            for range in ranges {
+                let start = range.start_glyph_id().read_from(bytes)?;
+                let end = range.end_glyph_id().read_from(bytes)?;
+                let start_coverage_index = range.start_coverage_index().read_from(bytes)?;
+                for glyph in start..=end {
+                    f(
+                        glyph,
+                        u32::from(start_coverage_index) + u32::from(glyph - start),
+                    )?
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
@@ -1,28 +1,42 @@
+mod cff;
 mod cmap;
+mod color;
+mod glyf;
+mod gsub;
+mod outlines;
 mod parsing;
 mod tables;
 mod types;
+mod variations;
+mod woff;
+mod woff2;
 
-use crate::fonts::cmap::Cmap;
+pub(crate) use self::color::{ColorGlyph, GlyphBitmap};
+pub use self::outlines::OutlineSegment;
+pub use self::variations::{NamedInstance, VariationAxis};
+
+use crate::fonts::cmap::{Cmap, VariationGlyph};
+use crate::fonts::color::ColorFormat;
 use crate::fonts::parsing::*;
 use crate::fonts::tables::*;
 use crate::fonts::types::Tag;
+use crate::fonts::variations::Variations;
 use std::borrow::Cow;
 use std::cmp;
 use std::sync::Arc;
 
 /// The EM square unit
-pub(crate) struct Em;
+pub struct Em;
 
 /// The unit of FWord and UFWord
 struct FontDesignUnit;
 
-#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
-pub(crate) struct GlyphId(pub(crate) u16);
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct GlyphId(pub u16);
 
 #[derive(Debug)]
 pub enum FontError {
-    /// Victor only supports TrueType fonts at the moment.
+    /// Victor only supports TrueType fonts and OpenType fonts with CFF outlines at the moment.
     UnsupportedFormat,
 
     /// The font file contains an offset to beyond the end of the file.
@@ -43,15 +57,77 @@ pub enum FontError {
 
     /// This font doesn’t have any horizontal metrics for glyphs.
     NoHorizontalGlyphMetrics,
+
+    /// The compressed or transformed data in this WOFF or WOFF2 font is invalid.
+    InvalidWebFont,
+
+    /// The requested face index is beyond the number of faces in this font collection.
+    NoSuchFace,
+
+    /// The variation tables (`fvar`, `avar`, `gvar`, `HVAR`) of this font are inconsistent.
+    InvalidVariationData,
+
+    /// The `CFF ` table of this font is invalid or uses unsupported features.
+    InvalidCompactFontFormat,
+
+    /// The license of this font does not allow embedding it in a document.
+    EmbeddingRestricted,
+}
+
+/// The embedding licensing rights of a font, from the `fsType` field of the `OS/2` table.
+///
+/// https://docs.microsoft.com/en-us/typography/opentype/spec/os2#fstype
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum EmbeddingPermissions {
+    /// The font may be embedded and permanently installed on the remote system.
+    Installable,
+
+    /// The font must not be embedded.
+    Restricted,
+
+    /// The font may be embedded in documents that are only viewed or printed.
+    PreviewAndPrint,
+
+    /// The font may be embedded in documents that can be edited.
+    Editable,
+}
+
+/// How glyph outlines are stored, which determines how the font is embedded in PDF.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum OutlineFormat {
+    /// A `glyf` table, in a font with the `true` or `0x00010000` scaler type.
+    TrueType,
+
+    /// A `CFF ` table, in a font with the `OTTO` scaler type.
+    CompactFontFormat,
 }
 
 pub struct Font {
-    bytes: Cow<'static, [u8]>,
+    /// Shared between the faces of a collection
+    bytes: Arc<Cow<'static, [u8]>>,
+
+    /// Not at the start of `bytes` for a face of a collection
+    offset_table: Position<OffsetSubtable>,
+
+    outline_format: OutlineFormat,
     cmap: Cmap,
     postscript_name: String,
+    family_name: Option<String>,
+    subfamily_name: Option<String>,
     glyph_count: u16,
     font_design_units_per_em: euclid::TypedScale<f32, Em, FontDesignUnit>,
     horizontal_metrics: Slice<LongHorizontalMetricsRecord>,
+    vertical_metrics: Option<Slice<LongVerticalMetricsRecord>>,
+
+    /// For a variable font, from the `fvar` and `avar` tables
+    variations: Option<Box<Variations>>,
+
+    /// From the `vert` OpenType feature, sorted
+    vertical_substitutions: Vec<(GlyphId, GlyphId)>,
+
+    /// From the `smcp` and `c2sc` OpenType features, sorted
+    small_caps_substitutions: Vec<(GlyphId, GlyphId)>,
+    capitals_to_small_caps_substitutions: Vec<(GlyphId, GlyphId)>,
 
     /// Distance from baseline of highest ascender
     ascender: euclid::Length<i16, FontDesignUnit>,
@@ -59,6 +135,36 @@ pub struct Font {
     /// Distance from baseline of lowest descender
     descender: euclid::Length<i16, FontDesignUnit>,
 
+    /// Recommended additional space between lines
+    line_gap: euclid::Length<i16, FontDesignUnit>,
+
+    /// Height of lowercase letters such as “x”, and of uppercase letters
+    x_height: euclid::Length<i16, FontDesignUnit>,
+    cap_height: euclid::Length<i16, FontDesignUnit>,
+
+    /// Recommended distance of subscripts below the baseline, and of superscripts above it
+    subscript_offset: euclid::Length<i16, FontDesignUnit>,
+    superscript_offset: euclid::Length<i16, FontDesignUnit>,
+
+    /// Distance from baseline of the top of the underline, negative below the baseline
+    underline_position: euclid::Length<i16, FontDesignUnit>,
+    underline_thickness: euclid::Length<i16, FontDesignUnit>,
+
+    /// Distance from baseline of the top of the strikeout stroke
+    strikeout_position: euclid::Length<i16, FontDesignUnit>,
+    strikeout_thickness: euclid::Length<i16, FontDesignUnit>,
+
+    /// From 1 (thin) to 1000 (black), 400 is normal
+    weight_class: u16,
+
+    /// From 1 (ultra-condensed) to 9 (ultra-expanded), 5 is normal
+    width_class: u16,
+
+    embedding_permissions: EmbeddingPermissions,
+
+    /// Which tables colour glyphs are taken from, if any
+    color_format: Option<ColorFormat>,
+
     /// The bounding box of the union of all glyphs
     min_x: euclid::Length<i16, FontDesignUnit>,
     min_y: euclid::Length<i16, FontDesignUnit>,
@@ -66,46 +172,199 @@ pub struct Font {
     max_y: euclid::Length<i16, FontDesignUnit>,
 }
 
+/// Look up a glyph in sorted substitutions
+fn substitute(substitutions: &[(GlyphId, GlyphId)], glyph_id: GlyphId) -> Option<GlyphId> {
+    substitutions
+        .binary_search_by_key(&glyph_id, |&(from, _)| from)
+        .ok()
+        .map(|index| substitutions[index].1)
+}
+
 #[cfg(target_pointer_width = "64")]
 fn _assert_size_of() {
-    let _ = std::mem::transmute::<Cmap, [u8; 24]>;
-    let _ = std::mem::transmute::<Font, [u8; 112]>;
+    let _ = std::mem::transmute::<Cmap, [u8; 40]>;
+    let _ = std::mem::transmute::<Font, [u8; 272]>;
 }
 
 impl Font {
+    /// Parse a TrueType or OpenType font, possibly compressed as WOFF or WOFF2.
+    ///
+    /// For a collection, this returns the first face.
     pub fn parse<B: Into<Cow<'static, [u8]>>>(bytes: B) -> Result<Arc<Self>, FontError> {
-        Self::parse_cow(bytes.into())
+        Self::parse_with_index(bytes, 0)
     }
 
-    fn parse_cow(bytes: Cow<'static, [u8]>) -> Result<Arc<Self>, FontError> {
-        let mut font = Self::parse_without_cow_bytes_field(&bytes)?;
+    /// Parse the face at `index` in a collection (`.ttc`).
+    ///
+    /// For a single font, only index 0 is valid.
+    pub fn parse_with_index<B: Into<Cow<'static, [u8]>>>(
+        bytes: B,
+        index: u32,
+    ) -> Result<Arc<Self>, FontError> {
+        let bytes = Arc::new(decompress(bytes.into())?);
+        let offset_table = face_offset_tables(&bytes)?
+            .into_iter()
+            .nth(index as usize)
+            .ok_or(FontError::NoSuchFace)?;
+        Self::parse_face(bytes, offset_table).map(Arc::new)
+    }
+
+    /// Parse every face of a collection (`.ttc`), or the single face of another font file.
+    pub fn parse_collection<B: Into<Cow<'static, [u8]>>>(
+        bytes: B,
+    ) -> Result<Vec<Arc<Self>>, FontError> {
+        let bytes = Arc::new(decompress(bytes.into())?);
+        face_offset_tables(&bytes)?
+            .into_iter()
+            .map(|offset_table| Self::parse_face(bytes.clone(), offset_table).map(Arc::new))
+            .collect()
+    }
+
+    fn parse_face(
+        bytes: Arc<Cow<'static, [u8]>>,
+        offset_table: Position<OffsetSubtable>,
+    ) -> Result<Self, FontError> {
+        let mut font = Self::parse_without_cow_bytes_field(&bytes, offset_table)?;
         font.bytes = bytes;
-        Ok(Arc::new(font))
+        Ok(font)
     }
 
     #[inline]
-    fn parse_without_cow_bytes_field(bytes: &[u8]) -> Result<Self, FontError> {
-        let bytes: &[u8] = &*bytes;
-        let offset_table = Position::<OffsetSubtable>::initial();
+    fn parse_without_cow_bytes_field(
+        bytes: &[u8],
+        offset_table: Position<OffsetSubtable>,
+    ) -> Result<Self, FontError> {
         let scaler_type = offset_table.scaler_type().read_from(bytes)?;
         const TRUETYPE: u32 = 0x74727565; // "true" in big-endian
-        if scaler_type != TRUETYPE && scaler_type != 0x_0001_0000 {
-            Err(FontError::UnsupportedFormat)?
-        }
+        const OPENTYPE_CFF: u32 = 0x4F54544F; // "OTTO" in big-endian
+        let outline_format = match scaler_type {
+            TRUETYPE | 0x_0001_0000 => OutlineFormat::TrueType,
+            OPENTYPE_CFF => OutlineFormat::CompactFontFormat,
+            _ => Err(FontError::UnsupportedFormat)?,
+        };
         let table_directory = Slice::new(
             offset_table.followed_by::<TableDirectoryEntry>(),
             offset_table.table_count().read_from(bytes)?,
         );
+        if outline_format == OutlineFormat::CompactFontFormat {
+            // Outlines are only parsed when needed, but a font without them should fail here
+            // rather than when computing glyph bounds or when a PDF reader renders it.
+            table_directory.find_table::<CompactFontFormatHeader>(bytes)?;
+        }
 
         let maxp = table_directory.find_table::<MaximumProfile>(bytes)?;
         let header = table_directory.find_table::<FontHeader>(bytes)?;
         let glyph_count = maxp.num_glyphs().read_from(bytes)?;
         let horizontal_header = table_directory.find_table::<HorizontalHeader>(bytes)?;
+        let vertical_metrics = match table_directory.find_table::<VerticalHeader>(bytes) {
+            Ok(vertical_header) => Some(Slice::new(
+                table_directory.find_table::<LongVerticalMetricsRecord>(bytes)?,
+                vertical_header
+                    .number_of_long_vertical_metrics()
+                    .read_from(bytes)?,
+            )),
+            Err(FontError::MissingTable) => None,
+            Err(error) => return Err(error),
+        };
+        let os2 = match table_directory.find_table::<OS2>(bytes) {
+            Ok(os2) => Some(os2),
+            Err(FontError::MissingTable) => None,
+            Err(error) => return Err(error),
+        };
+        let post = match table_directory.find_table::<PostScriptHeader>(bytes) {
+            Ok(post) => Some(post),
+            Err(FontError::MissingTable) => None,
+            Err(error) => return Err(error),
+        };
+
+        let cmap = Cmap::parse(bytes, table_directory)?;
+        let max_y = header.max_y().read_from(bytes)?;
+        let mut ascender = horizontal_header.ascender().read_from(bytes)?;
+        let mut descender = horizontal_header.descender().read_from(bytes)?;
+        let mut line_gap = horizontal_header.line_gap().read_from(bytes)?;
+        let mut x_height = None;
+        let mut cap_height = None;
+        let mut subscript_offset = None;
+        let mut superscript_offset = None;
+        let mut strikeout = None;
+        let mut weight_class = 400;
+        let mut width_class = 5;
+        let mut embedding_permissions = EmbeddingPermissions::Installable;
+        if let Some(os2) = os2 {
+            const USE_TYPO_METRICS: u16 = 1 << 7;
+            if os2.fs_selection().read_from(bytes)? & USE_TYPO_METRICS != 0 {
+                ascender = os2.typo_ascender().read_from(bytes)?;
+                descender = os2.typo_descender().read_from(bytes)?;
+                line_gap = os2.typo_line_gap().read_from(bytes)?;
+            }
+            if os2.version().read_from(bytes)? >= 2 {
+                x_height = Some(os2.x_height().read_from(bytes)?);
+                cap_height = Some(os2.cap_height().read_from(bytes)?);
+            }
+            // The sign is not used consistently by fonts, the direction is implied
+            let abs = |offset: euclid::Length<i16, FontDesignUnit>| {
+                euclid::Length::new(offset.get().abs())
+            };
+            subscript_offset = Some(abs(os2.subscript_y_offset().read_from(bytes)?));
+            superscript_offset = Some(abs(os2.superscript_y_offset().read_from(bytes)?));
+            strikeout = Some((
+                os2.strikeout_position().read_from(bytes)?,
+                os2.strikeout_size().read_from(bytes)?,
+            ));
+            weight_class = os2.weight_class().read_from(bytes)?;
+            width_class = os2.width_class().read_from(bytes)?;
+            embedding_permissions =
+                EmbeddingPermissions::from_fs_type(os2.fs_type().read_from(bytes)?);
+        }
+        // Fonts without an `OS/2` table or with an old version of it:
+        // measure glyphs like FreeType does, or make a guess.
+        let glyph_top = |ch: char| match outline_format {
+            OutlineFormat::TrueType => glyph_top(bytes, table_directory, header, &cmap, ch),
+            OutlineFormat::CompactFontFormat => Ok(None),
+        };
+        let x_height = match x_height {
+            Some(x_height) => x_height,
+            None => glyph_top('x')?.unwrap_or(ascender / 2),
+        };
+        let cap_height = match cap_height {
+            Some(cap_height) => cap_height,
+            None => glyph_top('H')?.unwrap_or(ascender),
+        };
+        // Without `OS/2`, use the same proportions as common fonts
+        let units_per_em = header.units_per_em().read_from(bytes)?;
+        let subscript_offset =
+            subscript_offset.unwrap_or(euclid::Length::new((units_per_em.get() / 5) as i16));
+        let superscript_offset =
+            superscript_offset.unwrap_or(euclid::Length::new((units_per_em.get() / 3) as i16));
+        let (underline_position, underline_thickness) = match post {
+            Some(post) => (
+                post.underline_position().read_from(bytes)?,
+                post.underline_thickness().read_from(bytes)?,
+            ),
+            None => (
+                euclid::Length::new(-((units_per_em.get() / 10) as i16)),
+                euclid::Length::new((units_per_em.get() / 20) as i16),
+            ),
+        };
+        // Centered on half the x-height, like the dash of common fonts
+        let (strikeout_position, strikeout_thickness) =
+            strikeout.unwrap_or(((x_height + underline_thickness) / 2, underline_thickness));
 
         Ok(Font {
-            bytes: b""[..].into(),
-            postscript_name: read_postscript_name(&bytes, table_directory)?,
-            cmap: Cmap::parse(bytes, table_directory)?,
+            bytes: Arc::new(b""[..].into()),
+            offset_table,
+            outline_format,
+            postscript_name: read_name(bytes, table_directory, POSTSCRIPT_NAME)?
+                .ok_or(FontError::NoSupportedPostscriptName)?,
+            family_name: match read_name(bytes, table_directory, TYPOGRAPHIC_FAMILY_NAME)? {
+                Some(name) => Some(name),
+                None => read_name(bytes, table_directory, FAMILY_NAME)?,
+            },
+            subfamily_name: match read_name(bytes, table_directory, TYPOGRAPHIC_SUBFAMILY_NAME)? {
+                Some(name) => Some(name),
+                None => read_name(bytes, table_directory, SUBFAMILY_NAME)?,
+            },
+            cmap,
             glyph_count,
             horizontal_metrics: Slice::new(
                 table_directory.find_table::<LongHorizontalMetricsRecord>(bytes)?,
@@ -113,19 +372,165 @@ impl Font {
                     .number_of_long_horizontal_metrics()
                     .read_from(bytes)?,
             ),
-            font_design_units_per_em: header.units_per_em().read_from(bytes)?.cast(),
-            ascender: horizontal_header.ascender().read_from(bytes)?,
-            descender: horizontal_header.descender().read_from(bytes)?,
+            vertical_metrics,
+            variations: Variations::parse(bytes, table_directory)?.map(Box::new),
+            vertical_substitutions: gsub::single_substitutions(
+                bytes,
+                table_directory,
+                Tag(*b"vert"),
+            )?,
+            small_caps_substitutions: gsub::single_substitutions(
+                bytes,
+                table_directory,
+                Tag(*b"smcp"),
+            )?,
+            capitals_to_small_caps_substitutions: gsub::single_substitutions(
+                bytes,
+                table_directory,
+                Tag(*b"c2sc"),
+            )?,
+            font_design_units_per_em: units_per_em.cast(),
+            ascender,
+            descender,
+            line_gap,
+            x_height,
+            cap_height,
+            subscript_offset,
+            superscript_offset,
+            underline_position,
+            underline_thickness,
+            strikeout_position,
+            strikeout_thickness,
+            weight_class,
+            width_class,
+            embedding_permissions,
+            color_format: ColorFormat::detect(|tag| {
+                table_directory.find_table_bytes(bytes, tag).is_ok()
+            }),
             min_x: header.min_x().read_from(bytes)?,
             min_y: header.min_y().read_from(bytes)?,
             max_x: header.max_x().read_from(bytes)?,
-            max_y: header.max_y().read_from(bytes)?,
+            max_y,
         })
     }
 
+    /// The data of the font file, which for a face of a collection is the whole collection.
     pub fn bytes(&self) -> &[u8] {
         &self.bytes
     }
+
+    /// The data of a standalone font file for this face.
+    ///
+    /// For a face of a collection, the tables it uses are copied into a new sfnt.
+    pub fn sfnt_bytes(&self) -> Result<Cow<[u8]>, FontError> {
+        if self.offset_table == Position::initial() {
+            return Ok(Cow::Borrowed(&self.bytes));
+        }
+        let scaler_type = self.offset_table.scaler_type().read_from(&self.bytes)?;
+        Ok(Cow::Owned(write_sfnt(scaler_type, 0, self.tables()?)))
+    }
+
+    fn table_directory(&self) -> Result<Slice<TableDirectoryEntry>, FontError> {
+        Ok(Slice::new(
+            self.offset_table.followed_by::<TableDirectoryEntry>(),
+            self.offset_table.table_count().read_from(&self.bytes)?,
+        ))
+    }
+
+    /// The tag and data of each table of this face
+    fn tables(&self) -> Result<Vec<(Tag, &[u8])>, FontError> {
+        let bytes: &[u8] = &self.bytes;
+        let table_directory = self.table_directory()?;
+        let mut tables = Vec::with_capacity(table_directory.count() as usize);
+        for entry in table_directory {
+            let table = Slice::<u8>::new(
+                Position::<OffsetSubtable>::initial()
+                    .offset_bytes(entry.table_offset().read_from(bytes)?),
+                entry.length().read_from(bytes)?,
+            );
+            tables.push((entry.tag().read_from(bytes)?, table.read_from(bytes)?))
+        }
+        Ok(tables)
+    }
+
+    /// The axes of a variable font, or an empty slice for a static font.
+    pub fn variation_axes(&self) -> &[VariationAxis] {
+        match self.variations {
+            Some(ref variations) => &variations.axes,
+            None => &[],
+        }
+    }
+
+    /// The predefined instances of a variable font, such as “Bold” or “Condensed Light”.
+    pub fn named_instances(&self) -> &[NamedInstance] {
+        match self.variations {
+            Some(ref variations) => &variations.named_instances,
+            None => &[],
+        }
+    }
+
+    /// A static font with the outlines and metrics of this variable font
+    /// at the given position in its design space.
+    ///
+    /// Each `(tag, value)` pair sets an axis in user coordinates, for example `(*b"wght", 600.)`.
+    /// Later pairs override earlier ones, values are clamped to the axis’ range,
+    /// unknown axes are ignored, and unspecified axes are at their default value.
+    ///
+    /// This returns the same font for a static font or at the default position.
+    /// Instances are cached, so that a given instance is only embedded once in a PDF file.
+    pub fn instantiate(
+        self: &Arc<Self>,
+        settings: &[([u8; 4], f32)],
+    ) -> Result<Arc<Self>, FontError> {
+        let variations = match self.variations {
+            Some(ref variations) => variations,
+            None => return Ok(self.clone()),
+        };
+        let user_coordinates = variations.user_coordinates(settings);
+        let normalized = variations.normalize(&user_coordinates);
+        if normalized.iter().all(|&value| value == 0) {
+            return Ok(self.clone());
+        }
+        let mut instances = variations.instances.lock().unwrap();
+        if let Some(instance) = instances.get(&normalized) {
+            return Ok(instance.clone());
+        }
+        let bytes = variations::instantiate(self, &normalized)?;
+        let mut instance = Self::parse_face(Arc::new(bytes.into()), Position::initial())?;
+        instance.postscript_name =
+            variations.instance_postscript_name(&self.postscript_name, &user_coordinates);
+        let instance = Arc::new(instance);
+        instances.insert(normalized, instance.clone());
+        Ok(instance)
+    }
+
+    /// The font family name, such as “Bitstream Vera Sans”
+    pub fn family_name(&self) -> Option<&str> {
+        self.family_name.as_deref()
+    }
+
+    /// The style within the family, such as “Bold Oblique”
+    pub fn subfamily_name(&self) -> Option<&str> {
+        self.subfamily_name.as_deref()
+    }
+
+    /// The weight of this face, from 1 to 1000 like the `font-weight` property
+    pub fn weight_class(&self) -> u16 {
+        self.weight_class
+    }
+
+    /// The width of this face, from 1 (ultra-condensed) to 9 (ultra-expanded)
+    pub fn width_class(&self) -> u16 {
+        self.width_class
+    }
+
+    pub fn embedding_permissions(&self) -> EmbeddingPermissions {
+        self.embedding_permissions
+    }
+
+    pub(crate) fn outline_format(&self) -> OutlineFormat {
+        self.outline_format
+    }
     pub(crate) fn postscript_name(&self) -> &str {
         &self.postscript_name
     }
@@ -140,14 +545,32 @@ impl Font {
         self.cmap.each_code_point(&self.bytes, f)
     }
 
-    pub(crate) fn glyph_id(&self, ch: char) -> Result<GlyphId, FontError> {
+    /// The glyph for a character, or the `.notdef` glyph if the font doesn’t have one
+    pub fn glyph_id(&self, ch: char) -> Result<GlyphId, FontError> {
         let ch = ch as u32;
-        let result = match self.cmap {
-            Cmap::Format4(ref table) => table.get(&self.bytes, ch),
-            Cmap::Format12(ref table) => table.get(&self.bytes, ch),
-        };
         const NOTDEF_GLYPH: u16 = 0;
-        Ok(GlyphId(result?.unwrap_or(NOTDEF_GLYPH)))
+        Ok(GlyphId(
+            self.cmap.get(&self.bytes, ch)?.unwrap_or(NOTDEF_GLYPH),
+        ))
+    }
+
+    /// The glyph for `ch` followed by the variation selector `selector`,
+    /// if this font supports that variation sequence
+    pub(crate) fn variation_glyph_id(
+        &self,
+        ch: char,
+        selector: char,
+    ) -> Result<Option<GlyphId>, FontError> {
+        Ok(
+            match self
+                .cmap
+                .get_variation(&self.bytes, ch as u32, selector as u32)?
+            {
+                Some(VariationGlyph::Default) => Some(self.glyph_id(ch)?),
+                Some(VariationGlyph::NonDefault(glyph_id)) => Some(GlyphId(glyph_id)),
+                None => None,
+            },
+        )
     }
 
     pub(crate) fn glyph_width(
@@ -168,6 +591,45 @@ impl Font {
         Ok(self.to_ems(w))
     }
 
+    /// The vertical advance, for vertical writing modes
+    pub(crate) fn glyph_height(
+        &self,
+        glyph_id: GlyphId,
+    ) -> Result<euclid::Length<f32, Em>, FontError> {
+        let vertical_metrics = match self.vertical_metrics {
+            Some(ref metrics) if metrics.count() > 0 => metrics,
+            // Fonts without vertical metrics are typically not designed for vertical text,
+            // use one em per glyph.
+            _ => return Ok(euclid::Length::new(1.)),
+        };
+        let index = cmp::min(glyph_id.0 as u32, vertical_metrics.count() - 1);
+        let h = vertical_metrics
+            .get_unchecked(index)
+            .advance_height()
+            .read_from(&self.bytes)?;
+        Ok(self.to_ems(h))
+    }
+
+    /// The alternate form of a glyph for vertical writing, if any
+    pub(crate) fn vertical_glyph(&self, glyph_id: GlyphId) -> GlyphId {
+        substitute(&self.vertical_substitutions, glyph_id).unwrap_or(glyph_id)
+    }
+
+    /// Whether the font has small capitals for lowercase letters
+    pub(crate) fn has_small_caps(&self) -> bool {
+        !self.small_caps_substitutions.is_empty()
+    }
+
+    /// The small capital form of a lowercase glyph, if any
+    pub(crate) fn small_caps_glyph(&self, glyph_id: GlyphId) -> Option<GlyphId> {
+        substitute(&self.small_caps_substitutions, glyph_id)
+    }
+
+    /// The small capital form of an uppercase glyph, if any
+    pub(crate) fn capital_to_small_caps_glyph(&self, glyph_id: GlyphId) -> Option<GlyphId> {
+        substitute(&self.capitals_to_small_caps_substitutions, glyph_id)
+    }
+
     fn to_ems<T>(&self, length: euclid::Length<T, FontDesignUnit>) -> euclid::Length<f32, Em>
     where
         T: num_traits::NumCast + Clone,
@@ -181,6 +643,33 @@ impl Font {
     pub(crate) fn descender(&self) -> euclid::Length<f32, Em> {
         self.to_ems(self.descender)
     }
+    pub(crate) fn line_gap(&self) -> euclid::Length<f32, Em> {
+        self.to_ems(self.line_gap)
+    }
+    pub(crate) fn x_height(&self) -> euclid::Length<f32, Em> {
+        self.to_ems(self.x_height)
+    }
+    pub(crate) fn cap_height(&self) -> euclid::Length<f32, Em> {
+        self.to_ems(self.cap_height)
+    }
+    pub(crate) fn subscript_offset(&self) -> euclid::Length<f32, Em> {
+        self.to_ems(self.subscript_offset)
+    }
+    pub(crate) fn superscript_offset(&self) -> euclid::Length<f32, Em> {
+        self.to_ems(self.superscript_offset)
+    }
+    pub(crate) fn underline_position(&self) -> euclid::Length<f32, Em> {
+        self.to_ems(self.underline_position)
+    }
+    pub(crate) fn underline_thickness(&self) -> euclid::Length<f32, Em> {
+        self.to_ems(self.underline_thickness)
+    }
+    pub(crate) fn strikeout_position(&self) -> euclid::Length<f32, Em> {
+        self.to_ems(self.strikeout_position)
+    }
+    pub(crate) fn strikeout_thickness(&self) -> euclid::Length<f32, Em> {
+        self.to_ems(self.strikeout_thickness)
+    }
     pub(crate) fn min_x(&self) -> euclid::Length<f32, Em> {
         self.to_ems(self.min_x)
     }
@@ -195,28 +684,106 @@ impl Font {
     }
 }
 
-fn read_postscript_name(
+fn decompress(bytes: Cow<'static, [u8]>) -> Result<Cow<'static, [u8]>, FontError> {
+    let signature = Position::<OffsetSubtable>::initial()
+        .scaler_type()
+        .read_from(&bytes)?;
+    Ok(match signature {
+        woff::SIGNATURE => woff::decompress(&bytes)?.into(),
+        woff2::SIGNATURE => woff2::decompress(&bytes)?.into(),
+        _ => bytes,
+    })
+}
+
+/// The position of the offset table of each face in a font file.
+fn face_offset_tables(bytes: &[u8]) -> Result<Vec<Position<OffsetSubtable>>, FontError> {
+    let offset_table = Position::<OffsetSubtable>::initial();
+    const COLLECTION: u32 = 0x74746366; // "ttcf" in big-endian
+    if offset_table.scaler_type().read_from(bytes)? != COLLECTION {
+        return Ok(vec![offset_table]);
+    }
+    let header = offset_table.cast::<CollectionHeader>();
+    Slice::new(
+        header.followed_by::<u32>(),
+        header.num_fonts().read_from(bytes)?,
+    )
+    .into_iter()
+    .map(|offset| Ok(offset_table.offset_bytes(offset.read_from(bytes)?)))
+    .collect()
+}
+
+/// The top of the bounding box of the glyph for `ch` in a TrueType font, if it has an outline
+fn glyph_top(
     bytes: &[u8],
     table_directory: Slice<TableDirectoryEntry>,
-) -> Result<String, FontError> {
-    /// Macintosh encodings seem to be ASCII-compatible, and a PostScript name is within ASCII
-    fn decode_macintosh(string_bytes: &[u8]) -> String {
-        String::from_utf8_lossy(string_bytes).into_owned()
+    header: Position<FontHeader>,
+    cmap: &Cmap,
+    ch: char,
+) -> Result<Option<euclid::Length<i16, FontDesignUnit>>, FontError> {
+    let glyph_id = match cmap.get(bytes, ch as u32)? {
+        Some(glyph_id) => glyph_id,
+        None => return Ok(None),
+    };
+    let long_loca = header.index_to_loc_format().read_from(bytes)? != 0;
+    let data = glyf::glyph_data(
+        table_directory.find_table_bytes(bytes, Tag(*b"glyf"))?,
+        table_directory.find_table_bytes(bytes, Tag(*b"loca"))?,
+        long_loca,
+        glyph_id,
+    )?;
+    if data.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(euclid::Length::new(Reader::at(data, 8)?.i16()?)))
+}
+
+impl EmbeddingPermissions {
+    fn from_fs_type(fs_type: u16) -> Self {
+        // Only one of these bits should be set, the least restrictive one wins otherwise.
+        if fs_type & 0x0008 != 0 {
+            EmbeddingPermissions::Editable
+        } else if fs_type & 0x0004 != 0 {
+            EmbeddingPermissions::PreviewAndPrint
+        } else if fs_type & 0x0002 != 0 {
+            EmbeddingPermissions::Restricted
+        } else {
+            EmbeddingPermissions::Installable
+        }
     }
+}
 
-    /// Latin-1 range only
-    fn decode_ucs2(string_bytes: &[u8]) -> String {
+// https://docs.microsoft.com/en-us/typography/opentype/spec/name#name-ids
+const FAMILY_NAME: u16 = 1;
+const SUBFAMILY_NAME: u16 = 2;
+const POSTSCRIPT_NAME: u16 = 6;
+const TYPOGRAPHIC_FAMILY_NAME: u16 = 16;
+const TYPOGRAPHIC_SUBFAMILY_NAME: u16 = 17;
+
+/// The string for this name ID, if the font has one in a supported encoding
+///
+/// When there are several, Unicode strings are preferred over Macintosh ones,
+/// and English over other languages.
+fn read_name(
+    bytes: &[u8],
+    table_directory: Slice<TableDirectoryEntry>,
+    name_id: u16,
+) -> Result<Option<String>, FontError> {
+    /// Mac OS Roman, the only Macintosh encoding supported
+    fn decode_macintosh(string_bytes: &[u8]) -> String {
         string_bytes
+            .iter()
+            .map(|&byte| decode_mac_roman(byte))
+            .collect()
+    }
+
+    fn decode_utf16(string_bytes: &[u8]) -> String {
+        let code_units = string_bytes
             .chunks(2)
-            .map(|chunk| {
-                if chunk.len() < 2 || chunk[0] != 0 {
-                    '\u{FFFD}'
-                } else {
-                    chunk[1] as char
-                }
-            })
-            .collect::<String>()
-    };
+            .map(|chunk| u16::from_be_bytes([chunk[0], *chunk.get(1).unwrap_or(&0)]));
+        std::char::decode_utf16(code_units)
+            .map(|result| result.unwrap_or('\u{FFFD}'))
+            .collect()
+    }
 
     let naming_table_header = table_directory.find_table::<NamingTableHeader>(bytes)?;
     let name_records = Slice::new(
@@ -233,27 +800,133 @@ fn read_postscript_name(
         .read_from(bytes)
     };
 
+    // (rank, record), lower ranks are preferred
+    let mut best = None;
     for record in name_records {
-        const POSTSCRIPT_NAME: u16 = 6;
-        if record.name_id().read_from(bytes)? != POSTSCRIPT_NAME {
+        if record.name_id().read_from(bytes)? != name_id {
             continue;
         }
 
+        const UNICODE: u16 = 0;
         const MACINTOSH: u16 = 1;
         const MICROSOFT: u16 = 3;
-        const UNICODE_BMP: u16 = 1;
-        let postscript_name = match (
+        const MACINTOSH_ROMAN: u16 = 0;
+        const MACINTOSH_ENGLISH: u16 = 0;
+        const MICROSOFT_SYMBOL: u16 = 0;
+        const MICROSOFT_UNICODE_BMP: u16 = 1;
+        const MICROSOFT_UNICODE_FULL: u16 = 10;
+        const MICROSOFT_ENGLISH_US: u16 = 0x0409;
+        let language_id = record.language_id().read_from(bytes)?;
+        let rank = match (
             record.platform_id().read_from(bytes)?,
             record.encoding_id().read_from(bytes)?,
         ) {
-            (MACINTOSH, _) => decode_macintosh(string_bytes(record)?),
-            (MICROSOFT, UNICODE_BMP) => decode_ucs2(string_bytes(record)?),
+            (MICROSOFT, MICROSOFT_SYMBOL)
+            | (MICROSOFT, MICROSOFT_UNICODE_BMP)
+            | (MICROSOFT, MICROSOFT_UNICODE_FULL) => {
+                if language_id == MICROSOFT_ENGLISH_US {
+                    0
+                } else if language_id & 0xFF == MICROSOFT_ENGLISH_US & 0xFF {
+                    // Other English-speaking regions
+                    1
+                } else {
+                    3
+                }
+            }
+            // Unicode platform strings have no language
+            (UNICODE, _) => 2,
+            (MACINTOSH, MACINTOSH_ROMAN) if language_id == MACINTOSH_ENGLISH => 4,
+            (MACINTOSH, MACINTOSH_ROMAN) => 5,
             _ => continue,
         };
-        return Ok(postscript_name);
+        if best.map_or(true, |(best_rank, _)| rank < best_rank) {
+            best = Some((rank, record))
+        }
     }
 
-    Err(FontError::NoSupportedPostscriptName)
+    Ok(match best {
+        Some((rank, record)) if rank >= 4 => Some(decode_macintosh(string_bytes(record)?)),
+        Some((_, record)) => Some(decode_utf16(string_bytes(record)?)),
+        None => None,
+    })
+}
+
+/// The second half of the Mac OS Roman encoding, the first half is ASCII
+const MAC_ROMAN_HIGH_HALF: &str = "\
+    ÄÅÇÉÑÖÜáàâäãåçéèêëíìîïñóòôöõúùûü†°¢£§•¶ß®©™´¨≠ÆØ∞±≤≥¥µ∂∑∏π∫ªºΩæø\
+    ¿¡¬√ƒ≈∆«»…\u{A0}ÀÃÕŒœ–—“”‘’÷◊ÿŸ⁄€‹›ﬁﬂ‡·‚„‰ÂÊÁËÈÍÎÏÌÓÔ\
+    \u{F8FF}ÒÚÛÙıˆ˜¯˘˙˚¸˝˛ˇ";
+
+fn decode_mac_roman(byte: u8) -> char {
+    if byte < 0x80 {
+        byte as char
+    } else {
+        MAC_ROMAN_HIGH_HALF
+            .chars()
+            .nth(byte as usize - 0x80)
+            .unwrap()
+    }
+}
+
+fn encode_mac_roman(ch: char) -> Option<u8> {
+    if (ch as u32) < 0x80 {
+        Some(ch as u8)
+    } else {
+        MAC_ROMAN_HIGH_HALF
+            .chars()
+            .position(|c| c == ch)
+            .map(|index| 0x80 + index as u8)
+    }
+}
+
+/// Serialize an offset table, a table directory, and the tables’ data.
+///
+/// This is used to decompress WOFF and WOFF2, and to extract a face from a collection.
+fn write_sfnt<T: AsRef<[u8]>>(
+    scaler_type: u32,
+    size_hint: u32,
+    mut tables: Vec<(Tag, T)>,
+) -> Vec<u8> {
+    fn checksum(data: &[u8]) -> u32 {
+        data.chunks(4).fold(0_u32, |sum, chunk| {
+            let mut word = [0; 4];
+            word[..chunk.len()].copy_from_slice(chunk);
+            sum.wrapping_add(u32::from_be_bytes(word))
+        })
+    }
+    fn padding(length: usize) -> usize {
+        (4 - length % 4) % 4
+    }
+
+    // `Slice::binary_search_by_key` requires the table directory to be sorted.
+    tables.sort_by_key(|&(tag, _)| tag);
+
+    let table_count = tables.len() as u16;
+    let entry_selector = 15 - table_count.max(1).leading_zeros() as u16;
+    let search_range = 16_u16 << entry_selector;
+
+    let mut sfnt = Vec::with_capacity(size_hint as usize);
+    sfnt.extend_from_slice(&scaler_type.to_be_bytes());
+    sfnt.extend_from_slice(&table_count.to_be_bytes());
+    sfnt.extend_from_slice(&search_range.to_be_bytes());
+    sfnt.extend_from_slice(&entry_selector.to_be_bytes());
+    sfnt.extend_from_slice(&(table_count * 16 - search_range).to_be_bytes());
+
+    let mut offset = 12 + 16 * tables.len();
+    for (tag, data) in &tables {
+        let data = data.as_ref();
+        sfnt.extend_from_slice(&tag.0);
+        sfnt.extend_from_slice(&checksum(data).to_be_bytes());
+        sfnt.extend_from_slice(&(offset as u32).to_be_bytes());
+        sfnt.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        offset += data.len() + padding(data.len());
+    }
+    for (_, data) in &tables {
+        let data = data.as_ref();
+        sfnt.extend_from_slice(data);
+        sfnt.extend_from_slice(&[0; 3][..padding(data.len())]);
+    }
+    sfnt
 }
 
 trait SfntTable {
@@ -267,6 +940,18 @@ impl Slice<TableDirectoryEntry> {
         let offset = entry.table_offset().read_from(bytes)?;
         Ok(Position::<OffsetSubtable>::initial().offset_bytes(offset))
     }
+
+    /// The data of a table that is not parsed through a `#[derive(SfntTable)]` struct
+    fn find_table_bytes<'a>(&self, bytes: &'a [u8], tag: Tag) -> Result<&'a [u8], FontError> {
+        let search = self.binary_search_by_key(&tag, |entry| entry.tag().read_from(bytes))?;
+        let entry = search.ok_or(FontError::MissingTable)?;
+        Slice::<u8>::new(
+            Position::<OffsetSubtable>::initial()
+                .offset_bytes(entry.table_offset().read_from(bytes)?),
+            entry.length().read_from(bytes)?,
+        )
+        .read_from(bytes)
+    }
 }
 
 #[doc(hidden)]
@@ -0,0 +1,311 @@
+use crate::fonts::glyf::{glyph_data, Glyph, ARGS_ARE_XY_VALUES};
+use crate::fonts::tables::FontHeader;
+use crate::fonts::types::Tag;
+use crate::fonts::{cff, Em, Font, FontError, GlyphId, OutlineFormat};
+use crate::primitives::{point, Point, Rect, Size};
+
+/// A segment of a glyph outline.
+///
+/// Coordinates are in em units, with the y axis pointing up from the baseline.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum OutlineSegment {
+    /// Start a new contour
+    MoveTo(Point<Em>),
+    LineTo(Point<Em>),
+
+    /// A control point, and the end point
+    QuadraticCurveTo(Point<Em>, Point<Em>),
+
+    /// Two control points, and the end point
+    CubicCurveTo(Point<Em>, Point<Em>, Point<Em>),
+
+    /// Draw a line back to the start of the contour
+    Close,
+}
+
+/// Accumulates outline segments, converting from font design units to ems.
+pub(in crate::fonts) struct OutlineBuilder {
+    segments: Vec<OutlineSegment>,
+    ems_per_font_design_unit: f32,
+}
+
+impl OutlineBuilder {
+    fn point(&self, x: f32, y: f32) -> Point<Em> {
+        point(x, y) * self.ems_per_font_design_unit
+    }
+
+    pub(in crate::fonts) fn move_to(&mut self, x: f32, y: f32) {
+        let to = self.point(x, y);
+        self.segments.push(OutlineSegment::MoveTo(to))
+    }
+
+    pub(in crate::fonts) fn line_to(&mut self, x: f32, y: f32) {
+        let to = self.point(x, y);
+        self.segments.push(OutlineSegment::LineTo(to))
+    }
+
+    pub(in crate::fonts) fn quadratic_curve_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let control = self.point(x1, y1);
+        let to = self.point(x, y);
+        self.segments
+            .push(OutlineSegment::QuadraticCurveTo(control, to))
+    }
+
+    pub(in crate::fonts) fn cubic_curve_to(
+        &mut self,
+        (x1, y1): (f32, f32),
+        (x2, y2): (f32, f32),
+        (x, y): (f32, f32),
+    ) {
+        let control_1 = self.point(x1, y1);
+        let control_2 = self.point(x2, y2);
+        let to = self.point(x, y);
+        self.segments
+            .push(OutlineSegment::CubicCurveTo(control_1, control_2, to))
+    }
+
+    pub(in crate::fonts) fn close(&mut self) {
+        self.segments.push(OutlineSegment::Close)
+    }
+}
+
+impl Font {
+    /// The outline of a glyph, empty for glyphs such as a space.
+    ///
+    /// TrueType fonts have quadratic curves, and OpenType fonts with CFF outlines cubic ones.
+    /// The components of composite glyphs are included.
+    pub fn glyph_outline(&self, glyph_id: GlyphId) -> Result<Vec<OutlineSegment>, FontError> {
+        let mut builder = OutlineBuilder {
+            segments: Vec::new(),
+            ems_per_font_design_unit: 1. / self.font_design_units_per_em.get(),
+        };
+        let bytes: &[u8] = &self.bytes;
+        let table_directory = self.table_directory()?;
+        match self.outline_format {
+            OutlineFormat::TrueType => {
+                let glyf = table_directory.find_table_bytes(bytes, Tag(*b"glyf"))?;
+                let loca = table_directory.find_table_bytes(bytes, Tag(*b"loca"))?;
+                let header = table_directory.find_table::<FontHeader>(bytes)?;
+                let long_loca = header.index_to_loc_format().read_from(bytes)? != 0;
+                let glyph = |glyph_id| glyph_data(glyf, loca, long_loca, glyph_id);
+                for contour in &truetype_contours(&glyph, glyph_id.0, 0)? {
+                    truetype_contour_segments(contour, &mut builder)
+                }
+            }
+            OutlineFormat::CompactFontFormat => {
+                let cff = table_directory.find_table_bytes(bytes, Tag(*b"CFF "))?;
+                cff::glyph_outline(cff, glyph_id.0, &mut builder)?
+            }
+        }
+        Ok(builder.segments)
+    }
+
+    /// The smallest rectangle that contains the outline of a glyph,
+    /// or `None` for a glyph without an outline.
+    ///
+    /// Unlike the bounding boxes stored in fonts, this is tight around curves.
+    pub fn glyph_bounds(&self, glyph_id: GlyphId) -> Result<Option<Rect<Em>>, FontError> {
+        let mut bounds: Option<(Point<Em>, Point<Em>)> = None;
+        let mut include = |p: Point<Em>| {
+            bounds = Some(match bounds {
+                Some((min, max)) => (min.min(p), max.max(p)),
+                None => (p, p),
+            })
+        };
+        let mut current = point(0., 0.);
+        for segment in self.glyph_outline(glyph_id)? {
+            match segment {
+                OutlineSegment::MoveTo(to) | OutlineSegment::LineTo(to) => {
+                    include(to);
+                    current = to
+                }
+                OutlineSegment::QuadraticCurveTo(control, to) => {
+                    include(to);
+                    for &t in &quadratic_extrema(current, control, to) {
+                        let mt = 1. - t;
+                        include(
+                            (current.to_vector() * (mt * mt)
+                                + control.to_vector() * (2. * mt * t)
+                                + to.to_vector() * (t * t))
+                                .to_point(),
+                        )
+                    }
+                    current = to
+                }
+                OutlineSegment::CubicCurveTo(control_1, control_2, to) => {
+                    include(to);
+                    for &t in &cubic_extrema(current, control_1, control_2, to) {
+                        let mt = 1. - t;
+                        include(
+                            (current.to_vector() * (mt * mt * mt)
+                                + control_1.to_vector() * (3. * mt * mt * t)
+                                + control_2.to_vector() * (3. * mt * t * t)
+                                + to.to_vector() * (t * t * t))
+                                .to_point(),
+                        )
+                    }
+                    current = to
+                }
+                OutlineSegment::Close => {}
+            }
+        }
+        Ok(bounds.map(|(min, max)| Rect::new(min, Size::new(max.x - min.x, max.y - min.y))))
+    }
+}
+
+/// Parameters in (0, 1) where a quadratic Bézier curve may reach an extreme x or y value
+fn quadratic_extrema(p0: Point<Em>, p1: Point<Em>, p2: Point<Em>) -> Vec<f32> {
+    let mut parameters = Vec::new();
+    for &(a, b, c) in &[(p0.x, p1.x, p2.x), (p0.y, p1.y, p2.y)] {
+        // The derivative is zero at t = (a - b) / (a - 2b + c)
+        let denominator = a - 2. * b + c;
+        if denominator != 0. {
+            parameters.push((a - b) / denominator)
+        }
+    }
+    parameters.retain(|&t| 0. < t && t < 1.);
+    parameters
+}
+
+/// Parameters in (0, 1) where a cubic Bézier curve may reach an extreme x or y value
+fn cubic_extrema(p0: Point<Em>, p1: Point<Em>, p2: Point<Em>, p3: Point<Em>) -> Vec<f32> {
+    let mut parameters = Vec::new();
+    for &(a, b, c, d) in &[(p0.x, p1.x, p2.x, p3.x), (p0.y, p1.y, p2.y, p3.y)] {
+        // The derivative divided by 3 is qa t² + qb t + qc
+        let qa = -a + 3. * b - 3. * c + d;
+        let qb = 2. * (a - 2. * b + c);
+        let qc = b - a;
+        if qa.abs() < 1e-9 {
+            if qb != 0. {
+                parameters.push(-qc / qb)
+            }
+            continue;
+        }
+        let discriminant = qb * qb - 4. * qa * qc;
+        if discriminant >= 0. {
+            let root = discriminant.sqrt();
+            parameters.push((-qb + root) / (2. * qa));
+            parameters.push((-qb - root) / (2. * qa));
+        }
+    }
+    parameters.retain(|&t| 0. < t && t < 1.);
+    parameters
+}
+
+#[derive(Clone, Copy)]
+struct ContourPoint {
+    x: f32,
+    y: f32,
+    on_curve: bool,
+}
+
+/// The contours of a TrueType glyph in font design units,
+/// with the components of a composite glyph transformed and positioned.
+fn truetype_contours<'a, F>(
+    glyph: &F,
+    glyph_id: u16,
+    depth: u32,
+) -> Result<Vec<Vec<ContourPoint>>, FontError>
+where
+    F: Fn(u16) -> Result<&'a [u8], FontError>,
+{
+    const MAX_COMPONENT_DEPTH: u32 = 16;
+    Ok(match Glyph::parse(glyph(glyph_id)?)? {
+        Glyph::Empty => Vec::new(),
+        Glyph::Simple(simple) => {
+            let mut start = 0;
+            simple
+                .end_points
+                .iter()
+                .map(|&end| {
+                    let end = usize::from(end) + 1;
+                    let contour = simple
+                        .points
+                        .get(start..end)
+                        .unwrap_or(&[])
+                        .iter()
+                        .map(|point| ContourPoint {
+                            x: f32::from(point.x),
+                            y: f32::from(point.y),
+                            on_curve: point.on_curve,
+                        })
+                        .collect();
+                    start = end;
+                    contour
+                })
+                .collect()
+        }
+        Glyph::Composite(composite) => {
+            let mut contours: Vec<Vec<ContourPoint>> = Vec::new();
+            if depth >= MAX_COMPONENT_DEPTH {
+                return Ok(contours);
+            }
+            for component in &composite.components {
+                let [a, b, c, d] = component.matrix();
+                let mut component_contours =
+                    truetype_contours(glyph, component.glyph_id, depth + 1)?;
+                for point in component_contours.iter_mut().flatten() {
+                    let (x, y) = (point.x, point.y);
+                    point.x = a * x + c * y;
+                    point.y = b * x + d * y;
+                }
+                let (arg1, arg2) = component.arguments;
+                let (dx, dy) = if component.flags & ARGS_ARE_XY_VALUES != 0 {
+                    (arg1 as f32, arg2 as f32)
+                } else {
+                    // Align a point of the component with a point of the previous components
+                    match (
+                        contours.iter().flatten().nth(arg1 as usize),
+                        component_contours.iter().flatten().nth(arg2 as usize),
+                    ) {
+                        (Some(p1), Some(p2)) => (p1.x - p2.x, p1.y - p2.y),
+                        _ => (0., 0.),
+                    }
+                };
+                for point in component_contours.iter_mut().flatten() {
+                    point.x += dx;
+                    point.y += dy;
+                }
+                contours.extend(component_contours)
+            }
+            contours
+        }
+    })
+}
+
+/// Convert a contour of on-curve and off-curve points to quadratic curves and lines.
+///
+/// Between two consecutive off-curve points, there is an implied on-curve point in the middle.
+fn truetype_contour_segments(contour: &[ContourPoint], builder: &mut OutlineBuilder) {
+    let middle = |p1: ContourPoint, p2: ContourPoint| ((p1.x + p2.x) / 2., (p1.y + p2.y) / 2.);
+    let (last, first) = match (contour.last(), contour.first()) {
+        (Some(&last), Some(&first)) => (last, first),
+        _ => return,
+    };
+    // Start at an on-curve point, or at an implied one if there is none
+    let (start, rest) = match contour.iter().position(|point| point.on_curve) {
+        Some(index) => (
+            (contour[index].x, contour[index].y),
+            contour[index + 1..].iter().chain(&contour[..index]),
+        ),
+        None => (middle(last, first), contour.iter().chain(&contour[..0])),
+    };
+    builder.move_to(start.0, start.1);
+    let mut control: Option<ContourPoint> = None;
+    for &point in rest {
+        match (point.on_curve, control) {
+            (true, Some(c)) => builder.quadratic_curve_to(c.x, c.y, point.x, point.y),
+            (true, None) => builder.line_to(point.x, point.y),
+            (false, Some(c)) => {
+                let (x, y) = middle(c, point);
+                builder.quadratic_curve_to(c.x, c.y, x, y)
+            }
+            (false, None) => {}
+        }
+        control = if point.on_curve { None } else { Some(point) };
+    }
+    if let Some(c) = control {
+        builder.quadratic_curve_to(c.x, c.y, start.0, start.1)
+    }
+    builder.close()
+}
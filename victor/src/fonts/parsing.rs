@@ -57,6 +57,22 @@ impl<T> Position<T> {
         self.offset_bytes(mem::size_of::<T>() as u32)
     }
 
+    /// Start sequential reading at this position
+    pub(in crate::fonts) fn reader(self, bytes: &[u8]) -> Result<Reader, FontError> {
+        Reader::at(bytes, self.byte_position as usize)
+    }
+
+    /// Overwrite a big-endian 16-bit value, in a copy of a table
+    pub(in crate::fonts) fn write_u16(self, bytes: &mut [u8], value: u16) -> Result<(), FontError> {
+        bytes
+            .get_mut(self.byte_position as usize..)
+            .ok_or(FontError::OffsetBeyondEof)?
+            .get_mut(..2)
+            .ok_or(FontError::OffsetPlusLengthBeyondEof)?
+            .copy_from_slice(&value.to_be_bytes());
+        Ok(())
+    }
+
     pub(in crate::fonts) fn read_from(self, bytes: &[u8]) -> Result<T, FontError>
     where
         T: ReadFromBytes,
@@ -222,6 +238,74 @@ where
     }
 }
 
+/// Sequential reading of variable-length data
+pub(in crate::fonts) struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(in crate::fonts) fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes }
+    }
+
+    /// Start reading at `offset` bytes from the start of `bytes`
+    pub(in crate::fonts) fn at(bytes: &'a [u8], offset: usize) -> Result<Self, FontError> {
+        bytes
+            .get(offset..)
+            .map(Reader::new)
+            .ok_or(FontError::OffsetBeyondEof)
+    }
+
+    /// A new reader, `offset` bytes after the current position of this one
+    pub(in crate::fonts) fn at_offset(&self, offset: usize) -> Result<Self, FontError> {
+        Reader::at(self.bytes, offset)
+    }
+
+    pub(in crate::fonts) fn take(&mut self, length: usize) -> Result<&'a [u8], FontError> {
+        if length > self.bytes.len() {
+            return Err(FontError::OffsetPlusLengthBeyondEof);
+        }
+        let (taken, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    pub(in crate::fonts) fn skip(&mut self, length: usize) -> Result<(), FontError> {
+        self.take(length).map(|_| ())
+    }
+
+    pub(in crate::fonts) fn u8(&mut self) -> Result<u8, FontError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(in crate::fonts) fn i8(&mut self) -> Result<i8, FontError> {
+        self.u8().map(|value| value as i8)
+    }
+
+    pub(in crate::fonts) fn u16(&mut self) -> Result<u16, FontError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub(in crate::fonts) fn u24(&mut self) -> Result<u32, FontError> {
+        let bytes = self.take(3)?;
+        Ok(u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]))
+    }
+
+    pub(in crate::fonts) fn i16(&mut self) -> Result<i16, FontError> {
+        self.u16().map(|value| value as i16)
+    }
+
+    pub(in crate::fonts) fn u32(&mut self) -> Result<u32, FontError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub(in crate::fonts) fn i32(&mut self) -> Result<i32, FontError> {
+        self.u32().map(|value| value as i32)
+    }
+}
+
 // ~~~~ Boring trait impls ~~~~
 
 impl<T> Copy for Position<T> {}
@@ -275,15 +359,36 @@ impl<T> Iterator for SliceIter<T> {
 impl<T> DoubleEndedIterator for SliceIter<T> {
     fn next_back(&mut self) -> Option<Self::Item> {
         if self.start != self.end {
-            let next = self.end;
-            let byte_position = next.byte_position - mem::size_of::<T>() as u32;
+            let byte_position = self.end.byte_position - mem::size_of::<T>() as u32;
             self.end = Position {
                 byte_position,
                 ty: PhantomData,
             };
-            Some(next)
+            Some(self.end)
         } else {
             None
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reverse_slice_iteration() {
+        let bytes = [0, 1, 0, 2, 0, 3, 0, 4];
+        let slice = Slice::new(Position::initial().offset_bytes::<u16, u32>(2), 3_u32);
+        let read = |iter: &mut dyn Iterator<Item = Position<u16>>| -> Vec<u16> {
+            iter.map(|position| position.read_from(&bytes).unwrap())
+                .collect()
+        };
+        assert_eq!(read(&mut slice.into_iter()), [2, 3, 4]);
+        assert_eq!(read(&mut slice.into_iter().rev()), [4, 3, 2]);
+
+        let mut iter = slice.into_iter();
+        assert_eq!(iter.next_back().unwrap().read_from(&bytes).unwrap(), 4);
+        assert_eq!(iter.next().unwrap().read_from(&bytes).unwrap(), 2);
+        assert_eq!(read(&mut iter), [3]);
+    }
+}
@@ -17,7 +17,16 @@ pub(in crate::fonts) struct TableDirectoryEntry {
     tag: Tag,
     _checksum: u32,
     table_offset: u32,
-    _length: u32,
+    length: u32,
+}
+
+/// https://docs.microsoft.com/en-us/typography/opentype/spec/otff#ttc-header
+#[derive(SfntTable)]
+pub(in crate::fonts) struct CollectionHeader {
+    _ttc_tag: Tag,
+    _major_version: u16,
+    _minor_version: u16,
+    num_fonts: u32,
 }
 
 #[derive(SfntTable)]
@@ -41,7 +50,7 @@ pub(in crate::fonts) struct NamingTableHeader {
 pub(in crate::fonts) struct NameRecord {
     platform_id: u16,
     encoding_id: u16,
-    _language_id: u16,
+    language_id: u16,
     name_id: u16,
     length: u16,
     string_offset: u16,
@@ -61,6 +70,13 @@ pub(in crate::fonts) struct CmapEncodingRecord {
     subtable_offset: u32,
 }
 
+#[derive(SfntTable)]
+pub(in crate::fonts) struct CmapFormat0Header {
+    _format: u16,
+    _length: u16,
+    _language: u16,
+}
+
 #[derive(SfntTable)]
 pub(in crate::fonts) struct CmapFormat4Header {
     _format: u16,
@@ -72,6 +88,15 @@ pub(in crate::fonts) struct CmapFormat4Header {
     _range_shift: u16,
 }
 
+#[derive(SfntTable)]
+pub(in crate::fonts) struct CmapFormat6Header {
+    _format: u16,
+    _length: u16,
+    _language: u16,
+    first_code: u16,
+    entry_count: u16,
+}
+
 #[derive(SfntTable)]
 pub(in crate::fonts) struct CmapFormat12Header {
     _format: u16,
@@ -106,22 +131,30 @@ pub(in crate::fonts) struct FontHeader {
     _mac_style: u16,
     _smallest_readable_size_in_px_per_em: u16,
     _font_direction_hint: i16,
-    _index_to_loc_format: i16,
+    index_to_loc_format: i16,
     _glyph_data_format: i16,
     __padding: u16,
 }
 
+/// https://docs.microsoft.com/en-us/typography/opentype/spec/cff
+#[derive(SfntTable)]
+#[tag = "CFF "]
+pub(in crate::fonts) struct CompactFontFormatHeader {
+    _major_and_minor_version: u16,
+    _header_size_and_offset_size: u16,
+}
+
 #[derive(SfntTable)]
 #[tag = "hhea"]
 pub(in crate::fonts) struct HorizontalHeader {
     _version: FixedPoint,
     ascender: FWord,
     descender: FWord,
-    _line_gap: FWord,
-    _max_advance_width: UFWord,
-    _min_left_side_bearing: FWord,
-    _max_left_side_bearing: FWord,
-    _x_max_extent: FWord,
+    line_gap: FWord,
+    max_advance_width: UFWord,
+    min_left_side_bearing: FWord,
+    min_right_side_bearing: FWord,
+    x_max_extent: FWord,
     _caret_slope_rise: i16,
     _caret_slope_run: i16,
     _carret_offset: FWord,
@@ -133,9 +166,300 @@ pub(in crate::fonts) struct HorizontalHeader {
     number_of_long_horizontal_metrics: u16,
 }
 
+/// https://docs.microsoft.com/en-us/typography/opentype/spec/post
+///
+/// Only the header, glyph names are not used.
+#[derive(SfntTable)]
+#[tag = "post"]
+pub(in crate::fonts) struct PostScriptHeader {
+    _version: FixedPoint,
+    _italic_angle: FixedPoint,
+    underline_position: FWord,
+    underline_thickness: FWord,
+    _is_fixed_pitch: u32,
+}
+
+/// https://docs.microsoft.com/en-us/typography/opentype/spec/os2
+///
+/// Fields after `win_descent` are only present in later versions of this table.
+#[derive(SfntTable)]
+#[tag = "OS/2"]
+pub(in crate::fonts) struct OS2 {
+    version: u16,
+    _x_avg_char_width: FWord,
+    weight_class: u16,
+    width_class: u16,
+    fs_type: u16,
+    _subscript_x_size: FWord,
+    _subscript_y_size: FWord,
+    _subscript_x_offset: FWord,
+    subscript_y_offset: FWord,
+    _superscript_x_size: FWord,
+    _superscript_y_size: FWord,
+    _superscript_x_offset: FWord,
+    superscript_y_offset: FWord,
+    strikeout_size: FWord,
+    strikeout_position: FWord,
+    _family_class: i16,
+    // `panose`, `ulUnicodeRange1` to `4`, and `achVendID` are not 4-byte aligned
+    __unaligned_1: u16,
+    __unaligned_2: u16,
+    __unaligned_3: u16,
+    __unaligned_4: u16,
+    __unaligned_5: u16,
+    __unaligned_6: u16,
+    __unaligned_7: u16,
+    __unaligned_8: u16,
+    __unaligned_9: u16,
+    __unaligned_10: u16,
+    __unaligned_11: u16,
+    __unaligned_12: u16,
+    __unaligned_13: u16,
+    __unaligned_14: u16,
+    __unaligned_15: u16,
+    fs_selection: u16,
+    _first_char_index: u16,
+    _last_char_index: u16,
+    typo_ascender: FWord,
+    typo_descender: FWord,
+    typo_line_gap: FWord,
+    _win_ascent: UFWord,
+    _win_descent: UFWord,
+    // Version 1
+    // `ulCodePageRange1` and `2`, not 4-byte aligned
+    __code_page_range_1: u16,
+    __code_page_range_2: u16,
+    __code_page_range_3: u16,
+    __code_page_range_4: u16,
+    // Version 2
+    x_height: FWord,
+    cap_height: FWord,
+    _default_char: u16,
+    _break_char: u16,
+    _max_context: u16,
+}
+
 #[derive(SfntTable)]
 #[tag = "hmtx"]
 pub(in crate::fonts) struct LongHorizontalMetricsRecord {
     advance_width: UFWord,
     _left_side_bearing: i16,
 }
+
+#[derive(SfntTable)]
+#[tag = "vhea"]
+pub(in crate::fonts) struct VerticalHeader {
+    _version: FixedPoint,
+    _vertical_ascender: FWord,
+    _vertical_descender: FWord,
+    _vertical_line_gap: FWord,
+    _max_advance_height: UFWord,
+    _min_top_side_bearing: FWord,
+    _min_bottom_side_bearing: FWord,
+    _y_max_extent: FWord,
+    _caret_slope_rise: i16,
+    _caret_slope_run: i16,
+    _carret_offset: FWord,
+    __reserved_1: i16,
+    __reserved_2: i16,
+    __reserved_3: i16,
+    __reserved_4: i16,
+    _metric_data_format: i16,
+    number_of_long_vertical_metrics: u16,
+}
+
+#[derive(SfntTable)]
+#[tag = "vmtx"]
+pub(in crate::fonts) struct LongVerticalMetricsRecord {
+    advance_height: UFWord,
+    _top_side_bearing: i16,
+}
+
+#[derive(SfntTable)]
+#[tag = "GSUB"]
+pub(in crate::fonts) struct GlyphSubstitutionHeader {
+    _major_version: u16,
+    _minor_version: u16,
+    _script_list_offset: u16,
+    feature_list_offset: u16,
+    lookup_list_offset: u16,
+}
+
+#[derive(SfntTable)]
+pub(in crate::fonts) struct FeatureListHeader {
+    feature_count: u16,
+}
+
+#[derive(SfntTable)]
+pub(in crate::fonts) struct FeatureRecord {
+    feature_tag: Tag,
+    feature_offset: u16,
+}
+
+#[derive(SfntTable)]
+pub(in crate::fonts) struct FeatureTableHeader {
+    _feature_params_offset: u16,
+    lookup_index_count: u16,
+}
+
+#[derive(SfntTable)]
+pub(in crate::fonts) struct LookupListHeader {
+    lookup_count: u16,
+}
+
+#[derive(SfntTable)]
+pub(in crate::fonts) struct LookupTableHeader {
+    lookup_type: u16,
+    _lookup_flag: u16,
+    sub_table_count: u16,
+}
+
+#[derive(SfntTable)]
+pub(in crate::fonts) struct ExtensionSubstitution {
+    _format: u16,
+    extension_lookup_type: u16,
+    extension_offset: u32,
+}
+
+#[derive(SfntTable)]
+pub(in crate::fonts) struct SingleSubstitutionFormat1 {
+    _format: u16,
+    coverage_offset: u16,
+    delta_glyph_id: i16,
+}
+
+#[derive(SfntTable)]
+pub(in crate::fonts) struct SingleSubstitutionFormat2 {
+    _format: u16,
+    coverage_offset: u16,
+    glyph_count: u16,
+}
+
+#[derive(SfntTable)]
+pub(in crate::fonts) struct CoverageFormat1 {
+    _format: u16,
+    glyph_count: u16,
+}
+
+#[derive(SfntTable)]
+pub(in crate::fonts) struct CoverageFormat2 {
+    _format: u16,
+    range_count: u16,
+}
+
+#[derive(SfntTable)]
+pub(in crate::fonts) struct CoverageRangeRecord {
+    start_glyph_id: u16,
+    end_glyph_id: u16,
+    start_coverage_index: u16,
+}
+
+/// https://docs.microsoft.com/en-us/typography/opentype/spec/fvar
+#[derive(SfntTable)]
+#[tag = "fvar"]
+pub(in crate::fonts) struct FontVariationsHeader {
+    _major_version: u16,
+    _minor_version: u16,
+    axes_array_offset: u16,
+    __reserved: u16,
+    axis_count: u16,
+    axis_size: u16,
+    instance_count: u16,
+    instance_size: u16,
+}
+
+#[derive(SfntTable)]
+pub(in crate::fonts) struct VariationAxisRecord {
+    axis_tag: Tag,
+    min_value: FixedPoint,
+    default_value: FixedPoint,
+    max_value: FixedPoint,
+    flags: u16,
+    axis_name_id: u16,
+}
+
+#[derive(SfntTable)]
+pub(in crate::fonts) struct InstanceRecordHeader {
+    subfamily_name_id: u16,
+    _flags: u16,
+}
+
+/// https://docs.microsoft.com/en-us/typography/opentype/spec/avar
+#[derive(SfntTable)]
+#[tag = "avar"]
+pub(in crate::fonts) struct AxisVariationsHeader {
+    _major_version: u16,
+    _minor_version: u16,
+    __reserved: u16,
+    axis_count: u16,
+}
+
+/// https://docs.microsoft.com/en-us/typography/opentype/spec/gvar
+#[derive(SfntTable)]
+pub(in crate::fonts) struct GlyphVariationsHeader {
+    _major_version: u16,
+    _minor_version: u16,
+    axis_count: u16,
+    shared_tuple_count: u16,
+    shared_tuples_offset: u32,
+    glyph_count: u16,
+    flags: u16,
+    glyph_variation_data_array_offset: u32,
+}
+
+/// https://docs.microsoft.com/en-us/typography/opentype/spec/hvar
+#[derive(SfntTable)]
+pub(in crate::fonts) struct HorizontalMetricsVariationsHeader {
+    _major_version: u16,
+    _minor_version: u16,
+    item_variation_store_offset: u32,
+    advance_width_mapping_offset: u32,
+    _lsb_mapping_offset: u32,
+    _rsb_mapping_offset: u32,
+}
+
+/// https://www.w3.org/TR/WOFF/#WOFFHeader
+#[derive(SfntTable)]
+pub(in crate::fonts) struct WoffHeader {
+    _signature: u32,
+    flavor: u32,
+    _length: u32,
+    num_tables: u16,
+    _reserved: u16,
+    total_sfnt_size: u32,
+    _major_version: u16,
+    _minor_version: u16,
+    _meta_offset: u32,
+    _meta_length: u32,
+    _meta_original_length: u32,
+    _private_offset: u32,
+    _private_length: u32,
+}
+
+#[derive(SfntTable)]
+pub(in crate::fonts) struct WoffTableDirectoryEntry {
+    tag: Tag,
+    table_offset: u32,
+    compressed_length: u32,
+    original_length: u32,
+    _original_checksum: u32,
+}
+
+/// https://www.w3.org/TR/WOFF2/#woff20Header
+#[derive(SfntTable)]
+pub(in crate::fonts) struct Woff2Header {
+    _signature: u32,
+    flavor: u32,
+    _length: u32,
+    num_tables: u16,
+    _reserved: u16,
+    total_sfnt_size: u32,
+    total_compressed_size: u32,
+    _major_version: u16,
+    _minor_version: u16,
+    _meta_offset: u32,
+    _meta_length: u32,
+    _meta_original_length: u32,
+    _private_offset: u32,
+    _private_length: u32,
+}
@@ -8,7 +8,7 @@ pub(in crate::fonts) type FWord = euclid::Length<i16, FontDesignUnit>;
 pub(in crate::fonts) type UFWord = euclid::Length<u16, FontDesignUnit>;
 
 /// 32-bit signed fixed-point number (16.16)
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, ReadFromBytes)]
 pub(in crate::fonts) struct FixedPoint(pub u32);
 
 /// Instant in time as seconds since 1904-01-01 midnight UTC
@@ -20,6 +20,12 @@ pub(in crate::fonts) struct Tag(pub [u8; 4]);
 
 // ~~~~ Trait impls ~~~~
 
+impl From<FixedPoint> for f32 {
+    fn from(value: FixedPoint) -> Self {
+        value.0 as i32 as f32 / 65536.
+    }
+}
+
 impl fmt::Debug for Tag {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for &b in &self.0 {
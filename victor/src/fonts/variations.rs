@@ -0,0 +1,926 @@
+//! OpenType font variations
+//!
+//! https://docs.microsoft.com/en-us/typography/opentype/spec/otvaroverview
+//!
+//! PDF has no equivalent, so a static font is created for each set of coordinates in use.
+
+use crate::fonts::glyf::{glyph_data, Glyph, ARGS_ARE_XY_VALUES};
+use crate::fonts::parsing::*;
+use crate::fonts::tables::*;
+use crate::fonts::types::{FixedPoint, Tag};
+use crate::fonts::{read_name, write_sfnt, Font, FontError, OutlineFormat};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// An axis of the design space of a variable font, such as weight or width.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariationAxis {
+    /// For example `*b"wght"` or `*b"wdth"`
+    pub tag: [u8; 4],
+    pub min_value: f32,
+    pub default_value: f32,
+    pub max_value: f32,
+
+    /// The font designer recommends not exposing this axis in user interfaces.
+    pub hidden: bool,
+
+    pub name: Option<String>,
+}
+
+/// A named position in the design space of a variable font, such as “Bold Condensed”.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamedInstance {
+    pub subfamily_name: Option<String>,
+    pub postscript_name: Option<String>,
+
+    /// One value per axis, in the same order as `Font::variation_axes`
+    pub coordinates: Vec<f32>,
+}
+
+pub(in crate::fonts) struct Variations {
+    pub(in crate::fonts) axes: Vec<VariationAxis>,
+    pub(in crate::fonts) named_instances: Vec<NamedInstance>,
+
+    /// From the `avar` table: for each axis, normalized coordinates before and after mapping
+    segment_maps: Vec<Vec<(i16, i16)>>,
+
+    /// Static fonts created by `Font::instantiate`, by normalized coordinates
+    pub(in crate::fonts) instances: Mutex<HashMap<Vec<i16>, Arc<Font>>>,
+}
+
+/// Normalized coordinates are F2Dot14 numbers, between -1.0 and 1.0
+const ONE: f32 = 16384.;
+
+impl Variations {
+    pub(in crate::fonts) fn parse(
+        bytes: &[u8],
+        table_directory: Slice<TableDirectoryEntry>,
+    ) -> Result<Option<Self>, FontError> {
+        let header = match table_directory.find_table::<FontVariationsHeader>(bytes) {
+            Ok(header) => header,
+            Err(FontError::MissingTable) => return Ok(None),
+            Err(error) => return Err(error),
+        };
+        let axis_count = header.axis_count().read_from(bytes)?;
+        let axis_size = header.axis_size().read_from(bytes)?;
+        let axes_start: Position<()> =
+            header.offset_bytes(header.axes_array_offset().read_from(bytes)?);
+
+        let mut axes = Vec::with_capacity(axis_count as usize);
+        for i in 0..u32::from(axis_count) {
+            let record =
+                axes_start.offset_bytes::<VariationAxisRecord, _>(i * u32::from(axis_size));
+            const HIDDEN_AXIS: u16 = 0x0001;
+            let name_id = record.axis_name_id().read_from(bytes)?;
+            axes.push(VariationAxis {
+                tag: record.axis_tag().read_from(bytes)?.0,
+                min_value: record.min_value().read_from(bytes)?.into(),
+                default_value: record.default_value().read_from(bytes)?.into(),
+                max_value: record.max_value().read_from(bytes)?.into(),
+                hidden: record.flags().read_from(bytes)? & HIDDEN_AXIS != 0,
+                name: read_name(bytes, table_directory, name_id)?,
+            })
+        }
+
+        let instance_size = header.instance_size().read_from(bytes)?;
+        let instances_start: Position<()> =
+            axes_start.offset_bytes(u32::from(axis_count) * u32::from(axis_size));
+        let coordinates_size = 4 * axis_count;
+        let mut named_instances = Vec::new();
+        for i in 0..u32::from(header.instance_count().read_from(bytes)?) {
+            let record: Position<InstanceRecordHeader> =
+                instances_start.offset_bytes(i * u32::from(instance_size));
+            let coordinates = Slice::new(record.followed_by::<FixedPoint>(), axis_count)
+                .into_iter()
+                .map(|value| Ok(value.read_from(bytes)?.into()))
+                .collect::<Result<_, FontError>>()?;
+            // The PostScript name ID is optional, present if the record is large enough.
+            let postscript_name = if instance_size >= coordinates_size + 6 {
+                let name_id = record
+                    .offset_bytes::<u16, _>(4 + coordinates_size)
+                    .read_from(bytes)?;
+                read_name(bytes, table_directory, name_id)?
+            } else {
+                None
+            };
+            let subfamily_name_id = record.subfamily_name_id().read_from(bytes)?;
+            named_instances.push(NamedInstance {
+                subfamily_name: read_name(bytes, table_directory, subfamily_name_id)?,
+                postscript_name,
+                coordinates,
+            })
+        }
+
+        let mut segment_maps = Vec::new();
+        match table_directory.find_table::<AxisVariationsHeader>(bytes) {
+            Ok(avar) => {
+                if avar.axis_count().read_from(bytes)? != axis_count {
+                    return Err(FontError::InvalidVariationData);
+                }
+                let mut reader = avar.followed_by::<()>().reader(bytes)?;
+                for _ in 0..axis_count {
+                    let mut map = Vec::new();
+                    for _ in 0..reader.u16()? {
+                        map.push((reader.i16()?, reader.i16()?))
+                    }
+                    segment_maps.push(map)
+                }
+            }
+            Err(FontError::MissingTable) => {}
+            Err(error) => return Err(error),
+        }
+
+        Ok(Some(Variations {
+            axes,
+            named_instances,
+            segment_maps,
+            instances: Mutex::new(HashMap::new()),
+        }))
+    }
+
+    /// The value of each axis, in user coordinates, with defaults for unspecified axes.
+    ///
+    /// Later `(tag, value)` pairs override earlier ones, unknown tags are ignored.
+    pub(in crate::fonts) fn user_coordinates(&self, settings: &[([u8; 4], f32)]) -> Vec<f32> {
+        self.axes
+            .iter()
+            .map(|axis| {
+                let value = settings
+                    .iter()
+                    .rev()
+                    .find(|&&(tag, _)| tag == axis.tag)
+                    .map_or(axis.default_value, |&(_, value)| value);
+                value.max(axis.min_value).min(axis.max_value)
+            })
+            .collect()
+    }
+
+    /// https://docs.microsoft.com/en-us/typography/opentype/spec/otvaroverview#coordinate-scales-and-normalization
+    pub(in crate::fonts) fn normalize(&self, user_coordinates: &[f32]) -> Vec<i16> {
+        self.axes
+            .iter()
+            .zip(user_coordinates)
+            .enumerate()
+            .map(|(i, (axis, &value))| {
+                let normalized = if value < axis.default_value {
+                    (value - axis.default_value) / (axis.default_value - axis.min_value)
+                } else if value > axis.default_value {
+                    (value - axis.default_value) / (axis.max_value - axis.default_value)
+                } else {
+                    0.
+                };
+                let normalized = (normalized * ONE).round() as i16;
+                match self.segment_maps.get(i) {
+                    Some(map) => apply_segment_map(map, normalized),
+                    None => normalized,
+                }
+            })
+            .collect()
+    }
+
+    /// The PostScript name of a named instance at these coordinates,
+    /// or one derived from the coordinates.
+    pub(in crate::fonts) fn instance_postscript_name(
+        &self,
+        default_postscript_name: &str,
+        user_coordinates: &[f32],
+    ) -> String {
+        let named = self.named_instances.iter().find(|instance| {
+            instance.coordinates == user_coordinates && instance.postscript_name.is_some()
+        });
+        if let Some(instance) = named {
+            return instance.postscript_name.clone().unwrap();
+        }
+        // Similar to https://adobe-type-tools.github.io/font-tech-notes/pdfs/5902.AdobePSNameGeneration.pdf
+        let mut name = default_postscript_name.to_owned();
+        for (axis, &value) in self.axes.iter().zip(user_coordinates) {
+            if value != axis.default_value {
+                name.push('_');
+                name.push_str(&value.to_string());
+                name.extend(
+                    axis.tag
+                        .iter()
+                        .filter(|b| b.is_ascii_alphanumeric())
+                        .map(|&b| b as char),
+                );
+            }
+        }
+        name
+    }
+}
+
+/// https://docs.microsoft.com/en-us/typography/opentype/spec/avar#table-formats
+fn apply_segment_map(map: &[(i16, i16)], value: i16) -> i16 {
+    for (i, &(from, to)) in map.iter().enumerate() {
+        if value == from {
+            return to;
+        }
+        if value < from {
+            if i == 0 {
+                return value;
+            }
+            let (previous_from, previous_to) = map[i - 1];
+            if from == previous_from {
+                return previous_to;
+            }
+            // Widened, since values in a malformed map can be further apart than `i16::MAX`
+            let delta = |a: i16, b: i16| (i32::from(a) - i32::from(b)) as f32;
+            let ratio = delta(value, previous_from) / delta(from, previous_from);
+            return (f32::from(previous_to) + ratio * delta(to, previous_to)).round() as i16;
+        }
+    }
+    value
+}
+
+/// The scalar for one axis of a region, in normalized coordinates.
+///
+/// https://docs.microsoft.com/en-us/typography/opentype/spec/otvaroverview#algorithm-for-interpolation-of-instance-values
+fn axis_scalar(value: i16, start: i16, peak: i16, end: i16) -> f32 {
+    // Invalid regions are ignored
+    if peak == 0 || value == peak || start > peak || peak > end || (start < 0 && end > 0) {
+        return 1.;
+    }
+    if value <= start || value >= end {
+        return 0.;
+    }
+    if value < peak {
+        f32::from(value - start) / f32::from(peak - start)
+    } else {
+        f32::from(end - value) / f32::from(end - peak)
+    }
+}
+
+/// Create the tables of a static font with variations applied to outlines and advance widths.
+///
+/// Other variation tables (`cvar`, `MVAR`, `VVAR`, …) are dropped without being applied.
+pub(in crate::fonts) fn instantiate(
+    font: &Font,
+    coordinates: &[i16],
+) -> Result<Vec<u8>, FontError> {
+    if font.outline_format != OutlineFormat::TrueType {
+        // Applying variations to CFF2 outlines is not supported.
+        return Err(FontError::UnsupportedFormat);
+    }
+    let tables = font.tables()?;
+    let find = |tag: &[u8; 4]| {
+        tables
+            .iter()
+            .find(|&&(t, _)| t == Tag(*tag))
+            .map(|&(_, data)| data)
+    };
+    let required = |tag| find(tag).ok_or(FontError::MissingTable);
+    let head = required(b"head")?;
+    let hhea = required(b"hhea")?;
+    let hmtx = required(b"hmtx")?;
+    let glyf = required(b"glyf")?;
+    let loca = required(b"loca")?;
+    let gvar = find(b"gvar");
+    let hvar = find(b"HVAR");
+
+    let head_position = Position::<OffsetSubtable>::initial().cast::<FontHeader>();
+    let hhea_position = Position::<OffsetSubtable>::initial().cast::<HorizontalHeader>();
+    let long_loca = head_position.index_to_loc_format().read_from(head)? != 0;
+    let long_metrics_count = hhea_position
+        .number_of_long_horizontal_metrics()
+        .read_from(hhea)?;
+    if long_metrics_count == 0 {
+        return Err(FontError::NoHorizontalGlyphMetrics);
+    }
+    let horizontal_metrics = |glyph_id: u16| -> Result<(u16, i16), FontError> {
+        if glyph_id < long_metrics_count {
+            let mut reader = Reader::at(hmtx, usize::from(glyph_id) * 4)?;
+            Ok((reader.u16()?, reader.i16()?))
+        } else {
+            let last = usize::from(long_metrics_count) * 4;
+            let advance = Reader::at(hmtx, last - 4)?.u16()?;
+            let lsb_index = usize::from(glyph_id - long_metrics_count);
+            Ok((advance, Reader::at(hmtx, last + lsb_index * 2)?.i16()?))
+        }
+    };
+
+    let mut glyphs = Vec::with_capacity(usize::from(font.glyph_count));
+    let mut advances = Vec::with_capacity(usize::from(font.glyph_count));
+    let mut left_phantom_points = Vec::with_capacity(usize::from(font.glyph_count));
+    for glyph_id in 0..font.glyph_count {
+        let data = glyph_data(glyf, loca, long_loca, glyph_id)?;
+        let mut glyph = Glyph::parse(data)?;
+        let (advance, left_side_bearing) = horizontal_metrics(glyph_id)?;
+        let x_min = match glyph {
+            Glyph::Empty => 0,
+            _ => Reader::at(data, 2)?.i16()?,
+        };
+
+        // “Phantom points” for horizontal metrics, and vertical metrics (not used here).
+        let left = f32::from(x_min) - f32::from(left_side_bearing);
+        let phantom_points = [
+            (left, 0.),
+            (left + f32::from(advance), 0.),
+            (0., 0.),
+            (0., 0.),
+        ];
+        let (mut points, end_points) = match glyph {
+            Glyph::Empty => (Vec::new(), &[][..]),
+            Glyph::Simple(ref simple) => (
+                simple
+                    .points
+                    .iter()
+                    .map(|point| (f32::from(point.x), f32::from(point.y)))
+                    .collect(),
+                &simple.end_points[..],
+            ),
+            Glyph::Composite(ref composite) => (
+                composite
+                    .components
+                    .iter()
+                    .map(|component| {
+                        if component.flags & ARGS_ARE_XY_VALUES != 0 {
+                            (component.arguments.0 as f32, component.arguments.1 as f32)
+                        } else {
+                            (0., 0.)
+                        }
+                    })
+                    .collect(),
+                &[][..],
+            ),
+        };
+        points.extend_from_slice(&phantom_points);
+        let deltas = match gvar {
+            Some(gvar) => glyph_deltas(gvar, glyph_id, coordinates, &points, end_points)?,
+            None => vec![(0., 0.); points.len()],
+        };
+
+        let round = |value: f32| value.round().max(-32768.).min(32767.) as i16;
+        match glyph {
+            Glyph::Empty => {}
+            Glyph::Simple(ref mut simple) => {
+                for (point, &(dx, dy)) in simple.points.iter_mut().zip(&deltas) {
+                    point.x = round(f32::from(point.x) + dx);
+                    point.y = round(f32::from(point.y) + dy);
+                }
+            }
+            Glyph::Composite(ref mut composite) => {
+                for (component, &(dx, dy)) in composite.components.iter_mut().zip(&deltas) {
+                    if component.flags & ARGS_ARE_XY_VALUES != 0 {
+                        let (x, y) = component.arguments;
+                        component.arguments = (
+                            i32::from(round(x as f32 + dx)),
+                            i32::from(round(y as f32 + dy)),
+                        );
+                    }
+                }
+            }
+        }
+        let phantom_deltas = &deltas[deltas.len() - 4..];
+        let left = left + phantom_deltas[0].0;
+        let advance = match hvar {
+            Some(hvar) => f32::from(advance) + advance_delta(hvar, glyph_id, coordinates)?,
+            None => f32::from(advance) + phantom_deltas[1].0 - phantom_deltas[0].0,
+        };
+        advances.push(advance.round().max(0.).min(65535.) as u16);
+        left_phantom_points.push(round(left));
+        glyphs.push(glyph);
+    }
+
+    let mut new_glyf = Vec::with_capacity(glyf.len());
+    let mut new_loca = Vec::with_capacity((glyphs.len() + 1) * 4);
+    let mut new_hmtx = Vec::with_capacity(glyphs.len() * 4);
+    let mut font_bbox: Option<[i16; 4]> = None;
+    let mut min_left_side_bearing = i16::max_value();
+    let mut min_right_side_bearing = i16::max_value();
+    let mut x_max_extent = i16::min_value();
+    for (glyph_id, glyph) in glyphs.iter().enumerate() {
+        new_loca.extend_from_slice(&(new_glyf.len() as u32).to_be_bytes());
+        let bbox = bounding_box(&outline_points(&glyphs, glyph_id, 0));
+        let advance = advances[glyph_id];
+        let left_side_bearing = bbox[0].wrapping_sub(left_phantom_points[glyph_id]);
+        new_hmtx.extend_from_slice(&advance.to_be_bytes());
+        new_hmtx.extend_from_slice(&left_side_bearing.to_be_bytes());
+        if let Glyph::Empty = glyph {
+            continue;
+        }
+        glyph.write(bbox, &mut new_glyf);
+        while new_glyf.len() % 4 != 0 {
+            new_glyf.push(0)
+        }
+        let width = bbox[2].wrapping_sub(bbox[0]);
+        min_left_side_bearing = min_left_side_bearing.min(left_side_bearing);
+        min_right_side_bearing = min_right_side_bearing.min(
+            (advance as i16)
+                .wrapping_sub(left_side_bearing)
+                .wrapping_sub(width),
+        );
+        x_max_extent = x_max_extent.max(left_side_bearing.wrapping_add(width));
+        font_bbox = Some(match font_bbox {
+            None => bbox,
+            Some(b) => [
+                b[0].min(bbox[0]),
+                b[1].min(bbox[1]),
+                b[2].max(bbox[2]),
+                b[3].max(bbox[3]),
+            ],
+        });
+    }
+    new_loca.extend_from_slice(&(new_glyf.len() as u32).to_be_bytes());
+
+    let mut new_head = head.to_vec();
+    let has_outlines = font_bbox.is_some();
+    let font_bbox = font_bbox.unwrap_or([0; 4]);
+    head_position
+        .min_x()
+        .write_u16(&mut new_head, font_bbox[0] as u16)?;
+    head_position
+        .min_y()
+        .write_u16(&mut new_head, font_bbox[1] as u16)?;
+    head_position
+        .max_x()
+        .write_u16(&mut new_head, font_bbox[2] as u16)?;
+    head_position
+        .max_y()
+        .write_u16(&mut new_head, font_bbox[3] as u16)?;
+    const LONG_OFFSETS: u16 = 1;
+    head_position
+        .index_to_loc_format()
+        .write_u16(&mut new_head, LONG_OFFSETS)?;
+
+    let mut new_hhea = hhea.to_vec();
+    let max_advance = advances.iter().cloned().max().unwrap_or(0);
+    hhea_position
+        .max_advance_width()
+        .write_u16(&mut new_hhea, max_advance)?;
+    if has_outlines {
+        hhea_position
+            .min_left_side_bearing()
+            .write_u16(&mut new_hhea, min_left_side_bearing as u16)?;
+        hhea_position
+            .min_right_side_bearing()
+            .write_u16(&mut new_hhea, min_right_side_bearing as u16)?;
+        hhea_position
+            .x_max_extent()
+            .write_u16(&mut new_hhea, x_max_extent as u16)?;
+    }
+    hhea_position
+        .number_of_long_horizontal_metrics()
+        .write_u16(&mut new_hhea, font.glyph_count)?;
+
+    const VARIATION_TABLES: [&[u8; 4]; 7] = [
+        b"avar", b"cvar", b"fvar", b"gvar", b"HVAR", b"MVAR", b"VVAR",
+    ];
+    let mut new_tables = Vec::with_capacity(tables.len());
+    for &(tag, data) in &tables {
+        if VARIATION_TABLES.iter().any(|t| tag == Tag(**t)) {
+            continue;
+        }
+        let data = match &tag.0 {
+            b"glyf" => &new_glyf[..],
+            b"loca" => &new_loca[..],
+            b"hmtx" => &new_hmtx[..],
+            b"head" => &new_head[..],
+            b"hhea" => &new_hhea[..],
+            _ => data,
+        };
+        new_tables.push((tag, data))
+    }
+    let scaler_type = font.offset_table.scaler_type().read_from(&font.bytes)?;
+    Ok(write_sfnt(scaler_type, 0, new_tables))
+}
+
+/// The points of a glyph’s outline, including those of components of a composite glyph
+fn outline_points(glyphs: &[Glyph], glyph_id: usize, depth: u32) -> Vec<(f32, f32)> {
+    const MAX_COMPONENT_DEPTH: u32 = 16;
+    match glyphs.get(glyph_id) {
+        Some(Glyph::Simple(simple)) => simple
+            .points
+            .iter()
+            .map(|point| (f32::from(point.x), f32::from(point.y)))
+            .collect(),
+        Some(Glyph::Composite(composite)) if depth < MAX_COMPONENT_DEPTH => {
+            let mut points: Vec<(f32, f32)> = Vec::new();
+            for component in &composite.components {
+                let [a, b, c, d] = component.matrix();
+                let component_points: Vec<_> =
+                    outline_points(glyphs, usize::from(component.glyph_id), depth + 1)
+                        .into_iter()
+                        .map(|(x, y)| (a * x + c * y, b * x + d * y))
+                        .collect();
+                let (arg1, arg2) = component.arguments;
+                let (dx, dy) = if component.flags & ARGS_ARE_XY_VALUES != 0 {
+                    (arg1 as f32, arg2 as f32)
+                } else {
+                    // Align a point of the component with a point of the previous components
+                    match (
+                        points.get(arg1 as usize),
+                        component_points.get(arg2 as usize),
+                    ) {
+                        (Some(&(x1, y1)), Some(&(x2, y2))) => (x1 - x2, y1 - y2),
+                        _ => (0., 0.),
+                    }
+                };
+                points.extend(component_points.iter().map(|&(x, y)| (x + dx, y + dy)))
+            }
+            points
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// `[x_min, y_min, x_max, y_max]`
+fn bounding_box(points: &[(f32, f32)]) -> [i16; 4] {
+    if points.is_empty() {
+        return [0; 4];
+    }
+    let mut bbox = [
+        std::f32::INFINITY,
+        std::f32::INFINITY,
+        std::f32::NEG_INFINITY,
+        std::f32::NEG_INFINITY,
+    ];
+    for &(x, y) in points {
+        bbox = [
+            bbox[0].min(x),
+            bbox[1].min(y),
+            bbox[2].max(x),
+            bbox[3].max(y),
+        ]
+    }
+    [
+        bbox[0].floor() as i16,
+        bbox[1].floor() as i16,
+        bbox[2].ceil() as i16,
+        bbox[3].ceil() as i16,
+    ]
+}
+
+/// The total delta for each point of a glyph, including the four phantom points at the end.
+///
+/// For a simple glyph, `end_points` is the index of the last point of each contour.
+/// It is used to infer deltas of points that a variation does not reference.
+///
+/// https://docs.microsoft.com/en-us/typography/opentype/spec/gvar
+fn glyph_deltas(
+    gvar: &[u8],
+    glyph_id: u16,
+    coordinates: &[i16],
+    points: &[(f32, f32)],
+    end_points: &[u16],
+) -> Result<Vec<(f32, f32)>, FontError> {
+    let mut deltas = vec![(0., 0.); points.len()];
+    let header = Position::<OffsetSubtable>::initial().cast::<GlyphVariationsHeader>();
+    let axis_count = usize::from(header.axis_count().read_from(gvar)?);
+    if axis_count != coordinates.len() {
+        return Err(FontError::InvalidVariationData);
+    }
+    if glyph_id >= header.glyph_count().read_from(gvar)? {
+        return Ok(deltas);
+    }
+    const LONG_OFFSETS: u16 = 1;
+    let mut offsets = header.followed_by::<()>().reader(gvar)?;
+    let (start, end) = if header.flags().read_from(gvar)? & LONG_OFFSETS != 0 {
+        offsets.skip(usize::from(glyph_id) * 4)?;
+        (offsets.u32()?, offsets.u32()?)
+    } else {
+        offsets.skip(usize::from(glyph_id) * 2)?;
+        (u32::from(offsets.u16()?) * 2, u32::from(offsets.u16()?) * 2)
+    };
+    let array_offset = header.glyph_variation_data_array_offset().read_from(gvar)?;
+    let data = Reader::at(gvar, (array_offset + start) as usize)?
+        .take(end.saturating_sub(start) as usize)?;
+    if data.is_empty() {
+        return Ok(deltas);
+    }
+
+    let shared_tuple = |index: u16| -> Result<Vec<i16>, FontError> {
+        if index >= header.shared_tuple_count().read_from(gvar)? {
+            return Err(FontError::InvalidVariationData);
+        }
+        let offset = header.shared_tuples_offset().read_from(gvar)? as usize;
+        let mut reader = Reader::at(gvar, offset + usize::from(index) * axis_count * 2)?;
+        (0..axis_count).map(|_| reader.i16()).collect()
+    };
+    let read_tuple = |reader: &mut Reader| -> Result<Vec<i16>, FontError> {
+        (0..axis_count).map(|_| reader.i16()).collect()
+    };
+
+    const SHARED_POINT_NUMBERS: u16 = 0x8000;
+    const COUNT_MASK: u16 = 0x0FFF;
+    const EMBEDDED_PEAK_TUPLE: u16 = 0x8000;
+    const INTERMEDIATE_REGION: u16 = 0x4000;
+    const PRIVATE_POINT_NUMBERS: u16 = 0x2000;
+    const TUPLE_INDEX_MASK: u16 = 0x0FFF;
+
+    let mut headers = Reader::new(data);
+    let tuple_variation_count = headers.u16()?;
+    let mut serialized_data = Reader::at(data, usize::from(headers.u16()?))?;
+    let shared_point_numbers = if tuple_variation_count & SHARED_POINT_NUMBERS != 0 {
+        read_packed_point_numbers(&mut serialized_data)?
+    } else {
+        None
+    };
+    for _ in 0..tuple_variation_count & COUNT_MASK {
+        let variation_data_size = headers.u16()?;
+        let tuple_index = headers.u16()?;
+        let peak = if tuple_index & EMBEDDED_PEAK_TUPLE != 0 {
+            read_tuple(&mut headers)?
+        } else {
+            shared_tuple(tuple_index & TUPLE_INDEX_MASK)?
+        };
+        let intermediate = if tuple_index & INTERMEDIATE_REGION != 0 {
+            Some((read_tuple(&mut headers)?, read_tuple(&mut headers)?))
+        } else {
+            None
+        };
+        let mut tuple_data = Reader::new(serialized_data.take(usize::from(variation_data_size))?);
+
+        let mut scalar = 1.;
+        for (i, (&value, &peak)) in coordinates.iter().zip(&peak).enumerate() {
+            let (start, end) = match intermediate {
+                Some((ref start, ref end)) => (start[i], end[i]),
+                None => (peak.min(0), peak.max(0)),
+            };
+            scalar *= axis_scalar(value, start, peak, end)
+        }
+        if scalar == 0. {
+            continue;
+        }
+
+        let private_point_numbers;
+        let point_numbers = if tuple_index & PRIVATE_POINT_NUMBERS != 0 {
+            private_point_numbers = read_packed_point_numbers(&mut tuple_data)?;
+            &private_point_numbers
+        } else {
+            &shared_point_numbers
+        };
+        match point_numbers {
+            None => {
+                let x_deltas = read_packed_deltas(&mut tuple_data, points.len())?;
+                let y_deltas = read_packed_deltas(&mut tuple_data, points.len())?;
+                for (delta, (dx, dy)) in deltas.iter_mut().zip(x_deltas.into_iter().zip(y_deltas)) {
+                    delta.0 += scalar * dx;
+                    delta.1 += scalar * dy;
+                }
+            }
+            Some(numbers) => {
+                let x_deltas = read_packed_deltas(&mut tuple_data, numbers.len())?;
+                let y_deltas = read_packed_deltas(&mut tuple_data, numbers.len())?;
+                let mut tuple_deltas = vec![None; points.len()];
+                for (&number, (dx, dy)) in numbers.iter().zip(x_deltas.into_iter().zip(y_deltas)) {
+                    if let Some(delta) = tuple_deltas.get_mut(usize::from(number)) {
+                        *delta = Some((dx, dy))
+                    }
+                }
+                infer_deltas(&mut tuple_deltas, points, end_points);
+                for (delta, tuple_delta) in deltas.iter_mut().zip(tuple_deltas) {
+                    if let Some((dx, dy)) = tuple_delta {
+                        delta.0 += scalar * dx;
+                        delta.1 += scalar * dy;
+                    }
+                }
+            }
+        }
+    }
+    Ok(deltas)
+}
+
+/// `None` means all points of the glyph.
+///
+/// https://docs.microsoft.com/en-us/typography/opentype/spec/otvarcommonformats#packed-point-numbers
+fn read_packed_point_numbers(reader: &mut Reader) -> Result<Option<Vec<u16>>, FontError> {
+    const POINTS_ARE_WORDS: u8 = 0x80;
+    const POINT_RUN_COUNT_MASK: u8 = 0x7F;
+
+    let first = reader.u8()?;
+    let count = if first & POINTS_ARE_WORDS != 0 {
+        usize::from(first & POINT_RUN_COUNT_MASK) << 8 | usize::from(reader.u8()?)
+    } else {
+        usize::from(first)
+    };
+    if count == 0 {
+        return Ok(None);
+    }
+    let mut numbers = Vec::with_capacity(count);
+    let mut number: u16 = 0;
+    while numbers.len() < count {
+        let control = reader.u8()?;
+        for _ in 0..=control & POINT_RUN_COUNT_MASK {
+            let difference = if control & POINTS_ARE_WORDS != 0 {
+                reader.u16()?
+            } else {
+                u16::from(reader.u8()?)
+            };
+            number = number.wrapping_add(difference);
+            numbers.push(number)
+        }
+    }
+    numbers.truncate(count);
+    Ok(Some(numbers))
+}
+
+/// https://docs.microsoft.com/en-us/typography/opentype/spec/otvarcommonformats#packed-deltas
+fn read_packed_deltas(reader: &mut Reader, count: usize) -> Result<Vec<f32>, FontError> {
+    const DELTAS_ARE_ZERO: u8 = 0x80;
+    const DELTAS_ARE_WORDS: u8 = 0x40;
+    const DELTAS_ARE_LONGS: u8 = 0xC0;
+    const DELTA_RUN_COUNT_MASK: u8 = 0x3F;
+
+    let mut deltas = Vec::with_capacity(count);
+    while deltas.len() < count {
+        let control = reader.u8()?;
+        for _ in 0..=control & DELTA_RUN_COUNT_MASK {
+            let delta = match control & DELTAS_ARE_LONGS {
+                DELTAS_ARE_LONGS => reader.i32()?,
+                DELTAS_ARE_ZERO => 0,
+                DELTAS_ARE_WORDS => i32::from(reader.i16()?),
+                _ => i32::from(reader.i8()?),
+            };
+            deltas.push(delta as f32)
+        }
+    }
+    deltas.truncate(count);
+    Ok(deltas)
+}
+
+/// Interpolate deltas for the points of each contour not referenced by a variation.
+///
+/// https://docs.microsoft.com/en-us/typography/opentype/spec/gvar#inferred-deltas-for-un-referenced-point-numbers
+fn infer_deltas(deltas: &mut [Option<(f32, f32)>], points: &[(f32, f32)], end_points: &[u16]) {
+    fn interpolate(value: f32, (value1, delta1): (f32, f32), (value2, delta2): (f32, f32)) -> f32 {
+        let ((value1, delta1), (value2, delta2)) = if value1 <= value2 {
+            ((value1, delta1), (value2, delta2))
+        } else {
+            ((value2, delta2), (value1, delta1))
+        };
+        if value1 == value2 {
+            if delta1 == delta2 {
+                delta1
+            } else {
+                0.
+            }
+        } else if value <= value1 {
+            delta1
+        } else if value >= value2 {
+            delta2
+        } else {
+            delta1 + (value - value1) * (delta2 - delta1) / (value2 - value1)
+        }
+    }
+
+    let mut start = 0;
+    for &end in end_points {
+        let end = usize::from(end);
+        if end < start || end >= deltas.len() {
+            break;
+        }
+        let touched: Vec<usize> = (start..=end).filter(|&i| deltas[i].is_some()).collect();
+        for (k, &first) in touched.iter().enumerate() {
+            let second = touched[(k + 1) % touched.len()];
+            let (dx1, dy1) = deltas[first].unwrap();
+            let (dx2, dy2) = deltas[second].unwrap();
+            let (x1, y1) = points[first];
+            let (x2, y2) = points[second];
+            // Untouched points between `first` and `second`, wrapping around the contour
+            let mut i = first;
+            loop {
+                i = if i == end { start } else { i + 1 };
+                if i == second {
+                    break;
+                }
+                let (x, y) = points[i];
+                deltas[i] = Some((
+                    interpolate(x, (x1, dx1), (x2, dx2)),
+                    interpolate(y, (y1, dy1), (y2, dy2)),
+                ))
+            }
+        }
+        start = end + 1
+    }
+}
+
+/// https://docs.microsoft.com/en-us/typography/opentype/spec/hvar
+fn advance_delta(hvar: &[u8], glyph_id: u16, coordinates: &[i16]) -> Result<f32, FontError> {
+    let header = Position::<OffsetSubtable>::initial().cast::<HorizontalMetricsVariationsHeader>();
+    let mapping_offset = header.advance_width_mapping_offset().read_from(hvar)?;
+    let (outer, inner) = if mapping_offset == 0 {
+        (0, glyph_id)
+    } else {
+        delta_set_index(Reader::at(hvar, mapping_offset as usize)?, glyph_id)?
+    };
+    let store_offset = header.item_variation_store_offset().read_from(hvar)? as usize;
+    item_variation_delta(
+        hvar.get(store_offset..).ok_or(FontError::OffsetBeyondEof)?,
+        outer,
+        inner,
+        coordinates,
+    )
+}
+
+/// `(outer, inner)` indices into an item variation store
+///
+/// https://docs.microsoft.com/en-us/typography/opentype/spec/otvarcommonformats#associating-target-items-to-variation-data
+fn delta_set_index(mut map: Reader, index: u16) -> Result<(u16, u16), FontError> {
+    const INNER_INDEX_BIT_COUNT_MASK: u8 = 0x0F;
+    const MAP_ENTRY_SIZE_MASK: u8 = 0x30;
+
+    let format = map.u8()?;
+    let entry_format = map.u8()?;
+    let map_count = if format == 0 {
+        u32::from(map.u16()?)
+    } else {
+        map.u32()?
+    };
+    if map_count == 0 {
+        return Err(FontError::InvalidVariationData);
+    }
+    let entry_size = usize::from((entry_format & MAP_ENTRY_SIZE_MASK) >> 4) + 1;
+    let inner_bit_count = u32::from(entry_format & INNER_INDEX_BIT_COUNT_MASK) + 1;
+    // Indices beyond the end of the map use the last entry.
+    map.skip(u32::from(index).min(map_count - 1) as usize * entry_size)?;
+    let entry = map
+        .take(entry_size)?
+        .iter()
+        .fold(0_u32, |entry, &byte| entry << 8 | u32::from(byte));
+    Ok((
+        (entry >> inner_bit_count) as u16,
+        (entry & ((1 << inner_bit_count) - 1)) as u16,
+    ))
+}
+
+/// https://docs.microsoft.com/en-us/typography/opentype/spec/otvarcommonformats#item-variation-store
+fn item_variation_delta(
+    store: &[u8],
+    outer: u16,
+    inner: u16,
+    coordinates: &[i16],
+) -> Result<f32, FontError> {
+    let mut header = Reader::new(store);
+    let _format = header.u16()?;
+    let region_list_offset = header.u32()? as usize;
+    if outer >= header.u16()? {
+        return Ok(0.);
+    }
+    header.skip(usize::from(outer) * 4)?;
+    let mut data = Reader::at(store, header.u32()? as usize)?;
+    let item_count = data.u16()?;
+    let word_delta_count = data.u16()?;
+    let region_indices = (0..data.u16()?)
+        .map(|_| data.u16())
+        .collect::<Result<Vec<_>, _>>()?;
+    if inner >= item_count {
+        return Ok(0.);
+    }
+
+    const LONG_WORDS: u16 = 0x8000;
+    const WORD_DELTA_COUNT_MASK: u16 = 0x7FFF;
+    let long_words = word_delta_count & LONG_WORDS != 0;
+    let word_count = usize::from(word_delta_count & WORD_DELTA_COUNT_MASK);
+    if word_count > region_indices.len() {
+        return Err(FontError::InvalidVariationData);
+    }
+    let short_count = region_indices.len() - word_count;
+    let row_size = if long_words {
+        word_count * 4 + short_count * 2
+    } else {
+        word_count * 2 + short_count
+    };
+    data.skip(usize::from(inner) * row_size)?;
+
+    let mut regions = Reader::at(store, region_list_offset)?;
+    let axis_count = usize::from(regions.u16()?);
+    let region_count = regions.u16()?;
+    if axis_count != coordinates.len() {
+        return Err(FontError::InvalidVariationData);
+    }
+    let mut total = 0.;
+    for (k, &region_index) in region_indices.iter().enumerate() {
+        let delta = match (k < word_count, long_words) {
+            (true, true) => data.i32()?,
+            (true, false) | (false, true) => i32::from(data.i16()?),
+            (false, false) => i32::from(data.i8()?),
+        };
+        if region_index >= region_count {
+            continue;
+        }
+        let mut region = Reader::at(
+            store,
+            region_list_offset + 4 + usize::from(region_index) * axis_count * 6,
+        )?;
+        let mut scalar = 1.;
+        for &value in coordinates {
+            let (start, peak, end) = (region.i16()?, region.i16()?, region.i16()?);
+            scalar *= axis_scalar(value, start, peak, end)
+        }
+        total += scalar * delta as f32
+    }
+    Ok(total)
+}
+
+#[test]
+fn malformed_segment_maps() {
+    let map = [(-16384, -16384), (0, 0), (8192, 12288), (16384, 16384)];
+    assert_eq!(apply_segment_map(&map, 4096), 6144);
+    // Differences between values from the font do not fit in `i16`
+    let extreme = [(i16::MIN, i16::MAX), (i16::MAX, i16::MIN)];
+    assert_eq!(apply_segment_map(&extreme, 0), -1);
+    // Unsorted, with repeated values
+    let unsorted = [(0, 0), (-8192, 4096), (-8192, -4096), (16384, 16384)];
+    assert_eq!(apply_segment_map(&unsorted, 8192), 9557);
+    assert_eq!(apply_segment_map(&unsorted, -8192), -8192);
+}
@@ -0,0 +1,46 @@
+//! WOFF 1.0 https://www.w3.org/TR/WOFF/
+
+use crate::fonts::parsing::*;
+use crate::fonts::tables::*;
+use crate::fonts::{write_sfnt, FontError};
+use std::borrow::Cow;
+use std::io::Read;
+
+pub(in crate::fonts) const SIGNATURE: u32 = 0x774F4646; // "wOFF" in big-endian
+
+/// Decompress a WOFF file into the sfnt (TrueType or OpenType) font it contains.
+pub(in crate::fonts) fn decompress(bytes: &[u8]) -> Result<Vec<u8>, FontError> {
+    let header = Position::<OffsetSubtable>::initial().cast::<WoffHeader>();
+    let table_directory = Slice::new(
+        header.followed_by::<WoffTableDirectoryEntry>(),
+        header.num_tables().read_from(bytes)?,
+    );
+    let mut tables = Vec::with_capacity(table_directory.count() as usize);
+    for entry in table_directory {
+        let data = Slice::<u8>::new(
+            Position::<OffsetSubtable>::initial()
+                .offset_bytes(entry.table_offset().read_from(bytes)?),
+            entry.compressed_length().read_from(bytes)?,
+        )
+        .read_from(bytes)?;
+        let original_length = entry.original_length().read_from(bytes)?;
+        let data = if data.len() as u32 == original_length {
+            Cow::Borrowed(data)
+        } else {
+            let mut decompressed = Vec::with_capacity(original_length as usize);
+            flate2::read::ZlibDecoder::new(data)
+                .read_to_end(&mut decompressed)
+                .map_err(|_| FontError::InvalidWebFont)?;
+            if decompressed.len() as u32 != original_length {
+                return Err(FontError::InvalidWebFont);
+            }
+            Cow::Owned(decompressed)
+        };
+        tables.push((entry.tag().read_from(bytes)?, data))
+    }
+    Ok(write_sfnt(
+        header.flavor().read_from(bytes)?,
+        header.total_sfnt_size().read_from(bytes)?,
+        tables,
+    ))
+}
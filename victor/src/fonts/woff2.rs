@@ -0,0 +1,421 @@
+//! WOFF 2.0 https://www.w3.org/TR/WOFF2/
+
+use crate::fonts::glyf::{encode_simple_glyph_points, Point};
+use crate::fonts::parsing::*;
+use crate::fonts::tables::*;
+use crate::fonts::types::Tag;
+use crate::fonts::{write_sfnt, FontError};
+use std::io::Read;
+
+pub(in crate::fonts) const SIGNATURE: u32 = 0x774F4632; // "wOF2" in big-endian
+
+/// https://www.w3.org/TR/WOFF2/#table_dir_format
+const KNOWN_TAGS: [&[u8; 4]; 63] = [
+    b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post", b"cvt ", b"fpgm",
+    b"glyf", b"loca", b"prep", b"CFF ", b"VORG", b"EBDT", b"EBLC", b"gasp", b"hdmx", b"kern",
+    b"LTSH", b"PCLT", b"VDMX", b"vhea", b"vmtx", b"BASE", b"GDEF", b"GPOS", b"GSUB", b"EBSC",
+    b"JSTF", b"MATH", b"CBDT", b"CBLC", b"COLR", b"CPAL", b"SVG ", b"sbix", b"acnt", b"avar",
+    b"bdat", b"bloc", b"bsln", b"cvar", b"fdsc", b"feat", b"fmtx", b"fvar", b"gvar", b"hsty",
+    b"just", b"lcar", b"mort", b"morx", b"opbd", b"prop", b"trak", b"Zapf", b"Silf", b"Glat",
+    b"Gloc", b"Feat", b"Sill",
+];
+
+const GLYF: Tag = Tag(*b"glyf");
+const LOCA: Tag = Tag(*b"loca");
+const HMTX: Tag = Tag(*b"hmtx");
+
+struct TableDirectoryEntry {
+    tag: Tag,
+    original_length: u32,
+    /// The length in the decompressed stream, if different from `original_length`
+    transform_length: Option<u32>,
+}
+
+/// Decompress a WOFF2 file into the sfnt (TrueType or OpenType) font it contains.
+pub(in crate::fonts) fn decompress(bytes: &[u8]) -> Result<Vec<u8>, FontError> {
+    let header = Position::<OffsetSubtable>::initial().cast::<Woff2Header>();
+    let flavor = header.flavor().read_from(bytes)?;
+    const COLLECTION: u32 = 0x74746366; // "ttcf" in big-endian
+    if flavor == COLLECTION {
+        return Err(FontError::UnsupportedFormat);
+    }
+
+    let mut directory = Reader::new(bytes);
+    directory.skip(std::mem::size_of::<Woff2Header>())?;
+    let mut entries = Vec::new();
+    for _ in 0..header.num_tables().read_from(bytes)? {
+        let flags = directory.u8()?;
+        let tag = match flags & 0x3F {
+            0x3F => {
+                let b = directory.take(4)?;
+                Tag([b[0], b[1], b[2], b[3]])
+            }
+            index => Tag(*KNOWN_TAGS[index as usize]),
+        };
+        let original_length = directory.uint_base128()?;
+        // For `glyf` and `loca` transform version 0 is the transformed one,
+        // for other tables it is the null transform.
+        let transform_version = flags >> 6;
+        let transformed = if tag == GLYF || tag == LOCA {
+            transform_version == 0
+        } else {
+            transform_version != 0
+        };
+        let transform_length = if transformed {
+            Some(directory.uint_base128()?)
+        } else {
+            None
+        };
+        entries.push(TableDirectoryEntry {
+            tag,
+            original_length,
+            transform_length,
+        })
+    }
+
+    let compressed = directory.take(header.total_compressed_size().read_from(bytes)? as usize)?;
+    let mut decompressed = Vec::new();
+    brotli_decompressor::Decompressor::new(compressed, 4096)
+        .read_to_end(&mut decompressed)
+        .map_err(|_| FontError::InvalidWebFont)?;
+
+    let mut stream = Reader::new(&decompressed);
+    let mut tables = Vec::with_capacity(entries.len());
+    for entry in &entries {
+        let length = entry.transform_length.unwrap_or(entry.original_length);
+        tables.push((entry.tag, stream.take(length as usize)?.to_vec()));
+    }
+
+    let find = |tables: &[(Tag, Vec<u8>)], tag: Tag| tables.iter().position(|&(t, _)| t == tag);
+    let transformed = |tag: Tag| {
+        entries
+            .iter()
+            .any(|entry| entry.tag == tag && entry.transform_length.is_some())
+    };
+    let mut glyph_x_mins = None;
+    if transformed(GLYF) {
+        let glyf = find(&tables, GLYF).ok_or(FontError::InvalidWebFont)?;
+        let loca = find(&tables, LOCA).ok_or(FontError::InvalidWebFont)?;
+        let reconstructed = reconstruct_glyf_and_loca(&tables[glyf].1)?;
+        tables[glyf].1 = reconstructed.glyf;
+        tables[loca].1 = reconstructed.loca;
+        glyph_x_mins = Some(reconstructed.x_mins);
+    }
+    if transformed(HMTX) {
+        let hmtx = find(&tables, HMTX).ok_or(FontError::InvalidWebFont)?;
+        let hhea = find(&tables, Tag(*b"hhea")).ok_or(FontError::InvalidWebFont)?;
+        let number_of_long_horizontal_metrics = Position::<OffsetSubtable>::initial()
+            .cast::<HorizontalHeader>()
+            .number_of_long_horizontal_metrics()
+            .read_from(&tables[hhea].1)?;
+        let x_mins = glyph_x_mins.ok_or(FontError::InvalidWebFont)?;
+        tables[hmtx].1 =
+            reconstruct_hmtx(&tables[hmtx].1, number_of_long_horizontal_metrics, &x_mins)?;
+    }
+
+    Ok(write_sfnt(
+        flavor,
+        header.total_sfnt_size().read_from(bytes)?,
+        tables,
+    ))
+}
+
+struct ReconstructedGlyf {
+    glyf: Vec<u8>,
+    loca: Vec<u8>,
+
+    /// Used to reconstruct left side bearings in `hmtx`
+    x_mins: Vec<i16>,
+}
+
+/// https://www.w3.org/TR/WOFF2/#glyf_table_format
+fn reconstruct_glyf_and_loca(transformed: &[u8]) -> Result<ReconstructedGlyf, FontError> {
+    let mut header = Reader::new(transformed);
+    let _reserved = header.u16()?;
+    let option_flags = header.u16()?;
+    let glyph_count = header.u16()?;
+    let long_offsets = header.u16()? != 0;
+    let mut stream_lengths = [0_u32; 7];
+    for length in &mut stream_lengths {
+        *length = header.u32()?
+    }
+    let mut substream = |i: usize| header.take(stream_lengths[i] as usize).map(Reader::new);
+    let mut contour_counts = substream(0)?;
+    let mut point_counts = substream(1)?;
+    let mut flags = substream(2)?;
+    let mut glyphs = substream(3)?;
+    let mut composites = substream(4)?;
+    let mut bboxes = substream(5)?;
+    let mut instructions = substream(6)?;
+    const HAS_OVERLAP_SIMPLE_BITMAP: u16 = 1;
+    let overlap_bitmap = if option_flags & HAS_OVERLAP_SIMPLE_BITMAP != 0 {
+        Some(header.take((glyph_count as usize + 7) / 8)?)
+    } else {
+        None
+    };
+
+    let bbox_bitmap = bboxes.take(((glyph_count as usize + 31) / 32) * 4)?;
+    let bit_is_set = |bitmap: &[u8], index: usize| bitmap[index / 8] & (0x80 >> (index % 8)) != 0;
+
+    let mut glyf = Vec::new();
+    let mut loca = Vec::new();
+    let mut x_mins = Vec::with_capacity(glyph_count as usize);
+    let write_loca = |loca: &mut Vec<u8>, offset: usize| {
+        if long_offsets {
+            loca.extend_from_slice(&(offset as u32).to_be_bytes())
+        } else {
+            loca.extend_from_slice(&((offset / 2) as u16).to_be_bytes())
+        }
+    };
+    for glyph in 0..glyph_count as usize {
+        write_loca(&mut loca, glyf.len());
+        let explicit_bbox = if bit_is_set(bbox_bitmap, glyph) {
+            Some([bboxes.i16()?, bboxes.i16()?, bboxes.i16()?, bboxes.i16()?])
+        } else {
+            None
+        };
+        let contour_count = contour_counts.i16()?;
+        match contour_count {
+            0 => {
+                if explicit_bbox.is_some() {
+                    return Err(FontError::InvalidWebFont);
+                }
+                x_mins.push(0);
+                continue;
+            }
+            -1 => {
+                // Composite glyph
+                let bbox = explicit_bbox.ok_or(FontError::InvalidWebFont)?;
+                glyf.extend_from_slice(&contour_count.to_be_bytes());
+                for &value in &bbox {
+                    glyf.extend_from_slice(&value.to_be_bytes());
+                }
+                let have_instructions = copy_composite_glyph(&mut composites, &mut glyf)?;
+                if have_instructions {
+                    let length = glyphs.u16_255()?;
+                    glyf.extend_from_slice(&length.to_be_bytes());
+                    glyf.extend_from_slice(instructions.take(length as usize)?);
+                }
+                x_mins.push(bbox[0]);
+            }
+            _ if contour_count > 0 => {
+                let mut end_points = Vec::with_capacity(contour_count as usize);
+                let mut point_count: u16 = 0;
+                for _ in 0..contour_count {
+                    point_count = point_count
+                        .checked_add(point_counts.u16_255()?)
+                        .ok_or(FontError::InvalidWebFont)?;
+                    end_points.push(point_count.wrapping_sub(1));
+                }
+                let point_flags = flags.take(point_count as usize)?;
+                let points = decode_triplets(point_flags, &mut glyphs)?;
+                let instruction_length = glyphs.u16_255()?;
+
+                let bbox = match explicit_bbox {
+                    Some(bbox) => bbox,
+                    None => points.iter().fold(
+                        [
+                            i16::max_value(),
+                            i16::max_value(),
+                            i16::min_value(),
+                            i16::min_value(),
+                        ],
+                        |[x_min, y_min, x_max, y_max], point| {
+                            [
+                                x_min.min(point.x),
+                                y_min.min(point.y),
+                                x_max.max(point.x),
+                                y_max.max(point.y),
+                            ]
+                        },
+                    ),
+                };
+                glyf.extend_from_slice(&contour_count.to_be_bytes());
+                for &value in &bbox {
+                    glyf.extend_from_slice(&value.to_be_bytes());
+                }
+                for end_point in end_points {
+                    glyf.extend_from_slice(&end_point.to_be_bytes());
+                }
+                glyf.extend_from_slice(&instruction_length.to_be_bytes());
+                glyf.extend_from_slice(instructions.take(instruction_length as usize)?);
+                let overlap = match overlap_bitmap {
+                    Some(bitmap) => bit_is_set(bitmap, glyph),
+                    None => false,
+                };
+                encode_simple_glyph_points(&points, overlap, &mut glyf);
+                x_mins.push(bbox[0]);
+            }
+            _ => return Err(FontError::InvalidWebFont),
+        }
+        let padding = if long_offsets { 4 } else { 2 };
+        while glyf.len() % padding != 0 {
+            glyf.push(0)
+        }
+    }
+    write_loca(&mut loca, glyf.len());
+    Ok(ReconstructedGlyf { glyf, loca, x_mins })
+}
+
+/// https://www.w3.org/TR/WOFF2/#triplet_decoding
+fn decode_triplets(flags: &[u8], glyphs: &mut Reader) -> Result<Vec<Point>, FontError> {
+    fn with_sign(flag: u8, value: i32) -> i32 {
+        if flag & 1 != 0 {
+            value
+        } else {
+            -value
+        }
+    }
+
+    let mut points = Vec::with_capacity(flags.len());
+    let (mut x, mut y) = (0_i32, 0_i32);
+    for &flag in flags {
+        let on_curve = flag & 0x80 == 0;
+        let flag = flag & 0x7F;
+        let (dx, dy) = if flag < 10 {
+            let b = i32::from(glyphs.u8()?);
+            (0, with_sign(flag, (i32::from(flag & 14) << 7) + b))
+        } else if flag < 20 {
+            let b = i32::from(glyphs.u8()?);
+            (with_sign(flag, (i32::from((flag - 10) & 14) << 7) + b), 0)
+        } else if flag < 84 {
+            let b0 = i32::from(flag - 20);
+            let b1 = i32::from(glyphs.u8()?);
+            (
+                with_sign(flag, 1 + (b0 & 0x30) + (b1 >> 4)),
+                with_sign(flag >> 1, 1 + ((b0 & 0x0C) << 2) + (b1 & 0x0F)),
+            )
+        } else if flag < 120 {
+            let b0 = i32::from(flag - 84);
+            let (b1, b2) = (i32::from(glyphs.u8()?), i32::from(glyphs.u8()?));
+            (
+                with_sign(flag, 1 + ((b0 / 12) << 8) + b1),
+                with_sign(flag >> 1, 1 + (((b0 % 12) >> 2) << 8) + b2),
+            )
+        } else if flag < 124 {
+            let b = glyphs.take(3)?;
+            let (b0, b1, b2) = (i32::from(b[0]), i32::from(b[1]), i32::from(b[2]));
+            (
+                with_sign(flag, (b0 << 4) + (b1 >> 4)),
+                with_sign(flag >> 1, ((b1 & 0x0F) << 8) + b2),
+            )
+        } else {
+            let (dx, dy) = (i32::from(glyphs.u16()?), i32::from(glyphs.u16()?));
+            (with_sign(flag, dx), with_sign(flag >> 1, dy))
+        };
+        x += dx;
+        y += dy;
+        points.push(Point {
+            x: x as i16,
+            y: y as i16,
+            on_curve,
+        })
+    }
+    Ok(points)
+}
+
+/// Copy component records from the composite stream,
+/// and return whether the glyph has instructions.
+///
+/// https://docs.microsoft.com/en-us/typography/opentype/spec/glyf#composite-glyph-description
+fn copy_composite_glyph(composites: &mut Reader, glyf: &mut Vec<u8>) -> Result<bool, FontError> {
+    const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+    const WE_HAVE_A_SCALE: u16 = 0x0008;
+    const MORE_COMPONENTS: u16 = 0x0020;
+    const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+    const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+    const WE_HAVE_INSTRUCTIONS: u16 = 0x0100;
+
+    let mut have_instructions = false;
+    loop {
+        let flags = composites.u16()?;
+        have_instructions |= flags & WE_HAVE_INSTRUCTIONS != 0;
+        let mut length = 2; // glyph index
+        length += if flags & ARG_1_AND_2_ARE_WORDS != 0 {
+            4
+        } else {
+            2
+        };
+        if flags & WE_HAVE_A_SCALE != 0 {
+            length += 2
+        } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+            length += 4
+        } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+            length += 8
+        }
+        glyf.extend_from_slice(&flags.to_be_bytes());
+        glyf.extend_from_slice(composites.take(length)?);
+        if flags & MORE_COMPONENTS == 0 {
+            return Ok(have_instructions);
+        }
+    }
+}
+
+/// https://www.w3.org/TR/WOFF2/#hmtx_table_format
+fn reconstruct_hmtx(
+    transformed: &[u8],
+    number_of_long_horizontal_metrics: u16,
+    glyph_x_mins: &[i16],
+) -> Result<Vec<u8>, FontError> {
+    const PROPORTIONAL_LSBS_ABSENT: u8 = 1;
+    const MONOSPACED_LSBS_ABSENT: u8 = 2;
+
+    let long_metrics_count = number_of_long_horizontal_metrics as usize;
+    if long_metrics_count == 0 || long_metrics_count > glyph_x_mins.len() {
+        return Err(FontError::InvalidWebFont);
+    }
+    let mut reader = Reader::new(transformed);
+    let flags = reader.u8()?;
+    let advance_widths = reader.take(long_metrics_count * 2)?;
+    let mut left_side_bearings = Vec::with_capacity(glyph_x_mins.len());
+    for (glyph, &x_min) in glyph_x_mins.iter().enumerate() {
+        let absent = if glyph < long_metrics_count {
+            flags & PROPORTIONAL_LSBS_ABSENT != 0
+        } else {
+            flags & MONOSPACED_LSBS_ABSENT != 0
+        };
+        left_side_bearings.push(if absent { x_min } else { reader.i16()? })
+    }
+
+    let mut hmtx = Vec::with_capacity(long_metrics_count * 2 + glyph_x_mins.len() * 2);
+    for (glyph, left_side_bearing) in left_side_bearings.iter().enumerate() {
+        if glyph < long_metrics_count {
+            hmtx.extend_from_slice(&advance_widths[glyph * 2..][..2]);
+        }
+        hmtx.extend_from_slice(&left_side_bearing.to_be_bytes())
+    }
+    Ok(hmtx)
+}
+
+impl<'a> Reader<'a> {
+    /// https://www.w3.org/TR/WOFF2/#DataTypes
+    fn uint_base128(&mut self) -> Result<u32, FontError> {
+        let mut accumulator: u32 = 0;
+        for i in 0..5 {
+            let byte = self.u8()?;
+            // No leading zeros, no overflow
+            if (i == 0 && byte == 0x80) || accumulator & 0xFE00_0000 != 0 {
+                return Err(FontError::InvalidWebFont);
+            }
+            accumulator = (accumulator << 7) | u32::from(byte & 0x7F);
+            if byte & 0x80 == 0 {
+                return Ok(accumulator);
+            }
+        }
+        Err(FontError::InvalidWebFont)
+    }
+
+    /// https://www.w3.org/TR/WOFF2/#DataTypes
+    fn u16_255(&mut self) -> Result<u16, FontError> {
+        const ONE_MORE_BYTE_CODE_1: u8 = 255;
+        const ONE_MORE_BYTE_CODE_2: u8 = 254;
+        const WORD_CODE: u8 = 253;
+        const LOWEST_U_CODE: u16 = 253;
+        match self.u8()? {
+            WORD_CODE => self.u16(),
+            ONE_MORE_BYTE_CODE_1 => Ok(u16::from(self.u8()?) + LOWEST_U_CODE),
+            ONE_MORE_BYTE_CODE_2 => Ok(u16::from(self.u8()?) + LOWEST_U_CODE * 2),
+            code => Ok(u16::from(code)),
+        }
+    }
+}
@@ -283,27 +283,37 @@ impl<T> flow_relative::Rect<T> {
 
     pub fn to_physical(
         &self,
+        // The writing mode of the containing block, in which `self` is expressed
         mode: (WritingMode, Direction),
-        // Will be needed for other writing modes
-        // FIXME: what if the containing block has a different mode?
-        // https://drafts.csswg.org/css-writing-modes/#orthogonal-flows
-        _containing_block: &physical::Rect<T>,
+        containing_block: &physical::Rect<T>,
     ) -> physical::Rect<T>
     where
-        T: Clone,
+        T: Add<Output = T> + Sub<Output = T> + Copy,
     {
+        use Direction::*;
+        use WritingMode::*;
+
+        // https://drafts.csswg.org/css-writing-modes/#logical-to-physical
+        let size = self.size.size_to_physical(mode);
+        let cb_size = &containing_block.size;
+        let (inline, block) = (self.start_corner.inline, self.start_corner.block);
         // Top-left corner
-        let (tl_x, tl_y) = if let (WritingMode::HorizontalTb, Direction::Ltr) = mode {
-            (&self.start_corner.inline, &self.start_corner.block)
-        } else {
-            unimplemented!()
+        let tl_x = match mode {
+            (HorizontalTb, Ltr) => inline,
+            (HorizontalTb, Rtl) => cb_size.x - inline - size.x,
+            (VerticalRl, _) | (SidewaysRl, _) => cb_size.x - block - size.x,
+            (VerticalLr, _) | (SidewaysLr, _) => block,
+        };
+        let tl_y = match mode {
+            (HorizontalTb, _) => block,
+            (VerticalRl, Ltr) | (SidewaysRl, Ltr) | (VerticalLr, Ltr) | (SidewaysLr, Rtl) => inline,
+            (VerticalRl, Rtl) | (SidewaysRl, Rtl) | (VerticalLr, Rtl) | (SidewaysLr, Ltr) => {
+                cb_size.y - inline - size.y
+            }
         };
         physical::Rect {
-            top_left: physical::Vec2 {
-                x: tl_x.clone(),
-                y: tl_y.clone(),
-            },
-            size: self.size.size_to_physical(mode),
+            top_left: physical::Vec2 { x: tl_x, y: tl_y },
+            size,
         }
     }
 }
@@ -0,0 +1,188 @@
+//! Hyphenation with Frank Liang’s algorithm, using the same patterns as TeX.
+//!
+//! No patterns are built in: callers must `register` them for each language,
+//! for example from the `hyph-*.tex` files of a TeX distribution.
+//! Without patterns for the `lang` of some content, `hyphens: auto` behaves like
+//! `hyphens: manual` and lines only break with a hyphen at soft hyphens (U+00AD).
+//!
+//! https://tug.org/docs/liang/
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Minimum number of characters before and after a hyphen, as `\lefthyphenmin`
+/// and `\righthyphenmin` in plain TeX.
+const LEFT_MIN: usize = 2;
+const RIGHT_MIN: usize = 3;
+
+pub struct Patterns {
+    /// The letters of each pattern, with the value before each letter and after the last one
+    patterns: HashMap<String, Vec<u8>>,
+
+    /// Length of the longest pattern, in characters
+    max_len: usize,
+
+    /// Words hyphenated explicitly, with the character positions of their hyphens
+    exceptions: HashMap<String, Vec<usize>>,
+}
+
+impl Patterns {
+    /// Parse hyphenation patterns in the syntax of TeX, such as `.hy3ph he2n`
+    /// separated by white space, typically in a `\patterns{…}` group.
+    /// Exceptions like `ta-ble` can be given in a `\hyphenation{…}` group.
+    /// `%` starts a comment until the end of the line.
+    pub fn parse(source: &str) -> Self {
+        let mut patterns = Patterns {
+            patterns: HashMap::new(),
+            max_len: 0,
+            exceptions: HashMap::new(),
+        };
+        let mut in_exceptions = false;
+        let tokens = source
+            .lines()
+            .map(|line| line.split('%').next().unwrap())
+            .flat_map(|line| line.split(|c: char| c.is_whitespace() || c == '{' || c == '}'))
+            .filter(|token| !token.is_empty());
+        for token in tokens {
+            match token {
+                "\\patterns" => in_exceptions = false,
+                "\\hyphenation" => in_exceptions = true,
+                _ if in_exceptions => patterns.add_exception(token),
+                _ => patterns.add_pattern(token),
+            }
+        }
+        patterns
+    }
+
+    fn add_pattern(&mut self, pattern: &str) {
+        let mut letters = String::new();
+        let mut values = vec![0];
+        for ch in pattern.chars() {
+            match ch.to_digit(10) {
+                Some(digit) => *values.last_mut().unwrap() = digit as u8,
+                None => {
+                    letters.extend(ch.to_lowercase());
+                    values.push(0)
+                }
+            }
+        }
+        self.max_len = self.max_len.max(values.len() - 1);
+        self.patterns.insert(letters, values);
+    }
+
+    fn add_exception(&mut self, word: &str) {
+        let mut letters = String::new();
+        let mut positions = Vec::new();
+        for ch in word.chars() {
+            if ch == '-' {
+                positions.push(letters.chars().count())
+            } else {
+                letters.extend(ch.to_lowercase())
+            }
+        }
+        self.exceptions.insert(letters, positions);
+    }
+
+    /// Byte offsets in `word` where a line may break with a hyphen
+    pub fn hyphenate(&self, word: &str) -> Vec<usize> {
+        // Keep one character per character of `word`, to map positions back to it
+        let chars: Vec<char> = word
+            .chars()
+            .map(|ch| ch.to_lowercase().next().unwrap_or(ch))
+            .collect();
+        let len = chars.len();
+        if len < LEFT_MIN + RIGHT_MIN {
+            return Vec::new();
+        }
+        let lowercase: String = chars.iter().collect();
+        let positions = match self.exceptions.get(&lowercase) {
+            Some(positions) => positions.clone(),
+            None => {
+                // Patterns can match at the start or end of the word, marked with a period
+                let dotted: Vec<char> = Some('.')
+                    .into_iter()
+                    .chain(chars.iter().cloned())
+                    .chain(Some('.'))
+                    .collect();
+                // `values[i]` is between `dotted[i - 1]` and `dotted[i]`
+                let mut values = vec![0_u8; dotted.len() + 1];
+                let mut key = String::new();
+                for start in 0..dotted.len() {
+                    key.clear();
+                    for &ch in dotted[start..].iter().take(self.max_len) {
+                        key.push(ch);
+                        if let Some(pattern) = self.patterns.get(&key) {
+                            for (value, &pattern_value) in values[start..].iter_mut().zip(pattern) {
+                                *value = (*value).max(pattern_value)
+                            }
+                        }
+                    }
+                }
+                // Odd values allow a hyphen
+                (1..len).filter(|&i| values[i + 1] % 2 == 1).collect()
+            }
+        };
+        let offsets: Vec<usize> = word.char_indices().map(|(offset, _)| offset).collect();
+        positions
+            .into_iter()
+            .filter(|&i| LEFT_MIN <= i && i <= len - RIGHT_MIN)
+            .map(|i| offsets[i])
+            .collect()
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref REGISTERED: Mutex<HashMap<String, Arc<Patterns>>> = Mutex::new(HashMap::new());
+}
+
+/// Use these patterns for `hyphens: auto` in content of the given language,
+/// as a BCP 47 tag like in the `lang` attribute.
+/// This applies to documents laid out afterwards, in any thread.
+///
+/// Tags are matched case-insensitively, and patterns for `en` also apply to `en-US`
+/// unless other patterns are registered for `en-US`.
+pub fn register(language: &str, patterns: Patterns) {
+    REGISTERED
+        .lock()
+        .unwrap()
+        .insert(language.to_ascii_lowercase(), Arc::new(patterns));
+}
+
+pub(crate) fn patterns_for(language: &str) -> Option<Arc<Patterns>> {
+    let registered = REGISTERED.lock().unwrap();
+    let mut tag = language.to_ascii_lowercase();
+    loop {
+        if let Some(patterns) = registered.get(&tag) {
+            return Some(patterns.clone());
+        }
+        let subtag_start = tag.rfind('-')?;
+        tag.truncate(subtag_start)
+    }
+}
+
+#[test]
+fn liang() {
+    // From Liang’s thesis
+    let patterns = Patterns::parse(
+        r"
+        \patterns{ % comment
+            hy3ph he2n hena4 hen5at 1na n2at 1tio 2io o2n
+        }
+        \hyphenation{ ta-ble }
+        ",
+    );
+    let hyphenate = |word| {
+        let offsets = patterns.hyphenate(word);
+        let mut hyphenated = String::from(word);
+        for &offset in offsets.iter().rev() {
+            hyphenated.insert(offset, '-')
+        }
+        hyphenated
+    };
+    assert_eq!(hyphenate("hyphenation"), "hy-phen-ation");
+    assert_eq!(hyphenate("Hyphenation"), "Hy-phen-ation");
+    assert_eq!(hyphenate("table"), "ta-ble");
+    assert_eq!(hyphenate("tables"), "tables");
+    // Too short for the minimum number of characters around a hyphen
+    assert_eq!(hyphenate("hyph"), "hyph");
+}
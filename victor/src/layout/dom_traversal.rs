@@ -2,6 +2,7 @@ use super::*;
 use crate::dom::{Document, NodeData, NodeId};
 use crate::style::StyleSet;
 use atomic_refcell::AtomicRefMut;
+use html5ever::LocalName;
 
 pub(super) struct Context<'a> {
     pub document: &'a Document,
@@ -117,6 +118,12 @@ fn traverse_element<'dom>(
             display,
             match ReplacedContent::for_element(element_id, context) {
                 Some(replaced) => Contents::Replaced(replaced),
+                // https://html.spec.whatwg.org/multipage/rendering.html#phrasing-content-3
+                // `br { display-outside: newline }` is approximated as a preserved newline,
+                // with `white-space: pre-line` in the user-agent stylesheet.
+                None if context.is_html_element(element_id, &local_name!("br")) => {
+                    Contents::OfPseudoElement(vec![PseudoElementContentItem::Text("\n".into())])
+                }
                 None => Contents::OfElement(element_id),
             },
             context.element_box_slot(element_id),
@@ -272,6 +279,11 @@ impl Drop for BoxSlot<'_> {
 }
 
 impl Context<'_> {
+    pub(super) fn is_html_element(&self, node_id: NodeId, local_name: &LocalName) -> bool {
+        let name = &self.document[node_id].as_element().unwrap().name;
+        name.ns == ns!(html) && name.local == *local_name
+    }
+
     fn layout_data_mut(&self, element_id: NodeId) -> AtomicRefMut<LayoutDataForElement> {
         self.document[element_id]
             .as_element()
@@ -342,3 +354,8 @@ impl Context<'_> {
         }
     }
 }
+
+/// Whether this text does not generate anonymous boxes between table parts or flex items
+pub(super) fn is_white_space_only(text: &str) -> bool {
+    text.chars().all(|ch| ch.is_ascii_whitespace())
+}
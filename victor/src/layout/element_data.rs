@@ -16,4 +16,9 @@ pub(super) enum LayoutBox {
     DisplayContents,
     BlockLevel(Arc<BlockLevelBox>),
     InlineLevel(Arc<InlineLevelBox>),
+    FlexLevel(Arc<FlexLevelBox>),
+    GridLevel(Arc<GridLevelBox>),
+    /// Table-internal boxes and captions are only referenced by their table.
+    /// Elements that the table fix-up treats as `display: none` also get this.
+    TableInternal,
 }
@@ -0,0 +1,109 @@
+use super::*;
+
+impl FlexFormattingContext {
+    pub fn construct<'a>(
+        context: &'a Context<'a>,
+        style: &Arc<ComputedValues>,
+        contents: NonReplacedContents,
+    ) -> Self {
+        let mut builder = FlexContainerBuilder {
+            context,
+            style: style.clone(),
+            children: Vec::new(),
+            ongoing_anonymous_item: None,
+        };
+        contents.traverse(style, context, &mut builder);
+        builder.finish()
+    }
+}
+
+/// Each in-flow child element of a flex container becomes a flex item.
+/// Contiguous text is wrapped in an anonymous flex item, unless it is only white space.
+///
+/// https://drafts.csswg.org/css-flexbox-1/#flex-items
+struct FlexContainerBuilder<'a> {
+    context: &'a Context<'a>,
+    style: Arc<ComputedValues>,
+    children: Vec<Arc<FlexLevelBox>>,
+    /// The contents of an anonymous flex item can come from several sibling nodes
+    ongoing_anonymous_item: Option<(Arc<ComputedValues>, BlockContainerBuilder<'a>)>,
+}
+
+impl<'a> FlexContainerBuilder<'a> {
+    fn end_ongoing_anonymous_item(&mut self) {
+        if let Some((style, builder)) = self.ongoing_anonymous_item.take() {
+            let (contents, contains_floats) = builder.finish();
+            let contents = IndependentFormattingContext::Flow(BlockFormattingContext {
+                contents,
+                contains_floats: contains_floats == ContainsFloats::Yes,
+                content_sizes: ContentSizesCache::default(),
+            });
+            self.children
+                .push(Arc::new(FlexLevelBox::FlexItem(FlexItemBox {
+                    style,
+                    contents,
+                })))
+        }
+    }
+
+    fn finish(mut self) -> FlexFormattingContext {
+        self.end_ongoing_anonymous_item();
+        // Items are laid out in order-modified document order.
+        // The sort is stable, so items with the same `order` stay in document order.
+        // https://drafts.csswg.org/css-display-3/#order-modified-document-order
+        let mut children = self.children;
+        children.sort_by_key(|child| child.style().flex.order);
+        FlexFormattingContext {
+            children,
+            content_sizes: ContentSizesCache::default(),
+        }
+    }
+}
+
+impl<'a> TraversalHandler<'a> for FlexContainerBuilder<'a> {
+    fn handle_element(
+        &mut self,
+        style: &Arc<ComputedValues>,
+        display: DisplayGeneratingBox,
+        contents: Contents,
+        box_slot: BoxSlot<'a>,
+    ) {
+        self.end_ongoing_anonymous_item();
+        let display_inside = match display {
+            DisplayGeneratingBox::OutsideInside { inside, .. } => inside,
+            // Children of a flex container are blockified, so this does not happen
+            DisplayGeneratingBox::LayoutInternal(_) => DisplayInside::Flow,
+        };
+        // `float` does not apply to flex items
+        let mut style = style.clone();
+        let contents = IndependentFormattingContext::construct(
+            self.context,
+            &mut style,
+            display_inside,
+            contents,
+        );
+        let box_ = Arc::new(if style.box_.position.is_absolutely_positioned() {
+            FlexLevelBox::OutOfFlowAbsolutelyPositionedBox(AbsolutelyPositionedBox {
+                style,
+                contents,
+            })
+        } else {
+            FlexLevelBox::FlexItem(FlexItemBox { style, contents })
+        });
+        self.children.push(box_.clone());
+        box_slot.set(LayoutBox::FlexLevel(box_))
+    }
+
+    fn handle_text(&mut self, text: &str, parent_style: &Arc<ComputedValues>) {
+        if self.ongoing_anonymous_item.is_none() && is_white_space_only(text) {
+            return;
+        }
+        let (context, style) = (self.context, &self.style);
+        let (_, builder) = self.ongoing_anonymous_item.get_or_insert_with(|| {
+            let style = ComputedValues::anonymous_inheriting_from(Some(style));
+            let builder = BlockContainerBuilder::new(context, style.clone());
+            (style, builder)
+        });
+        builder.handle_text(text, parent_style)
+    }
+}
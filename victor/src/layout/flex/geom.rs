@@ -0,0 +1,160 @@
+//! Geometry in the main and cross axes of a flex container
+//!
+//! https://drafts.csswg.org/css-flexbox-1/#box-model
+
+use super::*;
+
+/// The axes of a flex container, in terms of the flow-relative axes of its writing mode
+#[derive(Clone, Copy)]
+pub(super) struct FlexAxes {
+    /// Whether the main axis is the inline axis, for `row` and `row-reverse`
+    pub row: bool,
+    /// Whether main-start is the end side of the axis, for `row-reverse` and `column-reverse`
+    pub main_reverse: bool,
+    /// Whether cross-start is the end side of the axis, for `wrap-reverse`
+    pub cross_reverse: bool,
+}
+
+#[derive(Clone, Debug)]
+pub(super) struct FlexRelativeVec2<T> {
+    pub main: T,
+    pub cross: T,
+}
+
+#[derive(Clone, Debug)]
+pub(super) struct FlexRelativeSides<T> {
+    pub main_start: T,
+    pub main_end: T,
+    pub cross_start: T,
+    pub cross_end: T,
+}
+
+impl FlexAxes {
+    pub fn new(style: &ComputedValues) -> Self {
+        let (row, main_reverse) = match style.flex.flex_direction {
+            FlexDirection::Row => (true, false),
+            FlexDirection::RowReverse => (true, true),
+            FlexDirection::Column => (false, false),
+            FlexDirection::ColumnReverse => (false, true),
+        };
+        FlexAxes {
+            row,
+            main_reverse,
+            cross_reverse: style.flex.flex_wrap == FlexWrap::WrapReverse,
+        }
+    }
+
+    pub fn vec2<T>(&self, v: Vec2<T>) -> FlexRelativeVec2<T> {
+        if self.row {
+            FlexRelativeVec2 {
+                main: v.inline,
+                cross: v.block,
+            }
+        } else {
+            FlexRelativeVec2 {
+                main: v.block,
+                cross: v.inline,
+            }
+        }
+    }
+
+    pub fn flow_relative_vec2<T>(&self, v: FlexRelativeVec2<T>) -> Vec2<T> {
+        if self.row {
+            Vec2 {
+                inline: v.main,
+                block: v.cross,
+            }
+        } else {
+            Vec2 {
+                inline: v.cross,
+                block: v.main,
+            }
+        }
+    }
+
+    pub fn sides<T: Clone>(&self, sides: &Sides<T>) -> FlexRelativeSides<T> {
+        let (main, cross) = if self.row {
+            (
+                (&sides.inline_start, &sides.inline_end),
+                (&sides.block_start, &sides.block_end),
+            )
+        } else {
+            (
+                (&sides.block_start, &sides.block_end),
+                (&sides.inline_start, &sides.inline_end),
+            )
+        };
+        let swap = |(start, end), reverse| if reverse { (end, start) } else { (start, end) };
+        let (main_start, main_end) = swap(main, self.main_reverse);
+        let (cross_start, cross_end) = swap(cross, self.cross_reverse);
+        FlexRelativeSides {
+            main_start: main_start.clone(),
+            main_end: main_end.clone(),
+            cross_start: cross_start.clone(),
+            cross_end: cross_end.clone(),
+        }
+    }
+
+    pub fn flow_relative_sides<T>(&self, sides: FlexRelativeSides<T>) -> Sides<T> {
+        let swap = |start, end, reverse| if reverse { (end, start) } else { (start, end) };
+        let (main_start, main_end) = swap(sides.main_start, sides.main_end, self.main_reverse);
+        let (cross_start, cross_end) = swap(sides.cross_start, sides.cross_end, self.cross_reverse);
+        if self.row {
+            Sides {
+                inline_start: main_start,
+                inline_end: main_end,
+                block_start: cross_start,
+                block_end: cross_end,
+            }
+        } else {
+            Sides {
+                inline_start: cross_start,
+                inline_end: cross_end,
+                block_start: main_start,
+                block_end: main_end,
+            }
+        }
+    }
+
+    /// The flow-relative start corner of a box, given the distance of its start corner
+    /// from the main-start and cross-start edges of a container of size `container`
+    pub fn flow_relative_start_corner(
+        &self,
+        start: FlexRelativeVec2<Length>,
+        size: &FlexRelativeVec2<Length>,
+        container: &FlexRelativeVec2<Length>,
+    ) -> Vec2<Length> {
+        let flip = |start: Length, size: Length, container: Length, reverse| {
+            if reverse {
+                container - start - size
+            } else {
+                start
+            }
+        };
+        self.flow_relative_vec2(FlexRelativeVec2 {
+            main: flip(start.main, size.main, container.main, self.main_reverse),
+            cross: flip(start.cross, size.cross, container.cross, self.cross_reverse),
+        })
+    }
+}
+
+impl FlexRelativeSides<Length> {
+    pub fn main_sum(&self) -> Length {
+        self.main_start + self.main_end
+    }
+
+    pub fn cross_sum(&self) -> Length {
+        self.cross_start + self.cross_end
+    }
+}
+
+impl FlexRelativeSides<LengthOrAuto> {
+    pub fn auto_is(&self, f: impl Fn() -> Length) -> FlexRelativeSides<Length> {
+        FlexRelativeSides {
+            main_start: self.main_start.auto_is(&f),
+            main_end: self.main_end.auto_is(&f),
+            cross_start: self.cross_start.auto_is(&f),
+            cross_end: self.cross_end.auto_is(&f),
+        }
+    }
+}
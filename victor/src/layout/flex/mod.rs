@@ -0,0 +1,732 @@
+//! Flex layout
+//!
+//! https://drafts.csswg.org/css-flexbox-1/
+
+use super::*;
+use geom::{FlexAxes, FlexRelativeSides, FlexRelativeVec2};
+use rayon::prelude::*;
+use std::ops::Range;
+
+mod construct;
+mod geom;
+
+/// The contents of a flex container
+///
+/// https://drafts.csswg.org/css-flexbox-1/#flex-containers
+#[derive(Debug)]
+pub(super) struct FlexFormattingContext {
+    /// In order-modified document order
+    children: Vec<Arc<FlexLevelBox>>,
+    content_sizes: ContentSizesCache,
+}
+
+#[derive(Debug)]
+pub(super) enum FlexLevelBox {
+    FlexItem(FlexItemBox),
+    OutOfFlowAbsolutelyPositionedBox(AbsolutelyPositionedBox),
+}
+
+#[derive(Debug)]
+pub(super) struct FlexItemBox {
+    style: Arc<ComputedValues>,
+    contents: IndependentFormattingContext,
+}
+
+/// A flex item during layout, with sides and sizes in the writing mode of the flex container.
+/// Sizes are of the content box.
+struct FlexItem<'a> {
+    box_: &'a FlexItemBox,
+    padding: Sides<Length>,
+    border: Sides<Length>,
+    /// Sums of padding and border
+    pb: FlexRelativeSides<Length>,
+    margin: FlexRelativeSides<LengthOrAuto>,
+    /// The used `width` and `height`, if definite
+    size: FlexRelativeVec2<LengthOrAuto>,
+    align_self: ItemAlignment,
+    /// https://drafts.csswg.org/css-flexbox-1/#flex-base-size
+    flex_base_size: Length,
+    /// The automatic minimum size, the only one since there is no `min-width` or `min-height`
+    ///
+    /// https://drafts.csswg.org/css-flexbox-1/#min-size-auto
+    min_main_size: Length,
+    /// https://drafts.csswg.org/css-flexbox-1/#hypothetical-main-size
+    hypothetical_main_size: Length,
+    /// In a column flex container, the inline size of the item does not depend on its main size
+    column_cross_size: Length,
+    /// In a column flex container, the layout with an automatic block size
+    /// that gave the size of the contents
+    content_layout: Option<ItemLayout<'a>>,
+}
+
+struct ItemLayout<'a> {
+    flow_children: FlowChildren,
+    absolutely_positioned_fragments: Vec<AbsolutelyPositionedFragment<'a>>,
+    /// Of the content box, in the writing mode of the flex container
+    size: Vec2<Length>,
+}
+
+impl FlexLevelBox {
+    fn style(&self) -> &Arc<ComputedValues> {
+        match self {
+            FlexLevelBox::FlexItem(item) => &item.style,
+            FlexLevelBox::OutOfFlowAbsolutelyPositionedBox(box_) => &box_.style,
+        }
+    }
+}
+
+impl FlexFormattingContext {
+    /// `style` is that of the flex container
+    pub(super) fn inline_content_sizes(&self, style: &ComputedValues) -> ContentSizes {
+        self.content_sizes.get_or_compute(|| {
+            let mode = style.writing_mode();
+            let items: Vec<_> = self
+                .children
+                .par_iter()
+                .filter_map(|child| match &**child {
+                    FlexLevelBox::FlexItem(item) => Some(sizing::outer_inline_content_sizes(
+                        &item.style,
+                        mode,
+                        || item.contents.inline_content_sizes(&item.style),
+                    )),
+                    FlexLevelBox::OutOfFlowAbsolutelyPositionedBox(_) => None,
+                })
+                .collect();
+            // FIXME: use a definite `flex-basis` instead of the content sizes of the item
+            if !FlexAxes::new(style).row {
+                return items.iter().fold(ContentSizes::zero(), |a, b| a.max(*b));
+            }
+            let gaps = style
+                .align
+                .column_gap
+                .percentage_relative_to(Length::zero())
+                * items.len().saturating_sub(1) as f32;
+            let max_content = items
+                .iter()
+                .fold(gaps, |sum, sizes| sum + sizes.max_content);
+            let min_content = if style.flex.flex_wrap == FlexWrap::Nowrap {
+                items
+                    .iter()
+                    .fold(gaps, |sum, sizes| sum + sizes.min_content)
+            } else {
+                items
+                    .iter()
+                    .fold(Length::zero(), |max, sizes| max.max(sizes.min_content))
+            };
+            ContentSizes {
+                min_content,
+                max_content,
+            }
+        })
+    }
+
+    /// https://drafts.csswg.org/css-flexbox-1/#layout-algorithm
+    pub(super) fn layout<'a>(
+        &'a self,
+        containing_block: &ContainingBlock,
+        tree_rank: usize,
+        absolutely_positioned_fragments: &mut Vec<AbsolutelyPositionedFragment<'a>>,
+    ) -> FlowChildren {
+        let style = containing_block.style;
+        let mode = containing_block.mode;
+        let cbis = containing_block.inline_size;
+        let axes = FlexAxes::new(style);
+        let single_line = style.flex.flex_wrap == FlexWrap::Nowrap;
+        let zero = Length::zero();
+        // Of the content box of the flex container, if definite
+        let container_size = axes.vec2(Vec2 {
+            inline: LengthOrAuto::Length(cbis),
+            block: containing_block.block_size,
+        });
+        let gap = axes.vec2(Vec2 {
+            inline: style.align.column_gap.percentage_relative_to(cbis),
+            block: style
+                .align
+                .row_gap
+                .percentage_relative_to(containing_block.block_size.auto_is(Length::zero)),
+        });
+
+        let mut items: Vec<_> = self
+            .children
+            .par_iter()
+            .filter_map(|child| match &**child {
+                FlexLevelBox::FlexItem(box_) => Some(FlexItem::new(
+                    box_,
+                    containing_block,
+                    axes,
+                    single_line,
+                    tree_rank,
+                )),
+                FlexLevelBox::OutOfFlowAbsolutelyPositionedBox(_) => None,
+            })
+            .collect();
+        for child in &self.children {
+            if let FlexLevelBox::OutOfFlowAbsolutelyPositionedBox(box_) = &**child {
+                // FIXME: align the static position as if this were the sole flex item
+                absolutely_positioned_fragments.push(box_.layout(Vec2::zero(), tree_rank, mode))
+            }
+        }
+
+        // Collect items into flex lines,
+        // only broken in multi-line containers with a definite main size
+        // https://drafts.csswg.org/css-flexbox-1/#algo-line-break
+        let available_main_size = match container_size.main {
+            LengthOrAuto::Length(size) if !single_line => Some(size),
+            _ => None,
+        };
+        let mut lines = Vec::new();
+        let mut start = 0;
+        let mut line_size = zero;
+        for (index, item) in items.iter().enumerate() {
+            let outer = item.outer_hypothetical_main_size();
+            if index == start {
+                line_size = outer
+            } else if available_main_size
+                .map_or(false, |available| line_size + gap.main + outer > available)
+            {
+                lines.push(start..index);
+                start = index;
+                line_size = outer
+            } else {
+                line_size += gap.main + outer
+            }
+        }
+        lines.push(start..items.len());
+        let main_gaps = |line: &Range<usize>| gap.main * line.len().saturating_sub(1) as f32;
+        let container_main_size = container_size.main.auto_is(|| {
+            lines
+                .iter()
+                .map(|line| {
+                    items[line.clone()]
+                        .iter()
+                        .fold(main_gaps(line), |sum, item| {
+                            sum + item.outer_hypothetical_main_size()
+                        })
+                })
+                .fold(zero, Length::max)
+        });
+        let main_sizes: Vec<_> = lines
+            .iter()
+            .flat_map(|line| {
+                resolve_flexible_lengths(
+                    &items[line.clone()],
+                    container_main_size - main_gaps(line),
+                )
+            })
+            .collect();
+
+        // Lay out items with their used main size, to find their hypothetical cross size
+        let layouts: Vec<_> = items
+            .par_iter_mut()
+            .zip(main_sizes.par_iter())
+            .map(|(item, &main_size)| {
+                if axes.row {
+                    item.layout(main_size, item.size.cross, tree_rank)
+                } else {
+                    match item.content_layout.take() {
+                        // Skip another layout if the main size is that of the contents
+                        Some(layout) if layout.size.block == main_size => layout,
+                        _ => item.layout(
+                            item.column_cross_size,
+                            LengthOrAuto::Length(main_size),
+                            tree_rank,
+                        ),
+                    }
+                }
+            })
+            .collect();
+
+        // Cross sizes of flex lines
+        // https://drafts.csswg.org/css-flexbox-1/#algo-cross-line
+        let mut line_baselines = Vec::with_capacity(lines.len());
+        let mut line_cross_sizes: Vec<_> = lines
+            .iter()
+            .map(|line| {
+                let mut largest = zero;
+                let mut above_baseline = zero;
+                let mut below_baseline = zero;
+                for index in line.clone() {
+                    let (item, layout) = (&items[index], &layouts[index]);
+                    let outer = item.outer_cross_size(axes, layout);
+                    if item.is_baseline_aligned(axes) {
+                        let baseline = item.outer_baseline(layout);
+                        above_baseline.max_assign(baseline);
+                        below_baseline.max_assign(outer - baseline);
+                    } else {
+                        largest.max_assign(outer)
+                    }
+                }
+                line_baselines.push(above_baseline);
+                match container_size.cross {
+                    LengthOrAuto::Length(size) if single_line => size,
+                    _ => largest.max(above_baseline + below_baseline),
+                }
+            })
+            .collect();
+        let cross_gaps = gap.cross * (lines.len() - 1) as f32;
+        let container_cross_size = container_size.cross.auto_is(|| {
+            line_cross_sizes
+                .iter()
+                .fold(cross_gaps, |sum, size| sum + *size)
+        });
+
+        // https://drafts.csswg.org/css-flexbox-1/#algo-line-align
+        let mut free_space = line_cross_sizes
+            .iter()
+            .fold(container_cross_size - cross_gaps, |free, size| free - *size);
+        let align_content = style.align.align_content;
+        if let ContentDistribution::Normal | ContentDistribution::Stretch = align_content {
+            if free_space > zero {
+                let extra = free_space / lines.len() as f32;
+                for size in &mut line_cross_sizes {
+                    *size += extra
+                }
+                free_space = zero
+            }
+        }
+        let (mut line_start, between_lines) =
+            distribute(align_content, free_space, lines.len(), axes.cross_reverse);
+        let mut item_lines = Vec::with_capacity(items.len());
+        let mut line_starts = Vec::with_capacity(lines.len());
+        for (line_index, line) in lines.iter().enumerate() {
+            item_lines.extend(line.clone().map(|_| line_index));
+            line_starts.push(line_start);
+            line_start += line_cross_sizes[line_index] + gap.cross + between_lines;
+        }
+
+        // Stretch items whose cross size is auto to that of their line
+        // https://drafts.csswg.org/css-flexbox-1/#algo-stretch
+        let layouts: Vec<_> = layouts
+            .into_par_iter()
+            .enumerate()
+            .map(|(index, layout)| {
+                let item = &items[index];
+                if !item.is_stretched() {
+                    return layout;
+                }
+                let stretched = (line_cross_sizes[item_lines[index]]
+                    - item.pb.cross_sum()
+                    - item.margin.auto_is(Length::zero).cross_sum())
+                .max(Length::zero());
+                if axes.vec2(layout.size.clone()).cross == stretched {
+                    return layout;
+                }
+                if axes.row {
+                    item.layout(
+                        main_sizes[index],
+                        LengthOrAuto::Length(stretched),
+                        tree_rank,
+                    )
+                } else {
+                    item.layout(
+                        stretched,
+                        LengthOrAuto::Length(main_sizes[index]),
+                        tree_rank,
+                    )
+                }
+            })
+            .collect();
+
+        // Main-axis and cross-axis alignment, then fragments
+        // https://drafts.csswg.org/css-flexbox-1/#main-alignment
+        // https://drafts.csswg.org/css-flexbox-1/#cross-alignment
+        let container_content_size = FlexRelativeVec2 {
+            main: container_main_size,
+            cross: container_cross_size,
+        };
+        let mut layouts = layouts.into_iter();
+        let mut fragments = Vec::with_capacity(items.len());
+        let mut last_baseline = None;
+        for (line_index, line) in lines.iter().enumerate() {
+            let line_cross_size = line_cross_sizes[line_index];
+            let line_items = &items[line.clone()];
+            let mut free_space = line_items.iter().zip(&main_sizes[line.clone()]).fold(
+                container_main_size - main_gaps(line),
+                |free, (item, size)| {
+                    free - *size - item.pb.main_sum() - item.margin.auto_is(Length::zero).main_sum()
+                },
+            );
+            let auto_margins = line_items
+                .iter()
+                .map(|item| {
+                    (item.margin.main_start == LengthOrAuto::Auto) as usize
+                        + (item.margin.main_end == LengthOrAuto::Auto) as usize
+                })
+                .sum::<usize>();
+            let mut auto_margin = zero;
+            if free_space > zero && auto_margins > 0 {
+                auto_margin = free_space / auto_margins as f32;
+                free_space = zero
+            }
+            let (mut main_position, between_items) = distribute(
+                style.align.justify_content,
+                free_space,
+                line.len(),
+                axes.main_reverse,
+            );
+            for (item, &main_size) in line_items.iter().zip(&main_sizes[line.clone()]) {
+                let layout = layouts.next().unwrap();
+                let content_size = axes.vec2(layout.size.clone());
+                let outer_cross_size = item.outer_cross_size(axes, &layout);
+                let cross_free_space = line_cross_size - outer_cross_size;
+                let (cross_start_margin, cross_end_margin) =
+                    match (item.margin.cross_start, item.margin.cross_end) {
+                        (LengthOrAuto::Auto, LengthOrAuto::Auto) => {
+                            let margin = cross_free_space.max(zero) / 2.;
+                            (margin, margin)
+                        }
+                        (LengthOrAuto::Auto, LengthOrAuto::Length(end)) => {
+                            (cross_free_space.max(zero), end)
+                        }
+                        (LengthOrAuto::Length(start), LengthOrAuto::Auto) => {
+                            (start, cross_free_space.max(zero))
+                        }
+                        (LengthOrAuto::Length(start), LengthOrAuto::Length(end)) => {
+                            let offset = match item.align_self {
+                                ItemAlignment::Normal
+                                | ItemAlignment::Stretch
+                                | ItemAlignment::FlexStart => zero,
+                                ItemAlignment::FlexEnd => cross_free_space,
+                                ItemAlignment::Center => cross_free_space / 2.,
+                                // FIXME: use the writing mode of the item for `self-start`
+                                ItemAlignment::Start | ItemAlignment::SelfStart => {
+                                    if axes.cross_reverse {
+                                        cross_free_space
+                                    } else {
+                                        zero
+                                    }
+                                }
+                                ItemAlignment::End | ItemAlignment::SelfEnd => {
+                                    if axes.cross_reverse {
+                                        zero
+                                    } else {
+                                        cross_free_space
+                                    }
+                                }
+                                ItemAlignment::Baseline if item.is_baseline_aligned(axes) => {
+                                    line_baselines[line_index] - item.outer_baseline(&layout)
+                                }
+                                ItemAlignment::Baseline => zero,
+                            };
+                            (start + offset, end)
+                        }
+                    };
+                let margin = FlexRelativeSides {
+                    main_start: item.margin.main_start.auto_is(|| auto_margin),
+                    main_end: item.margin.main_end.auto_is(|| auto_margin),
+                    cross_start: cross_start_margin,
+                    cross_end: cross_end_margin,
+                };
+                let content_start = FlexRelativeVec2 {
+                    main: main_position + margin.main_start + item.pb.main_start,
+                    cross: line_starts[line_index] + margin.cross_start + item.pb.cross_start,
+                };
+                main_position +=
+                    main_size + item.pb.main_sum() + margin.main_sum() + gap.main + between_items;
+
+                let start_corner = axes.flow_relative_start_corner(
+                    content_start,
+                    &content_size,
+                    &container_content_size,
+                );
+                let style = &item.box_.style;
+                let mut fragment = BoxFragment {
+                    style: style.clone(),
+                    children: layout.flow_children.fragments,
+                    content_rect: Rect {
+                        start_corner: &start_corner
+                            + &relative_adjustement(style, cbis, containing_block.block_size, mode),
+                        size: layout.size,
+                    },
+                    padding: item.padding.clone(),
+                    border: item.border.clone(),
+                    margin: axes.flow_relative_sides(margin),
+                    block_margins_collapsed_with_children: CollapsedBlockMargins::zero(),
+                    last_baseline: layout.flow_children.last_baseline,
+                };
+                place_absolutely_positioned_fragments(
+                    &mut fragment,
+                    layout.absolutely_positioned_fragments,
+                    start_corner,
+                    mode,
+                    cbis,
+                    absolutely_positioned_fragments,
+                );
+                fragments.push(fragment)
+            }
+
+            // “If any of the flex items on the flex container’s first line participate
+            //  in baseline alignment, the flex container’s main-axis baseline
+            //  is the baseline of those flex items. Otherwise […] the first flex item”
+            // https://drafts.csswg.org/css-flexbox-1/#flex-baselines
+            if line_index == 0 {
+                let first_line = &fragments[line.clone()];
+                last_baseline = line
+                    .clone()
+                    .position(|index| items[index].is_baseline_aligned(axes))
+                    .or_else(|| line.clone().next().map(|_| 0))
+                    .map(|index| {
+                        let fragment = &first_line[index];
+                        fragment.content_rect.start_corner.block
+                            + fragment.last_baseline.unwrap_or_else(|| {
+                                fragment.content_rect.size.block
+                                    + fragment.padding.block_end
+                                    + fragment.border.block_end
+                            })
+                    });
+            }
+        }
+
+        FlowChildren {
+            fragments: fragments.into_iter().map(Fragment::Box).collect(),
+            block_size: axes.flow_relative_vec2(container_content_size).block,
+            collapsible_margins_in_children: CollapsedBlockMargins::zero(),
+            last_baseline,
+        }
+    }
+}
+
+impl<'a> FlexItem<'a> {
+    /// Determines the flex base size and hypothetical main size of a flex item.
+    /// Items of single-line column containers are stretched early
+    /// since their inline size is needed to lay them out.
+    ///
+    /// https://drafts.csswg.org/css-flexbox-1/#algo-main-item
+    fn new(
+        box_: &'a FlexItemBox,
+        containing_block: &ContainingBlock,
+        axes: FlexAxes,
+        single_line: bool,
+        tree_rank: usize,
+    ) -> Self {
+        let style = &box_.style;
+        let mode = containing_block.mode;
+        let cbis = containing_block.inline_size;
+        let padding = style.padding(mode).percentages_relative_to(cbis);
+        let border = style.border_width(mode).percentages_relative_to(cbis);
+        let margin = style.margin(mode).percentages_relative_to(cbis);
+        let pb = &padding + &border;
+        let box_size = style.box_size(mode);
+        let available_inline = cbis - pb.inline_sum() - margin.auto_is(Length::zero).inline_sum();
+        let content_sizes = || box_.contents.inline_content_sizes(style);
+        let size = axes.vec2(Vec2 {
+            inline: sizing::inline_size(box_size.inline, cbis, available_inline, content_sizes),
+            block: sizing::block_size(box_size.block, containing_block.block_size),
+        });
+        let mut item = FlexItem {
+            box_,
+            pb: axes.sides(&pb),
+            margin: axes.sides(&margin),
+            padding,
+            border,
+            size,
+            align_self: style
+                .align
+                .align_self
+                .or_items(containing_block.style.align.align_items),
+            flex_base_size: Length::zero(),
+            min_main_size: Length::zero(),
+            hypothetical_main_size: Length::zero(),
+            column_cross_size: Length::zero(),
+            content_layout: None,
+        };
+
+        let basis = match style.flex.flex_basis {
+            FlexBasis::Content => LengthOrAuto::Auto,
+            FlexBasis::Size(Size::Auto) => item.size.main,
+            FlexBasis::Size(basis) if axes.row => {
+                sizing::inline_size(basis, cbis, available_inline, content_sizes)
+            }
+            FlexBasis::Size(basis) => sizing::block_size(basis, containing_block.block_size),
+        };
+        let (content_main_size, content_min_main_size) = if axes.row {
+            let sizes = content_sizes();
+            (sizes.max_content, sizes.min_content)
+        } else {
+            item.column_cross_size = item.size.cross.auto_is(|| {
+                if single_line && item.is_stretched() {
+                    available_inline.max(Length::zero())
+                } else {
+                    content_sizes().shrink_to_fit(available_inline)
+                }
+            });
+            let layout = item.layout(item.column_cross_size, LengthOrAuto::Auto, tree_rank);
+            let block_size = layout.size.block;
+            item.content_layout = Some(layout);
+            (block_size, block_size)
+        };
+        item.flex_base_size = basis.auto_is(|| content_main_size);
+        item.min_main_size = match item.size.main {
+            LengthOrAuto::Length(size) => size.min(content_min_main_size),
+            LengthOrAuto::Auto => content_min_main_size,
+        };
+        item.hypothetical_main_size = item.flex_base_size.max(item.min_main_size);
+        item
+    }
+
+    /// Lays out the contents of this item with a content box of the given size
+    fn layout(
+        &self,
+        inline_size: Length,
+        block_size: LengthOrAuto,
+        tree_rank: usize,
+    ) -> ItemLayout<'a> {
+        let style = &self.box_.style;
+        // FIXME: orthogonal flows
+        let containing_block_for_children = ContainingBlock {
+            inline_size,
+            block_size,
+            mode: style.writing_mode(),
+            style,
+        };
+        let mut absolutely_positioned_fragments = Vec::new();
+        let flow_children = self.box_.contents.layout(
+            &containing_block_for_children,
+            tree_rank,
+            &mut absolutely_positioned_fragments,
+        );
+        ItemLayout {
+            size: Vec2 {
+                inline: inline_size,
+                block: block_size.auto_is(|| flow_children.block_size),
+            },
+            flow_children,
+            absolutely_positioned_fragments,
+        }
+    }
+
+    fn outer_hypothetical_main_size(&self) -> Length {
+        self.hypothetical_main_size
+            + self.pb.main_sum()
+            + self.margin.auto_is(Length::zero).main_sum()
+    }
+
+    fn outer_cross_size(&self, axes: FlexAxes, layout: &ItemLayout) -> Length {
+        axes.vec2(layout.size.clone()).cross
+            + self.pb.cross_sum()
+            + self.margin.auto_is(Length::zero).cross_sum()
+    }
+
+    fn has_auto_cross_margin(&self) -> bool {
+        self.margin.cross_start == LengthOrAuto::Auto || self.margin.cross_end == LengthOrAuto::Auto
+    }
+
+    /// https://drafts.csswg.org/css-flexbox-1/#valdef-align-items-stretch
+    fn is_stretched(&self) -> bool {
+        matches!(
+            self.align_self,
+            ItemAlignment::Normal | ItemAlignment::Stretch
+        ) && self.size.cross == LengthOrAuto::Auto
+            && !self.has_auto_cross_margin()
+    }
+
+    /// Only the inline-axis baselines of items in row containers are supported.
+    /// With `wrap-reverse`, baseline alignment falls back to `flex-start`.
+    fn is_baseline_aligned(&self, axes: FlexAxes) -> bool {
+        self.align_self == ItemAlignment::Baseline
+            && axes.row
+            && !axes.cross_reverse
+            && !self.has_auto_cross_margin()
+    }
+
+    /// How far from the cross-start edge of the margin box the baseline is,
+    /// synthesized from the border box if there is no line box.
+    fn outer_baseline(&self, layout: &ItemLayout) -> Length {
+        self.margin.cross_start.auto_is(Length::zero)
+            + self.pb.cross_start
+            + layout
+                .flow_children
+                .last_baseline
+                .unwrap_or(layout.size.block + self.pb.cross_end)
+    }
+}
+
+/// The used main sizes of the items of a flex line,
+/// given the space available for their margin boxes.
+///
+/// https://drafts.csswg.org/css-flexbox-1/#resolve-flexible-lengths
+fn resolve_flexible_lengths(items: &[FlexItem], available: Length) -> Vec<Length> {
+    let zero = Length::zero();
+    let outer_extra =
+        |item: &FlexItem| item.pb.main_sum() + item.margin.auto_is(Length::zero).main_sum();
+    let growing = items
+        .iter()
+        .fold(zero, |sum, item| sum + item.outer_hypothetical_main_size())
+        < available;
+    let flex_factor = |item: &FlexItem| {
+        if growing {
+            item.box_.style.flex.flex_grow.0
+        } else {
+            item.box_.style.flex.flex_shrink.0
+        }
+    };
+    let mut sizes: Vec<_> = items
+        .iter()
+        .map(|item| item.hypothetical_main_size)
+        .collect();
+    let mut frozen: Vec<_> = items
+        .iter()
+        .map(|item| {
+            flex_factor(item) == 0.
+                || (growing && item.flex_base_size > item.hypothetical_main_size)
+                || (!growing && item.flex_base_size < item.hypothetical_main_size)
+        })
+        .collect();
+    let remaining_free_space = |sizes: &[Length], frozen: &[bool]| {
+        items
+            .iter()
+            .zip(sizes)
+            .zip(frozen)
+            .fold(available, |free, ((item, size), &frozen)| {
+                free - outer_extra(item) - if frozen { *size } else { item.flex_base_size }
+            })
+    };
+    let initial_free_space = remaining_free_space(&sizes, &frozen);
+    while frozen.contains(&false) {
+        let unfrozen = || {
+            items
+                .iter()
+                .zip(&frozen)
+                .filter(|(_, &frozen)| !frozen)
+                .map(|(item, _)| item)
+        };
+        let mut free_space = remaining_free_space(&sizes, &frozen);
+        let factor_sum: f32 = unfrozen().map(flex_factor).sum();
+        if factor_sum < 1. {
+            let scaled = initial_free_space * factor_sum;
+            if scaled.px.abs() < free_space.px.abs() {
+                free_space = scaled
+            }
+        }
+        let scaled_shrink_factor_sum: f32 = unfrozen()
+            .map(|item| item.box_.style.flex.flex_shrink.0 * item.flex_base_size.px)
+            .sum();
+        let mut violations = vec![zero; items.len()];
+        let mut total_violation = zero;
+        for (index, item) in items.iter().enumerate() {
+            if frozen[index] {
+                continue;
+            }
+            let ratio = if growing {
+                item.box_.style.flex.flex_grow.0 / factor_sum
+            } else if scaled_shrink_factor_sum > 0. {
+                item.box_.style.flex.flex_shrink.0 * item.flex_base_size.px
+                    / scaled_shrink_factor_sum
+            } else {
+                0.
+            };
+            let size = item.flex_base_size + free_space * ratio;
+            let clamped = size.max(item.min_main_size);
+            violations[index] = clamped - size;
+            total_violation += clamped - size;
+            sizes[index] = clamped
+        }
+        for (index, frozen) in frozen.iter_mut().enumerate() {
+            let violation = violations[index];
+            *frozen |= total_violation == zero
+                || (total_violation > zero && violation > zero)
+                || (total_violation < zero && violation < zero)
+        }
+    }
+    sizes
+}
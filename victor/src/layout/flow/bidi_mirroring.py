@@ -0,0 +1,55 @@
+#!/usr/bin/env python3
+"""Generate bidi_mirroring.rs from the Unicode Character Database.
+
+Download https://www.unicode.org/Public/UCD/latest/ucd/BidiMirroring.txt
+then run this script in its directory with the path to that file.
+"""
+
+import sys
+
+
+def main(path):
+    lines = open(path, encoding="utf-8").read().splitlines()
+    version = lines[0].lstrip("# ").replace("BidiMirroring-", "").replace(".txt", "")
+    pairs = []
+    for line in lines:
+        data = line.partition("#")[0]
+        if not data.strip():
+            continue
+        code_point, mirrored = data.split(";")
+        pairs.append((int(code_point, 16), int(mirrored, 16)))
+    pairs.sort()
+
+    with open("bidi_mirroring.rs", "w", encoding="utf-8") as rs:
+        rs.write("//! The `Bidi_Mirroring_Glyph` property of Unicode %s\n" % version)
+        rs.write("//!\n")
+        rs.write("//! Generated by bidi_mirroring.py from BidiMirroring.txt, do not edit.\n")
+        rs.write("\n")
+        rs.write("/// https://www.unicode.org/reports/tr9/#L4\n")
+        rs.write("///\n")
+        rs.write("/// Characters with the `Bidi_Mirrored` property but no mirroring character\n")
+        rs.write("/// in this table would need a mirrored glyph from the font.\n")
+        rs.write("pub(super) fn mirrored(ch: char) -> char {\n")
+        rs.write("    match PAIRS.binary_search_by_key(&ch, |&(ch, _)| ch) {\n")
+        rs.write("        Ok(index) => PAIRS[index].1,\n")
+        rs.write("        Err(_) => ch,\n")
+        rs.write("    }\n")
+        rs.write("}\n")
+        rs.write("\n")
+        rs.write("/// Sorted by the first character\n")
+        rs.write("static PAIRS: [(char, char); %d] = [\n" % len(pairs))
+        for code_point, mirrored in pairs:
+            rs.write("    ('\\u{%X}', '\\u{%X}'),\n" % (code_point, mirrored))
+        rs.write("];\n")
+        rs.write("\n")
+        rs.write("#[test]\n")
+        rs.write("fn pairs() {\n")
+        rs.write("    assert!(PAIRS.windows(2).all(|w| w[0].0 < w[1].0));\n")
+        rs.write("    assert!(PAIRS.iter().all(|&(a, b)| mirrored(b) == a));\n")
+        rs.write("    let rtl: String = \"(a)⟨∈⊂⁅〈（\".chars().map(mirrored).collect();\n")
+        rs.write("    assert_eq!(rtl, \")a(⟩∋⊃⁆〉）\");\n")
+        rs.write("}\n")
+
+
+if __name__ == "__main__":
+    main(*sys.argv[1:])
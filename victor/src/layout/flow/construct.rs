@@ -1,15 +1,17 @@
+use super::text_transform::{self, Language};
 use super::*;
 
 impl BlockFormattingContext {
     pub fn construct<'a>(
         context: &'a Context<'a>,
-        style: &'a Arc<ComputedValues>,
+        style: &Arc<ComputedValues>,
         contents: NonReplacedContents,
     ) -> Self {
         let (contents, contains_floats) = BlockContainer::construct(context, style, contents);
         Self {
             contents,
             contains_floats: contains_floats == ContainsFloats::Yes,
+            content_sizes: ContentSizesCache::default(),
         }
     }
 }
@@ -34,6 +36,10 @@ enum IntermediateBlockLevelBox {
         display_inside: DisplayInside,
         contents: Contents,
     },
+    AnonymousTable {
+        style: Arc<ComputedValues>,
+        contents: TableFormattingContext,
+    },
 }
 
 /// A block container that may still have to be constructed.
@@ -52,9 +58,9 @@ enum IntermediateBlockContainer {
 ///
 /// This builder starts from the first child of a given DOM node
 /// and does a preorder traversal of all of its inclusive siblings.
-struct BlockContainerBuilder<'a> {
+pub(in crate::layout) struct BlockContainerBuilder<'a> {
     context: &'a Context<'a>,
-    block_container_style: &'a Arc<ComputedValues>,
+    block_container_style: Arc<ComputedValues>,
 
     /// The list of block-level boxes of the final block container.
     ///
@@ -101,15 +107,36 @@ struct BlockContainerBuilder<'a> {
 
     /// Whether the resulting block container contains any float box.
     contains_floats: ContainsFloats,
+
+    /// The anonymous table being built around consecutive table-internal boxes
+    /// that are not in a table. The first item is the style of its wrapper box.
+    ///
+    /// Boxed since it contains block container builders for anonymous cells.
+    ///
+    /// https://drafts.csswg.org/css2/tables.html#anonymous-boxes
+    ongoing_anonymous_table: Option<(Arc<ComputedValues>, Box<TableBuilder<'a>>)>,
 }
 
 impl BlockContainer {
     pub fn construct<'a>(
         context: &'a Context<'a>,
-        block_container_style: &'a Arc<ComputedValues>,
+        block_container_style: &Arc<ComputedValues>,
         contents: NonReplacedContents,
     ) -> (BlockContainer, ContainsFloats) {
-        let mut builder = BlockContainerBuilder {
+        let mut builder = BlockContainerBuilder::new(context, block_container_style.clone());
+        contents.traverse(block_container_style, context, &mut builder);
+        builder.finish()
+    }
+}
+
+impl<'a> BlockContainerBuilder<'a> {
+    /// Also used for the contents of an anonymous table cell,
+    /// which can come from several sibling nodes
+    pub(in crate::layout) fn new(
+        context: &'a Context<'a>,
+        block_container_style: Arc<ComputedValues>,
+    ) -> Self {
+        BlockContainerBuilder {
             context,
             block_container_style,
             block_level_boxes: Default::default(),
@@ -117,30 +144,32 @@ impl BlockContainer {
             ongoing_inline_boxes_stack: Default::default(),
             anonymous_style: Default::default(),
             contains_floats: Default::default(),
-        };
+            ongoing_anonymous_table: None,
+        }
+    }
 
-        contents.traverse(block_container_style, context, &mut builder);
+    pub(in crate::layout) fn finish(mut self) -> (BlockContainer, ContainsFloats) {
+        self.end_ongoing_anonymous_table();
 
-        debug_assert!(builder.ongoing_inline_boxes_stack.is_empty());
+        debug_assert!(self.ongoing_inline_boxes_stack.is_empty());
 
-        if !builder
+        if !self
             .ongoing_inline_formatting_context
             .inline_level_boxes
             .is_empty()
         {
-            if builder.block_level_boxes.is_empty() {
-                let container = BlockContainer::InlineFormattingContext(
-                    builder.ongoing_inline_formatting_context,
-                );
-                return (container, builder.contains_floats);
+            if self.block_level_boxes.is_empty() {
+                let container =
+                    BlockContainer::InlineFormattingContext(self.ongoing_inline_formatting_context);
+                return (container, self.contains_floats);
             }
-            builder.end_ongoing_inline_formatting_context();
+            self.end_ongoing_inline_formatting_context();
         }
 
-        let mut contains_floats = builder.contains_floats;
+        let context = self.context;
+        let mut contains_floats = self.contains_floats;
         let container = BlockContainer::BlockLevelBoxes(
-            builder
-                .block_level_boxes
+            self.block_level_boxes
                 .into_par_iter()
                 .mapfold_reduce_into(
                     &mut contains_floats,
@@ -166,7 +195,21 @@ impl<'a> TraversalHandler<'a> for BlockContainerBuilder<'a> {
         contents: Contents,
         box_slot: BoxSlot<'a>,
     ) {
+        if let DisplayGeneratingBox::LayoutInternal(_) = display {
+            let context = self.context;
+            let parent_style = self.current_inline_box_style().clone();
+            let inline = !self.ongoing_inline_boxes_stack.is_empty();
+            let (_, table) = self.ongoing_anonymous_table.get_or_insert_with(|| {
+                let (wrapper_style, table) =
+                    TableBuilder::new_anonymous(context, &parent_style, inline);
+                (wrapper_style, Box::new(table))
+            });
+            table.handle_element(style, display, contents, box_slot);
+            return;
+        }
+        self.end_ongoing_anonymous_table();
         match display {
+            DisplayGeneratingBox::LayoutInternal(_) => unreachable!(),
             DisplayGeneratingBox::OutsideInside { outside, inside } => match outside {
                 DisplayOutside::Inline => box_slot.set(LayoutBox::InlineLevel(
                     self.handle_inline_level_element(style, inside, contents),
@@ -192,104 +235,148 @@ impl<'a> TraversalHandler<'a> for BlockContainerBuilder<'a> {
     }
 
     fn handle_text(&mut self, input: &str, parent_style: &Arc<ComputedValues>) {
-        let (leading_whitespace, mut input) = self.handle_leading_whitespace(input);
-        if leading_whitespace || !input.is_empty() {
-            // This text node should be pushed either to the next ongoing
-            // inline level box with the parent style of that inline level box
-            // that will be ended, or directly to the ongoing inline formatting
-            // context with the parent style of that builder.
-            let inlines = self.current_inline_level_boxes();
-
-            fn last_text(inlines: &mut [Arc<InlineLevelBox>]) -> Option<&mut String> {
-                let last = inlines.last_mut()?;
-                if let InlineLevelBox::TextRun(_) = &**last {
-                    // We never clone text run boxes, so the refcount is 1 and unwrap succeeds:
-                    let last = Arc::get_mut(last).unwrap();
-                    if let InlineLevelBox::TextRun(TextRun { text, .. }) = last {
-                        Some(text)
-                    } else {
-                        unreachable!()
-                    }
-                } else {
-                    None
-                }
+        if let Some((_, table)) = &mut self.ongoing_anonymous_table {
+            if input.chars().all(|ch| ch.is_ascii_whitespace()) {
+                table.handle_text(input, parent_style);
+                return;
             }
+            self.end_ongoing_anonymous_table()
+        }
+        let white_space = parent_style.inherited_text.white_space;
+        let mut text = String::new();
+        if white_space.preserves_spaces() {
+            text.push_str(input)
+        } else {
+            let follows_collapsible_space = self.follows_collapsible_space();
+            collapse_white_space(input, white_space, follows_collapsible_space, &mut text)
+        }
+        if text.is_empty() {
+            return;
+        }
+        let transform = parent_style.inherited_text.text_transform;
+        if !transform.is_none() {
+            let language = Language::from_tag(parent_style.lang.as_deref());
+            text = text_transform::transform(&text, transform, language, self.preceding_char())
+        }
 
-            let mut new_text_run_contents;
-            let output;
-            if let Some(text) = last_text(inlines) {
-                // Append to the existing text run
-                new_text_run_contents = None;
-                output = text;
+        // This text node should be pushed either to the next ongoing
+        // inline level box with the parent style of that inline level box
+        // that will be ended, or directly to the ongoing inline formatting
+        // context with the parent style of that builder.
+        let inlines = self.current_inline_level_boxes();
+
+        fn last_text(inlines: &mut [Arc<InlineLevelBox>]) -> Option<&mut String> {
+            let last = inlines.last_mut()?;
+            if let InlineLevelBox::TextRun(_) = &**last {
+                // We never clone text run boxes, so the refcount is 1 and unwrap succeeds:
+                let last = Arc::get_mut(last).unwrap();
+                if let InlineLevelBox::TextRun(TextRun { text, .. }) = last {
+                    Some(text)
+                } else {
+                    unreachable!()
+                }
             } else {
-                new_text_run_contents = Some(String::new());
-                output = new_text_run_contents.as_mut().unwrap();
+                None
             }
+        }
+
+        if let Some(existing) = last_text(inlines) {
+            // Append to the existing text run
+            existing.push_str(&text)
+        } else {
+            let parent_style = parent_style.clone();
+            inlines.push(Arc::new(InlineLevelBox::TextRun(TextRun {
+                parent_style,
+                text,
+            })))
+        }
+    }
+}
 
-            if leading_whitespace {
-                output.push(' ')
+/// https://drafts.csswg.org/css-text-3/#white-space-phase-1
+///
+/// Tabs and segment breaks (unless preserved) become spaces,
+/// and any space following another collapsible space is removed.
+fn collapse_white_space(
+    input: &str,
+    white_space: WhiteSpace,
+    mut follows_collapsible_space: bool,
+    output: &mut String,
+) {
+    for ch in input.chars() {
+        match ch {
+            '\n' if white_space.preserves_segment_breaks() => {
+                // Collapsible spaces around a preserved segment break are removed
+                if output.ends_with(' ') {
+                    output.pop();
+                }
+                output.push('\n');
+                follows_collapsible_space = true
             }
-            loop {
-                if let Some(i) = input.bytes().position(|b| b.is_ascii_whitespace()) {
-                    let (non_whitespace, rest) = input.split_at(i);
-                    output.push_str(non_whitespace);
+            _ if ch.is_ascii_whitespace() => {
+                if !follows_collapsible_space {
                     output.push(' ');
-                    if let Some(i) = rest.bytes().position(|b| !b.is_ascii_whitespace()) {
-                        input = &rest[i..];
-                    } else {
-                        break;
-                    }
-                } else {
-                    output.push_str(input);
-                    break;
+                    follows_collapsible_space = true
                 }
             }
-
-            if let Some(text) = new_text_run_contents {
-                let parent_style = parent_style.clone();
-                inlines.push(Arc::new(InlineLevelBox::TextRun(TextRun {
-                    parent_style,
-                    text,
-                })))
+            _ => {
+                output.push(ch);
+                follows_collapsible_space = false
             }
         }
     }
 }
 
 impl<'a> BlockContainerBuilder<'a> {
-    /// Returns:
-    ///
-    /// * Whether this text run has preserved (non-collapsible) leading whitespace
-    /// * The contents starting at the first non-whitespace character (or the empty string)
-    fn handle_leading_whitespace<'text>(&mut self, text: &'text str) -> (bool, &'text str) {
+    /// Whether a collapsible space at this point would be removed,
+    /// because it follows another one, a preserved segment break,
+    /// or is at the start of the paragraph
+    fn follows_collapsible_space(&self) -> bool {
         // FIXME: this is only an approximation of
         // https://drafts.csswg.org/css2/text.html#white-space-model
-        if !text.starts_with(|c: char| c.is_ascii_whitespace()) {
-            return (false, text);
+        match self.preceding_text() {
+            None => true,
+            // A space preserved by `white-space: pre` or `pre-wrap` is not collapsible
+            Some((' ', Some(style))) => !style.inherited_text.white_space.preserves_spaces(),
+            Some((ch, _)) => ch == '\n',
         }
-        let mut inline_level_boxes = self.current_inline_level_boxes().iter().rev();
-        let mut stack = Vec::new();
-        let preserved = loop {
-            match inline_level_boxes.next().map(|b| &**b) {
-                Some(InlineLevelBox::TextRun(r)) => break !r.text.ends_with(' '),
-                Some(InlineLevelBox::Atomic { .. }) => break false,
-                Some(InlineLevelBox::OutOfFlowAbsolutelyPositionedBox(_))
-                | Some(InlineLevelBox::OutOfFlowFloatBox(_)) => {}
-                Some(InlineLevelBox::InlineBox(b)) => {
-                    stack.push(inline_level_boxes);
-                    inline_level_boxes = b.children.iter().rev()
-                }
-                None => {
-                    if let Some(iter) = stack.pop() {
-                        inline_level_boxes = iter
-                    } else {
-                        break false; // Paragraph start
+    }
+
+    /// The last character of text content so far in the ongoing inline formatting context,
+    /// with atomic inlines as U+FFFC OBJECT REPLACEMENT CHARACTER,
+    /// or `None` at the start of the paragraph
+    fn preceding_char(&self) -> Option<char> {
+        self.preceding_text().map(|(ch, _)| ch)
+    }
+
+    /// Like `preceding_char`, with the parent style of the text run it is from
+    fn preceding_text(&self) -> Option<(char, Option<&ComputedValues>)> {
+        fn last_char(
+            inline_level_boxes: &[Arc<InlineLevelBox>],
+        ) -> Option<(char, Option<&ComputedValues>)> {
+            inline_level_boxes
+                .iter()
+                .rev()
+                .find_map(|box_| match &**box_ {
+                    // Text runs are never empty
+                    InlineLevelBox::TextRun(r) => {
+                        Some((r.text.chars().next_back()?, Some(&*r.parent_style)))
                     }
-                }
-            }
-        };
-        let text = text.trim_start_matches(|c: char| c.is_ascii_whitespace());
-        (preserved, text)
+                    InlineLevelBox::Atomic { .. } => Some(('\u{FFFC}', None)),
+                    InlineLevelBox::InlineBox(b) => last_char(&b.children),
+                    InlineLevelBox::OutOfFlowAbsolutelyPositionedBox(_)
+                    | InlineLevelBox::OutOfFlowFloatBox(_) => None,
+                })
+        }
+        // Innermost ongoing inline box first
+        self.ongoing_inline_boxes_stack
+            .iter()
+            .rev()
+            .map(|b| &b.children[..])
+            .chain(std::iter::once(
+                &self.ongoing_inline_formatting_context.inline_level_boxes[..],
+            ))
+            .find_map(last_char)
     }
 
     fn handle_inline_level_element(
@@ -301,7 +388,7 @@ impl<'a> BlockContainerBuilder<'a> {
         let box_ = match contents.try_into() {
             Err(replaced) => Arc::new(InlineLevelBox::Atomic {
                 style: style.clone(),
-                contents: replaced,
+                contents: IndependentFormattingContext::Replaced(replaced),
             }),
             Ok(non_replaced) => match display_inside {
                 DisplayInside::Flow => {
@@ -315,6 +402,7 @@ impl<'a> BlockContainerBuilder<'a> {
                     });
 
                     NonReplacedContents::traverse(non_replaced, &style, self.context, self);
+                    self.end_ongoing_anonymous_table();
 
                     let mut inline_box = self
                         .ongoing_inline_boxes_stack
@@ -323,9 +411,19 @@ impl<'a> BlockContainerBuilder<'a> {
                     inline_box.last_fragment = true;
                     Arc::new(InlineLevelBox::InlineBox(inline_box))
                 }
-                DisplayInside::FlowRoot => {
-                    // a.k.a. `inline-block`
-                    unimplemented!()
+                // a.k.a. `inline-block`, `inline-table`, `inline-flex`, and `inline-grid`
+                DisplayInside::FlowRoot
+                | DisplayInside::Table
+                | DisplayInside::Flex
+                | DisplayInside::Grid => {
+                    let mut style = style.clone();
+                    let contents = IndependentFormattingContext::construct(
+                        self.context,
+                        &mut style,
+                        display_inside,
+                        non_replaced.into(),
+                    );
+                    Arc::new(InlineLevelBox::Atomic { style, contents })
                 }
             },
         };
@@ -383,12 +481,19 @@ impl<'a> BlockContainerBuilder<'a> {
         // context needs to be ended.
         self.end_ongoing_inline_formatting_context();
 
+        // https://drafts.csswg.org/css-writing-modes/#block-flow
+        // “If a box has a different writing-mode value than its parent box,
+        //  […] it establishes an independent formatting context.”
+        let same_writing_mode =
+            style.writing_mode().0 == self.block_container_style.writing_mode().0;
         let intermediate_box = match contents.try_into() {
             Ok(contents) => match display_inside {
-                DisplayInside::Flow => IntermediateBlockLevelBox::SameFormattingContextBlock {
-                    style,
-                    contents: IntermediateBlockContainer::Deferred { contents },
-                },
+                DisplayInside::Flow if same_writing_mode => {
+                    IntermediateBlockLevelBox::SameFormattingContextBlock {
+                        style,
+                        contents: IntermediateBlockContainer::Deferred { contents },
+                    }
+                }
                 _ => IntermediateBlockLevelBox::Independent {
                     style,
                     display_inside,
@@ -409,7 +514,7 @@ impl<'a> BlockContainerBuilder<'a> {
 
     fn handle_absolutely_positioned_element(
         &mut self,
-        style: Arc<ComputedValues>,
+        mut style: Arc<ComputedValues>,
         display_inside: DisplayInside,
         contents: Contents,
         box_slot: BoxSlot<'a>,
@@ -426,7 +531,7 @@ impl<'a> BlockContainerBuilder<'a> {
                 AbsolutelyPositionedBox {
                     contents: IndependentFormattingContext::construct(
                         self.context,
-                        &style,
+                        &mut style,
                         display_inside,
                         contents,
                     ),
@@ -440,7 +545,7 @@ impl<'a> BlockContainerBuilder<'a> {
 
     fn handle_float_element(
         &mut self,
-        style: Arc<ComputedValues>,
+        mut style: Arc<ComputedValues>,
         display_inside: DisplayInside,
         contents: Contents,
         box_slot: BoxSlot<'a>,
@@ -458,7 +563,7 @@ impl<'a> BlockContainerBuilder<'a> {
             let box_ = Arc::new(InlineLevelBox::OutOfFlowFloatBox(FloatBox {
                 contents: IndependentFormattingContext::construct(
                     self.context,
-                    &style,
+                    &mut style,
                     display_inside,
                     contents,
                 ),
@@ -484,7 +589,7 @@ impl<'a> BlockContainerBuilder<'a> {
             return;
         }
 
-        let block_container_style = self.block_container_style;
+        let block_container_style = &self.block_container_style;
         let anonymous_style = self.anonymous_style.get_or_insert_with(|| {
             // If parent_style is None, the parent is the document node,
             // in which case anonymous inline boxes should inherit their
@@ -501,6 +606,31 @@ impl<'a> BlockContainerBuilder<'a> {
         self.block_level_boxes.push((box_, BoxSlot::dummy()))
     }
 
+    fn end_ongoing_anonymous_table(&mut self) {
+        let (style, table) = match self.ongoing_anonymous_table.take() {
+            Some(ongoing) => ongoing,
+            None => return,
+        };
+        let contents = table.finish();
+        // The anonymous table is inline-level if its parent is an inline box
+        if self.ongoing_inline_boxes_stack.is_empty() {
+            self.end_ongoing_inline_formatting_context();
+            let box_ = IntermediateBlockLevelBox::AnonymousTable { style, contents };
+            self.block_level_boxes.push((box_, BoxSlot::dummy()))
+        } else {
+            let contents = IndependentFormattingContext::Table(contents);
+            let box_ = Arc::new(InlineLevelBox::Atomic { style, contents });
+            self.current_inline_level_boxes().push(box_)
+        }
+    }
+
+    /// The style of the innermost ongoing inline box, if any, or of the block container
+    fn current_inline_box_style(&self) -> &Arc<ComputedValues> {
+        self.ongoing_inline_boxes_stack
+            .last()
+            .map_or(&self.block_container_style, |inline_box| &inline_box.style)
+    }
+
     fn current_inline_level_boxes(&mut self) -> &mut Vec<Arc<InlineLevelBox>> {
         match self.ongoing_inline_boxes_stack.last_mut() {
             Some(last) => &mut last.children,
@@ -527,13 +657,13 @@ impl IntermediateBlockLevelBox {
                 (block_level_box, contains_floats)
             }
             IntermediateBlockLevelBox::Independent {
-                style,
+                mut style,
                 display_inside,
                 contents,
             } => {
                 let contents = IndependentFormattingContext::construct(
                     context,
-                    &style,
+                    &mut style,
                     display_inside,
                     contents,
                 );
@@ -543,7 +673,7 @@ impl IntermediateBlockLevelBox {
                 )
             }
             IntermediateBlockLevelBox::OutOfFlowAbsolutelyPositionedBox {
-                style,
+                mut style,
                 display_inside,
                 contents,
             } => {
@@ -551,7 +681,7 @@ impl IntermediateBlockLevelBox {
                     AbsolutelyPositionedBox {
                         contents: IndependentFormattingContext::construct(
                             context,
-                            &style,
+                            &mut style,
                             display_inside,
                             contents,
                         ),
@@ -561,13 +691,13 @@ impl IntermediateBlockLevelBox {
                 (block_level_box, ContainsFloats::No)
             }
             IntermediateBlockLevelBox::OutOfFlowFloatBox {
-                style,
+                mut style,
                 display_inside,
                 contents,
             } => {
                 let contents = IndependentFormattingContext::construct(
                     context,
-                    &style,
+                    &mut style,
                     display_inside,
                     contents,
                 );
@@ -577,6 +707,13 @@ impl IntermediateBlockLevelBox {
                 }));
                 (block_level_box, ContainsFloats::Yes)
             }
+            IntermediateBlockLevelBox::AnonymousTable { style, contents } => {
+                let contents = IndependentFormattingContext::Table(contents);
+                (
+                    Arc::new(BlockLevelBox::Independent { style, contents }),
+                    ContainsFloats::No,
+                )
+            }
         }
     }
 }
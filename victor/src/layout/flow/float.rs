@@ -7,12 +7,344 @@ pub(in crate::layout) struct FloatBox {
 }
 
 /// Data kept during layout about the floats in a given block formatting context.
+///
+/// Positions are relative to the content box of the box that establishes
+/// the block formatting context, in its writing mode.
 pub(in crate::layout) struct FloatContext {
-    // TODO
+    /// That of the box that establishes the block formatting context
+    direction: Direction,
+    /// Margin boxes of the floats placed so far, in tree order
+    floats: Vec<PlacedFloat>,
+    /// “The outer top of a floating box may not be higher than the outer top
+    ///  of any block or floated box generated by an element earlier in the source document.”
+    ceiling: Length,
+    /// Where the content box of the block container being laid out starts,
+    /// in the direction of the block formatting context
+    pub(super) containing_block_inline_start: Length,
+    /// Where the next block-level box or line box starts, before `pending_margin`
+    pub(super) block_position: Length,
+    /// Margins not yet collapsed with those of the next block-level box
+    pub(super) pending_margin: CollapsedMargin,
+    /// Set by the layout of a block-level box that was moved down past floats,
+    /// to where its border box starts. Its margins do not collapse with the previous ones.
+    pub(super) clearance: Option<Length>,
+}
+
+struct PlacedFloat {
+    side: FloatSide,
+    rect: Rect<Length>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum FloatSide {
+    InlineStart,
+    InlineEnd,
+}
+
+impl FloatSide {
+    /// `left` and `right` are line-relative
+    ///
+    /// https://drafts.csswg.org/css-writing-modes/#line-left
+    fn new(float: Float, direction: Direction) -> Option<Self> {
+        match (float, direction) {
+            (Float::None, _) => None,
+            (Float::Left, Direction::Ltr) | (Float::Right, Direction::Rtl) => {
+                Some(FloatSide::InlineStart)
+            }
+            (Float::Left, Direction::Rtl) | (Float::Right, Direction::Ltr) => {
+                Some(FloatSide::InlineEnd)
+            }
+        }
+    }
+
+    fn is_cleared_by(self, clear: Clear, direction: Direction) -> bool {
+        let float = match clear {
+            Clear::None => return false,
+            Clear::Both => return true,
+            Clear::Left => Float::Left,
+            Clear::Right => Float::Right,
+        };
+        FloatSide::new(float, direction) == Some(self)
+    }
 }
 
 impl FloatContext {
-    pub fn new() -> Self {
-        FloatContext {}
+    pub fn new(direction: Direction) -> Self {
+        FloatContext {
+            direction,
+            floats: Vec::new(),
+            ceiling: Length::zero(),
+            containing_block_inline_start: Length::zero(),
+            block_position: Length::zero(),
+            pending_margin: CollapsedMargin::zero(),
+            clearance: None,
+        }
+    }
+
+    /// The block-end edge of the lowest float
+    pub(super) fn lowest_float_end(&self) -> Option<Length> {
+        self.floats
+            .iter()
+            .map(|float| float.rect.start_corner.block + float.rect.size.block)
+            .fold(None, |lowest, end| {
+                Some(lowest.map_or(end, |l: Length| l.max(end)))
+            })
+    }
+
+    /// Where a box with this value of `clear` can start, below the floats it clears
+    ///
+    /// https://drafts.csswg.org/css2/visuren.html#flow-control
+    pub(super) fn clear_position(&self, clear: Clear) -> Option<Length> {
+        self.floats
+            .iter()
+            .filter(|float| float.side.is_cleared_by(clear, self.direction))
+            .map(|float| float.rect.start_corner.block + float.rect.size.block)
+            .fold(None, |lowest, end| {
+                Some(lowest.map_or(end, |l: Length| l.max(end)))
+            })
+    }
+
+    /// The start and end of the space between floats in the containing block,
+    /// relative to its content box, for content that spans the block-axis range
+    /// from `block_start` to `block_end`.
+    pub(super) fn available_inline_range(
+        &self,
+        containing_block: &ContainingBlock,
+        block_start: Length,
+        block_end: Length,
+    ) -> (Length, Length) {
+        let range = self.space_between_floats(containing_block, block_start, block_end);
+        self.to_containing_block(containing_block, range)
+    }
+
+    /// Like `available_inline_range`, but relative to the block formatting context
+    fn space_between_floats(
+        &self,
+        containing_block: &ContainingBlock,
+        block_start: Length,
+        block_end: Length,
+    ) -> (Length, Length) {
+        let mut start = self.containing_block_inline_start;
+        let mut end = start + containing_block.inline_size;
+        for float in self.overlapping(block_start, block_end) {
+            let rect = &float.rect;
+            match float.side {
+                FloatSide::InlineStart => {
+                    start.max_assign(rect.start_corner.inline + rect.size.inline)
+                }
+                FloatSide::InlineEnd => end = end.min(rect.start_corner.inline),
+            }
+        }
+        (start, end)
+    }
+
+    /// Converts a range in the inline axis, relative to the block formatting context,
+    /// to one relative to the content box of the containing block in its direction.
+    fn to_containing_block(
+        &self,
+        containing_block: &ContainingBlock,
+        (start, end): (Length, Length),
+    ) -> (Length, Length) {
+        let containing_block_start = self.containing_block_inline_start;
+        if containing_block.mode.1 == self.direction {
+            (start - containing_block_start, end - containing_block_start)
+        } else {
+            let containing_block_end = containing_block_start + containing_block.inline_size;
+            (containing_block_end - end, containing_block_end - start)
+        }
+    }
+
+    /// Moves `containing_block_inline_start` to the content box of a child
+    /// with these sums of margin, border and padding on its inline-start and inline-end sides.
+    pub(super) fn enter_child(
+        &mut self,
+        containing_block: &ContainingBlock,
+        inline_start: Length,
+        inline_end: Length,
+    ) {
+        self.containing_block_inline_start += if containing_block.mode.1 == self.direction {
+            inline_start
+        } else {
+            inline_end
+        }
+    }
+
+    /// Where the first float beside content spanning from `block_start` to `block_end` ends,
+    /// which is the next position to try for content that does not fit next to floats.
+    pub(super) fn next_float_end(&self, block_start: Length, block_end: Length) -> Option<Length> {
+        self.overlapping(block_start, block_end)
+            .map(|float| float.rect.start_corner.block + float.rect.size.block)
+            .fold(None, |next, end| {
+                Some(next.map_or(end, |n: Length| n.min(end)))
+            })
+    }
+
+    fn overlapping(
+        &self,
+        block_start: Length,
+        block_end: Length,
+    ) -> impl Iterator<Item = &PlacedFloat> {
+        self.floats.iter().filter(move |float| {
+            let float_start = float.rect.start_corner.block;
+            let float_end = float_start + float.rect.size.block;
+            // Content with a zero block size still has floats beside it
+            float_end > block_start && (float_start < block_end || float_start <= block_start)
+        })
+    }
+
+    /// Places a float as high as possible and then as far towards its side as possible,
+    /// not higher than `block_position`. Returns the start corner of its margin box,
+    /// relative to the content box of the containing block in the inline axis
+    /// and to the block formatting context in the block axis.
+    ///
+    /// https://drafts.csswg.org/css2/visuren.html#float-position
+    pub(super) fn place(
+        &mut self,
+        style: &ComputedValues,
+        containing_block: &ContainingBlock,
+        margin_box_size: Vec2<Length>,
+        block_position: Length,
+    ) -> Vec2<Length> {
+        let side = FloatSide::new(style.box_.float, self.direction).unwrap();
+        let mut block = block_position.max(self.ceiling);
+        if let Some(clear) = self.clear_position(style.box_.clear) {
+            block.max_assign(clear)
+        }
+        let (start, end) = loop {
+            let block_end = block + margin_box_size.block;
+            let (start, end) = self.space_between_floats(containing_block, block, block_end);
+            if end - start >= margin_box_size.inline {
+                break (start, end);
+            }
+            // A float wider than its containing block goes below the other floats
+            match self.next_float_end(block, block_end) {
+                Some(next) => block = next,
+                None => break (start, end),
+            }
+        };
+        let inline_start = match side {
+            FloatSide::InlineStart => start,
+            FloatSide::InlineEnd => end - margin_box_size.inline,
+        };
+        let rect = Rect {
+            start_corner: Vec2 {
+                inline: inline_start,
+                block,
+            },
+            size: margin_box_size,
+        };
+        let (inline, _) = self.to_containing_block(
+            containing_block,
+            (inline_start, inline_start + rect.size.inline),
+        );
+        self.floats.push(PlacedFloat { side, rect });
+        self.ceiling = block;
+        Vec2 { inline, block }
+    }
+}
+
+impl FloatBox {
+    /// Lays out the contents of this float, with its margin box at the origin.
+    ///
+    /// https://drafts.csswg.org/css2/visudet.html#float-width
+    /// https://drafts.csswg.org/css2/visudet.html#block-root-margin
+    pub(super) fn layout<'a>(
+        &'a self,
+        containing_block: &ContainingBlock,
+        tree_rank: usize,
+        absolutely_positioned_fragments: &mut Vec<AbsolutelyPositionedFragment<'a>>,
+    ) -> BoxFragment {
+        let style = &self.style;
+        let cbis = containing_block.inline_size;
+        let padding = style
+            .padding(containing_block.mode)
+            .percentages_relative_to(cbis);
+        let border = style
+            .border_width(containing_block.mode)
+            .percentages_relative_to(cbis);
+        // “If 'margin-left', or 'margin-right' are computed as 'auto', their used value is '0'.”
+        let margin = style
+            .margin(containing_block.mode)
+            .percentages_relative_to(cbis)
+            .auto_is(Length::zero);
+        let pb = &padding + &border;
+        let box_size = style.box_size(containing_block.mode);
+        // FIXME: orthogonal flows, see `layout_in_flow_non_replaced_block_level`
+        let mode = style.writing_mode();
+        let available = cbis - pb.inline_sum() - margin.inline_sum();
+        let content_sizes = || self.contents.inline_content_sizes(style);
+        let inline_size = sizing::inline_size(box_size.inline, cbis, available, content_sizes)
+            .auto_is(|| content_sizes().shrink_to_fit(available));
+        let block_size = sizing::block_size(box_size.block, containing_block.block_size);
+        let containing_block_for_children = ContainingBlock {
+            inline_size,
+            block_size,
+            mode,
+            style,
+        };
+        let mut nested_abspos = vec![];
+        let mut flow_children = self.contents.layout(
+            &containing_block_for_children,
+            tree_rank,
+            if style.box_.position.is_relatively_positioned() {
+                &mut nested_abspos
+            } else {
+                absolutely_positioned_fragments
+            },
+        );
+        let content_size = Vec2 {
+            inline: inline_size,
+            block: block_size.auto_is(|| flow_children.block_size),
+        };
+        if style.box_.position.is_relatively_positioned() {
+            AbsolutelyPositionedFragment::in_positioned_containing_block(
+                &nested_abspos,
+                &mut flow_children.fragments,
+                &content_size
+                    .size_to_physical(containing_block.mode)
+                    .size_to_flow_relative(mode),
+                &style.padding(mode).percentages_relative_to(cbis),
+                mode,
+            )
+        }
+        let mut start_corner = Vec2 {
+            inline: margin.inline_start + pb.inline_start,
+            block: margin.block_start + pb.block_start,
+        };
+        start_corner += &relative_adjustement(
+            style,
+            cbis,
+            containing_block.block_size,
+            containing_block.mode,
+        );
+        BoxFragment {
+            style: style.clone(),
+            children: flow_children.fragments,
+            content_rect: Rect {
+                start_corner,
+                size: content_size,
+            },
+            padding,
+            border,
+            margin,
+            block_margins_collapsed_with_children: CollapsedBlockMargins::zero(),
+            // Out of flow
+            last_baseline: None,
+        }
+    }
+}
+
+/// The size of the margin box of a fragment
+pub(super) fn margin_box_size(fragment: &BoxFragment) -> Vec2<Length> {
+    let size = &fragment.content_rect.size;
+    Vec2 {
+        inline: size.inline
+            + fragment.padding.inline_sum()
+            + fragment.border.inline_sum()
+            + fragment.margin.inline_sum(),
+        block: size.block
+            + fragment.padding.block_sum()
+            + fragment.border.block_sum()
+            + fragment.margin.block_sum(),
     }
 }
@@ -1,10 +1,15 @@
+use super::bidi_mirroring::mirrored;
+use super::line_breaking::*;
+use super::text_transform::{push_uppercase, Language};
 use super::*;
-use crate::fonts::BITSTREAM_VERA_SANS;
-use crate::text::ShapedSegment;
+use crate::text::{ShapedSegment, ShapedSegmentState};
+use std::mem::replace;
+use unicode_bidi::{BidiInfo, Level};
 
 #[derive(Debug, Default)]
 pub(in crate::layout) struct InlineFormattingContext {
     pub(super) inline_level_boxes: Vec<Arc<InlineLevelBox>>,
+    pub(super) content_sizes: ContentSizesCache,
 }
 
 #[derive(Debug)]
@@ -15,8 +20,7 @@ pub(in crate::layout) enum InlineLevelBox {
     OutOfFlowFloatBox(FloatBox),
     Atomic {
         style: Arc<ComputedValues>,
-        // FIXME: this should be IndependentFormattingContext:
-        contents: ReplacedContent,
+        contents: IndependentFormattingContext,
     },
 }
 
@@ -39,11 +43,32 @@ struct InlineNestingLevelState<'box_tree> {
     remaining_boxes: std::slice::Iter<'box_tree, Arc<InlineLevelBox>>,
     fragments_so_far: Vec<Fragment>,
     inline_start: Length,
-    max_block_size_of_fragments_so_far: Length,
+    /// The union of the layout bounds of inline boxes on the current line,
+    /// relative to the baseline of this nesting level
+    layout_bounds_so_far: Option<BaselineExtents>,
+    /// Among the fragments so far (possibly nested), those aligned with the line box.
+    /// They are positioned when the line box is finished.
+    line_relative_boxes: Vec<LineRelativeBox>,
+}
+
+/// An inline box with `vertical-align: top` or `bottom`
+struct LineRelativeBox {
+    /// Indices of its fragment in `fragments_so_far` and then in `children` of boxes,
+    /// innermost first so that finishing an enclosing box can push to it.
+    path: Vec<usize>,
+    /// Position of its baseline relative to the baseline of the nesting level that holds this
+    baseline_offset: Length,
+    /// Of the box’s aligned subtree, relative to its own baseline
+    layout_bounds: BaselineExtents,
+    vertical_align: VerticalAlign,
 }
 
 struct PartialInlineBoxFragment<'box_tree> {
     style: Arc<ComputedValues>,
+    metrics: InlineBoxMetrics,
+    /// How far below the baseline of its parent this box’s baseline is,
+    /// or `None` when aligned with the line box instead.
+    baseline_shift: Option<Length>,
     start_corner: Vec2<Length>,
     padding: Sides<Length>,
     border: Sides<Length>,
@@ -53,16 +78,86 @@ struct PartialInlineBoxFragment<'box_tree> {
 }
 
 struct InlineFormattingContextState<'box_tree, 'cb> {
-    containing_block: &'cb ContainingBlock,
+    containing_block: &'cb ContainingBlock<'cb>,
     line_boxes: LinesBoxes,
     inline_position: Length,
+    /// Where content starts on the current line, after `text-indent`
+    line_start: Length,
     partial_inline_boxes_stack: Vec<PartialInlineBoxFragment<'box_tree>>,
     current_nesting_level: InlineNestingLevelState<'box_tree>,
+    bidi: BidiLevels,
+    break_opportunities: BreakOpportunities,
+    float_context: Option<&'cb mut FloatContext>,
+    /// Where the content box of the containing block starts in the block axis,
+    /// relative to the block formatting context
+    float_context_block_start: Length,
+    /// Floats that did not fit on the current line, placed below it
+    pending_floats: Vec<BoxFragment>,
+}
+
+/// The result of running the Unicode Bidirectional Algorithm
+/// on the text content of an inline formatting context.
+/// https://drafts.csswg.org/css-writing-modes/#bidi-algo
+struct BidiLevels {
+    /// One per byte of text content
+    levels: Vec<Level>,
+    /// Where each text run starts in `levels`, in tree order
+    text_run_starts: std::vec::IntoIter<usize>,
 }
 
 struct LinesBoxes {
     boxes: Vec<Fragment>,
     next_line_block_position: Length,
+    /// Line boxes without text, preserved white space, or inline boxes with
+    /// inline-axis margins, borders, or padding are treated as zero-height.
+    /// https://drafts.csswg.org/css2/visuren.html#phantom-line-box
+    current_line_has_content: bool,
+    /// Of the last line box that was not treated as zero-height
+    last_baseline: Option<Length>,
+    /// The part of the containing block next to floats, for the current line
+    line_inline_start: Length,
+    line_inline_size: Length,
+    /// How far floats moved the current line down, undone if it is treated as zero-height
+    moved_past_floats: Length,
+}
+
+/// Block-axis metrics of an inline box, or of the strut of a line box
+///
+/// https://drafts.csswg.org/css2/visudet.html#inline-box-height
+#[derive(Clone, Copy)]
+struct InlineBoxMetrics {
+    /// A in CSS 2: distance from the top of the content area to the baseline
+    ascent: Length,
+    /// D in CSS 2: distance from the baseline to the bottom of the content area
+    descent: Length,
+    /// The used value of `line-height`
+    line_height: Length,
+    x_height: Length,
+    /// Where children with `vertical-align: sub` or `super` have their baseline
+    subscript_offset: Length,
+    superscript_offset: Length,
+}
+
+/// Distances from a baseline along the block axis, towards block-start and block-end
+#[derive(Clone, Copy)]
+struct BaselineExtents {
+    above: Length,
+    below: Length,
+}
+
+/// Measures content as if lines broke at every soft wrap opportunity for `min-content`,
+/// and only at forced line breaks for `max-content`.
+struct ContentSizesComputation<'cb> {
+    containing_block_style: &'cb ComputedValues,
+    break_opportunities: BreakOpportunities,
+    sizes: ContentSizes,
+    /// Of the current line, with only forced line breaks
+    line: Length,
+    /// Since the last soft wrap opportunity
+    segment: Length,
+    /// Collapsible spaces at the end of `line` or `segment` so far, which hang if it ends there
+    line_trailing_spaces: Length,
+    segment_trailing_spaces: Length,
 }
 
 impl InlineFormattingContext {
@@ -71,22 +166,43 @@ impl InlineFormattingContext {
         containing_block: &ContainingBlock,
         tree_rank: usize,
         absolutely_positioned_fragments: &mut Vec<AbsolutelyPositionedFragment<'a>>,
+        float_context: Option<&mut FloatContext>,
     ) -> FlowChildren {
+        let line_start = text_indent(containing_block, true);
+        let float_context_block_start = match &float_context {
+            Some(float_context) => {
+                float_context.block_position + float_context.pending_margin.solve()
+            }
+            None => Length::zero(),
+        };
         let mut ifc = InlineFormattingContextState {
             containing_block,
             partial_inline_boxes_stack: Vec::new(),
             line_boxes: LinesBoxes {
                 boxes: Vec::new(),
                 next_line_block_position: Length::zero(),
+                current_line_has_content: false,
+                last_baseline: None,
+                line_inline_start: Length::zero(),
+                line_inline_size: containing_block.inline_size,
+                moved_past_floats: Length::zero(),
             },
-            inline_position: Length::zero(),
+            inline_position: line_start,
+            line_start,
             current_nesting_level: InlineNestingLevelState {
                 remaining_boxes: self.inline_level_boxes.iter(),
                 fragments_so_far: Vec::with_capacity(self.inline_level_boxes.len()),
                 inline_start: Length::zero(),
-                max_block_size_of_fragments_so_far: Length::zero(),
+                layout_bounds_so_far: None,
+                line_relative_boxes: Vec::new(),
             },
+            bidi: self.bidi_levels(containing_block),
+            break_opportunities: self.break_opportunities(),
+            float_context,
+            float_context_block_start,
+            pending_floats: Vec::new(),
         };
+        ifc.fit_line_between_floats();
         loop {
             if let Some(child) = ifc.current_nesting_level.remaining_boxes.next() {
                 match &**child {
@@ -95,10 +211,13 @@ impl InlineFormattingContext {
                         ifc.partial_inline_boxes_stack.push(partial)
                     }
                     InlineLevelBox::TextRun(run) => run.layout(&mut ifc),
-                    InlineLevelBox::Atomic { style: _, contents } => {
-                        // FIXME
-                        match *contents {}
-                    }
+                    InlineLevelBox::Atomic { style, contents } => layout_atomic(
+                        &mut ifc,
+                        style,
+                        contents,
+                        tree_rank,
+                        absolutely_positioned_fragments,
+                    ),
                     InlineLevelBox::OutOfFlowAbsolutelyPositionedBox(box_) => {
                         let initial_start_corner = match box_.style.specified_display {
                             Display::GeneratingBox(DisplayGeneratingBox::OutsideInside {
@@ -106,22 +225,42 @@ impl InlineFormattingContext {
                                 inside: _,
                             }) => Vec2 {
                                 inline: match outside {
-                                    DisplayOutside::Inline => ifc.inline_position,
+                                    DisplayOutside::Inline => {
+                                        ifc.line_boxes.line_inline_start + ifc.inline_position
+                                    }
                                     DisplayOutside::Block => Length::zero(),
                                 },
                                 block: ifc.line_boxes.next_line_block_position,
                             },
+                            // Blockified like `display: block`
+                            Display::GeneratingBox(DisplayGeneratingBox::LayoutInternal(_)) => {
+                                Vec2 {
+                                    inline: Length::zero(),
+                                    block: ifc.line_boxes.next_line_block_position,
+                                }
+                            }
                             Display::Contents => {
                                 panic!("display:contents does not generate an abspos box")
                             }
                             Display::None => panic!("display:none does not generate an abspos box"),
                         };
-                        absolutely_positioned_fragments
-                            .push(box_.layout(initial_start_corner, tree_rank));
+                        absolutely_positioned_fragments.push(box_.layout(
+                            initial_start_corner,
+                            tree_rank,
+                            containing_block.mode,
+                        ));
                     }
-                    InlineLevelBox::OutOfFlowFloatBox(_box_) => {
-                        // TODO
-                        continue;
+                    InlineLevelBox::OutOfFlowFloatBox(box_) => {
+                        // Floats are only placed with a float context
+                        if ifc.float_context.is_some() {
+                            // FIXME: static positions of absolutely positioned descendants
+                            let fragment = box_.layout(
+                                containing_block,
+                                tree_rank,
+                                absolutely_positioned_fragments,
+                            );
+                            ifc.float(fragment)
+                        }
                     }
                 }
             } else
@@ -134,44 +273,731 @@ impl InlineFormattingContext {
                 );
                 ifc.current_nesting_level = partial.parent_nesting_level
             } else {
-                ifc.line_boxes
-                    .finish_line(&mut ifc.current_nesting_level, containing_block);
+                ifc.line_boxes.finish_line(
+                    &mut ifc.current_nesting_level,
+                    containing_block,
+                    ifc.inline_position,
+                    true,
+                );
+                ifc.place_pending_floats();
                 return FlowChildren {
                     fragments: ifc.line_boxes.boxes,
                     block_size: ifc.line_boxes.next_line_block_position,
                     collapsible_margins_in_children: CollapsedBlockMargins::zero(),
+                    last_baseline: ifc.line_boxes.last_baseline,
                 };
             }
         }
     }
+
+    /// `containing_block_style` is that of the block container
+    /// that establishes this inline formatting context.
+    pub(super) fn inline_content_sizes(
+        &self,
+        containing_block_style: &ComputedValues,
+    ) -> ContentSizes {
+        self.content_sizes.get_or_compute(|| {
+            // Percentages of the containing block are treated as zero
+            let text_indent = containing_block_style.inherited_text.text_indent;
+            let first_line_start = if text_indent.hanging {
+                Length::zero()
+            } else {
+                text_indent.length.percentage_relative_to(Length::zero())
+            };
+            let mut computation = ContentSizesComputation {
+                containing_block_style,
+                break_opportunities: self.break_opportunities(),
+                sizes: ContentSizes::zero(),
+                line: first_line_start,
+                segment: first_line_start,
+                line_trailing_spaces: Length::zero(),
+                segment_trailing_spaces: Length::zero(),
+            };
+            computation.traverse(&self.inline_level_boxes);
+            computation.forced_line_break();
+            let mut sizes = computation.sizes;
+            sizes.max_content.max_assign(sizes.min_content);
+            sizes
+        })
+    }
+
+    fn bidi_levels(&self, containing_block: &ContainingBlock) -> BidiLevels {
+        fn collect(
+            boxes: &[Arc<InlineLevelBox>],
+            text: &mut String,
+            text_run_starts: &mut Vec<usize>,
+        ) {
+            for box_ in boxes {
+                match &**box_ {
+                    InlineLevelBox::InlineBox(inline) => {
+                        let (start, end) = bidi_control_codes(&inline.style);
+                        text.push_str(start);
+                        collect(&inline.children, text, text_run_starts);
+                        text.push_str(end);
+                    }
+                    InlineLevelBox::TextRun(run) => {
+                        text_run_starts.push(text.len());
+                        text.push_str(&run.text);
+                    }
+                    // https://drafts.csswg.org/css-writing-modes/#bidi-atomic-inlines
+                    InlineLevelBox::Atomic { .. } => text.push('\u{FFFC}'),
+                    InlineLevelBox::OutOfFlowAbsolutelyPositionedBox(_)
+                    | InlineLevelBox::OutOfFlowFloatBox(_) => {}
+                }
+            }
+        }
+
+        let mut text = String::new();
+        let mut text_run_starts = Vec::new();
+        collect(&self.inline_level_boxes, &mut text, &mut text_run_starts);
+        // With `unicode-bidi: plaintext`, the level of each paragraph is that of
+        // its first strong character (rules P2 and P3) rather than from `direction`.
+        // https://drafts.csswg.org/css-writing-modes/#valdef-unicode-bidi-plaintext
+        let paragraph_level = match (
+            containing_block.style.text.unicode_bidi,
+            containing_block.mode.1,
+        ) {
+            (UnicodeBidi::Plaintext, _) => None,
+            (_, Direction::Ltr) => Some(Level::ltr()),
+            (_, Direction::Rtl) => Some(Level::rtl()),
+        };
+        BidiLevels {
+            levels: BidiInfo::new(&text, paragraph_level).levels,
+            text_run_starts: text_run_starts.into_iter(),
+        }
+    }
+}
+
+/// https://drafts.csswg.org/css-writing-modes/#bidi-control-codes-injection-table
+fn bidi_control_codes(style: &ComputedValues) -> (&'static str, &'static str) {
+    use Direction::*;
+    use UnicodeBidi::*;
+    match (style.text.unicode_bidi, style.inherited_box.direction) {
+        (Normal, _) => ("", ""),
+        (Embed, Ltr) => ("\u{202A}", "\u{202C}"),
+        (Embed, Rtl) => ("\u{202B}", "\u{202C}"),
+        (Isolate, Ltr) => ("\u{2066}", "\u{2069}"),
+        (Isolate, Rtl) => ("\u{2067}", "\u{2069}"),
+        (BidiOverride, Ltr) => ("\u{202D}", "\u{202C}"),
+        (BidiOverride, Rtl) => ("\u{202E}", "\u{202C}"),
+        (IsolateOverride, Ltr) => ("\u{2066}\u{202D}", "\u{202C}\u{2069}"),
+        (IsolateOverride, Rtl) => ("\u{2067}\u{202E}", "\u{202C}\u{2069}"),
+        (Plaintext, _) => ("\u{2068}", "\u{2069}"),
+    }
+}
+
+impl InlineFormattingContextState<'_, '_> {
+    /// Finishes the current line box, splitting the ongoing inline boxes across the break
+    fn new_line(&mut self, forced_line_break: bool) {
+        let each_line = self
+            .containing_block
+            .style
+            .inherited_text
+            .text_indent
+            .each_line;
+        let line_start = text_indent(self.containing_block, forced_line_break && each_line);
+        self.current_nesting_level.inline_start = line_start;
+        let mut nesting_level = &mut self.current_nesting_level;
+        for partial in self.partial_inline_boxes_stack.iter_mut().rev() {
+            partial.finish_layout(nesting_level, &mut self.inline_position, true);
+            partial.start_corner.inline = Length::zero();
+            partial.padding.inline_start = Length::zero();
+            partial.border.inline_start = Length::zero();
+            partial.margin.inline_start = Length::zero();
+            partial.parent_nesting_level.inline_start = line_start;
+            nesting_level = &mut partial.parent_nesting_level;
+        }
+        self.line_boxes.finish_line(
+            nesting_level,
+            self.containing_block,
+            self.inline_position,
+            forced_line_break,
+        );
+        // Fragments of the top level are positioned relative to the line box
+        nesting_level.inline_start = Length::zero();
+        if let Some(outermost) = self.partial_inline_boxes_stack.first_mut() {
+            outermost.start_corner.inline = line_start
+        }
+        self.inline_position = line_start;
+        self.line_start = line_start;
+        self.place_pending_floats();
+        self.fit_line_between_floats();
+    }
+
+    /// Places a float on the current line if it fits, or below it
+    ///
+    /// https://drafts.csswg.org/css2/visuren.html#float-position
+    fn float(&mut self, fragment: BoxFragment) {
+        let available = self.line_boxes.line_inline_size - self.inline_position;
+        if self.inline_position <= self.line_start || margin_box_size(&fragment).inline <= available
+        {
+            let line_top =
+                self.float_context_block_start + self.line_boxes.next_line_block_position;
+            self.place_float(fragment, line_top);
+            // The content already on the line moves with the line box
+            self.fit_line_between_floats()
+        } else {
+            self.pending_floats.push(fragment)
+        }
+    }
+
+    fn place_pending_floats(&mut self) {
+        let line_top = self.float_context_block_start + self.line_boxes.next_line_block_position;
+        for fragment in take(&mut self.pending_floats) {
+            self.place_float(fragment, line_top)
+        }
+    }
+
+    fn place_float(&mut self, mut fragment: BoxFragment, block_position: Length) {
+        let float_context = self.float_context.as_deref_mut().unwrap();
+        let margin_box_start = float_context.place(
+            &fragment.style,
+            self.containing_block,
+            margin_box_size(&fragment),
+            block_position,
+        );
+        let start_corner = &mut fragment.content_rect.start_corner;
+        start_corner.inline += margin_box_start.inline;
+        start_corner.block += margin_box_start.block - self.float_context_block_start;
+        self.line_boxes.boxes.push(Fragment::Box(fragment))
+    }
+
+    /// Shortens the current line box to the space between floats,
+    /// and moves it below them if there is none.
+    ///
+    /// FIXME: also move it down when its first unbreakable content does not fit
+    fn fit_line_between_floats(&mut self) {
+        let float_context = match &self.float_context {
+            Some(float_context) => float_context,
+            None => return,
+        };
+        // The line box is at least as tall as its strut
+        let strut = InlineBoxMetrics::new(self.containing_block.style).layout_bounds();
+        let lines = &mut self.line_boxes;
+        loop {
+            let top = self.float_context_block_start + lines.next_line_block_position;
+            let bottom = top + strut.above + strut.below;
+            let (start, end) =
+                float_context.available_inline_range(self.containing_block, top, bottom);
+            let line_is_empty = self.inline_position <= self.line_start;
+            if end <= start && line_is_empty {
+                if let Some(next) = float_context.next_float_end(top, bottom) {
+                    lines.moved_past_floats += next - top;
+                    lines.next_line_block_position += next - top;
+                    continue;
+                }
+            }
+            lines.line_inline_start = start;
+            lines.line_inline_size = end - start;
+            break;
+        }
+    }
+}
+
+impl ContentSizesComputation<'_> {
+    fn traverse(&mut self, boxes: &[Arc<InlineLevelBox>]) {
+        let mode = self.containing_block_style.writing_mode();
+        let zero = Length::zero();
+        for box_ in boxes {
+            match &**box_ {
+                InlineLevelBox::InlineBox(inline) => {
+                    let style = &inline.style;
+                    let padding = style.padding(mode).percentages_relative_to(zero);
+                    let border = style.border_width(mode).percentages_relative_to(zero);
+                    let margin = style
+                        .margin(mode)
+                        .percentages_relative_to(zero)
+                        .auto_is(Length::zero);
+                    let pbm = &(&padding + &border) + &margin;
+                    if inline.first_fragment {
+                        self.add_content(pbm.inline_start)
+                    }
+                    self.traverse(&inline.children);
+                    if inline.last_fragment {
+                        self.add_content(pbm.inline_end)
+                    }
+                }
+                InlineLevelBox::TextRun(run) => run.inline_content_sizes(self),
+                InlineLevelBox::Atomic { style, contents } => {
+                    if style.inherited_text.white_space.allows_wrapping() {
+                        self.soft_wrap_opportunity(zero)
+                    }
+                    let outer = sizing::outer_inline_content_sizes(style, mode, || {
+                        contents.inline_content_sizes(style)
+                    });
+                    self.add_content_sizes(outer)
+                }
+                InlineLevelBox::OutOfFlowFloatBox(float) => {
+                    let style = &float.style;
+                    let outer = sizing::outer_inline_content_sizes(style, mode, || {
+                        float.contents.inline_content_sizes(style)
+                    });
+                    // A float is beside the line, and does not break it
+                    self.line += outer.max_content;
+                    self.sizes.min_content.max_assign(outer.min_content)
+                }
+                InlineLevelBox::OutOfFlowAbsolutelyPositionedBox(_) => {}
+            }
+        }
+    }
+
+    fn add_content(&mut self, inline_size: Length) {
+        self.add_content_sizes(ContentSizes {
+            min_content: inline_size,
+            max_content: inline_size,
+        })
+    }
+
+    /// Content that differs between `min-content` and `max-content`, such as an atomic inline.
+    /// Spaces before it do not hang.
+    fn add_content_sizes(&mut self, sizes: ContentSizes) {
+        self.line += replace(&mut self.line_trailing_spaces, Length::zero()) + sizes.max_content;
+        self.segment +=
+            replace(&mut self.segment_trailing_spaces, Length::zero()) + sizes.min_content;
+    }
+
+    fn add_collapsible_space(&mut self, inline_size: Length) {
+        self.line_trailing_spaces += inline_size;
+        self.segment_trailing_spaces += inline_size;
+    }
+
+    /// `hyphen` is the inline size of the hyphen shown if the line breaks there
+    fn soft_wrap_opportunity(&mut self, hyphen: Length) {
+        self.sizes.min_content.max_assign(self.segment + hyphen);
+        self.segment = Length::zero();
+        self.segment_trailing_spaces = Length::zero();
+    }
+
+    fn forced_line_break(&mut self) {
+        self.soft_wrap_opportunity(Length::zero());
+        self.sizes.max_content.max_assign(self.line);
+        self.line = Length::zero();
+        self.line_trailing_spaces = Length::zero();
+    }
+}
+
+impl InlineNestingLevelState<'_> {
+    fn include_layout_bounds(&mut self, bounds: BaselineExtents) {
+        self.layout_bounds_so_far = Some(match self.layout_bounds_so_far {
+            Some(so_far) => so_far.union(bounds),
+            None => bounds,
+        })
+    }
+}
+
+impl InlineBoxMetrics {
+    fn new(style: &ComputedValues) -> Self {
+        let font = style.font();
+        let font_size = style.font.font_size;
+        let mut ascent = font_size * font.ascender();
+        // Negative in the font, below the baseline
+        let mut descent = -(font_size * font.descender());
+        let line_height = match style.font.line_height {
+            // “We recommend a used value for 'normal' between 1.0 to 1.2.”
+            // Use the font’s own recommendation instead, like other implementations.
+            LineHeight::Normal => ascent + descent + font_size * font.line_gap(),
+            LineHeight::Number(number) => font_size.0 * number,
+            LineHeight::Length(length) => length,
+        };
+        if style.writing_mode().0.is_vertical() {
+            // FIXME: this is `text-orientation: upright`, centered on the central baseline
+            let half = (ascent + descent) / 2.;
+            ascent = half;
+            descent = half;
+        }
+        InlineBoxMetrics {
+            ascent,
+            descent,
+            line_height,
+            x_height: font_size * font.x_height(),
+            subscript_offset: font_size * font.subscript_offset(),
+            superscript_offset: font_size * font.superscript_offset(),
+        }
+    }
+
+    /// The content area plus half-leading on each side, which adds up to `line-height`
+    ///
+    /// https://drafts.csswg.org/css2/visudet.html#leading
+    fn layout_bounds(&self) -> BaselineExtents {
+        let half_leading = (self.line_height - (self.ascent + self.descent)) / 2.;
+        BaselineExtents {
+            above: self.ascent + half_leading,
+            below: self.descent + half_leading,
+        }
+    }
+}
+
+impl BaselineExtents {
+    fn union(self, other: Self) -> Self {
+        BaselineExtents {
+            above: self.above.max(other.above),
+            below: self.below.max(other.below),
+        }
+    }
+
+    /// Relative to a baseline that is `offset` above the current one
+    fn shifted(self, offset: Length) -> Self {
+        BaselineExtents {
+            above: self.above - offset,
+            below: self.below + offset,
+        }
+    }
+}
+
+/// `bounds` are those of the box relative to its own baseline,
+/// and `line_height` the used value of its `line-height` property.
+///
+/// https://drafts.csswg.org/css2/visudet.html#propdef-vertical-align
+fn baseline_shift(
+    style: &ComputedValues,
+    bounds: BaselineExtents,
+    line_height: Length,
+    parent: &InlineBoxMetrics,
+) -> Option<Length> {
+    Some(match style.text.vertical_align {
+        VerticalAlign::Baseline => Length::zero(),
+        VerticalAlign::Sub => parent.subscript_offset,
+        VerticalAlign::Super => -parent.superscript_offset,
+        // “Align the top of the box with the top of the parent's content area”
+        VerticalAlign::TextTop => bounds.above - parent.ascent,
+        // “Align the bottom of the box with the bottom of the parent's content area”
+        VerticalAlign::TextBottom => parent.descent - bounds.below,
+        // “Align the vertical midpoint of the box with the baseline of the parent box
+        //  plus half the x-height of the parent.”
+        VerticalAlign::Middle => (bounds.above - bounds.below - parent.x_height) / 2.,
+        VerticalAlign::LengthOrPercentage(raise) => -raise.percentage_relative_to(line_height),
+        VerticalAlign::Top | VerticalAlign::Bottom => return None,
+    })
 }
 
 impl LinesBoxes {
+    /// `content_inline_size` is where the content of the line ends in logical order.
+    /// `last_line` is for the end of the block or a forced line break,
+    /// where `text-align-last` applies.
     fn finish_line(
         &mut self,
         top_nesting_level: &mut InlineNestingLevelState,
         containing_block: &ContainingBlock,
+        content_inline_size: Length,
+        last_line: bool,
     ) {
+        // Each line box starts with a zero-width inline box
+        // with the font and line height properties of the block container.
+        // https://drafts.csswg.org/css2/visudet.html#strut
+        let strut = InlineBoxMetrics::new(containing_block.style).layout_bounds();
+        let mut layout_bounds = match top_nesting_level.layout_bounds_so_far.take() {
+            Some(bounds) => bounds.union(strut),
+            None => strut,
+        };
+        // Grow the line box as needed for boxes aligned with its top or bottom edge
+        let mut line_relative_boxes = take(&mut top_nesting_level.line_relative_boxes);
+        for box_ in &line_relative_boxes {
+            let box_size = box_.layout_bounds.above + box_.layout_bounds.below;
+            match box_.vertical_align {
+                VerticalAlign::Top => layout_bounds
+                    .below
+                    .max_assign(box_size - layout_bounds.above),
+                _ => layout_bounds
+                    .above
+                    .max_assign(box_size - layout_bounds.below),
+            }
+        }
         let start_corner = Vec2 {
-            inline: Length::zero(),
+            inline: self.line_inline_start,
             block: self.next_line_block_position,
         };
+        let moved_past_floats = replace(&mut self.moved_past_floats, Length::zero());
         let size = Vec2 {
-            inline: containing_block.inline_size,
-            block: std::mem::replace(
-                &mut top_nesting_level.max_block_size_of_fragments_so_far,
-                Length::zero(),
-            ),
+            inline: self.line_inline_size,
+            block: if take(&mut self.current_line_has_content) {
+                self.last_baseline = Some(start_corner.block + layout_bounds.above);
+                layout_bounds.above + layout_bounds.below
+            } else {
+                self.next_line_block_position -= moved_past_floats;
+                Length::zero()
+            },
         };
         self.next_line_block_position += size.block;
+        let mut children = take(&mut top_nesting_level.fragments_so_far);
+        // Fragments were positioned relative to the baseline of the line
+        for child in &mut children {
+            start_corner_mut(child).block += layout_bounds.above
+        }
+        // Enclosing boxes were finished last. Align them first, their descendants move with them.
+        while let Some(box_) = line_relative_boxes.pop() {
+            let baseline_offset = match box_.vertical_align {
+                VerticalAlign::Top => box_.layout_bounds.above - layout_bounds.above,
+                _ => layout_bounds.below - box_.layout_bounds.below,
+            };
+            let delta = baseline_offset - box_.baseline_offset;
+            start_corner_mut(fragment_at(&mut children, &box_.path)).block += delta;
+            for descendant in &mut line_relative_boxes {
+                if descendant.path.ends_with(&box_.path) {
+                    descendant.baseline_offset += delta
+                }
+            }
+        }
+        let content_inline_size = content_inline_size - trim_trailing_spaces(&mut children);
+        let free_space = self.line_inline_size - content_inline_size;
+        // Content that overflows is start-aligned
+        if free_space > Length::zero() {
+            let direction = containing_block.mode.1;
+            let offset = match text_align(containing_block.style, last_line) {
+                TextAlign::Start => Length::zero(),
+                TextAlign::End => free_space,
+                TextAlign::Center => free_space / 2.,
+                TextAlign::Left if direction == Direction::Ltr => Length::zero(),
+                TextAlign::Left => free_space,
+                TextAlign::Right if direction == Direction::Ltr => free_space,
+                TextAlign::Right => Length::zero(),
+                TextAlign::Justify => {
+                    let spaces = count_spaces(&children);
+                    if spaces > 0 {
+                        justify(&mut children, free_space / spaces as f32);
+                    }
+                    Length::zero()
+                }
+            };
+            for child in &mut children {
+                start_corner_mut(child).inline += offset
+            }
+        }
+        reorder(&mut children, containing_block.mode.1);
         self.boxes.push(Fragment::Anonymous(AnonymousFragment {
-            children: take(&mut top_nesting_level.fragments_so_far),
+            children,
             rect: Rect { start_corner, size },
             mode: containing_block.mode,
         }))
     }
 }
 
+/// Where content starts on a line. `first_line` is for the first line of the block,
+/// or with `each-line` a line after a forced line break.
+///
+/// https://drafts.csswg.org/css-text-3/#text-indent-property
+fn text_indent(containing_block: &ContainingBlock, first_line: bool) -> Length {
+    let indent = containing_block.style.inherited_text.text_indent;
+    if first_line != indent.hanging {
+        indent
+            .length
+            .percentage_relative_to(containing_block.inline_size)
+    } else {
+        Length::zero()
+    }
+}
+
+/// https://drafts.csswg.org/css-text-3/#text-align-last-property
+fn text_align(style: &ComputedValues, last_line: bool) -> TextAlign {
+    let text_align = style.inherited_text.text_align;
+    if !last_line {
+        return text_align;
+    }
+    match style.inherited_text.text_align_last {
+        TextAlignLast::Auto if text_align == TextAlign::Justify => TextAlign::Start,
+        TextAlignLast::Auto => text_align,
+        TextAlignLast::Start => TextAlign::Start,
+        TextAlignLast::End => TextAlign::End,
+        TextAlignLast::Left => TextAlign::Left,
+        TextAlignLast::Right => TextAlign::Right,
+        TextAlignLast::Center => TextAlign::Center,
+        TextAlignLast::Justify => TextAlign::Justify,
+    }
+}
+
+/// Removes collapsible spaces at the end of a line (in logical order)
+/// and returns their inline size.
+///
+/// https://drafts.csswg.org/css-text-3/#white-space-phase-2
+fn trim_trailing_spaces(fragments: &mut Vec<Fragment>) -> Length {
+    // Skip empty inline boxes, such as `<br>`
+    let last = fragments.iter().rposition(|fragment| match fragment {
+        Fragment::Box(b) => b.is_atomic_inline() || !b.children.is_empty(),
+        _ => true,
+    });
+    let last = match last {
+        Some(last) => last,
+        None => return Length::zero(),
+    };
+    let mut following = last + 1;
+    let trimmed = match &mut fragments[last] {
+        Fragment::Box(b) if !b.is_atomic_inline() => {
+            let trimmed = trim_trailing_spaces(&mut b.children);
+            b.content_rect.size.inline -= trimmed;
+            trimmed
+        }
+        Fragment::Text(t) if !t.parent_style.inherited_text.white_space.preserves_spaces() => {
+            // Glyphs of right-to-left text were reversed
+            let trimmed = t.text.trim_trailing_spaces(t.bidi_level.is_rtl()).unwrap();
+            let trimmed = t.font_size * trimmed;
+            t.content_rect.size.inline -= trimmed;
+            if t.text.glyphs.is_empty() {
+                following = last
+            }
+            trimmed
+        }
+        _ => Length::zero(),
+    };
+    if following == last {
+        // Nothing is left of this text fragment
+        fragments.remove(last);
+    }
+    for fragment in &mut fragments[following..] {
+        start_corner_mut(fragment).inline -= trimmed
+    }
+    trimmed
+}
+
+/// Justification opportunities on a line
+fn count_spaces(fragments: &[Fragment]) -> usize {
+    fragments
+        .iter()
+        .map(|fragment| match fragment {
+            Fragment::Box(b) if b.is_atomic_inline() => 0,
+            Fragment::Box(b) => count_spaces(&b.children),
+            Fragment::Text(t) => t.text.count_spaces().unwrap(),
+            Fragment::Anonymous(_) => 0,
+        })
+        .sum()
+}
+
+/// Widens each space by `extra`, moving the fragments that follow along the inline axis.
+/// Returns how much `fragments` grew in total.
+///
+/// https://drafts.csswg.org/css-text-3/#justify-algos
+fn justify(fragments: &mut [Fragment], extra: Length) -> Length {
+    let mut growth = Length::zero();
+    for fragment in fragments {
+        start_corner_mut(fragment).inline += growth;
+        match fragment {
+            Fragment::Box(b) if b.is_atomic_inline() => {}
+            Fragment::Box(b) => {
+                let grown = justify(&mut b.children, extra);
+                b.content_rect.size.inline += grown;
+                growth += grown
+            }
+            Fragment::Text(t) => {
+                let spaces = t.text.count_spaces().unwrap();
+                t.text.add_word_spacing(extra / t.font_size).unwrap();
+                let grown = extra * spaces as f32;
+                t.content_rect.size.inline += grown;
+                growth += grown
+            }
+            Fragment::Anonymous(_) => {}
+        }
+    }
+    growth
+}
+
+/// Reorders the fragments of a line (recursively in inline boxes)
+/// and moves them along the inline axis accordingly.
+///
+/// https://www.unicode.org/reports/tr9/#L2
+fn reorder(fragments: &mut [Fragment], direction: Direction) {
+    for fragment in fragments.iter_mut() {
+        match fragment {
+            Fragment::Box(b) if !b.is_atomic_inline() => {
+                reorder(&mut b.children, b.style.inherited_box.direction)
+            }
+            _ => {}
+        }
+    }
+
+    let base_level = match direction {
+        Direction::Ltr => Level::ltr(),
+        Direction::Rtl => Level::rtl(),
+    };
+    let mut levels_and_indices = fragments
+        .iter()
+        .enumerate()
+        .map(|(i, fragment)| (bidi_level(fragment).unwrap_or(base_level).number(), i))
+        .collect::<Vec<_>>();
+    let highest = levels_and_indices.iter().map(|&(level, _)| level).max();
+    let lowest_odd = levels_and_indices
+        .iter()
+        .map(|&(level, _)| level)
+        .filter(|level| level % 2 == 1)
+        .min();
+    if let (Some(highest), Some(lowest_odd)) = (highest, lowest_odd) {
+        // “From the highest level found in the text to the lowest odd level on each line,
+        //  reverse any contiguous sequence of characters that are at that level or higher.”
+        for level in (lowest_odd..=highest).rev() {
+            let mut i = 0;
+            while i < levels_and_indices.len() {
+                let start = i;
+                while i < levels_and_indices.len() && levels_and_indices[i].0 >= level {
+                    i += 1
+                }
+                levels_and_indices[start..i].reverse();
+                i = i.max(start + 1);
+            }
+        }
+    }
+    // The result is in left-to-right order.
+    if direction == Direction::Rtl {
+        levels_and_indices.reverse()
+    }
+
+    // Fragments were first positioned in logical order, with relative positioning applied.
+    // Keep the latter by moving each by the difference with its logical position.
+    let mut logical_positions = Vec::with_capacity(fragments.len());
+    let mut position = Length::zero();
+    for fragment in fragments.iter() {
+        logical_positions.push(position);
+        position += outer_inline_size(fragment);
+    }
+    let mut position = Length::zero();
+    for (_, i) in levels_and_indices {
+        let fragment = &mut fragments[i];
+        let size = outer_inline_size(fragment);
+        start_corner_mut(fragment).inline += position - logical_positions[i];
+        position += size;
+    }
+}
+
+/// `path` is innermost first, see `LineRelativeBox`
+fn fragment_at<'a>(fragments: &'a mut [Fragment], path: &[usize]) -> &'a mut Fragment {
+    let (&index, rest) = path.split_last().unwrap();
+    if rest.is_empty() {
+        return &mut fragments[index];
+    }
+    match &mut fragments[index] {
+        Fragment::Box(b) => fragment_at(&mut b.children, rest),
+        _ => unreachable!("only inline boxes have fragments nested in a line"),
+    }
+}
+
+fn start_corner_mut(fragment: &mut Fragment) -> &mut Vec2<Length> {
+    match fragment {
+        Fragment::Box(b) => &mut b.content_rect.start_corner,
+        Fragment::Anonymous(a) => &mut a.rect.start_corner,
+        Fragment::Text(t) => &mut t.content_rect.start_corner,
+    }
+}
+
+/// The lowest level of text in this fragment, if any
+fn bidi_level(fragment: &Fragment) -> Option<Level> {
+    match fragment {
+        // FIXME: this should be the level of U+FFFC in its place
+        Fragment::Box(b) if b.is_atomic_inline() => None,
+        Fragment::Box(b) => b.children.iter().filter_map(bidi_level).min(),
+        Fragment::Anonymous(_) => None,
+        Fragment::Text(t) => Some(t.bidi_level),
+    }
+}
+
+fn outer_inline_size(fragment: &Fragment) -> Length {
+    match fragment {
+        Fragment::Box(b) => {
+            b.content_rect.size.inline
+                + b.padding.inline_sum()
+                + b.border.inline_sum()
+                + b.margin.inline_sum()
+        }
+        Fragment::Anonymous(a) => a.rect.size.inline,
+        Fragment::Text(t) => t.content_rect.size.inline,
+    }
+}
+
 impl InlineBox {
     fn start_layout<'box_tree>(
         &'box_tree self,
@@ -179,30 +1005,61 @@ impl InlineBox {
     ) -> PartialInlineBoxFragment<'box_tree> {
         let style = self.style.clone();
         let cbis = ifc.containing_block.inline_size;
-        let mut padding = style.padding().percentages_relative_to(cbis);
-        let mut border = style.border_width().percentages_relative_to(cbis);
+        // The fragment is positioned among its siblings, in the mode of the parent box.
+        let parent_mode = ifc
+            .partial_inline_boxes_stack
+            .last()
+            .map_or(ifc.containing_block.mode, |parent| {
+                parent.style.writing_mode()
+            });
+        let mut padding = style.padding(parent_mode).percentages_relative_to(cbis);
+        let mut border = style
+            .border_width(parent_mode)
+            .percentages_relative_to(cbis);
         let mut margin = style
-            .margin()
+            .margin(parent_mode)
             .percentages_relative_to(cbis)
             .auto_is(Length::zero);
         if self.first_fragment {
-            ifc.inline_position += padding.inline_start + border.inline_start + margin.inline_start;
+            let inline_start = padding.inline_start + border.inline_start + margin.inline_start;
+            // FIXME: the inline-end side counts for the line it ends up on, not this one
+            let inline_end = padding.inline_end + border.inline_end + margin.inline_end;
+            if inline_start != Length::zero() || inline_end != Length::zero() {
+                ifc.line_boxes.current_line_has_content = true
+            }
+            ifc.inline_position += inline_start;
         } else {
             padding.inline_start = Length::zero();
             border.inline_start = Length::zero();
             margin.inline_start = Length::zero();
         }
+        // Vertical padding, borders, and margins do not affect the line box,
+        // only the content area is aligned to the baseline.
+        let metrics = InlineBoxMetrics::new(&style);
+        let parent_metrics = match ifc.partial_inline_boxes_stack.last() {
+            Some(parent) => parent.metrics,
+            None => InlineBoxMetrics::new(ifc.containing_block.style),
+        };
+        let baseline_shift = baseline_shift(
+            &style,
+            metrics.layout_bounds(),
+            metrics.line_height,
+            &parent_metrics,
+        );
         let mut start_corner = Vec2 {
-            block: padding.block_start + border.block_start + margin.block_start,
+            block: baseline_shift.unwrap_or(Length::zero()) - metrics.ascent,
             inline: ifc.inline_position - ifc.current_nesting_level.inline_start,
         };
         start_corner += &relative_adjustement(
             &style,
             ifc.containing_block.inline_size,
             ifc.containing_block.block_size,
+            parent_mode,
         );
         PartialInlineBoxFragment {
             style,
+            metrics,
+            baseline_shift,
             start_corner,
             padding,
             border,
@@ -214,7 +1071,8 @@ impl InlineBox {
                     remaining_boxes: self.children.iter(),
                     fragments_so_far: Vec::with_capacity(self.children.len()),
                     inline_start: ifc.inline_position,
-                    max_block_size_of_fragments_so_far: Length::zero(),
+                    layout_bounds_so_far: None,
+                    line_relative_boxes: Vec::new(),
                 },
             ),
         }
@@ -228,13 +1086,22 @@ impl<'box_tree> PartialInlineBoxFragment<'box_tree> {
         inline_position: &mut Length,
         at_line_break: bool,
     ) {
+        let mut children = take(&mut nesting_level.fragments_so_far);
+        // Children were positioned relative to the baseline, `ascent` below the content area top
+        for child in &mut children {
+            start_corner_mut(child).block += self.metrics.ascent
+        }
+        let mut layout_bounds = self.metrics.layout_bounds();
+        if let Some(children_bounds) = nesting_level.layout_bounds_so_far.take() {
+            layout_bounds = layout_bounds.union(children_bounds)
+        }
         let mut fragment = BoxFragment {
             style: self.style.clone(),
-            children: take(&mut nesting_level.fragments_so_far),
+            children,
             content_rect: Rect {
                 size: Vec2 {
                     inline: *inline_position - self.start_corner.inline,
-                    block: nesting_level.max_block_size_of_fragments_so_far,
+                    block: self.metrics.ascent + self.metrics.descent,
                 },
                 start_corner: self.start_corner.clone(),
             },
@@ -242,6 +1109,8 @@ impl<'box_tree> PartialInlineBoxFragment<'box_tree> {
             border: self.border.clone(),
             margin: self.margin.clone(),
             block_margins_collapsed_with_children: CollapsedBlockMargins::zero(),
+            // Its content is part of the line box it is on
+            last_baseline: None,
         };
         let last_fragment = self.last_box_tree_fragment && !at_line_break;
         if last_fragment {
@@ -253,14 +1122,28 @@ impl<'box_tree> PartialInlineBoxFragment<'box_tree> {
             fragment.border.inline_end = Length::zero();
             fragment.margin.inline_end = Length::zero();
         }
-        self.parent_nesting_level
-            .max_block_size_of_fragments_so_far
-            .max_assign(
-                fragment.content_rect.size.block
-                    + fragment.padding.block_sum()
-                    + fragment.border.block_sum()
-                    + fragment.margin.block_sum(),
-            );
+        let index = self.parent_nesting_level.fragments_so_far.len();
+        let shift = self.baseline_shift.unwrap_or(Length::zero());
+        for mut descendant in nesting_level.line_relative_boxes.drain(..) {
+            descendant.path.push(index);
+            descendant.baseline_offset += shift;
+            self.parent_nesting_level
+                .line_relative_boxes
+                .push(descendant)
+        }
+        if self.baseline_shift.is_some() {
+            self.parent_nesting_level
+                .include_layout_bounds(layout_bounds.shifted(shift));
+        } else {
+            self.parent_nesting_level
+                .line_relative_boxes
+                .push(LineRelativeBox {
+                    path: vec![index],
+                    baseline_offset: Length::zero(),
+                    layout_bounds,
+                    vertical_align: self.style.text.vertical_align,
+                })
+        }
         self.parent_nesting_level
             .fragments_so_far
             .push(Fragment::Box(fragment));
@@ -269,77 +1152,484 @@ impl<'box_tree> PartialInlineBoxFragment<'box_tree> {
 
 impl TextRun {
     fn layout(&self, ifc: &mut InlineFormattingContextState) {
-        let available = ifc.containing_block.inline_size - ifc.inline_position;
-        let mut chars = self.text.chars();
+        let levels_start = ifc.bidi.text_run_starts.next().unwrap();
+        let levels = &ifc.bidi.levels[levels_start..][..self.text.len()];
+        let levels = levels.to_vec();
+        let (break_opportunities, hyphenation_opportunities) =
+            ifc.break_opportunities.next_text_run(self.text.len());
+        let emergency_breaks = allows_emergency_breaks(&self.parent_style);
+        let white_space = self.parent_style.inherited_text.white_space;
+        let metrics = InlineBoxMetrics::new(&self.parent_style);
+        let letter_spacing = self.parent_style.inherited_text.letter_spacing.length();
+        let word_spacing = self.parent_style.inherited_text.word_spacing.length();
+        let caps = self.parent_style.font.font_variant_caps;
+        let language = Language::from_tag(self.parent_style.lang.as_deref());
+        let mut uppercase = String::new();
+        let mut chars = self.text.char_indices();
         loop {
-            let mut shaped = ShapedSegment::new_with_naive_shaping(BITSTREAM_VERA_SANS.clone());
+            let available = ifc.line_boxes.line_inline_size - ifc.inline_position;
+            let level = chars
+                .clone()
+                .next()
+                .map_or(Level::ltr(), |(offset, _)| levels[offset]);
+            let font = self.parent_style.font();
+            let mut font_size = self.parent_style.font.font_size;
+            // Without small capitals in the font, they are synthesized with scaled-down
+            // uppercase letters, in separate segments.
+            // https://drafts.csswg.org/css-fonts-4/#small-caps-synthesis
+            let synthesize_small_caps = caps != FontVariantCaps::Normal && !font.has_small_caps();
+            let synthesized = synthesize_small_caps
+                && chars
+                    .clone()
+                    .next()
+                    .map_or(false, |(_, ch)| is_small_cap(ch, caps));
+            if synthesized {
+                font_size =
+                    FontSize(font_size.0 * (font.x_height().get() / font.cap_height().get()))
+            }
+            // FIXME: this is `text-orientation: upright`, the initial value is `mixed`
+            let mut shaped = if ifc.containing_block.mode.0.is_vertical() {
+                ShapedSegment::new_with_naive_vertical_shaping(font)
+            } else {
+                ShapedSegment::new_with_naive_shaping(font)
+            };
+            if !synthesize_small_caps {
+                shaped.small_caps = caps != FontVariantCaps::Normal;
+                shaped.capitals_to_small_caps = caps == FontVariantCaps::AllSmallCaps;
+            }
+            shaped.letter_spacing = letter_spacing / font_size;
+            shaped.word_spacing = word_spacing / font_size;
+            let hyphen = shaped.hyphen_char().unwrap();
+            let hyphen_advance = shaped.char_advance(hyphen).unwrap() + shaped.letter_spacing;
             let mut last_break_opportunity = None;
+            let mut last_emergency_break = None;
+            // Trailing white space hangs: it is not considered when measuring for line breaking
+            let mut before_trailing_spaces: Option<ShapedSegmentState> = None;
+            let mut line_is_full = false;
+            let mut forced_line_break = false;
+            let mut tab = false;
             loop {
-                let next = chars.next();
-                if matches!(next, Some(' ') | None) {
-                    let inline_size = self.parent_style.font.font_size * shaped.advance_width;
+                let next_offset = chars.clone().next().map(|(offset, _)| offset);
+                // A segment ends where the bidi level changes,
+                // or where synthesized small capitals start or end
+                let next = chars.clone().next().filter(|&(offset, ch)| {
+                    levels[offset] == level
+                        && (!synthesize_small_caps || is_small_cap(ch, caps) == synthesized)
+                });
+                let at_break_opportunity = next_offset.map_or(false, |o| break_opportunities[o]);
+                // Breaking there shows a hyphen
+                let at_hyphenation_opportunity =
+                    next_offset.map_or(false, |o| hyphenation_opportunities[o]);
+                let measure = match next {
+                    None | Some((_, '\n')) | Some((_, '\t')) => true,
+                    Some(_) => at_break_opportunity || emergency_breaks,
+                };
+                // Never break before anything was placed on the line
+                let line_has_content =
+                    !shaped.glyphs.is_empty() || ifc.inline_position > ifc.line_start;
+                if measure && line_has_content {
+                    let mut advance_width = before_trailing_spaces
+                        .as_ref()
+                        .map_or(shaped.advance_width, |state| state.advance_width);
+                    if at_hyphenation_opportunity {
+                        advance_width += hyphen_advance
+                    }
+                    let inline_size = font_size * advance_width;
                     if inline_size > available {
-                        if let Some((state, iter)) = last_break_opportunity.take() {
+                        let break_ = last_break_opportunity
+                            .take()
+                            .or_else(|| last_emergency_break.take());
+                        if let Some((state, iter, hyphenate)) = break_ {
                             shaped.restore(&state);
+                            if hyphenate {
+                                shaped.append_char(hyphen).unwrap()
+                            }
                             chars = iter;
+                            line_is_full = true;
+                            break;
+                        } else if at_break_opportunity {
+                            // This content overflows, but at least the line can end here
+                            if at_hyphenation_opportunity {
+                                shaped.append_char(hyphen).unwrap()
+                            }
+                            line_is_full = true;
+                            break;
                         }
-                        break;
                     }
                 }
-                if let Some(ch) = next {
-                    if ch == ' ' {
-                        last_break_opportunity = Some((shaped.save(), chars.clone()))
+                match next {
+                    // Only present when `white-space` preserves them
+                    Some((_, '\n')) => {
+                        chars.next();
+                        forced_line_break = true;
+                        break;
                     }
-                    shaped.append_char(ch).unwrap()
-                } else {
-                    break;
+                    Some((_, '\t')) => {
+                        chars.next();
+                        tab = true;
+                        break;
+                    }
+                    Some((_, ch)) => {
+                        if at_break_opportunity && line_has_content {
+                            let state = before_trailing_spaces
+                                .clone()
+                                .unwrap_or_else(|| shaped.save());
+                            last_break_opportunity =
+                                Some((state, chars.clone(), at_hyphenation_opportunity))
+                        }
+                        if emergency_breaks && !shaped.glyphs.is_empty() && !is_combining(ch) {
+                            last_emergency_break = Some((shaped.save(), chars.clone(), false))
+                        }
+                        chars.next();
+                        // Only visible as a hyphen where a line breaks after it
+                        if ch == SOFT_HYPHEN {
+                            continue;
+                        }
+                        if ch == ' ' {
+                            if before_trailing_spaces.is_none() {
+                                before_trailing_spaces = Some(shaped.save())
+                            }
+                        } else {
+                            before_trailing_spaces = None
+                        }
+                        if synthesized {
+                            uppercase.clear();
+                            push_uppercase(ch, language, &mut uppercase);
+                            shaped.append(uppercase.chars()).unwrap()
+                        } else {
+                            let ch = if level.is_rtl() { mirrored(ch) } else { ch };
+                            shaped.append_char(ch).unwrap()
+                        }
+                    }
+                    None => break,
                 }
             }
-            let inline_size = self.parent_style.font.font_size * shaped.advance_width;
-            // https://www.w3.org/TR/CSS2/visudet.html#propdef-line-height
-            // 'normal':
-            // “set the used value to a "reasonable" value based on the font of the element.”
-            let line_height = self.parent_style.font.font_size.0 * 1.2;
-            let content_rect = Rect {
-                start_corner: Vec2 {
-                    block: Length::zero(),
-                    inline: ifc.inline_position - ifc.current_nesting_level.inline_start,
-                },
-                size: Vec2 {
-                    block: line_height,
-                    inline: inline_size,
-                },
+            if level.is_rtl() {
+                shaped.glyphs.reverse()
+            }
+            let tab_stop_interval = if tab {
+                Some(match self.parent_style.inherited_text.tab_size {
+                    TabSize::Spaces(spaces) => {
+                        let space = shaped.char_advance(' ').unwrap()
+                            + shaped.letter_spacing
+                            + shaped.word_spacing;
+                        font_size * space * spaces
+                    }
+                    TabSize::Length(length) => length,
+                })
+            } else {
+                None
             };
-            ifc.inline_position += inline_size;
-            ifc.current_nesting_level
-                .max_block_size_of_fragments_so_far
-                .max_assign(line_height);
-            ifc.current_nesting_level
-                .fragments_so_far
-                .push(Fragment::Text(TextFragment {
-                    parent_style: self.parent_style.clone(),
-                    content_rect,
-                    text: shaped,
-                }));
+            let half_ch = font_size * shaped.char_advance('0').unwrap() / 2.;
+            // The run may start with a break opportunity that was taken immediately
+            let has_glyphs = !shaped.glyphs.is_empty();
+            if has_glyphs || forced_line_break || tab {
+                ifc.line_boxes.current_line_has_content = true;
+                ifc.current_nesting_level
+                    .include_layout_bounds(metrics.layout_bounds());
+            }
+            if has_glyphs {
+                let inline_size = font_size * shaped.advance_width;
+                // The content area, positioned relative to the baseline
+                let content_rect = Rect {
+                    start_corner: Vec2 {
+                        block: -metrics.ascent,
+                        inline: ifc.inline_position - ifc.current_nesting_level.inline_start,
+                    },
+                    size: Vec2 {
+                        block: metrics.ascent + metrics.descent,
+                        inline: inline_size,
+                    },
+                };
+                ifc.inline_position += inline_size;
+                ifc.current_nesting_level
+                    .fragments_so_far
+                    .push(Fragment::Text(TextFragment {
+                        parent_style: self.parent_style.clone(),
+                        content_rect,
+                        text: shaped,
+                        font_size,
+                        bidi_level: level,
+                    }));
+            }
+            if let Some(interval) = tab_stop_interval {
+                ifc.inline_position = next_tab_stop(ifc.inline_position, interval, half_ch)
+            }
+            if forced_line_break || (line_is_full && !chars.as_str().is_empty()) {
+                ifc.new_line(forced_line_break);
+                // Collapsible spaces at the start of a line are removed
+                if !white_space.preserves_spaces() && chars.as_str().starts_with(' ') {
+                    chars.next();
+                }
+            }
             if chars.as_str().is_empty() {
                 break;
+            }
+        }
+    }
+}
+
+impl TextRun {
+    /// Soft wrap opportunities only count for `min-content` where the text is allowed to wrap,
+    /// which they already reflect. `overflow-wrap: break-word` does not affect content sizes.
+    ///
+    /// https://drafts.csswg.org/css-text-3/#overflow-wrap-property
+    fn inline_content_sizes(&self, computation: &mut ContentSizesComputation) {
+        let (break_opportunities, hyphenation_opportunities) = computation
+            .break_opportunities
+            .next_text_run(self.text.len());
+        let text = &self.parent_style.inherited_text;
+        let anywhere = text.white_space.allows_wrapping()
+            && (text.overflow_wrap == OverflowWrap::Anywhere
+                || text.word_break == WordBreak::BreakWord);
+        let caps = self.parent_style.font.font_variant_caps;
+        let language = Language::from_tag(self.parent_style.lang.as_deref());
+        let vertical = computation
+            .containing_block_style
+            .writing_mode()
+            .0
+            .is_vertical();
+        let new_segment = |font_size: FontSize| {
+            let font = self.parent_style.font();
+            let mut shaped = if vertical {
+                ShapedSegment::new_with_naive_vertical_shaping(font)
             } else {
-                // New line
-                ifc.current_nesting_level.inline_start = Length::zero();
-                let mut nesting_level = &mut ifc.current_nesting_level;
-                for partial in ifc.partial_inline_boxes_stack.iter_mut().rev() {
-                    partial.finish_layout(nesting_level, &mut ifc.inline_position, true);
-                    partial.start_corner.inline = Length::zero();
-                    partial.padding.inline_start = Length::zero();
-                    partial.border.inline_start = Length::zero();
-                    partial.margin.inline_start = Length::zero();
-                    partial.parent_nesting_level.inline_start = Length::zero();
-                    nesting_level = &mut partial.parent_nesting_level;
+                ShapedSegment::new_with_naive_shaping(font)
+            };
+            shaped.letter_spacing = text.letter_spacing.length() / font_size;
+            shaped.word_spacing = text.word_spacing.length() / font_size;
+            (shaped, font_size)
+        };
+        let (mut normal, font_size) = new_segment(self.parent_style.font.font_size);
+        let synthesize_small_caps =
+            caps != FontVariantCaps::Normal && !normal.font.has_small_caps();
+        if !synthesize_small_caps {
+            normal.small_caps = caps != FontVariantCaps::Normal;
+            normal.capitals_to_small_caps = caps == FontVariantCaps::AllSmallCaps;
+        }
+        let mut small_caps = if synthesize_small_caps {
+            let font = &normal.font;
+            Some(new_segment(FontSize(
+                font_size.0 * (font.x_height().get() / font.cap_height().get()),
+            )))
+        } else {
+            None
+        };
+        let hyphen = normal.hyphen_char().unwrap();
+        let hyphen_advance =
+            font_size * (normal.char_advance(hyphen).unwrap() + normal.letter_spacing);
+        let space = normal.char_advance(' ').unwrap() + normal.letter_spacing + normal.word_spacing;
+        let half_ch = font_size * normal.char_advance('0').unwrap() / 2.;
+        let mut uppercase = String::new();
+        for (offset, ch) in self.text.char_indices() {
+            if break_opportunities[offset] || (anywhere && offset > 0 && !is_combining(ch)) {
+                computation.soft_wrap_opportunity(if hyphenation_opportunities[offset] {
+                    hyphen_advance
+                } else {
+                    Length::zero()
+                })
+            }
+            match ch {
+                // Only present when `white-space` preserves them
+                '\n' => computation.forced_line_break(),
+                '\t' => {
+                    let interval = match text.tab_size {
+                        TabSize::Spaces(spaces) => font_size * space * spaces,
+                        TabSize::Length(length) => length,
+                    };
+                    let line = computation.line + computation.line_trailing_spaces;
+                    computation.add_content(next_tab_stop(line, interval, half_ch) - line)
+                }
+                SOFT_HYPHEN => {}
+                _ => {
+                    let synthesized = synthesize_small_caps && is_small_cap(ch, caps);
+                    let (shaped, font_size) = match &mut small_caps {
+                        Some((shaped, size)) if synthesized => (shaped, *size),
+                        _ => (&mut normal, font_size),
+                    };
+                    let before = shaped.advance_width;
+                    if synthesized {
+                        uppercase.clear();
+                        push_uppercase(ch, language, &mut uppercase);
+                        shaped.append(uppercase.chars()).unwrap()
+                    } else {
+                        shaped.append_char(ch).unwrap()
+                    }
+                    let inline_size = font_size * (shaped.advance_width - before);
+                    if ch == ' ' && !text.white_space.preserves_spaces() {
+                        computation.add_collapsible_space(inline_size)
+                    } else {
+                        computation.add_content(inline_size)
+                    }
                 }
-                ifc.line_boxes
-                    .finish_line(nesting_level, ifc.containing_block);
-                ifc.inline_position = Length::zero();
             }
         }
     }
 }
+
+/// An atomic inline-level box, such as an inline-block,
+/// laid out as an independent formatting context and placed on the line as a whole.
+///
+/// https://drafts.csswg.org/css2/visudet.html#inlineblock-width
+/// https://drafts.csswg.org/css2/visudet.html#block-root-margin
+fn layout_atomic<'a>(
+    ifc: &mut InlineFormattingContextState<'a, '_>,
+    style: &Arc<ComputedValues>,
+    contents: &'a IndependentFormattingContext,
+    tree_rank: usize,
+    absolutely_positioned_fragments: &mut Vec<AbsolutelyPositionedFragment<'a>>,
+) {
+    let cbis = ifc.containing_block.inline_size;
+    // The fragment is positioned among its siblings, in the mode of the parent box.
+    let parent_mode = ifc
+        .partial_inline_boxes_stack
+        .last()
+        .map_or(ifc.containing_block.mode, |parent| {
+            parent.style.writing_mode()
+        });
+    let padding = style.padding(parent_mode).percentages_relative_to(cbis);
+    let border = style
+        .border_width(parent_mode)
+        .percentages_relative_to(cbis);
+    // “If 'margin-left', or 'margin-right' are computed as 'auto', their used value is '0'.”
+    let margin = style
+        .margin(parent_mode)
+        .percentages_relative_to(cbis)
+        .auto_is(Length::zero);
+    let pb = &padding + &border;
+    let box_size = style.box_size(parent_mode);
+    // FIXME: orthogonal flows, see `layout_in_flow_non_replaced_block_level`
+    let mode = style.writing_mode();
+    let available = cbis - pb.inline_sum() - margin.inline_sum();
+    let content_sizes = || contents.inline_content_sizes(style);
+    let inline_size = sizing::inline_size(box_size.inline, cbis, available, content_sizes)
+        .auto_is(|| content_sizes().shrink_to_fit(available));
+    let block_size = sizing::block_size(box_size.block, ifc.containing_block.block_size);
+    let containing_block_for_children = ContainingBlock {
+        inline_size,
+        block_size,
+        mode,
+        style,
+    };
+    let abspos_so_far = absolutely_positioned_fragments.len();
+    let mut nested_abspos = vec![];
+    let mut flow_children = contents.layout(
+        &containing_block_for_children,
+        tree_rank,
+        if style.box_.position.is_relatively_positioned() {
+            &mut nested_abspos
+        } else {
+            absolutely_positioned_fragments
+        },
+    );
+    let content_size = Vec2 {
+        inline: inline_size,
+        block: block_size.auto_is(|| flow_children.block_size),
+    };
+
+    // Break before this box if it does not fit, unless nothing was placed on the line yet
+    let outer_inline_size = content_size.inline + pb.inline_sum() + margin.inline_sum();
+    let available = ifc.line_boxes.line_inline_size;
+    if ifc.inline_position + outer_inline_size > available && ifc.inline_position > ifc.line_start {
+        ifc.new_line(false)
+    }
+
+    let content_start = Vec2 {
+        inline: ifc.inline_position + margin.inline_start + pb.inline_start,
+        block: margin.block_start + pb.block_start,
+    };
+    // FIXME: static positions should follow this box when the line box is aligned
+    for fragment in &mut absolutely_positioned_fragments[abspos_so_far..] {
+        if let AbsoluteBoxOffsets::StaticStart { start } = &mut fragment.inline_start {
+            *start += ifc.line_boxes.line_inline_start + content_start.inline
+        }
+        if let AbsoluteBoxOffsets::StaticStart { start } = &mut fragment.block_start {
+            *start += ifc.line_boxes.next_line_block_position + content_start.block
+        }
+    }
+    if style.box_.position.is_relatively_positioned() {
+        AbsolutelyPositionedFragment::in_positioned_containing_block(
+            &nested_abspos,
+            &mut flow_children.fragments,
+            &content_size
+                .size_to_physical(parent_mode)
+                .size_to_flow_relative(mode),
+            &style.padding(mode).percentages_relative_to(cbis),
+            mode,
+        )
+    }
+
+    // “The baseline of an 'inline-block' is the baseline of its last line box in the normal
+    //  flow, unless it has either no in-flow line boxes […], in which case the baseline
+    //  is the bottom margin edge.”
+    let outer_block_size = content_size.block + pb.block_sum() + margin.block_sum();
+    let above = match flow_children.last_baseline {
+        Some(baseline) => content_start.block + baseline,
+        None => outer_block_size,
+    };
+    let layout_bounds = BaselineExtents {
+        above,
+        below: outer_block_size - above,
+    };
+    let metrics = InlineBoxMetrics::new(style);
+    let parent_metrics = match ifc.partial_inline_boxes_stack.last() {
+        Some(parent) => parent.metrics,
+        None => InlineBoxMetrics::new(ifc.containing_block.style),
+    };
+    let baseline_shift = baseline_shift(style, layout_bounds, metrics.line_height, &parent_metrics);
+
+    // Positioned relative to the baseline, like other fragments on the line
+    let mut start_corner = Vec2 {
+        block: baseline_shift.unwrap_or(Length::zero()) - above + content_start.block,
+        inline: content_start.inline - ifc.current_nesting_level.inline_start,
+    };
+    start_corner +=
+        &relative_adjustement(style, cbis, ifc.containing_block.block_size, parent_mode);
+    let fragment = BoxFragment {
+        style: style.clone(),
+        children: flow_children.fragments,
+        content_rect: Rect {
+            start_corner,
+            size: content_size,
+        },
+        padding,
+        border,
+        margin,
+        block_margins_collapsed_with_children: CollapsedBlockMargins::zero(),
+        last_baseline: flow_children.last_baseline,
+    };
+    ifc.inline_position += outer_inline_size;
+    ifc.line_boxes.current_line_has_content = true;
+    let nesting_level = &mut ifc.current_nesting_level;
+    match baseline_shift {
+        Some(shift) => nesting_level.include_layout_bounds(layout_bounds.shifted(shift)),
+        None => nesting_level.line_relative_boxes.push(LineRelativeBox {
+            path: vec![nesting_level.fragments_so_far.len()],
+            baseline_offset: Length::zero(),
+            layout_bounds,
+            vertical_align: style.text.vertical_align,
+        }),
+    }
+    nesting_level.fragments_so_far.push(Fragment::Box(fragment));
+}
+
+/// Whether a character is rendered as a synthesized small capital:
+/// lowercase letters, and uppercase ones for `all-small-caps`
+fn is_small_cap(ch: char, caps: FontVariantCaps) -> bool {
+    ch.is_lowercase() || (caps == FontVariantCaps::AllSmallCaps && ch.is_uppercase())
+}
+
+/// Tab stops are at multiples of `tab-size` from the start of the line
+///
+/// https://drafts.csswg.org/css-text-3/#tab-size-property
+fn next_tab_stop(position: Length, interval: Length, half_ch: Length) -> Length {
+    if interval <= Length::zero() {
+        return position;
+    }
+    let mut stop = interval * ((position.px / interval.px).floor() + 1.);
+    // “If this distance is less than 0.5ch, then the subsequent tab stop is used instead.”
+    if stop - position < half_ch {
+        stop += interval
+    }
+    stop
+}
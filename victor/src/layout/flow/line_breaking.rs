@@ -0,0 +1,234 @@
+//! https://drafts.csswg.org/css-text-3/#line-breaking
+
+use super::*;
+use crate::hyphenation;
+use xi_unicode::LineBreakIterator;
+
+pub(super) const SOFT_HYPHEN: char = '\u{AD}';
+
+/// Soft wrap opportunities in the text content of an inline formatting context,
+/// found with the Unicode Line Breaking Algorithm and tailored by `line-break` and `word-break`.
+///
+/// Text is considered across inline box boundaries,
+/// so that for example there is no opportunity in `a<b>b</b>` but there is one in `a <b>b</b>`.
+///
+/// https://www.unicode.org/reports/tr14/
+pub(super) struct BreakOpportunities {
+    /// One per byte of text content: whether a line may break just before that byte
+    opportunities: Vec<bool>,
+    /// One per byte of text content: whether a break there shows a hyphen
+    hyphens: Vec<bool>,
+    /// Where each text run starts in `opportunities`, in tree order
+    text_run_starts: std::vec::IntoIter<usize>,
+}
+
+impl BreakOpportunities {
+    /// For the next text run in tree order: break opportunities and hyphenation opportunities
+    pub(super) fn next_text_run(&mut self, len: usize) -> (Vec<bool>, Vec<bool>) {
+        let start = self.text_run_starts.next().unwrap();
+        (
+            self.opportunities[start..][..len].to_vec(),
+            self.hyphens[start..][..len].to_vec(),
+        )
+    }
+}
+
+impl InlineFormattingContext {
+    pub(super) fn break_opportunities(&self) -> BreakOpportunities {
+        fn collect<'a>(
+            boxes: &'a [Arc<InlineLevelBox>],
+            text: &mut String,
+            text_runs: &mut Vec<(usize, &'a TextRun)>,
+        ) {
+            for box_ in boxes {
+                match &**box_ {
+                    InlineLevelBox::InlineBox(inline) => collect(&inline.children, text, text_runs),
+                    InlineLevelBox::TextRun(run) => {
+                        text_runs.push((text.len(), run));
+                        text.push_str(&run.text);
+                    }
+                    // Atomic inlines are class CB, which UAX #14 leaves to tailoring.
+                    // CSS allows breaking before and after them.
+                    InlineLevelBox::Atomic { .. } => text.push('\u{FFFC}'),
+                    InlineLevelBox::OutOfFlowAbsolutelyPositionedBox(_)
+                    | InlineLevelBox::OutOfFlowFloatBox(_) => {}
+                }
+            }
+        }
+
+        let mut text = String::new();
+        let mut text_runs = Vec::new();
+        collect(&self.inline_level_boxes, &mut text, &mut text_runs);
+        let mut opportunities = vec![false; text.len()];
+        for (position, _is_hard_break) in LineBreakIterator::new(&text) {
+            // The end of the text is always included
+            if position < text.len() {
+                opportunities[position] = true
+            }
+        }
+        let mut hyphens = vec![false; text.len()];
+        for &(start, run) in &text_runs {
+            let opportunities = &mut opportunities[start..][..run.text.len()];
+            tailor(&run.text, &run.parent_style, opportunities);
+            hyphenate(
+                &run.text,
+                &run.parent_style,
+                opportunities,
+                &mut hyphens[start..][..run.text.len()],
+            )
+        }
+        BreakOpportunities {
+            opportunities,
+            hyphens,
+            text_run_starts: text_runs
+                .iter()
+                .map(|&(start, _)| start)
+                .collect::<Vec<_>>()
+                .into_iter(),
+        }
+    }
+}
+
+fn tailor(text: &str, style: &ComputedValues, opportunities: &mut [bool]) {
+    if !style.inherited_text.white_space.allows_wrapping() {
+        opportunities
+            .iter_mut()
+            .for_each(|opportunity| *opportunity = false);
+        return;
+    }
+    let line_break = style.inherited_text.line_break;
+    let word_break = style.inherited_text.word_break;
+    let mut previous = None;
+    for (offset, ch) in text.char_indices() {
+        let before = match previous.replace(ch) {
+            Some(before) => before,
+            // FIXME: tailor at text run boundaries too
+            None => continue,
+        };
+        let opportunity = &mut opportunities[offset];
+
+        // https://drafts.csswg.org/css-text-3/#word-break-property
+        let within_word = before.is_alphanumeric() && ch.is_alphanumeric();
+        match word_break {
+            WordBreak::Normal | WordBreak::BreakWord => {}
+            WordBreak::BreakAll => *opportunity |= within_word && !is_combining(ch),
+            WordBreak::KeepAll => *opportunity &= !within_word,
+        }
+
+        // https://drafts.csswg.org/css-text-3/#line-break-property
+        let after_cjk = is_ideographic_or_kana(before);
+        match line_break {
+            // UAX #14 resolves class CJ to NS, which matches `strict`.
+            LineBreak::Strict => {}
+            LineBreak::Auto | LineBreak::Normal => {
+                *opportunity |= after_cjk && is_conditional_japanese_starter(ch)
+            }
+            LineBreak::Loose => {
+                *opportunity |= after_cjk
+                    && (is_conditional_japanese_starter(ch)
+                        || is_iteration_mark(ch)
+                        || is_inseparable_or_cjk_hyphen(ch))
+            }
+            LineBreak::Anywhere => *opportunity = !is_combining(ch),
+        }
+    }
+}
+
+/// Adds hyphenation opportunities after soft hyphens, and within words for `hyphens: auto`
+///
+/// https://drafts.csswg.org/css-text-3/#hyphenation
+fn hyphenate(text: &str, style: &ComputedValues, opportunities: &mut [bool], hyphens: &mut [bool]) {
+    if !style.inherited_text.white_space.allows_wrapping() {
+        return;
+    }
+    let mode = style.inherited_text.hyphens;
+    for (offset, ch) in text.char_indices() {
+        let after = offset + ch.len_utf8();
+        // FIXME: tailor at text run boundaries too
+        if ch == SOFT_HYPHEN && after < text.len() {
+            opportunities[after] = mode != Hyphens::None;
+            hyphens[after] = mode != Hyphens::None;
+        }
+    }
+    if mode != Hyphens::Auto {
+        return;
+    }
+    let patterns = match style.lang.as_deref().and_then(hyphenation::patterns_for) {
+        Some(patterns) => patterns,
+        None => return,
+    };
+    let mut word_start = None;
+    for (offset, ch) in text.char_indices().chain(Some((text.len(), ' '))) {
+        if ch.is_alphabetic() || ch == SOFT_HYPHEN || (word_start.is_some() && is_combining(ch)) {
+            word_start.get_or_insert(offset);
+            continue;
+        }
+        if let Some(start) = word_start.take() {
+            let word = &text[start..offset];
+            // Only soft hyphens are used in words that have them
+            if !word.contains(SOFT_HYPHEN) {
+                for position in patterns.hyphenate(word) {
+                    opportunities[start + position] = true;
+                    hyphens[start + position] = true;
+                }
+            }
+        }
+    }
+}
+
+/// Whether a line that would otherwise overflow may be broken at an arbitrary point
+///
+/// https://drafts.csswg.org/css-text-3/#overflow-wrap-property
+pub(super) fn allows_emergency_breaks(style: &ComputedValues) -> bool {
+    if !style.inherited_text.white_space.allows_wrapping() {
+        return false;
+    }
+    match style.inherited_text.overflow_wrap {
+        OverflowWrap::BreakWord | OverflowWrap::Anywhere => true,
+        OverflowWrap::Normal => style.inherited_text.word_break == WordBreak::BreakWord,
+    }
+}
+
+/// Approximates not splitting grapheme clusters
+///
+/// FIXME: use extended grapheme cluster boundaries
+pub(super) fn is_combining(ch: char) -> bool {
+    matches!(
+        ch,
+        '\u{0300}'..='\u{036F}'
+            | '\u{1AB0}'..='\u{1AFF}'
+            | '\u{1DC0}'..='\u{1DFF}'
+            | '\u{20D0}'..='\u{20FF}'
+            | '\u{FE20}'..='\u{FE2F}'
+            | '\u{FE00}'..='\u{FE0F}'
+            | '\u{E0100}'..='\u{E01EF}'
+            | '\u{200D}'
+    )
+}
+
+fn is_ideographic_or_kana(ch: char) -> bool {
+    matches!(
+        ch,
+        '\u{3040}'..='\u{30FF}'
+            | '\u{31F0}'..='\u{31FF}'
+            | '\u{3400}'..='\u{4DBF}'
+            | '\u{4E00}'..='\u{9FFF}'
+            | '\u{F900}'..='\u{FAFF}'
+            | '\u{FF66}'..='\u{FF9F}'
+            | '\u{20000}'..='\u{2FFFF}'
+    )
+}
+
+/// Small kana and the prolonged sound mark, line breaking class CJ
+fn is_conditional_japanese_starter(ch: char) -> bool {
+    "ぁぃぅぇぉっゃゅょゎゕゖァィゥェォッャュョヮヵヶー".contains(ch)
+        || matches!(ch, '\u{31F0}'..='\u{31FF}' | '\u{FF67}'..='\u{FF70}')
+}
+
+fn is_iteration_mark(ch: char) -> bool {
+    matches!(ch, '々' | '〻' | 'ゝ' | 'ゞ' | 'ヽ' | 'ヾ')
+}
+
+fn is_inseparable_or_cjk_hyphen(ch: char) -> bool {
+    matches!(ch, '…' | '‥' | '‐' | '–' | '〜' | '゠')
+}
@@ -4,10 +4,13 @@ use super::*;
 use rayon::prelude::*;
 use rayon_croissant::ParallelIteratorExt;
 
+mod bidi_mirroring;
 mod construct;
 mod float;
 mod inline;
+mod line_breaking;
 mod root;
+mod text_transform;
 
 pub(super) use construct::*;
 pub(super) use float::*;
@@ -17,6 +20,7 @@ pub(super) use inline::*;
 pub(super) struct BlockFormattingContext {
     pub contents: BlockContainer,
     pub contains_floats: bool,
+    pub content_sizes: ContentSizesCache,
 }
 
 #[derive(Debug)]
@@ -43,22 +47,28 @@ pub(super) struct FlowChildren {
     pub fragments: Vec<Fragment>,
     pub block_size: Length,
     pub collapsible_margins_in_children: CollapsedBlockMargins,
+    /// The baseline of the last in-flow line box, relative to the start of the children
+    pub last_baseline: Option<Length>,
 }
 
 #[derive(Clone, Copy)]
 struct CollapsibleWithParentStartMargin(bool);
 
 impl BlockFormattingContext {
+    /// `style` is that of the box that establishes this formatting context
+    pub(super) fn inline_content_sizes(&self, style: &ComputedValues) -> ContentSizes {
+        self.content_sizes
+            .get_or_compute(|| self.contents.inline_content_sizes(style))
+    }
+
     pub(super) fn layout<'a>(
         &'a self,
         containing_block: &ContainingBlock,
         tree_rank: usize,
         absolutely_positioned_fragments: &mut Vec<AbsolutelyPositionedFragment<'a>>,
     ) -> FlowChildren {
-        let mut float_context;
-        let float_context = if self.contains_floats {
-            float_context = FloatContext::new();
-            Some(&mut float_context)
+        let mut float_context = if self.contains_floats {
+            Some(FloatContext::new(containing_block.mode.1))
         } else {
             None
         };
@@ -66,10 +76,14 @@ impl BlockFormattingContext {
             containing_block,
             tree_rank,
             absolutely_positioned_fragments,
-            float_context,
+            float_context.as_mut(),
             CollapsibleWithParentStartMargin(false),
         );
         flow_children.block_size += flow_children.collapsible_margins_in_children.end.solve();
+        // https://drafts.csswg.org/css2/visudet.html#root-height
+        if let Some(lowest_float_end) = float_context.and_then(|c| c.lowest_float_end()) {
+            flow_children.block_size.max_assign(lowest_float_end)
+        }
         flow_children
             .collapsible_margins_in_children
             .collapsed_through = false;
@@ -79,6 +93,52 @@ impl BlockFormattingContext {
 }
 
 impl BlockContainer {
+    /// `style` is that of the box whose contents this is
+    fn inline_content_sizes(&self, style: &ComputedValues) -> ContentSizes {
+        match self {
+            BlockContainer::BlockLevelBoxes(child_boxes) => {
+                let mode = style.writing_mode();
+                let is_float = |child: &Arc<BlockLevelBox>| {
+                    matches!(&**child, BlockLevelBox::OutOfFlowFloatBox(_))
+                };
+                if !child_boxes.iter().any(is_float) {
+                    return child_boxes
+                        .par_iter()
+                        .map(|child| child.inline_content_sizes(mode))
+                        .reduce(ContentSizes::zero, ContentSizes::max);
+                }
+                // Floats can be next to each other and to the following in-flow boxes,
+                // until they are cleared.
+                let mut sizes = ContentSizes::zero();
+                let mut floats = ContentSizes::zero();
+                for child in child_boxes {
+                    let child_sizes = child.inline_content_sizes(mode);
+                    let clear = match &**child {
+                        BlockLevelBox::SameFormattingContextBlock { style, .. }
+                        | BlockLevelBox::Independent { style, .. } => style.box_.clear,
+                        BlockLevelBox::OutOfFlowFloatBox(box_) => box_.style.box_.clear,
+                        BlockLevelBox::OutOfFlowAbsolutelyPositionedBox(_) => continue,
+                    };
+                    if clear != Clear::None {
+                        floats = ContentSizes::zero()
+                    }
+                    if is_float(child) {
+                        floats.min_content.max_assign(child_sizes.min_content);
+                        floats.max_content += child_sizes.max_content;
+                        sizes = sizes.max(floats)
+                    } else {
+                        sizes = sizes.max(ContentSizes {
+                            min_content: child_sizes.min_content.max(floats.min_content),
+                            max_content: child_sizes.max_content + floats.max_content,
+                        })
+                    }
+                }
+                sizes
+            }
+            BlockContainer::InlineFormattingContext(ifc) => ifc.inline_content_sizes(style),
+        }
+    }
+
     fn layout<'a>(
         &'a self,
         containing_block: &ContainingBlock,
@@ -96,9 +156,12 @@ impl BlockContainer {
                 float_context,
                 collapsible_with_parent_start_margin,
             ),
-            BlockContainer::InlineFormattingContext(ifc) => {
-                ifc.layout(containing_block, tree_rank, absolutely_positioned_fragments)
-            }
+            BlockContainer::InlineFormattingContext(ifc) => ifc.layout(
+                containing_block,
+                tree_rank,
+                absolutely_positioned_fragments,
+                float_context,
+            ),
         }
     }
 }
@@ -160,6 +223,25 @@ fn layout_block_level_children<'a>(
         }
     }
 
+    /// Clearance moves the border box of the fragment below floats,
+    /// to `border_start` relative to the content box of its parent.
+    /// Margins do not collapse across it.
+    fn place_block_level_fragment_with_clearance(
+        fragment: &mut Fragment,
+        placement_state: &mut PlacementState,
+        border_start: Length,
+    ) {
+        placement_state.next_in_flow_margin_collapses_with_parent_start_margin = false;
+        if let Fragment::Box(fragment) = fragment {
+            let fragment_block_size = fragment.padding.block_sum()
+                + fragment.border.block_sum()
+                + fragment.content_rect.size.block;
+            fragment.content_rect.start_corner.block += border_start;
+            placement_state.current_block_direction_position = border_start + fragment_block_size;
+            placement_state.current_margin = fragment.block_margins_collapsed_with_children.end;
+        }
+    }
+
     struct PlacementState {
         next_in_flow_margin_collapses_with_parent_start_margin: bool,
         start_margin: CollapsedMargin,
@@ -180,20 +262,73 @@ fn layout_block_level_children<'a>(
         // Because floats are involved, we do layout for this block formatting context
         // in tree order without parallelism. This enables mutable access
         // to a `FloatContext` that tracks every float encountered so far (again in tree order).
+        let inline_start = float_context.containing_block_inline_start;
+        let block_position = float_context.block_position;
+        let pending_margin = float_context.pending_margin;
         fragments = child_boxes
             .iter()
             .enumerate()
             .map(|(tree_rank, box_)| {
+                // Where the content box of this box starts, relative to the block formatting
+                // context. It only moves while the margins of children collapse with its own.
+                let start_margin = pending_margin.adjoin(&placement_state.start_margin);
+                let content_start = block_position + start_margin.solve();
+                float_context.containing_block_inline_start = inline_start;
+                if placement_state.next_in_flow_margin_collapses_with_parent_start_margin {
+                    float_context.block_position = block_position;
+                    float_context.pending_margin = start_margin;
+                } else {
+                    float_context.block_position =
+                        content_start + placement_state.current_block_direction_position;
+                    float_context.pending_margin = placement_state.current_margin;
+                }
+                if let BlockLevelBox::OutOfFlowFloatBox(float_box) = &**box_ {
+                    // A float goes after the margins that the next in-flow box collapses with
+                    let next_margin = next_collapsible_start_margin(
+                        &child_boxes[tree_rank + 1..],
+                        containing_block,
+                    );
+                    let content_start =
+                        if placement_state.next_in_flow_margin_collapses_with_parent_start_margin {
+                            block_position + start_margin.adjoin(&next_margin).solve()
+                        } else {
+                            content_start
+                        };
+                    let mut fragment = float_box.layout(
+                        containing_block,
+                        tree_rank,
+                        absolutely_positioned_fragments,
+                    );
+                    let margin_box_start = float_context.place(
+                        &float_box.style,
+                        containing_block,
+                        margin_box_size(&fragment),
+                        float_context.block_position
+                            + float_context.pending_margin.adjoin(&next_margin).solve(),
+                    );
+                    let start_corner = &mut fragment.content_rect.start_corner;
+                    start_corner.inline += margin_box_start.inline;
+                    start_corner.block += margin_box_start.block - content_start;
+                    return Fragment::Box(fragment);
+                }
                 let mut fragment = box_.layout(
                     containing_block,
                     tree_rank,
                     absolutely_positioned_fragments,
                     Some(float_context),
                 );
-                place_block_level_fragment(&mut fragment, &mut placement_state);
+                match float_context.clearance.take() {
+                    Some(border_start) => place_block_level_fragment_with_clearance(
+                        &mut fragment,
+                        &mut placement_state,
+                        border_start - content_start,
+                    ),
+                    None => place_block_level_fragment(&mut fragment, &mut placement_state),
+                }
                 fragment
             })
-            .collect()
+            .collect();
+        float_context.containing_block_inline_start = inline_start;
     } else {
         fragments = child_boxes
             .par_iter()
@@ -224,6 +359,13 @@ fn layout_block_level_children<'a>(
         tree_rank,
     );
 
+    let last_baseline = fragments.iter().rev().find_map(|fragment| match fragment {
+        Fragment::Box(b) => b
+            .last_baseline
+            .map(|baseline| b.content_rect.start_corner.block + baseline),
+        _ => None,
+    });
+
     FlowChildren {
         fragments,
         block_size: placement_state.current_block_direction_position,
@@ -233,10 +375,35 @@ fn layout_block_level_children<'a>(
             start: placement_state.start_margin,
             end: placement_state.current_margin,
         },
+        last_baseline,
     }
 }
 
 impl BlockLevelBox {
+    fn inline_content_sizes(
+        &self,
+        containing_block_mode: (WritingMode, Direction),
+    ) -> ContentSizes {
+        match self {
+            BlockLevelBox::SameFormattingContextBlock { style, contents } => {
+                sizing::outer_inline_content_sizes(style, containing_block_mode, || {
+                    contents.inline_content_sizes(style)
+                })
+            }
+            BlockLevelBox::Independent { style, contents } => {
+                sizing::outer_inline_content_sizes(style, containing_block_mode, || {
+                    contents.inline_content_sizes(style)
+                })
+            }
+            BlockLevelBox::OutOfFlowAbsolutelyPositionedBox(_) => ContentSizes::zero(),
+            BlockLevelBox::OutOfFlowFloatBox(box_) => {
+                sizing::outer_inline_content_sizes(&box_.style, containing_block_mode, || {
+                    box_.contents.inline_content_sizes(&box_.style)
+                })
+            }
+        }
+    }
+
     fn layout<'a>(
         &'a self,
         containing_block: &ContainingBlock,
@@ -251,7 +418,12 @@ impl BlockLevelBox {
                     absolutely_positioned_fragments,
                     style,
                     BlockLevelKind::SameFormattingContextBlock,
-                    |containing_block, nested_abspos, collapsible_with_parent_start_margin| {
+                    float_context,
+                    || contents.inline_content_sizes(style),
+                    |containing_block,
+                     nested_abspos,
+                     float_context,
+                     collapsible_with_parent_start_margin| {
                         contents.layout(
                             containing_block,
                             tree_rank,
@@ -272,24 +444,84 @@ impl BlockLevelBox {
                     absolutely_positioned_fragments,
                     style,
                     BlockLevelKind::EstablishesAnIndependentFormattingContext,
-                    |containing_block, nested_abspos, _| {
+                    float_context,
+                    || contents.inline_content_sizes(style),
+                    |containing_block, nested_abspos, _, _| {
                         contents.layout(containing_block, tree_rank, nested_abspos)
                     },
                 )),
             },
             BlockLevelBox::OutOfFlowAbsolutelyPositionedBox(box_) => {
-                absolutely_positioned_fragments.push(box_.layout(Vec2::zero(), tree_rank));
+                absolutely_positioned_fragments.push(box_.layout(
+                    Vec2::zero(),
+                    tree_rank,
+                    containing_block.mode,
+                ));
                 Fragment::Anonymous(AnonymousFragment::no_op(containing_block.mode))
             }
-            BlockLevelBox::OutOfFlowFloatBox(_box_) => {
-                // TODO
+            BlockLevelBox::OutOfFlowFloatBox(_) => {
+                // Floats are laid out and placed by `layout_block_level_children`,
+                // in a block formatting context that contains floats.
                 Fragment::Anonymous(AnonymousFragment::no_op(containing_block.mode))
             }
         }
     }
 }
 
-#[derive(PartialEq)]
+/// The start margin of the next in-flow box among `boxes`,
+/// adjoined with those of its first descendants that collapse with it.
+///
+/// FIXME: boxes whose margins collapse through are not skipped
+fn next_collapsible_start_margin(
+    boxes: &[Arc<BlockLevelBox>],
+    containing_block: &ContainingBlock,
+) -> CollapsedMargin {
+    let cbis = containing_block.inline_size;
+    let mode = containing_block.mode;
+    let margin_start = |style: &ComputedValues| {
+        CollapsedMargin::new(
+            style
+                .margin(mode)
+                .percentages_relative_to(cbis)
+                .block_start
+                .auto_is(Length::zero),
+        )
+    };
+    for box_ in boxes {
+        match &**box_ {
+            // Clearance would prevent its margins from collapsing
+            BlockLevelBox::SameFormattingContextBlock { style, .. }
+            | BlockLevelBox::Independent { style, .. }
+                if style.box_.clear != Clear::None =>
+            {
+                return CollapsedMargin::zero()
+            }
+            BlockLevelBox::SameFormattingContextBlock { style, contents } => {
+                let pb_start = style
+                    .padding(mode)
+                    .percentages_relative_to(cbis)
+                    .block_start
+                    + style
+                        .border_width(mode)
+                        .percentages_relative_to(cbis)
+                        .block_start;
+                let margin = margin_start(style);
+                return match contents {
+                    BlockContainer::BlockLevelBoxes(children) if pb_start == Length::zero() => {
+                        margin.adjoin(&next_collapsible_start_margin(children, containing_block))
+                    }
+                    _ => margin,
+                };
+            }
+            BlockLevelBox::Independent { style, .. } => return margin_start(style),
+            BlockLevelBox::OutOfFlowAbsolutelyPositionedBox(_)
+            | BlockLevelBox::OutOfFlowFloatBox(_) => {}
+        }
+    }
+    CollapsedMargin::zero()
+}
+
+#[derive(Clone, Copy, PartialEq)]
 enum BlockLevelKind {
     SameFormattingContextBlock,
     EstablishesAnIndependentFormattingContext,
@@ -302,61 +534,70 @@ fn layout_in_flow_non_replaced_block_level<'a>(
     absolutely_positioned_fragments: &mut Vec<AbsolutelyPositionedFragment<'a>>,
     style: &Arc<ComputedValues>,
     block_level_kind: BlockLevelKind,
-    layout_contents: impl FnOnce(
+    float_context: Option<&mut FloatContext>,
+    content_sizes: impl Fn() -> ContentSizes,
+    mut layout_contents: impl FnMut(
         &ContainingBlock,
         &mut Vec<AbsolutelyPositionedFragment<'a>>,
+        Option<&mut FloatContext>,
         CollapsibleWithParentStartMargin,
     ) -> FlowChildren,
 ) -> BoxFragment {
     let cbis = containing_block.inline_size;
-    let padding = style.padding().percentages_relative_to(cbis);
-    let border = style.border_width().percentages_relative_to(cbis);
-    let mut computed_margin = style.margin().percentages_relative_to(cbis);
+    let padding = style
+        .padding(containing_block.mode)
+        .percentages_relative_to(cbis);
+    let border = style
+        .border_width(containing_block.mode)
+        .percentages_relative_to(cbis);
+    let mut computed_margin = style
+        .margin(containing_block.mode)
+        .percentages_relative_to(cbis);
     let pb = &padding + &border;
-    let box_size = style.box_size();
-    let inline_size = box_size.inline.percentage_relative_to(cbis);
+    // Along the axes of the containing block
+    let box_size = style.box_size(containing_block.mode);
+    let mode = style.writing_mode();
+    // https://drafts.csswg.org/css-writing-modes/#orthogonal-flows
+    let orthogonal = mode.0.is_vertical() != containing_block.mode.0.is_vertical();
+    let block_level_kind = if orthogonal {
+        BlockLevelKind::EstablishesAnIndependentFormattingContext
+    } else {
+        block_level_kind
+    };
+    let inline_size = sizing::inline_size(
+        box_size.inline,
+        cbis,
+        cbis - pb.inline_sum() - computed_margin.auto_is(Length::zero).inline_sum(),
+        &content_sizes,
+    );
     if let LengthOrAuto::Length(is) = inline_size {
-        let inline_margins = cbis - is - pb.inline_sum();
-        use LengthOrAuto::*;
-        match (
-            &mut computed_margin.inline_start,
-            &mut computed_margin.inline_end,
-        ) {
-            (s @ &mut Auto, e @ &mut Auto) => {
-                *s = Length(inline_margins / 2.);
-                *e = Length(inline_margins / 2.);
-            }
-            (s @ &mut Auto, _) => {
-                *s = Length(inline_margins);
-            }
-            (_, e @ &mut Auto) => {
-                *e = Length(inline_margins);
-            }
-            (_, e @ _) => {
-                // Either the inline-end margin is auto,
-                // or we’re over-constrained and we do as if it were.
-                *e = Length(inline_margins);
-            }
-        }
+        solve_inline_margins(&mut computed_margin, cbis - is - pb.inline_sum());
     }
-    let margin = computed_margin.auto_is(Length::zero);
+    let mut margin = computed_margin.auto_is(Length::zero);
     let mut block_margins_collapsed_with_children = CollapsedBlockMargins::from_margin(&margin);
-    let inline_size = inline_size.auto_is(|| cbis - pb.inline_sum() - margin.inline_sum());
-    let block_size = match box_size.block {
-        LengthOrPercentageOrAuto::Length(l) => LengthOrAuto::Length(l),
-        LengthOrPercentageOrAuto::Percentage(p) => containing_block.block_size.map(|cbbs| cbbs * p),
-        LengthOrPercentageOrAuto::Auto => LengthOrAuto::Auto,
-    };
-    let containing_block_for_children = ContainingBlock {
-        inline_size,
-        block_size,
-        mode: style.writing_mode(),
+    let block_size = sizing::block_size(box_size.block, containing_block.block_size);
+    let mut containing_block_for_children = if orthogonal {
+        // The inline axis of this box is the block axis of its containing block.
+        // https://drafts.csswg.org/css-writing-modes/#orthogonal-auto
+        ContainingBlock {
+            inline_size: block_size.auto_is(|| {
+                // FIXME: when the containing block’s block size is not definite,
+                // this should use the size of the initial containing block instead.
+                let available = containing_block.block_size.auto_is(|| cbis);
+                available - pb.block_sum() - margin.block_sum()
+            }),
+            block_size: inline_size,
+            mode,
+            style,
+        }
+    } else {
+        ContainingBlock {
+            inline_size: inline_size.auto_is(|| cbis - pb.inline_sum() - margin.inline_sum()),
+            block_size,
+            mode,
+            style,
+        }
     };
-    // https://drafts.csswg.org/css-writing-modes/#orthogonal-flows
-    assert_eq!(
-        containing_block.mode, containing_block_for_children.mode,
-        "Mixed writing modes are not supported yet"
-    );
     let this_start_margin_can_collapse_with_children = CollapsibleWithParentStartMargin(
         block_level_kind == BlockLevelKind::SameFormattingContextBlock
             && pb.block_start == Length::zero(),
@@ -367,16 +608,127 @@ fn layout_in_flow_non_replaced_block_level<'a>(
             Length::zero(),
             LengthOrAuto::Auto,
         );
+    let relatively_positioned = style.box_.position.is_relatively_positioned();
     let mut nested_abspos = vec![];
-    let mut flow_children = layout_contents(
-        &containing_block_for_children,
-        if style.box_.position.is_relatively_positioned() {
-            &mut nested_abspos
-        } else {
-            absolutely_positioned_fragments
-        },
-        this_start_margin_can_collapse_with_children,
-    );
+    let abspos_so_far = absolutely_positioned_fragments.len();
+    // How far floats move the box towards the inline end of its containing block
+    let mut inline_offset = Length::zero();
+    let mut flow_children;
+    match float_context {
+        // FIXME: orthogonal flows next to floats
+        Some(float_context) if !orthogonal => {
+            let hypothetical_start = float_context.block_position
+                + float_context
+                    .pending_margin
+                    .adjoin(&CollapsedMargin::new(margin.block_start))
+                    .solve();
+            let mut border_start = hypothetical_start;
+            if let Some(clear) = float_context.clear_position(style.box_.clear) {
+                border_start.max_assign(clear)
+            }
+            if block_level_kind == BlockLevelKind::SameFormattingContextBlock {
+                float_context.enter_child(
+                    containing_block,
+                    margin.inline_start + pb.inline_start,
+                    margin.inline_end + pb.inline_end,
+                );
+                if this_start_margin_can_collapse_with_children.0
+                    && border_start == hypothetical_start
+                {
+                    float_context
+                        .pending_margin
+                        .adjoin_assign(&CollapsedMargin::new(margin.block_start))
+                } else {
+                    float_context.block_position = border_start + pb.block_start;
+                    float_context.pending_margin = CollapsedMargin::zero();
+                }
+                flow_children = layout_contents(
+                    &containing_block_for_children,
+                    if relatively_positioned {
+                        &mut nested_abspos
+                    } else {
+                        absolutely_positioned_fragments
+                    },
+                    Some(float_context),
+                    this_start_margin_can_collapse_with_children,
+                );
+            } else {
+                // “The border box of a table, a block-level replaced element, or an element
+                //  in the normal flow that establishes a new block formatting context […]
+                //  must not overlap the margin box of any floats in the same block formatting
+                //  context as the element itself.”
+                // https://drafts.csswg.org/css2/visuren.html#bfc-next-to-float
+                let containing_block_range = (Length::zero(), cbis);
+                loop {
+                    let (start, end) = float_context.available_inline_range(
+                        containing_block,
+                        border_start,
+                        border_start,
+                    );
+                    inline_offset = start;
+                    if inline_size == LengthOrAuto::Auto {
+                        containing_block_for_children.inline_size =
+                            end - start - pb.inline_sum() - margin.inline_sum()
+                    }
+                    nested_abspos.clear();
+                    absolutely_positioned_fragments.truncate(abspos_so_far);
+                    flow_children = layout_contents(
+                        &containing_block_for_children,
+                        if relatively_positioned {
+                            &mut nested_abspos
+                        } else {
+                            absolutely_positioned_fragments
+                        },
+                        None,
+                        this_start_margin_can_collapse_with_children,
+                    );
+                    let border_end = border_start
+                        + pb.block_sum()
+                        + block_size.auto_is(|| flow_children.block_size);
+                    let fits = (start, end) == containing_block_range
+                        || (containing_block_for_children.inline_size
+                            + pb.inline_sum()
+                            + margin.inline_sum()
+                            <= end - start
+                            && (inline_size != LengthOrAuto::Auto
+                                || content_sizes().min_content
+                                    <= containing_block_for_children.inline_size));
+                    let fits = fits
+                        && float_context.available_inline_range(
+                            containing_block,
+                            border_start,
+                            border_end,
+                        ) == (start, end);
+                    match float_context.next_float_end(border_start, border_end) {
+                        Some(next) if !fits => border_start = next,
+                        _ => break,
+                    }
+                }
+            }
+            if border_start != hypothetical_start {
+                float_context.clearance = Some(border_start)
+            }
+        }
+        _ => {
+            // An orthogonal flow establishes a block formatting context
+            let mut orthogonal_float_context =
+                FloatContext::new(containing_block_for_children.mode.1);
+            flow_children = layout_contents(
+                &containing_block_for_children,
+                if relatively_positioned {
+                    &mut nested_abspos
+                } else {
+                    absolutely_positioned_fragments
+                },
+                if orthogonal {
+                    Some(&mut orthogonal_float_context)
+                } else {
+                    None
+                },
+                this_start_margin_can_collapse_with_children,
+            );
+        }
+    }
     if this_start_margin_can_collapse_with_children.0 {
         block_margins_collapsed_with_children
             .start
@@ -406,25 +758,55 @@ fn layout_in_flow_non_replaced_block_level<'a>(
             && flow_children
                 .collapsible_margins_in_children
                 .collapsed_through;
-    let relative_adjustement = relative_adjustement(style, inline_size, block_size);
-    let block_size = block_size.auto_is(|| flow_children.block_size);
+    let content_size = if orthogonal {
+        Vec2 {
+            inline: inline_size.auto_is(|| flow_children.block_size),
+            block: containing_block_for_children.inline_size,
+        }
+    } else {
+        Vec2 {
+            inline: containing_block_for_children.inline_size,
+            block: block_size.auto_is(|| flow_children.block_size),
+        }
+    };
+    if orthogonal && inline_size == LengthOrAuto::Auto {
+        // Now that the size is known
+        solve_inline_margins(
+            &mut computed_margin,
+            cbis - content_size.inline - pb.inline_sum(),
+        );
+        margin = computed_margin.auto_is(Length::zero);
+    }
+    let relative_adjustement = relative_adjustement(
+        style,
+        content_size.inline,
+        if orthogonal {
+            LengthOrAuto::Length(content_size.block)
+        } else {
+            block_size
+        },
+        containing_block.mode,
+    );
     let content_rect = Rect {
         start_corner: Vec2 {
             block: pb.block_start + relative_adjustement.block,
-            inline: pb.inline_start + relative_adjustement.inline + margin.inline_start,
-        },
-        size: Vec2 {
-            block: block_size,
-            inline: inline_size,
+            inline: pb.inline_start
+                + relative_adjustement.inline
+                + margin.inline_start
+                + inline_offset,
         },
+        size: content_size,
     };
-    if style.box_.position.is_relatively_positioned() {
+    if relatively_positioned {
         AbsolutelyPositionedFragment::in_positioned_containing_block(
             &nested_abspos,
             &mut flow_children.fragments,
-            &content_rect.size,
-            &padding,
-            containing_block_for_children.mode,
+            &content_rect
+                .size
+                .size_to_physical(containing_block.mode)
+                .size_to_flow_relative(mode),
+            &style.padding(mode).percentages_relative_to(cbis),
+            mode,
         )
     }
     BoxFragment {
@@ -435,5 +817,36 @@ fn layout_in_flow_non_replaced_block_level<'a>(
         border,
         margin,
         block_margins_collapsed_with_children,
+        // In the writing mode of the children, which would be the wrong axis
+        last_baseline: if orthogonal {
+            None
+        } else {
+            flow_children.last_baseline
+        },
+    }
+}
+
+/// Resolve `auto` inline margins, given the space they share
+fn solve_inline_margins(computed_margin: &mut Sides<LengthOrAuto>, inline_margins: Length) {
+    use LengthOrAuto::*;
+    match (
+        &mut computed_margin.inline_start,
+        &mut computed_margin.inline_end,
+    ) {
+        (s @ &mut Auto, e @ &mut Auto) => {
+            *s = Length(inline_margins / 2.);
+            *e = Length(inline_margins / 2.);
+        }
+        (s @ &mut Auto, _) => {
+            *s = Length(inline_margins);
+        }
+        (_, e @ &mut Auto) => {
+            *e = Length(inline_margins);
+        }
+        (_, e @ _) => {
+            // Either the inline-end margin is auto,
+            // or we’re over-constrained and we do as if it were.
+            *e = Length(inline_margins);
+        }
     }
 }
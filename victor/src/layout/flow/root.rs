@@ -9,7 +9,11 @@ impl crate::dom::Document {
     }
 }
 
-struct BoxTreeRoot(BlockFormattingContext);
+struct BoxTreeRoot {
+    bfc: BlockFormattingContext,
+    /// https://drafts.csswg.org/css-writing-modes/#principal-flow
+    principal_writing_mode: (WritingMode, Direction),
+}
 
 impl BoxTreeRoot {
     pub fn construct(document: &dom::Document) -> Self {
@@ -20,18 +24,25 @@ impl BoxTreeRoot {
         };
         let root_element = document.root_element();
         let style = style_for_element(context.author_styles, context.document, root_element, None);
+        // FIXME: propagate from the body element:
+        // https://drafts.csswg.org/css-writing-modes/#principal-flow
+        let principal_writing_mode = style.writing_mode();
         let (contains_floats, boxes) = construct_for_root_element(&context, root_element, style);
-        Self(BlockFormattingContext {
-            contains_floats: contains_floats == ContainsFloats::Yes,
-            contents: BlockContainer::BlockLevelBoxes(boxes),
-        })
+        Self {
+            bfc: BlockFormattingContext {
+                contains_floats: contains_floats == ContainsFloats::Yes,
+                contents: BlockContainer::BlockLevelBoxes(boxes),
+                content_sizes: ContentSizesCache::default(),
+            },
+            principal_writing_mode,
+        }
     }
 }
 
 fn construct_for_root_element(
     context: &Context,
     root_element: dom::NodeId,
-    style: Arc<ComputedValues>,
+    mut style: Arc<ComputedValues>,
 ) -> (ContainsFloats, Vec<Arc<BlockLevelBox>>) {
     let replaced = ReplacedContent::for_element(root_element, context);
 
@@ -45,6 +56,8 @@ fn construct_for_root_element(
         Display::Contents => DisplayInside::Flow,
         // The root element is blockified, ignore DisplayOutside
         Display::GeneratingBox(DisplayGeneratingBox::OutsideInside { inside, .. }) => inside,
+        // https://drafts.csswg.org/css-display-3/#blockify
+        Display::GeneratingBox(DisplayGeneratingBox::LayoutInternal(_)) => DisplayInside::Flow,
     };
 
     if let Some(replaced) = replaced {
@@ -57,7 +70,7 @@ fn construct_for_root_element(
 
     let contents = IndependentFormattingContext::construct(
         context,
-        &style,
+        &mut style,
         display_inside,
         Contents::OfElement(root_element),
     );
@@ -86,23 +99,26 @@ fn construct_for_root_element(
 
 impl BoxTreeRoot {
     fn layout(&self, viewport: crate::primitives::Size<crate::primitives::CssPx>) -> Vec<Fragment> {
-        let initial_containing_block_size = Vec2 {
-            inline: Length { px: viewport.width },
-            block: Length {
+        let page_mode = (WritingMode::HorizontalTb, Direction::Ltr);
+        let mode = self.principal_writing_mode;
+        let viewport = crate::geom::physical::Vec2 {
+            x: Length { px: viewport.width },
+            y: Length {
                 px: viewport.height,
             },
         };
+        let initial_containing_block_size = viewport.size_to_flow_relative(mode);
 
+        let initial_values = ComputedValues::anonymous_inheriting_from(None);
         let initial_containing_block = ContainingBlock {
             inline_size: initial_containing_block_size.inline,
             block_size: LengthOrAuto::Length(initial_containing_block_size.block),
-            // FIXME: use the document’s mode:
-            // https://drafts.csswg.org/css-writing-modes/#principal-flow
-            mode: (WritingMode::HorizontalTb, Direction::Ltr),
+            mode,
+            style: &initial_values,
         };
         let dummy_tree_rank = 0;
         let mut absolutely_positioned_fragments = vec![];
-        let mut flow_children = self.0.layout(
+        let mut flow_children = self.bfc.layout(
             &initial_containing_block,
             dummy_tree_rank,
             &mut absolutely_positioned_fragments,
@@ -117,6 +133,14 @@ impl BoxTreeRoot {
                 .par_iter()
                 .map(|a| a.layout(&initial_containing_block)),
         );
-        flow_children.fragments
+        // Painting starts in the mode of the page
+        vec![Fragment::Anonymous(AnonymousFragment {
+            rect: Rect {
+                start_corner: Vec2::zero(),
+                size: viewport.size_to_flow_relative(page_mode),
+            },
+            children: flow_children.fragments,
+            mode,
+        })]
     }
 }
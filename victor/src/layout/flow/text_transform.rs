@@ -0,0 +1,147 @@
+//! https://drafts.csswg.org/css-text-3/#text-transform-property
+
+use super::line_breaking::is_combining;
+use super::*;
+
+/// Languages with case mapping rules beyond the default Unicode ones
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub(super) enum Language {
+    /// Turkish and Azerbaijani, where dotted and dotless i are distinct letters
+    Turkic,
+    /// Modern Greek, where uppercase letters drop their accents
+    Greek,
+    /// Dutch, where the “ij” digraph is capitalized as a whole
+    Dutch,
+    Other,
+}
+
+impl Language {
+    /// From a BCP 47 language tag such as the value of the `lang` attribute
+    pub(super) fn from_tag(tag: Option<&str>) -> Self {
+        let primary = tag.and_then(|tag| tag.split(&['-', '_'][..]).next());
+        match_ignore_ascii_case! { primary.unwrap_or(""),
+            "tr" | "az" => Language::Turkic,
+            "el" => Language::Greek,
+            "nl" => Language::Dutch,
+            _ => Language::Other,
+        }
+    }
+}
+
+/// Applies `text-transform` to text that has already gone through white space collapsing.
+///
+/// `previous` is the character before this text in the inline formatting context, if any,
+/// which decides whether `capitalize` sees the start of a word.
+pub(super) fn transform(
+    text: &str,
+    transform: TextTransform,
+    language: Language,
+    mut previous: Option<char>,
+) -> String {
+    let mut output = String::with_capacity(text.len());
+    match transform.case {
+        None => output.push_str(text),
+        Some(TextTransformCase::Uppercase) => {
+            for ch in text.chars() {
+                push_uppercase(ch, language, &mut output)
+            }
+        }
+        Some(TextTransformCase::Lowercase) => {
+            if language == Language::Turkic {
+                let text = text.replace('I', "ı").replace('İ', "i");
+                output.push_str(&text.to_lowercase())
+            } else {
+                // This handles the final form of sigma
+                output.push_str(&text.to_lowercase())
+            }
+        }
+        Some(TextTransformCase::Capitalize) => {
+            let mut chars = text.chars().peekable();
+            while let Some(ch) = chars.next() {
+                if !is_word_start(previous, ch) {
+                    output.push(ch)
+                } else if language == Language::Dutch
+                    && matches!(ch, 'i' | 'I')
+                    && matches!(chars.peek(), Some('j') | Some('J'))
+                {
+                    chars.next();
+                    output.push_str("IJ")
+                } else {
+                    push_titlecase(ch, language, &mut output)
+                }
+                previous = output.chars().next_back()
+            }
+        }
+    }
+    if transform.full_width {
+        output = output.chars().map(full_width).collect()
+    }
+    output
+}
+
+/// The first typographic letter unit of a word, approximated as a letter or digit
+/// that does not follow another one, or an apostrophe within a word.
+fn is_word_start(previous: Option<char>, ch: char) -> bool {
+    ch.is_alphanumeric()
+        && previous.map_or(true, |previous| {
+            !(previous.is_alphanumeric()
+                || is_combining(previous)
+                || matches!(previous, '\'' | '\u{2019}'))
+        })
+}
+
+pub(super) fn push_uppercase(ch: char, language: Language, output: &mut String) {
+    match language {
+        Language::Turkic if ch == 'i' => output.push('İ'),
+        // Combining acute accent and perispomeni, but the diaeresis is kept
+        Language::Greek if matches!(ch, '\u{0301}' | '\u{0342}') => {}
+        Language::Greek => output.extend(without_tonos(ch).to_uppercase()),
+        _ => output.extend(ch.to_uppercase()),
+    }
+}
+
+/// Like uppercase, but digraphs only have their first letter capitalized
+/// and Greek keeps its accents.
+fn push_titlecase(ch: char, language: Language, output: &mut String) {
+    match ch {
+        'Ǆ' | 'ǅ' | 'ǆ' => output.push('ǅ'),
+        'Ǉ' | 'ǈ' | 'ǉ' => output.push('ǈ'),
+        'Ǌ' | 'ǋ' | 'ǌ' => output.push('ǋ'),
+        'Ǳ' | 'ǲ' | 'ǳ' => output.push('ǲ'),
+        'i' if language == Language::Turkic => output.push('İ'),
+        _ => output.extend(ch.to_uppercase()),
+    }
+}
+
+/// Greek vowels with tonos, mapped to the same vowel without it
+fn without_tonos(ch: char) -> char {
+    match ch {
+        'ά' | 'Ά' => 'α',
+        'έ' | 'Έ' => 'ε',
+        'ή' | 'Ή' => 'η',
+        'ί' | 'Ί' => 'ι',
+        'ό' | 'Ό' => 'ο',
+        'ύ' | 'Ύ' => 'υ',
+        'ώ' | 'Ώ' => 'ω',
+        'ΐ' => 'ϊ',
+        'ΰ' => 'ϋ',
+        _ => ch,
+    }
+}
+
+/// https://drafts.csswg.org/css-text-3/#valdef-text-transform-full-width
+fn full_width(ch: char) -> char {
+    match ch {
+        '!'..='~' => std::char::from_u32(ch as u32 - 0x21 + 0xFF01).unwrap(),
+        '¢' => '￠',
+        '£' => '￡',
+        '¬' => '￢',
+        '¯' => '￣',
+        '¦' => '￤',
+        '¥' => '￥',
+        '₩' => '￦',
+        // U+0020 is not mapped to U+3000 IDEOGRAPHIC SPACE,
+        // so that it remains a collapsible space for line breaking and justification.
+        _ => ch,
+    }
+}
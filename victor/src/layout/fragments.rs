@@ -1,5 +1,6 @@
 use super::*;
 use crate::text::ShapedSegment;
+use unicode_bidi::Level;
 
 pub(crate) enum Fragment {
     Box(BoxFragment),
@@ -21,6 +22,10 @@ pub(crate) struct BoxFragment {
     pub margin: Sides<Length>,
 
     pub block_margins_collapsed_with_children: CollapsedBlockMargins,
+
+    /// The baseline of the last in-flow line box among the descendants, if any,
+    /// relative to the block-start edge of `content_rect`
+    pub last_baseline: Option<Length>,
 }
 
 pub(crate) struct CollapsedBlockMargins {
@@ -46,6 +51,12 @@ pub(crate) struct TextFragment {
     pub parent_style: Arc<ComputedValues>,
     pub content_rect: Rect<Length>,
     pub text: ShapedSegment,
+
+    /// The parent style’s, except smaller for synthesized small capitals
+    pub font_size: FontSize,
+
+    /// https://www.unicode.org/reports/tr9/#BD2
+    pub bidi_level: Level,
 }
 
 impl AnonymousFragment {
@@ -64,6 +75,29 @@ impl BoxFragment {
             .inflate(&self.padding)
             .inflate(&self.border)
     }
+
+    /// Whether this is the fragment of an atomic inline-level box, such as an inline-block,
+    /// whose contents are not part of the line box it is on
+    pub fn is_atomic_inline(&self) -> bool {
+        match self.style.box_.display {
+            Display::GeneratingBox(DisplayGeneratingBox::OutsideInside {
+                outside: DisplayOutside::Inline,
+                inside,
+            }) => inside != DisplayInside::Flow,
+            _ => false,
+        }
+    }
+
+    /// Whether this is the fragment of an inline box, whose contents are part of the line box
+    pub fn is_inline_box(&self) -> bool {
+        match self.style.box_.display {
+            Display::GeneratingBox(DisplayGeneratingBox::OutsideInside {
+                outside: DisplayOutside::Inline,
+                inside,
+            }) => inside == DisplayInside::Flow,
+            _ => false,
+        }
+    }
 }
 
 impl CollapsedBlockMargins {
@@ -0,0 +1,109 @@
+use super::*;
+
+impl GridFormattingContext {
+    pub fn construct<'a>(
+        context: &'a Context<'a>,
+        style: &Arc<ComputedValues>,
+        contents: NonReplacedContents,
+    ) -> Self {
+        let mut builder = GridContainerBuilder {
+            context,
+            style: style.clone(),
+            children: Vec::new(),
+            ongoing_anonymous_item: None,
+        };
+        contents.traverse(style, context, &mut builder);
+        builder.finish()
+    }
+}
+
+/// Each in-flow child element of a grid container becomes a grid item.
+/// Contiguous text is wrapped in an anonymous grid item, unless it is only white space.
+///
+/// https://drafts.csswg.org/css-grid-1/#grid-items
+struct GridContainerBuilder<'a> {
+    context: &'a Context<'a>,
+    style: Arc<ComputedValues>,
+    children: Vec<Arc<GridLevelBox>>,
+    /// The contents of an anonymous grid item can come from several sibling nodes
+    ongoing_anonymous_item: Option<(Arc<ComputedValues>, BlockContainerBuilder<'a>)>,
+}
+
+impl<'a> GridContainerBuilder<'a> {
+    fn end_ongoing_anonymous_item(&mut self) {
+        if let Some((style, builder)) = self.ongoing_anonymous_item.take() {
+            let (contents, contains_floats) = builder.finish();
+            let contents = IndependentFormattingContext::Flow(BlockFormattingContext {
+                contents,
+                contains_floats: contains_floats == ContainsFloats::Yes,
+                content_sizes: ContentSizesCache::default(),
+            });
+            self.children
+                .push(Arc::new(GridLevelBox::GridItem(GridItemBox {
+                    style,
+                    contents,
+                })))
+        }
+    }
+
+    fn finish(mut self) -> GridFormattingContext {
+        self.end_ongoing_anonymous_item();
+        // Items are placed in order-modified document order.
+        // The sort is stable, so items with the same `order` stay in document order.
+        // https://drafts.csswg.org/css-display-3/#order-modified-document-order
+        let mut children = self.children;
+        children.sort_by_key(|child| child.style().flex.order);
+        GridFormattingContext {
+            children,
+            content_sizes: ContentSizesCache::default(),
+        }
+    }
+}
+
+impl<'a> TraversalHandler<'a> for GridContainerBuilder<'a> {
+    fn handle_element(
+        &mut self,
+        style: &Arc<ComputedValues>,
+        display: DisplayGeneratingBox,
+        contents: Contents,
+        box_slot: BoxSlot<'a>,
+    ) {
+        self.end_ongoing_anonymous_item();
+        let display_inside = match display {
+            DisplayGeneratingBox::OutsideInside { inside, .. } => inside,
+            // Children of a grid container are blockified, so this does not happen
+            DisplayGeneratingBox::LayoutInternal(_) => DisplayInside::Flow,
+        };
+        // `float` does not apply to grid items
+        let mut style = style.clone();
+        let contents = IndependentFormattingContext::construct(
+            self.context,
+            &mut style,
+            display_inside,
+            contents,
+        );
+        let box_ = Arc::new(if style.box_.position.is_absolutely_positioned() {
+            GridLevelBox::OutOfFlowAbsolutelyPositionedBox(AbsolutelyPositionedBox {
+                style,
+                contents,
+            })
+        } else {
+            GridLevelBox::GridItem(GridItemBox { style, contents })
+        });
+        self.children.push(box_.clone());
+        box_slot.set(LayoutBox::GridLevel(box_))
+    }
+
+    fn handle_text(&mut self, text: &str, parent_style: &Arc<ComputedValues>) {
+        if self.ongoing_anonymous_item.is_none() && is_white_space_only(text) {
+            return;
+        }
+        let (context, style) = (self.context, &self.style);
+        let (_, builder) = self.ongoing_anonymous_item.get_or_insert_with(|| {
+            let style = ComputedValues::anonymous_inheriting_from(Some(style));
+            let builder = BlockContainerBuilder::new(context, style.clone());
+            (style, builder)
+        });
+        builder.handle_text(text, parent_style)
+    }
+}
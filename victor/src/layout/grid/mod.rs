@@ -0,0 +1,576 @@
+//! Grid layout
+//!
+//! https://drafts.csswg.org/css-grid-1/
+
+use super::*;
+use placement::{Area, GridTracks};
+use rayon::prelude::*;
+use std::ops::Range;
+use tracks::{AvailableSpace, TrackSizingItem};
+
+mod construct;
+mod placement;
+mod tracks;
+
+/// The contents of a grid container
+///
+/// https://drafts.csswg.org/css-grid-1/#grid-containers
+#[derive(Debug)]
+pub(super) struct GridFormattingContext {
+    /// In order-modified document order
+    children: Vec<Arc<GridLevelBox>>,
+    content_sizes: ContentSizesCache,
+}
+
+#[derive(Debug)]
+pub(super) enum GridLevelBox {
+    GridItem(GridItemBox),
+    OutOfFlowAbsolutelyPositionedBox(AbsolutelyPositionedBox),
+}
+
+#[derive(Debug)]
+pub(super) struct GridItemBox {
+    style: Arc<ComputedValues>,
+    contents: IndependentFormattingContext,
+}
+
+/// A grid item during layout, with sides and sizes in the writing mode of the grid container.
+/// Sizes are of the content box.
+struct GridItem<'a> {
+    box_: &'a GridItemBox,
+    area: Area,
+    padding: Sides<Length>,
+    border: Sides<Length>,
+    /// Sums of padding and border
+    pb: Sides<Length>,
+    margin: Sides<LengthOrAuto>,
+    justify_self: ItemAlignment,
+    align_self: ItemAlignment,
+    inline_size: Length,
+    layout: ItemLayout<'a>,
+}
+
+struct ItemLayout<'a> {
+    flow_children: FlowChildren,
+    absolutely_positioned_fragments: Vec<AbsolutelyPositionedFragment<'a>>,
+    /// Of the content box
+    block_size: Length,
+}
+
+/// The start and end of each track of one axis, from the start of the content box
+struct TrackPositions(Vec<(Length, Length)>);
+
+impl GridLevelBox {
+    fn style(&self) -> &Arc<ComputedValues> {
+        match self {
+            GridLevelBox::GridItem(item) => &item.style,
+            GridLevelBox::OutOfFlowAbsolutelyPositionedBox(box_) => &box_.style,
+        }
+    }
+}
+
+impl GridFormattingContext {
+    fn items(&self) -> Vec<&GridItemBox> {
+        self.children
+            .iter()
+            .filter_map(|child| match &**child {
+                GridLevelBox::GridItem(item) => Some(item),
+                GridLevelBox::OutOfFlowAbsolutelyPositionedBox(_) => None,
+            })
+            .collect()
+    }
+
+    /// `style` is that of the grid container
+    pub(super) fn inline_content_sizes(&self, style: &ComputedValues) -> ContentSizes {
+        self.content_sizes.get_or_compute(|| {
+            let mode = style.writing_mode();
+            let zero = Length::zero();
+            let items = self.items();
+            let gap = Vec2 {
+                inline: style.align.column_gap.percentage_relative_to(zero),
+                block: style.align.row_gap.percentage_relative_to(zero),
+            };
+            let grid = placement::place(
+                style,
+                &items,
+                Vec2 {
+                    inline: None,
+                    block: None,
+                },
+                &gap,
+            );
+            let sizing_items = column_sizing_items(&items, &grid.areas, mode);
+            let size = |available| {
+                let sizes =
+                    tracks::size_tracks(&grid.columns, &sizing_items, gap.inline, available, false);
+                TrackPositions::new(&sizes, &grid.columns.collapsed, zero, gap.inline).size()
+            };
+            ContentSizes {
+                min_content: size(AvailableSpace::MinContent),
+                max_content: size(AvailableSpace::MaxContent),
+            }
+        })
+    }
+
+    /// https://drafts.csswg.org/css-grid-1/#layout-algorithm
+    pub(super) fn layout<'a>(
+        &'a self,
+        containing_block: &ContainingBlock,
+        tree_rank: usize,
+        absolutely_positioned_fragments: &mut Vec<AbsolutelyPositionedFragment<'a>>,
+    ) -> FlowChildren {
+        let style = containing_block.style;
+        let mode = containing_block.mode;
+        let cbis = containing_block.inline_size;
+        let zero = Length::zero();
+        let definite_block_size = match containing_block.block_size {
+            LengthOrAuto::Length(size) => Some(size),
+            LengthOrAuto::Auto => None,
+        };
+        let gap = Vec2 {
+            inline: style.align.column_gap.percentage_relative_to(cbis),
+            block: style
+                .align
+                .row_gap
+                .percentage_relative_to(containing_block.block_size.auto_is(Length::zero)),
+        };
+        let boxes = self.items();
+        let grid = placement::place(
+            style,
+            &boxes,
+            Vec2 {
+                inline: Some(cbis),
+                block: definite_block_size,
+            },
+            &gap,
+        );
+        for child in &self.children {
+            if let GridLevelBox::OutOfFlowAbsolutelyPositionedBox(box_) = &**child {
+                // FIXME: use the grid area as the containing block and static position
+                absolutely_positioned_fragments.push(box_.layout(Vec2::zero(), tree_rank, mode))
+            }
+        }
+
+        // Size columns, then lay out items in their columns to find their block size
+        let justify_content = style.align.justify_content;
+        let columns = tracks::size_tracks(
+            &grid.columns,
+            &column_sizing_items(&boxes, &grid.areas, mode),
+            gap.inline,
+            AvailableSpace::Definite(cbis),
+            stretches_tracks(justify_content),
+        );
+        let column_positions = TrackPositions::distributed(
+            &columns,
+            &grid.columns.collapsed,
+            gap.inline,
+            justify_content,
+            cbis,
+        );
+        let items: Vec<_> = boxes
+            .par_iter()
+            .zip(&grid.areas)
+            .map(|(box_, area)| {
+                let area_inline_size = column_positions.span_size(&area.columns);
+                GridItem::new(
+                    box_,
+                    area.clone(),
+                    area_inline_size,
+                    containing_block,
+                    tree_rank,
+                )
+            })
+            .collect();
+
+        let align_content = style.align.align_content;
+        let row_sizing_items: Vec<_> = items
+            .iter()
+            .map(|item| {
+                let outer = item.layout.block_size
+                    + item.pb.block_sum()
+                    + item.margin.auto_is(Length::zero).block_sum();
+                TrackSizingItem {
+                    tracks: item.area.rows.clone(),
+                    contribution: ContentSizes {
+                        min_content: outer,
+                        max_content: outer,
+                    },
+                }
+            })
+            .collect();
+        let rows = tracks::size_tracks(
+            &grid.rows,
+            &row_sizing_items,
+            gap.block,
+            definite_block_size.map_or(AvailableSpace::MaxContent, AvailableSpace::Definite),
+            stretches_tracks(align_content),
+        );
+        let block_size = definite_block_size.unwrap_or_else(|| {
+            TrackPositions::new(&rows, &grid.rows.collapsed, zero, gap.block).size()
+        });
+        let row_positions = TrackPositions::distributed(
+            &rows,
+            &grid.rows.collapsed,
+            gap.block,
+            align_content,
+            block_size,
+        );
+
+        // Align items in their grid area, then fragments
+        // https://drafts.csswg.org/css-grid-1/#alignment
+        let fragments: Vec<_> = items
+            .into_par_iter()
+            .map(|mut item| {
+                let area_block_size = row_positions.span_size(&item.area.rows);
+                item.stretch_or_resolve_block_size(area_block_size, tree_rank);
+                let area_start = Vec2 {
+                    inline: column_positions.0[item.area.columns.start].0,
+                    block: row_positions.0[item.area.rows.start].0,
+                };
+                let border_box_size = Vec2 {
+                    inline: item.inline_size + item.pb.inline_sum(),
+                    block: item.layout.block_size + item.pb.block_sum(),
+                };
+                let (inline_start, inline_end) = align_in_area(
+                    item.justify_self,
+                    item.margin.inline_start,
+                    item.margin.inline_end,
+                    column_positions.span_size(&item.area.columns) - border_box_size.inline,
+                );
+                let (block_start, block_end) = align_in_area(
+                    item.align_self,
+                    item.margin.block_start,
+                    item.margin.block_end,
+                    area_block_size - border_box_size.block,
+                );
+                let margin = Sides {
+                    inline_start,
+                    inline_end,
+                    block_start,
+                    block_end,
+                };
+                let start_corner = Vec2 {
+                    inline: area_start.inline + margin.inline_start + item.pb.inline_start,
+                    block: area_start.block + margin.block_start + item.pb.block_start,
+                };
+                let style = &item.box_.style;
+                let mut fragment = BoxFragment {
+                    style: style.clone(),
+                    children: item.layout.flow_children.fragments,
+                    content_rect: Rect {
+                        start_corner: &start_corner
+                            + &relative_adjustement(style, cbis, containing_block.block_size, mode),
+                        size: Vec2 {
+                            inline: item.inline_size,
+                            block: item.layout.block_size,
+                        },
+                    },
+                    padding: item.padding,
+                    border: item.border,
+                    margin,
+                    block_margins_collapsed_with_children: CollapsedBlockMargins::zero(),
+                    last_baseline: item.layout.flow_children.last_baseline,
+                };
+                let mut nested_absolutely_positioned_fragments = Vec::new();
+                place_absolutely_positioned_fragments(
+                    &mut fragment,
+                    item.layout.absolutely_positioned_fragments,
+                    start_corner,
+                    mode,
+                    cbis,
+                    &mut nested_absolutely_positioned_fragments,
+                );
+                (item.area, fragment, nested_absolutely_positioned_fragments)
+            })
+            .collect();
+
+        // “If any of the grid items whose areas intersect the grid container’s first row
+        //  participate in baseline alignment, […] Otherwise, if the grid container has
+        //  at least one grid item whose area intersects the first row, the alignment
+        //  baseline is the baseline of the first such grid item in grid order.”
+        // FIXME: baseline alignment of grid items
+        // https://drafts.csswg.org/css-grid-1/#grid-baselines
+        let last_baseline = fragments
+            .iter()
+            .filter(|(area, ..)| area.rows.start == 0)
+            .min_by_key(|(area, ..)| area.columns.start)
+            .map(|(_, fragment, _)| {
+                fragment.content_rect.start_corner.block
+                    + fragment.last_baseline.unwrap_or_else(|| {
+                        fragment.content_rect.size.block
+                            + fragment.padding.block_end
+                            + fragment.border.block_end
+                    })
+            });
+        let fragments = fragments
+            .into_iter()
+            .map(|(_, fragment, nested)| {
+                absolutely_positioned_fragments.extend(nested);
+                Fragment::Box(fragment)
+            })
+            .collect();
+        FlowChildren {
+            fragments,
+            block_size,
+            collapsible_margins_in_children: CollapsedBlockMargins::zero(),
+            last_baseline,
+        }
+    }
+}
+
+impl<'a> GridItem<'a> {
+    /// Determines the inline size of a grid item in its grid area, and lays it out
+    /// to find its block size
+    fn new(
+        box_: &'a GridItemBox,
+        area: Area,
+        area_inline_size: Length,
+        containing_block: &ContainingBlock,
+        tree_rank: usize,
+    ) -> Self {
+        let style = &box_.style;
+        let mode = containing_block.mode;
+        // Percentages of the grid area
+        let padding = style
+            .padding(mode)
+            .percentages_relative_to(area_inline_size);
+        let border = style
+            .border_width(mode)
+            .percentages_relative_to(area_inline_size);
+        let margin = style.margin(mode).percentages_relative_to(area_inline_size);
+        let pb = &padding + &border;
+        let container_style = containing_block.style;
+        let justify_self = style
+            .align
+            .justify_self
+            .or_items(container_style.align.justify_items);
+        let align_self = style
+            .align
+            .align_self
+            .or_items(container_style.align.align_items);
+        let available =
+            area_inline_size - pb.inline_sum() - margin.auto_is(Length::zero).inline_sum();
+        let content_sizes = || box_.contents.inline_content_sizes(style);
+        let inline_size = sizing::inline_size(
+            style.box_size(mode).inline,
+            area_inline_size,
+            available,
+            content_sizes,
+        )
+        .auto_is(|| {
+            if is_stretched(justify_self, margin.inline_start, margin.inline_end) {
+                available.max(Length::zero())
+            } else {
+                content_sizes().shrink_to_fit(available)
+            }
+        });
+        // Percentages of the block size of the grid area are resolved once it is known
+        let block_size = sizing::block_size(style.box_size(mode).block, LengthOrAuto::Auto);
+        GridItem {
+            layout: layout_item(box_, inline_size, block_size, tree_rank),
+            box_,
+            area,
+            padding,
+            border,
+            pb,
+            margin,
+            justify_self,
+            align_self,
+            inline_size,
+        }
+    }
+
+    /// Lays out the item again if its block size depends on that of its grid area
+    fn stretch_or_resolve_block_size(&mut self, area_block_size: Length, tree_rank: usize) {
+        let style = &self.box_.style;
+        let block_size = match style.box_size(style.writing_mode()).block {
+            Size::Percentage(percentage) => area_block_size * percentage,
+            Size::Auto
+                if is_stretched(
+                    self.align_self,
+                    self.margin.block_start,
+                    self.margin.block_end,
+                ) =>
+            {
+                (area_block_size
+                    - self.pb.block_sum()
+                    - self.margin.auto_is(Length::zero).block_sum())
+                .max(Length::zero())
+            }
+            _ => return,
+        };
+        if block_size != self.layout.block_size {
+            self.layout = layout_item(
+                self.box_,
+                self.inline_size,
+                LengthOrAuto::Length(block_size),
+                tree_rank,
+            )
+        }
+    }
+}
+
+/// Lays out the contents of an item with a content box of the given size
+fn layout_item<'a>(
+    box_: &'a GridItemBox,
+    inline_size: Length,
+    block_size: LengthOrAuto,
+    tree_rank: usize,
+) -> ItemLayout<'a> {
+    let style = &box_.style;
+    // FIXME: orthogonal flows
+    let containing_block_for_children = ContainingBlock {
+        inline_size,
+        block_size,
+        mode: style.writing_mode(),
+        style,
+    };
+    let mut absolutely_positioned_fragments = Vec::new();
+    let flow_children = box_.contents.layout(
+        &containing_block_for_children,
+        tree_rank,
+        &mut absolutely_positioned_fragments,
+    );
+    ItemLayout {
+        block_size: block_size.auto_is(|| flow_children.block_size),
+        flow_children,
+        absolutely_positioned_fragments,
+    }
+}
+
+/// The inline content size contributions of items to the columns they span
+fn column_sizing_items(
+    items: &[&GridItemBox],
+    areas: &[Area],
+    containing_block_mode: (WritingMode, Direction),
+) -> Vec<TrackSizingItem> {
+    items
+        .par_iter()
+        .zip(areas)
+        .map(|(item, area)| TrackSizingItem {
+            tracks: area.columns.clone(),
+            contribution: sizing::outer_inline_content_sizes(
+                &item.style,
+                containing_block_mode,
+                || item.contents.inline_content_sizes(&item.style),
+            ),
+        })
+        .collect()
+}
+
+/// With `normal`, items without an automatic size or margins stretch to fill their grid area.
+///
+/// https://drafts.csswg.org/css-align-3/#justify-grid
+fn is_stretched(
+    alignment: ItemAlignment,
+    margin_start: LengthOrAuto,
+    margin_end: LengthOrAuto,
+) -> bool {
+    matches!(alignment, ItemAlignment::Normal | ItemAlignment::Stretch)
+        && margin_start != LengthOrAuto::Auto
+        && margin_end != LengthOrAuto::Auto
+}
+
+/// Whether `auto` tracks are stretched to fill the grid container
+///
+/// https://drafts.csswg.org/css-grid-1/#algo-stretch
+fn stretches_tracks(distribution: ContentDistribution) -> bool {
+    matches!(
+        distribution,
+        ContentDistribution::Normal | ContentDistribution::Stretch
+    )
+}
+
+/// The used start and end margins of an item in one axis of its grid area,
+/// given the space left by its border box and its margins that are not `auto`.
+///
+/// https://drafts.csswg.org/css-align-3/#justify-self-property
+/// https://drafts.csswg.org/css-grid-1/#auto-margins
+fn align_in_area(
+    alignment: ItemAlignment,
+    margin_start: LengthOrAuto,
+    margin_end: LengthOrAuto,
+    space: Length,
+) -> (Length, Length) {
+    let zero = Length::zero();
+    let free_space = space - margin_start.auto_is(Length::zero) - margin_end.auto_is(Length::zero);
+    match (margin_start, margin_end) {
+        (LengthOrAuto::Auto, LengthOrAuto::Auto) => {
+            let margin = free_space.max(zero) / 2.;
+            (margin, margin)
+        }
+        (LengthOrAuto::Auto, LengthOrAuto::Length(end)) => (free_space.max(zero), end),
+        (LengthOrAuto::Length(start), LengthOrAuto::Auto) => (start, free_space.max(zero)),
+        (LengthOrAuto::Length(start), LengthOrAuto::Length(end)) => {
+            let offset = match alignment {
+                // FIXME: baseline alignment, and the writing mode of the item for `self-*`
+                ItemAlignment::Normal
+                | ItemAlignment::Stretch
+                | ItemAlignment::Start
+                | ItemAlignment::FlexStart
+                | ItemAlignment::SelfStart
+                | ItemAlignment::Baseline => zero,
+                ItemAlignment::End | ItemAlignment::FlexEnd | ItemAlignment::SelfEnd => free_space,
+                ItemAlignment::Center => free_space / 2.,
+            };
+            (start + offset, end)
+        }
+    }
+}
+
+impl TrackPositions {
+    /// Tracks placed one after the other, from `start`, with `gap` between them
+    fn new(sizes: &[Length], collapsed: &[bool], start: Length, gap: Length) -> Self {
+        let mut position = start;
+        let mut previous_visible = false;
+        TrackPositions(
+            sizes
+                .iter()
+                .zip(collapsed)
+                .map(|(&size, &collapsed)| {
+                    // Gaps around collapsed tracks collapse too
+                    if !collapsed {
+                        if previous_visible {
+                            position += gap
+                        }
+                        previous_visible = true
+                    }
+                    let track = (position, position + size);
+                    position += size;
+                    track
+                })
+                .collect(),
+        )
+    }
+
+    /// Tracks aligned with `justify-content` or `align-content`
+    /// in a grid container of the given content size
+    ///
+    /// https://drafts.csswg.org/css-grid-1/#grid-align
+    fn distributed(
+        sizes: &[Length],
+        collapsed: &[bool],
+        gap: Length,
+        distribution: ContentDistribution,
+        container_size: Length,
+    ) -> Self {
+        let zero = Length::zero();
+        let free_space = container_size - Self::new(sizes, collapsed, zero, gap).size();
+        let visible = collapsed.iter().filter(|&&collapsed| !collapsed).count();
+        let (start, between) = distribute(distribution, free_space, visible, false);
+        Self::new(sizes, collapsed, start, gap + between)
+    }
+
+    /// From the start of the first track to the end of the last
+    fn size(&self) -> Length {
+        match (self.0.first(), self.0.last()) {
+            (Some(first), Some(last)) => last.1 - first.0,
+            _ => Length::zero(),
+        }
+    }
+
+    /// The size of the grid area that spans these tracks
+    fn span_size(&self, tracks: &Range<usize>) -> Length {
+        self.0[tracks.end - 1].1 - self.0[tracks.start].0
+    }
+}
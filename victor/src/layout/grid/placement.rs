@@ -0,0 +1,524 @@
+//! Placing grid items in the implicit grid
+//!
+//! https://drafts.csswg.org/css-grid-1/#placement
+
+use super::*;
+
+/// The grid after placing items
+pub(super) struct ImplicitGrid {
+    pub rows: GridTracks,
+    pub columns: GridTracks,
+    /// Of each grid item, in order-modified document order
+    pub areas: Vec<Area>,
+}
+
+/// The tracks of the implicit grid in one axis
+pub(super) struct GridTracks {
+    pub sizes: Vec<TrackSize>,
+    /// Whether each track is an empty repeated track of `auto-fit`, which collapses
+    pub collapsed: Vec<bool>,
+}
+
+/// The tracks that a grid area covers, numbered from the start of the implicit grid
+#[derive(Clone, Debug)]
+pub(super) struct Area {
+    pub rows: Range<usize>,
+    pub columns: Range<usize>,
+}
+
+/// The explicit grid in one axis
+///
+/// https://drafts.csswg.org/css-grid-1/#explicit-grids
+struct ExplicitTracks<'a> {
+    /// Including the implicitly-assigned names of named areas
+    line_names: Vec<Vec<Arc<str>>>,
+    sizes: Vec<TrackSize>,
+    /// Repeated tracks of `auto-fit`
+    auto_fit: Range<usize>,
+    /// https://drafts.csswg.org/css-grid-1/#auto-tracks
+    auto_sizes: &'a [TrackSize],
+}
+
+/// The position of an item in one axis, with lines numbered from the start of the explicit grid
+#[derive(Clone)]
+enum AxisPlacement {
+    Definite(Range<i32>),
+    /// The number of tracks that an automatically-placed item spans
+    Auto(usize),
+}
+
+/// Lines further than this from the start of the explicit grid are clamped,
+/// and spans are limited to as many tracks.
+///
+/// https://drafts.csswg.org/css-grid-1/#overlarge-grids
+const MAX_LINE: i32 = 10_000;
+
+/// The tracks of an area in the major and minor axes of auto-placement
+type AxesArea = (Range<usize>, Range<usize>);
+
+/// Which cells of the grid are occupied, by rows in the major axis of auto-placement
+#[derive(Default)]
+struct Occupancy(Vec<Vec<bool>>);
+
+pub(super) fn place(
+    style: &ComputedValues,
+    items: &[&GridItemBox],
+    size: Vec2<Option<Length>>,
+    gap: &Vec2<Length>,
+) -> ImplicitGrid {
+    let grid = &style.grid;
+    let areas = grid.grid_template_areas.0.as_ref();
+    let rows = ExplicitTracks::new(
+        &grid.grid_template_rows,
+        &grid.grid_auto_rows,
+        areas.map_or(0, |areas| areas.rows),
+        areas.map_or(&[][..], |areas| &areas.areas),
+        |area| &area.rows,
+        size.block,
+        gap.block,
+    );
+    let columns = ExplicitTracks::new(
+        &grid.grid_template_columns,
+        &grid.grid_auto_columns,
+        areas.map_or(0, |areas| areas.columns),
+        areas.map_or(&[][..], |areas| &areas.areas),
+        |area| &area.columns,
+        size.inline,
+        gap.inline,
+    );
+    let placements = items.iter().map(|item| {
+        let item = &item.style.grid;
+        (
+            rows.resolve(&item.grid_row_start, &item.grid_row_end),
+            columns.resolve(&item.grid_column_start, &item.grid_column_end),
+        )
+    });
+
+    // Auto-placement fills rows with `grid-auto-flow: row`, columns with `column`.
+    // The major axis is the one in which the grid grows to fit items.
+    let column_flow = grid.grid_auto_flow.column;
+    let (major, minor) = if column_flow {
+        (&columns, &rows)
+    } else {
+        (&rows, &columns)
+    };
+    let placements: Vec<_> = placements
+        .map(|(row, column)| {
+            if column_flow {
+                (column, row)
+            } else {
+                (row, column)
+            }
+        })
+        .collect();
+    let (areas, major_offset, minor_offset) =
+        auto_place(&placements, minor, grid.grid_auto_flow.dense);
+    let major_tracks = major.implicit_tracks(&areas, |area| &area.0, major_offset);
+    let minor_tracks = minor.implicit_tracks(&areas, |area| &area.1, minor_offset);
+    let (rows, columns) = if column_flow {
+        (minor_tracks, major_tracks)
+    } else {
+        (major_tracks, minor_tracks)
+    };
+    ImplicitGrid {
+        rows,
+        columns,
+        areas: areas
+            .into_iter()
+            .map(|(major, minor)| {
+                if column_flow {
+                    Area {
+                        rows: minor,
+                        columns: major,
+                    }
+                } else {
+                    Area {
+                        rows: major,
+                        columns: minor,
+                    }
+                }
+            })
+            .collect(),
+    }
+}
+
+/// Places items given their positions in the major and minor axes.
+/// Returns their areas, and the number of implicit tracks before the explicit grid
+/// in the major and minor axes.
+///
+/// https://drafts.csswg.org/css-grid-1/#auto-placement-algo
+fn auto_place(
+    placements: &[(AxisPlacement, AxisPlacement)],
+    minor: &ExplicitTracks,
+    dense: bool,
+) -> (Vec<AxesArea>, usize, usize) {
+    let definite_starts = |axis: fn(&(AxisPlacement, AxisPlacement)) -> &AxisPlacement| {
+        placements
+            .iter()
+            .filter_map(move |placement| match axis(placement) {
+                AxisPlacement::Definite(lines) => Some(lines.clone()),
+                AxisPlacement::Auto(_) => None,
+            })
+    };
+    let major_offset = definite_starts(|p| &p.0).fold(0, |offset, lines| offset.max(-lines.start));
+    let minor_offset = definite_starts(|p| &p.1).fold(0, |offset, lines| offset.max(-lines.start));
+    let to_tracks = |lines: &Range<i32>, offset: i32| {
+        (lines.start + offset) as usize..(lines.end + offset) as usize
+    };
+
+    // The minor axis fits items with a definite position there,
+    // and the largest span of the others
+    let mut minor_count = placements
+        .iter()
+        .map(|(_, minor)| match minor {
+            AxisPlacement::Definite(lines) => to_tracks(lines, minor_offset).end,
+            AxisPlacement::Auto(span) => *span,
+        })
+        .fold(minor.sizes.len() + minor_offset as usize, usize::max);
+
+    let mut areas = vec![None; placements.len()];
+    let mut occupancy = Occupancy::default();
+    // Items with a definite position in both axes
+    for (index, placement) in placements.iter().enumerate() {
+        if let (AxisPlacement::Definite(major), AxisPlacement::Definite(minor)) = placement {
+            let area = (
+                to_tracks(major, major_offset),
+                to_tracks(minor, minor_offset),
+            );
+            occupancy.occupy(&area);
+            areas[index] = Some(area)
+        }
+    }
+    // Items locked to a given track in the major axis
+    let mut cursors = Vec::new();
+    for (index, placement) in placements.iter().enumerate() {
+        if let (AxisPlacement::Definite(major), AxisPlacement::Auto(span)) = placement {
+            let major = to_tracks(major, major_offset);
+            if cursors.len() <= major.start {
+                cursors.resize(major.start + 1, 0)
+            }
+            let mut minor = if dense { 0 } else { cursors[major.start] };
+            while !occupancy.is_free(&major, &(minor..minor + span)) {
+                minor += 1
+            }
+            let area = (major, minor..minor + span);
+            cursors[area.0.start] = area.1.end;
+            minor_count = minor_count.max(area.1.end);
+            occupancy.occupy(&area);
+            areas[index] = Some(area)
+        }
+    }
+    // Other items
+    let (mut cursor_major, mut cursor_minor) = (0, 0);
+    for (index, placement) in placements.iter().enumerate() {
+        let major_span = match placement {
+            (AxisPlacement::Auto(span), _) => *span,
+            (AxisPlacement::Definite(_), _) => continue,
+        };
+        if dense {
+            cursor_major = 0;
+            cursor_minor = 0;
+        }
+        let minor = match &placement.1 {
+            AxisPlacement::Definite(minor) => {
+                let minor = to_tracks(minor, minor_offset);
+                if cursor_minor > minor.start {
+                    cursor_major += 1
+                }
+                cursor_minor = minor.start;
+                while !occupancy.is_free(&(cursor_major..cursor_major + major_span), &minor) {
+                    cursor_major += 1
+                }
+                minor
+            }
+            AxisPlacement::Auto(span) => loop {
+                if cursor_minor + span > minor_count {
+                    cursor_major += 1;
+                    cursor_minor = 0;
+                    continue;
+                }
+                let minor = cursor_minor..cursor_minor + span;
+                if occupancy.is_free(&(cursor_major..cursor_major + major_span), &minor) {
+                    break minor;
+                }
+                cursor_minor += 1
+            },
+        };
+        let area = (cursor_major..cursor_major + major_span, minor);
+        occupancy.occupy(&area);
+        areas[index] = Some(area)
+    }
+    let areas = areas.into_iter().map(Option::unwrap).collect();
+    (areas, major_offset as usize, minor_offset as usize)
+}
+
+impl Occupancy {
+    fn is_free(&self, major: &Range<usize>, minor: &Range<usize>) -> bool {
+        major.clone().all(|index| {
+            let row = self.0.get(index).map_or(&[][..], |row| &row[..]);
+            minor
+                .clone()
+                .all(|cell| !row.get(cell).cloned().unwrap_or(false))
+        })
+    }
+
+    fn occupy(&mut self, (major, minor): &AxesArea) {
+        if self.0.len() < major.end {
+            self.0.resize(major.end, Vec::new())
+        }
+        for row in &mut self.0[major.clone()] {
+            if row.len() < minor.end {
+                row.resize(minor.end, false)
+            }
+            for cell in &mut row[minor.clone()] {
+                *cell = true
+            }
+        }
+    }
+}
+
+impl<'a> ExplicitTracks<'a> {
+    fn new(
+        template: &GridTemplate,
+        auto_tracks: &'a GridAutoTracks,
+        area_track_count: usize,
+        areas: &[NamedArea],
+        area_tracks: impl Fn(&NamedArea) -> &Range<usize>,
+        available: Option<Length>,
+        gap: Length,
+    ) -> Self {
+        let (tracks, auto_fit) = match &template.0 {
+            None => (
+                Tracks {
+                    line_names: vec![Vec::new()],
+                    sizes: Vec::new(),
+                },
+                0..0,
+            ),
+            Some(list) => {
+                let repetitions = repetitions(list, available, gap);
+                let auto_fit = match &list.auto_repeat {
+                    Some((AutoRepeat::Fit, repeated)) => {
+                        let start = list.before.sizes.len();
+                        start..start + repetitions * repeated.sizes.len()
+                    }
+                    _ => 0..0,
+                };
+                (list.expand(repetitions), auto_fit)
+            }
+        };
+        let Tracks {
+            mut line_names,
+            mut sizes,
+        } = tracks;
+        // Tracks implied by `grid-template-areas` are sized like implicit tracks
+        let auto_sizes = &auto_tracks.0[..];
+        let template_count = sizes.len();
+        while sizes.len() < area_track_count {
+            sizes.push(auto_sizes[(sizes.len() - template_count) % auto_sizes.len()])
+        }
+        line_names.resize(sizes.len() + 1, Vec::new());
+        // https://drafts.csswg.org/css-grid-1/#implicit-named-lines
+        for area in areas {
+            let tracks = area_tracks(area);
+            line_names[tracks.start].push(format!("{}-start", area.name).into());
+            line_names[tracks.end].push(format!("{}-end", area.name).into());
+        }
+        ExplicitTracks {
+            line_names,
+            sizes,
+            auto_fit,
+            auto_sizes,
+        }
+    }
+
+    fn count(&self) -> i32 {
+        self.sizes.len() as i32
+    }
+
+    /// Explicit lines with this name, in ascending order
+    fn named_lines<'b>(&'b self, name: &'b str) -> impl DoubleEndedIterator<Item = i32> + 'b {
+        self.line_names
+            .iter()
+            .enumerate()
+            .filter(move |(_, names)| names.iter().any(|n| &**n == name))
+            .map(|(index, _)| index as i32)
+    }
+
+    /// The `n`th line, or the `n`th line with this name, counting from the end if negative.
+    /// If there are not enough such lines, all implicit lines are assumed to have the name.
+    ///
+    /// https://drafts.csswg.org/css-grid-1/#line-placement
+    fn line(&self, n: i32, name: Option<&str>) -> i32 {
+        let count = self.count();
+        match name {
+            None if n > 0 => n - 1,
+            None => n.saturating_add(count + 1),
+            Some(name) if n > 0 => {
+                let lines: Vec<_> = self.named_lines(name).collect();
+                match lines.get(n as usize - 1) {
+                    Some(&line) => line,
+                    None => n.saturating_add(count - lines.len() as i32),
+                }
+            }
+            Some(name) => {
+                let lines: Vec<_> = self.named_lines(name).rev().collect();
+                match lines.get(n.unsigned_abs() as usize - 1) {
+                    Some(&line) => line,
+                    None => n + lines.len() as i32,
+                }
+            }
+        }
+    }
+
+    /// The line `n` lines or `n` lines with this name away from `from`,
+    /// searching towards the end if `forward`
+    fn span_from(&self, from: i32, n: u32, name: Option<&str>, forward: bool) -> i32 {
+        let n = n as i32;
+        let name = match name {
+            Some(name) => name,
+            None if forward => return from.saturating_add(n),
+            None => return from.saturating_sub(n),
+        };
+        let lines: Vec<_> = if forward {
+            self.named_lines(name).filter(|&line| line > from).collect()
+        } else {
+            self.named_lines(name)
+                .rev()
+                .filter(|&line| line < from)
+                .collect()
+        };
+        match lines.get(n as usize - 1) {
+            Some(&line) => line,
+            None if forward => from
+                .max(self.count())
+                .saturating_add(n - lines.len() as i32),
+            None => from.min(0).saturating_sub(n - lines.len() as i32),
+        }
+    }
+
+    /// The line of a `grid-*-start` or `grid-*-end` value, if it is not a span
+    fn definite_line(&self, line: &GridLine, side: &str) -> Option<i32> {
+        match line {
+            GridLine::Auto | GridLine::Span(..) => None,
+            GridLine::Line(n, name) => Some(self.line(*n, name.as_deref())),
+            // The edge of the named area, or else the first line with this name
+            GridLine::Ident(name) => Some(
+                self.named_lines(&format!("{}-{}", name, side))
+                    .next()
+                    .unwrap_or_else(|| self.line(1, Some(name))),
+            ),
+        }
+    }
+
+    /// https://drafts.csswg.org/css-grid-1/#line-placement
+    fn resolve(&self, start: &GridLine, end: &GridLine) -> AxisPlacement {
+        let lines = match (
+            self.definite_line(start, "start"),
+            self.definite_line(end, "end"),
+        ) {
+            (Some(start), Some(end)) if start == end => start..start.saturating_add(1),
+            (Some(start), Some(end)) => start.min(end)..start.max(end),
+            (Some(start), None) => match end {
+                GridLine::Span(n, name) => start..self.span_from(start, *n, name.as_deref(), true),
+                _ => start..start.saturating_add(1),
+            },
+            (None, Some(end)) => match start {
+                GridLine::Span(n, name) => self.span_from(end, *n, name.as_deref(), false)..end,
+                _ => end.saturating_sub(1)..end,
+            },
+            // A span with a name counts as one track for auto-placement
+            (None, None) => {
+                let span = match (start, end) {
+                    (GridLine::Span(n, None), _) => *n,
+                    (GridLine::Span(_, Some(_)), _) => 1,
+                    (_, GridLine::Span(n, None)) => *n,
+                    _ => 1,
+                };
+                return AxisPlacement::Auto(span.min(MAX_LINE as u32) as usize);
+            }
+        };
+        // An area outside of the limited grid is truncated to its last track on that side
+        let clamp = |line: i32| line.clamp(-MAX_LINE, MAX_LINE);
+        let lines = match (clamp(lines.start), clamp(lines.end)) {
+            (MAX_LINE, MAX_LINE) => MAX_LINE - 1..MAX_LINE,
+            (start, end) if start == end => start..start + 1,
+            (start, end) => start..end,
+        };
+        AxisPlacement::Definite(lines)
+    }
+
+    /// The tracks of the implicit grid, given the areas of items
+    /// and the number of implicit tracks before the explicit grid
+    fn implicit_tracks<A>(
+        &self,
+        areas: &[A],
+        axis: impl Fn(&A) -> &Range<usize>,
+        offset: usize,
+    ) -> GridTracks {
+        let count = areas
+            .iter()
+            .map(|area| axis(area).end)
+            .fold(self.sizes.len() + offset, usize::max);
+        let auto_count = self.auto_sizes.len();
+        let sizes = (0..count)
+            .map(|index| {
+                if index < offset {
+                    // Implicit tracks before the explicit grid repeat the pattern backwards
+                    self.auto_sizes[auto_count - 1 - (offset - 1 - index) % auto_count]
+                } else if index - offset < self.sizes.len() {
+                    self.sizes[index - offset]
+                } else {
+                    self.auto_sizes[(index - offset - self.sizes.len()) % auto_count]
+                }
+            })
+            .collect();
+        let collapsed = (0..count)
+            .map(|index| {
+                index >= offset
+                    && self.auto_fit.contains(&(index - offset))
+                    && !areas.iter().any(|area| axis(area).contains(&index))
+            })
+            .collect();
+        GridTracks { sizes, collapsed }
+    }
+}
+
+/// The number of repetitions of `auto-fill` or `auto-fit`,
+/// as many as fit in the available space
+///
+/// https://drafts.csswg.org/css-grid-1/#auto-repeat
+fn repetitions(list: &TrackList, available: Option<Length>, gap: Length) -> usize {
+    let repeated = match &list.auto_repeat {
+        Some((_, repeated)) => repeated,
+        None => return 0,
+    };
+    let available = match available {
+        Some(available) => available,
+        None => return 1,
+    };
+    // Each track is treated as its maximum track sizing function if that is definite,
+    // or else as its minimum one
+    let fixed = |size: &TrackSize| {
+        tracks::fixed_breadth(size.max, available)
+            .or_else(|| tracks::fixed_breadth(size.min, available))
+    };
+    let mut repeated_size = Length::zero();
+    for size in &repeated.sizes {
+        match fixed(size) {
+            Some(length) => repeated_size += length,
+            None => return 1,
+        }
+    }
+    let others = list.before.sizes.iter().chain(&list.after.sizes);
+    let other_count = others.clone().count();
+    let other_size = others.filter_map(fixed).fold(Length::zero(), |a, b| a + b);
+    let per_repetition = repeated_size + gap * repeated.sizes.len() as f32;
+    if per_repetition <= Length::zero() {
+        return 1;
+    }
+    // n repetitions take `other + n * repeated + gap * (other_count + n * repeated_count - 1)`
+    let space = available - other_size - gap * (other_count as f32 - 1.);
+    // Avoid creating an unreasonable number of tracks
+    ((space.px / per_repetition.px).floor().max(1.) as usize).min(10_000)
+}
@@ -0,0 +1,381 @@
+//! The grid track sizing algorithm, for the tracks of one axis
+//!
+//! https://drafts.csswg.org/css-grid-1/#algo-track-sizing
+
+use super::*;
+
+/// https://drafts.csswg.org/css-sizing-3/#available
+#[derive(Clone, Copy)]
+pub(super) enum AvailableSpace {
+    Definite(Length),
+    MinContent,
+    MaxContent,
+}
+
+/// An item, with the tracks it spans and its outer content size contributions in their axis
+pub(super) struct TrackSizingItem {
+    pub tracks: Range<usize>,
+    pub contribution: ContentSizes,
+}
+
+/// A track sizing function with percentages resolved
+#[derive(Clone, Copy)]
+enum Breadth {
+    Fixed(Length),
+    Flex(f32),
+    MinContent,
+    MaxContent,
+    Auto,
+}
+
+struct Track {
+    min: Breadth,
+    max: Breadth,
+    base_size: Length,
+    /// `None` is infinite
+    growth_limit: Option<Length>,
+}
+
+/// A length or percentage breadth, resolved against this size
+pub(super) fn fixed_breadth(breadth: TrackBreadth, percentage_basis: Length) -> Option<Length> {
+    match breadth {
+        TrackBreadth::Length(length) => Some(length),
+        TrackBreadth::Percentage(percentage) => Some(percentage_basis * percentage),
+        _ => None,
+    }
+}
+
+/// The used size of each track
+///
+/// `stretch` is whether tracks with an `auto` maximum are stretched to fill the available space,
+/// with `normal` or `stretch` content distribution.
+pub(super) fn size_tracks(
+    grid_tracks: &GridTracks,
+    items: &[TrackSizingItem],
+    gap: Length,
+    available: AvailableSpace,
+    stretch: bool,
+) -> Vec<Length> {
+    let zero = Length::zero();
+    // Percentages behave as `auto` when the size of the grid container is indefinite
+    let percentage_basis = match available {
+        AvailableSpace::Definite(size) => Some(size),
+        AvailableSpace::MinContent | AvailableSpace::MaxContent => None,
+    };
+    let resolve = |breadth: TrackBreadth| match breadth {
+        TrackBreadth::Flex(Fr(factor)) => Breadth::Flex(factor),
+        TrackBreadth::MinContent => Breadth::MinContent,
+        TrackBreadth::MaxContent => Breadth::MaxContent,
+        TrackBreadth::Auto => Breadth::Auto,
+        TrackBreadth::Length(length) => Breadth::Fixed(length),
+        TrackBreadth::Percentage(percentage) => {
+            percentage_basis.map_or(Breadth::Auto, |basis| Breadth::Fixed(basis * percentage))
+        }
+    };
+
+    // Initialize each track’s base size and growth limit
+    // https://drafts.csswg.org/css-grid-1/#algo-init
+    let collapsed = &grid_tracks.collapsed;
+    let mut tracks: Vec<_> = grid_tracks
+        .sizes
+        .iter()
+        .zip(collapsed)
+        .map(|(size, &collapsed)| {
+            let (min, max) = if collapsed {
+                (Breadth::Fixed(zero), Breadth::Fixed(zero))
+            } else {
+                // A flexible minimum is not valid
+                (resolve(size.min), resolve(size.max))
+            };
+            let base_size = match min {
+                Breadth::Fixed(length) => length,
+                _ => zero,
+            };
+            let growth_limit = match max {
+                Breadth::Fixed(length) => Some(length.max(base_size)),
+                _ => None,
+            };
+            Track {
+                min,
+                max,
+                base_size,
+                growth_limit,
+            }
+        })
+        .collect();
+    // Collapsed tracks have no gaps around them
+    let gaps = |range: &Range<usize>| {
+        let visible = range.clone().filter(|&index| !collapsed[index]).count();
+        gap * visible.saturating_sub(1) as f32
+    };
+    let all = 0..tracks.len();
+
+    // Resolve intrinsic track sizes
+    // https://drafts.csswg.org/css-grid-1/#algo-content
+    let under_max_content = matches!(available, AvailableSpace::MaxContent);
+    let min_contribution = |min: Breadth, contribution: &ContentSizes| match min {
+        Breadth::MinContent => contribution.min_content,
+        Breadth::MaxContent => contribution.max_content,
+        Breadth::Auto if under_max_content => contribution.max_content,
+        // The minimum contribution, approximated by the min-content contribution
+        Breadth::Auto => contribution.min_content,
+        Breadth::Fixed(_) | Breadth::Flex(_) => zero,
+    };
+    let max_contribution = |max: Breadth, contribution: &ContentSizes| match max {
+        Breadth::MinContent => contribution.min_content,
+        Breadth::MaxContent | Breadth::Auto => contribution.max_content,
+        Breadth::Fixed(_) | Breadth::Flex(_) => zero,
+    };
+    let is_intrinsic = |breadth: Breadth| {
+        matches!(
+            breadth,
+            Breadth::MinContent | Breadth::MaxContent | Breadth::Auto
+        )
+    };
+    let crosses_flexible_track = |item: &TrackSizingItem, tracks: &[Track]| {
+        tracks[item.tracks.clone()]
+            .iter()
+            .any(|track| matches!(track.max, Breadth::Flex(_)))
+    };
+
+    // Items that span a single track
+    for item in items {
+        if item.tracks.len() != 1 || crosses_flexible_track(item, &tracks) {
+            continue;
+        }
+        let track = &mut tracks[item.tracks.start];
+        track
+            .base_size
+            .max_assign(min_contribution(track.min, &item.contribution));
+        if is_intrinsic(track.max) {
+            let contribution = max_contribution(track.max, &item.contribution);
+            track.growth_limit = Some(track.growth_limit.unwrap_or(zero).max(contribution))
+        }
+    }
+    for track in &mut tracks {
+        if let Some(limit) = &mut track.growth_limit {
+            limit.max_assign(track.base_size)
+        }
+    }
+
+    // Items that span several tracks, by increasing number of tracks.
+    // FIXME: distribute extra space to tracks with room below their growth limit first
+    // https://drafts.csswg.org/css-grid-1/#extra-space
+    let mut spanning: Vec<_> = items
+        .iter()
+        .filter(|item| item.tracks.len() > 1 && !crosses_flexible_track(item, &tracks))
+        .collect();
+    spanning.sort_by_key(|item| item.tracks.len());
+    for item in spanning {
+        let range = item.tracks.clone();
+        let spanned = || tracks[range.clone()].iter();
+        let contribution = spanned()
+            .map(|track| min_contribution(track.min, &item.contribution))
+            .fold(zero, Length::max);
+        let extra = spanned().fold(contribution - gaps(&range), |extra, track| {
+            extra - track.base_size
+        });
+        let growing: Vec<_> = range
+            .clone()
+            .filter(|&index| is_intrinsic(tracks[index].min))
+            .collect();
+        if extra > zero && !growing.is_empty() {
+            for &index in &growing {
+                tracks[index].base_size += extra / growing.len() as f32
+            }
+        }
+
+        let spanned = || tracks[range.clone()].iter();
+        let contribution = spanned()
+            .map(|track| max_contribution(track.max, &item.contribution))
+            .fold(zero, Length::max);
+        let extra = spanned().fold(contribution - gaps(&range), |extra, track| {
+            extra - track.growth_limit.unwrap_or(track.base_size)
+        });
+        let growing: Vec<_> = range
+            .filter(|&index| is_intrinsic(tracks[index].max))
+            .collect();
+        for &index in &growing {
+            let track = &mut tracks[index];
+            let mut limit = track.growth_limit.unwrap_or(track.base_size);
+            if extra > zero {
+                limit += extra / growing.len() as f32
+            }
+            track.growth_limit = Some(limit.max(track.base_size))
+        }
+    }
+
+    // Items that cross flexible tracks grow those with an intrinsic minimum,
+    // in proportion to their flex factor.
+    // Each track grows by the largest increase that an item needs.
+    let mut increases = vec![zero; tracks.len()];
+    for item in items {
+        if !crosses_flexible_track(item, &tracks) {
+            continue;
+        }
+        let range = item.tracks.clone();
+        let growing: Vec<_> = range
+            .clone()
+            .filter(|&index| {
+                let track = &tracks[index];
+                matches!(track.max, Breadth::Flex(_)) && is_intrinsic(track.min)
+            })
+            .collect();
+        let contribution = growing
+            .iter()
+            .map(|&index| min_contribution(tracks[index].min, &item.contribution))
+            .fold(zero, Length::max);
+        let extra = tracks[range.clone()]
+            .iter()
+            .fold(contribution - gaps(&range), |extra, track| {
+                extra - track.base_size
+            });
+        if extra <= zero || growing.is_empty() {
+            continue;
+        }
+        let factor_sum: f32 = growing
+            .iter()
+            .map(|&index| flex_factor(&tracks[index]))
+            .sum();
+        for &index in &growing {
+            let share = if factor_sum > 0. {
+                flex_factor(&tracks[index]) / factor_sum
+            } else {
+                1. / growing.len() as f32
+            };
+            increases[index].max_assign(extra * share)
+        }
+    }
+    for (track, increase) in tracks.iter_mut().zip(increases) {
+        track.base_size += increase;
+        let base_size = track.base_size;
+        track.growth_limit = Some(track.growth_limit.unwrap_or(base_size).max(base_size))
+    }
+    let limit = |track: &Track| track.growth_limit.unwrap_or(track.base_size);
+
+    // Maximize tracks
+    // https://drafts.csswg.org/css-grid-1/#algo-grow-tracks
+    match available {
+        AvailableSpace::Definite(size) => {
+            let mut free_space = tracks
+                .iter()
+                .fold(size - gaps(&all), |free, track| free - track.base_size);
+            loop {
+                let growable = tracks
+                    .iter()
+                    .filter(|track| track.base_size < limit(track))
+                    .count();
+                if free_space <= zero || growable == 0 {
+                    break;
+                }
+                let share = free_space / growable as f32;
+                for track in &mut tracks {
+                    let room = limit(track) - track.base_size;
+                    if room > zero {
+                        let growth = room.min(share);
+                        track.base_size += growth;
+                        free_space -= growth
+                    }
+                }
+            }
+        }
+        AvailableSpace::MaxContent => {
+            for track in &mut tracks {
+                track.base_size = limit(track)
+            }
+        }
+        AvailableSpace::MinContent => {}
+    }
+
+    // Expand flexible tracks
+    // https://drafts.csswg.org/css-grid-1/#algo-flex-tracks
+    let flex_fraction = match available {
+        AvailableSpace::MinContent => None,
+        AvailableSpace::Definite(size) => {
+            Some(find_fr_size(&tracks, all.clone(), size - gaps(&all)))
+        }
+        AvailableSpace::MaxContent => {
+            let from_tracks = tracks
+                .iter()
+                .filter(|track| matches!(track.max, Breadth::Flex(_)))
+                .map(|track| track.base_size / flex_factor(track).max(1.));
+            let from_items = items
+                .iter()
+                .filter(|item| crosses_flexible_track(item, &tracks))
+                .map(|item| {
+                    let range = item.tracks.clone();
+                    let space = item.contribution.max_content - gaps(&range);
+                    find_fr_size(&tracks, range, space)
+                });
+            Some(from_tracks.chain(from_items).fold(zero, Length::max))
+        }
+    };
+    if let Some(flex_fraction) = flex_fraction {
+        for track in &mut tracks {
+            if let Breadth::Flex(factor) = track.max {
+                track.base_size.max_assign(flex_fraction * factor)
+            }
+        }
+    }
+
+    // Stretch `auto` tracks
+    // https://drafts.csswg.org/css-grid-1/#algo-stretch
+    if let (AvailableSpace::Definite(size), true) = (available, stretch) {
+        let free_space = tracks
+            .iter()
+            .fold(size - gaps(&all), |free, track| free - track.base_size);
+        let auto = tracks
+            .iter()
+            .filter(|track| matches!(track.max, Breadth::Auto))
+            .count();
+        if free_space > zero && auto > 0 {
+            for track in &mut tracks {
+                if let Breadth::Auto = track.max {
+                    track.base_size += free_space / auto as f32
+                }
+            }
+        }
+    }
+
+    tracks.into_iter().map(|track| track.base_size).collect()
+}
+
+fn flex_factor(track: &Track) -> f32 {
+    match track.max {
+        Breadth::Flex(factor) => factor,
+        _ => 0.,
+    }
+}
+
+/// The size of `1fr` so that the given tracks fill this space,
+/// with flexible tracks that would be smaller than their base size treated as inflexible
+///
+/// https://drafts.csswg.org/css-grid-1/#algo-find-fr-size
+fn find_fr_size(tracks: &[Track], range: Range<usize>, space: Length) -> Length {
+    let mut inflexible = vec![false; tracks.len()];
+    loop {
+        let mut leftover = space;
+        let mut factor_sum = 0.;
+        for index in range.clone() {
+            let track = &tracks[index];
+            match track.max {
+                Breadth::Flex(factor) if !inflexible[index] => factor_sum += factor,
+                _ => leftover -= track.base_size,
+            }
+        }
+        let fraction = leftover / factor_sum.max(1.);
+        let mut changed = false;
+        for index in range.clone() {
+            let track = &tracks[index];
+            if let Breadth::Flex(factor) = track.max {
+                if !inflexible[index] && fraction * factor < track.base_size {
+                    inflexible[index] = true;
+                    changed = true
+                }
+            }
+        }
+        if !changed {
+            return fraction;
+        }
+    }
+}
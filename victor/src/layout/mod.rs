@@ -8,15 +8,23 @@ use std::sync::Arc;
 
 mod dom_traversal;
 mod element_data;
+mod flex;
 mod flow;
 mod fragments;
+mod grid;
 mod positioned;
 mod replaced;
+mod sizing;
+mod table;
 
 use dom_traversal::*;
+use flex::*;
 use flow::*;
+use grid::*;
 use positioned::*;
 use replaced::*;
+use sizing::{ContentSizes, ContentSizesCache};
+use table::*;
 
 pub(crate) use element_data::*;
 pub(crate) use fragments::*;
@@ -25,6 +33,9 @@ pub(crate) use fragments::*;
 #[derive(Debug)]
 enum IndependentFormattingContext {
     Flow(BlockFormattingContext),
+    Table(TableFormattingContext),
+    Flex(FlexFormattingContext),
+    Grid(GridFormattingContext),
 
     // Not called FC in specs, but behaves close enough
     Replaced(ReplacedContent),
@@ -33,12 +44,17 @@ enum IndependentFormattingContext {
 
 enum NonReplacedIFC<'a> {
     Flow(&'a BlockFormattingContext),
+    Table(&'a TableFormattingContext),
+    Flex(&'a FlexFormattingContext),
+    Grid(&'a GridFormattingContext),
 }
 
 impl IndependentFormattingContext {
+    /// For a table, `style` is replaced with that of the table wrapper box,
+    /// which is the one that participates in the parent formatting context.
     fn construct<'a>(
         context: &'a Context<'a>,
-        style: &'a Arc<ComputedValues>,
+        style: &mut Arc<ComputedValues>,
         display_inside: DisplayInside,
         contents: Contents,
     ) -> Self {
@@ -51,6 +67,21 @@ impl IndependentFormattingContext {
                         non_replaced,
                     ))
                 }
+                DisplayInside::Table => {
+                    let (wrapper_style, table_style) = style.table_wrapper_and_box();
+                    *style = wrapper_style;
+                    IndependentFormattingContext::Table(TableFormattingContext::construct(
+                        context,
+                        &table_style,
+                        non_replaced,
+                    ))
+                }
+                DisplayInside::Flex => IndependentFormattingContext::Flex(
+                    FlexFormattingContext::construct(context, style, non_replaced),
+                ),
+                DisplayInside::Grid => IndependentFormattingContext::Grid(
+                    GridFormattingContext::construct(context, style, non_replaced),
+                ),
             },
             Err(replaced) => IndependentFormattingContext::Replaced(replaced),
         }
@@ -60,6 +91,17 @@ impl IndependentFormattingContext {
         match self {
             IndependentFormattingContext::Replaced(r) => Ok(r),
             IndependentFormattingContext::Flow(f) => Err(NonReplacedIFC::Flow(f)),
+            IndependentFormattingContext::Table(t) => Err(NonReplacedIFC::Table(t)),
+            IndependentFormattingContext::Flex(f) => Err(NonReplacedIFC::Flex(f)),
+            IndependentFormattingContext::Grid(g) => Err(NonReplacedIFC::Grid(g)),
+        }
+    }
+
+    /// `style` is that of the box that establishes this formatting context
+    fn inline_content_sizes(&self, style: &ComputedValues) -> ContentSizes {
+        match self.as_replaced() {
+            Ok(replaced) => match *replaced {},
+            Err(ifc) => ifc.inline_content_sizes(style),
         }
     }
 
@@ -77,6 +119,15 @@ impl IndependentFormattingContext {
 }
 
 impl<'a> NonReplacedIFC<'a> {
+    fn inline_content_sizes(&self, style: &ComputedValues) -> ContentSizes {
+        match self {
+            NonReplacedIFC::Flow(bfc) => bfc.inline_content_sizes(style),
+            NonReplacedIFC::Table(table) => table.inline_content_sizes(),
+            NonReplacedIFC::Flex(flex) => flex.inline_content_sizes(style),
+            NonReplacedIFC::Grid(grid) => grid.inline_content_sizes(style),
+        }
+    }
+
     fn layout(
         &self,
         containing_block: &ContainingBlock,
@@ -87,14 +138,25 @@ impl<'a> NonReplacedIFC<'a> {
             NonReplacedIFC::Flow(bfc) => {
                 bfc.layout(containing_block, tree_rank, absolutely_positioned_fragments)
             }
+            NonReplacedIFC::Table(table) => {
+                table.layout(containing_block, tree_rank, absolutely_positioned_fragments)
+            }
+            NonReplacedIFC::Flex(flex) => {
+                flex.layout(containing_block, tree_rank, absolutely_positioned_fragments)
+            }
+            NonReplacedIFC::Grid(grid) => {
+                grid.layout(containing_block, tree_rank, absolutely_positioned_fragments)
+            }
         }
     }
 }
 
-struct ContainingBlock {
+struct ContainingBlock<'a> {
     inline_size: Length,
     block_size: LengthOrAuto,
     mode: (WritingMode, Direction),
+    /// Of the box that establishes this containing block
+    style: &'a ComputedValues,
 }
 
 struct DefiniteContainingBlock {
@@ -107,6 +169,7 @@ fn relative_adjustement(
     style: &ComputedValues,
     inline_size: Length,
     block_size: LengthOrAuto,
+    containing_block_mode: (WritingMode, Direction),
 ) -> Vec2<Length> {
     if !style.box_.position.is_relatively_positioned() {
         return Vec2::zero();
@@ -119,16 +182,60 @@ fn relative_adjustement(
         }
     }
     let block_size = block_size.auto_is(Length::zero);
-    let box_offsets = style.box_offsets().map_inline_and_block_axes(
-        |v| v.percentage_relative_to(inline_size),
-        |v| v.percentage_relative_to(block_size),
-    );
+    let box_offsets = style
+        .box_offsets(containing_block_mode)
+        .map_inline_and_block_axes(
+            |v| v.percentage_relative_to(inline_size),
+            |v| v.percentage_relative_to(block_size),
+        );
     Vec2 {
         inline: adjust(box_offsets.inline_start, box_offsets.inline_end),
         block: adjust(box_offsets.block_start, box_offsets.block_end),
     }
 }
 
+/// The offset of the first item, line, or track and the extra space between each,
+/// for `justify-content` or `align-content` given the free space.
+/// `reverse` is whether the flex-start side is the end side of the writing mode.
+///
+/// https://drafts.csswg.org/css-align-3/#distribution-values
+/// https://drafts.csswg.org/css-flexbox-1/#justify-content-property
+fn distribute(
+    distribution: ContentDistribution,
+    free_space: Length,
+    count: usize,
+    reverse: bool,
+) -> (Length, Length) {
+    let zero = Length::zero();
+    let count = count as f32;
+    // Distributing negative free space falls back to `flex-start` or `center`
+    match distribution {
+        ContentDistribution::Normal
+        | ContentDistribution::Stretch
+        | ContentDistribution::FlexStart => (zero, zero),
+        ContentDistribution::FlexEnd => (free_space, zero),
+        ContentDistribution::Start if reverse => (free_space, zero),
+        ContentDistribution::Start => (zero, zero),
+        ContentDistribution::End if reverse => (zero, zero),
+        ContentDistribution::End => (free_space, zero),
+        ContentDistribution::Center => (free_space / 2., zero),
+        ContentDistribution::SpaceBetween if free_space > zero && count > 1. => {
+            (zero, free_space / (count - 1.))
+        }
+        ContentDistribution::SpaceBetween => (zero, zero),
+        ContentDistribution::SpaceAround if free_space > zero && count > 0. => {
+            (free_space / count / 2., free_space / count)
+        }
+        ContentDistribution::SpaceEvenly if free_space > zero => {
+            let space = free_space / (count + 1.);
+            (space, space)
+        }
+        ContentDistribution::SpaceAround | ContentDistribution::SpaceEvenly => {
+            (free_space / 2., zero)
+        }
+    }
+}
+
 // FIXME: use std::mem::take when it’s stable
 // https://github.com/rust-lang/rust/issues/61129
 fn take<T>(x: &mut T) -> T
@@ -17,10 +17,10 @@ pub(super) struct AbsolutelyPositionedFragment<'box_> {
     pub(super) tree_rank: usize,
 
     pub(super) inline_start: AbsoluteBoxOffsets<LengthOrPercentage>,
-    inline_size: LengthOrPercentageOrAuto,
+    inline_size: Size,
 
     pub(super) block_start: AbsoluteBoxOffsets<LengthOrPercentage>,
-    block_size: LengthOrPercentageOrAuto,
+    block_size: Size,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -36,10 +36,13 @@ impl AbsolutelyPositionedBox {
         &'a self,
         initial_start_corner: Vec2<Length>,
         tree_rank: usize,
+        // FIXME: this is the mode of the static position’s containing block,
+        // which might differ from that of the actual containing block.
+        containing_block_mode: (WritingMode, Direction),
     ) -> AbsolutelyPositionedFragment {
         let style = &self.style;
-        let box_offsets = style.box_offsets();
-        let box_size = style.box_size();
+        let box_offsets = style.box_offsets(containing_block_mode);
+        let box_size = style.box_size(containing_block_mode);
 
         let inline_size = box_size.inline;
         let block_size = box_size.block;
@@ -117,9 +120,15 @@ impl<'a> AbsolutelyPositionedFragment<'a> {
         let cbis = containing_block.size.inline;
         let cbbs = containing_block.size.block;
 
-        let padding = style.padding().percentages_relative_to(cbis);
-        let border = style.border_width().percentages_relative_to(cbis);
-        let computed_margin = style.margin().percentages_relative_to(cbis);
+        let padding = style
+            .padding(containing_block.mode)
+            .percentages_relative_to(cbis);
+        let border = style
+            .border_width(containing_block.mode)
+            .percentages_relative_to(cbis);
+        let computed_margin = style
+            .margin(containing_block.mode)
+            .percentages_relative_to(cbis);
         let pb = &padding + &border;
 
         enum Anchor {
@@ -134,9 +143,8 @@ impl<'a> AbsolutelyPositionedFragment<'a> {
             computed_margin_end: LengthOrAuto,
             solve_margins: impl FnOnce(Length) -> (Length, Length),
             box_offsets: AbsoluteBoxOffsets<LengthOrPercentage>,
-            size: LengthOrPercentageOrAuto,
+            size: LengthOrAuto,
         ) -> (Anchor, LengthOrAuto, Length, Length) {
-            let size = size.percentage_relative_to(containing_size);
             match box_offsets {
                 AbsoluteBoxOffsets::StaticStart { start } => (
                     Anchor::Start(start),
@@ -200,6 +208,27 @@ impl<'a> AbsolutelyPositionedFragment<'a> {
             }
         }
 
+        let content_sizes = || {
+            self.absolutely_positioned_box
+                .contents
+                .inline_content_sizes(style)
+        };
+        // What `fit-content` is limited to, with `auto` margins treated as zero
+        let inline_offsets = match self.inline_start {
+            AbsoluteBoxOffsets::StaticStart { start } => start,
+            AbsoluteBoxOffsets::Start { start } => start.percentage_relative_to(cbis),
+            AbsoluteBoxOffsets::End { end } => end.percentage_relative_to(cbis),
+            AbsoluteBoxOffsets::Both { start, end } => {
+                start.percentage_relative_to(cbis) + end.percentage_relative_to(cbis)
+            }
+        };
+        let available = cbis
+            - inline_offsets
+            - pb.inline_sum()
+            - computed_margin.auto_is(Length::zero).inline_sum();
+        let specified_inline_size =
+            sizing::inline_size(self.inline_size, cbis, available, content_sizes);
+
         let (inline_anchor, inline_size, margin_inline_start, margin_inline_end) = solve_axis(
             cbis,
             pb.inline_sum(),
@@ -213,7 +242,7 @@ impl<'a> AbsolutelyPositionedFragment<'a> {
                 }
             },
             self.inline_start,
-            self.inline_size,
+            specified_inline_size,
         );
 
         let (block_anchor, block_size, margin_block_start, margin_block_end) = solve_axis(
@@ -223,7 +252,7 @@ impl<'a> AbsolutelyPositionedFragment<'a> {
             computed_margin.block_end,
             |margins| (margins / 2., margins / 2.),
             self.block_start,
-            self.block_size,
+            sizing::block_size(self.block_size, LengthOrAuto::Length(cbis)),
         );
 
         let margin = Sides {
@@ -239,18 +268,18 @@ impl<'a> AbsolutelyPositionedFragment<'a> {
                 Anchor::End(end) => cbis - end - pb.inline_sum() - margin.inline_sum(),
             };
 
-            // FIXME(nox): shrink-to-fit.
-            available_size
+            content_sizes().shrink_to_fit(available_size)
         });
 
         let containing_block_for_children = ContainingBlock {
             inline_size,
             block_size,
             mode: style.writing_mode(),
+            style,
         };
         // https://drafts.csswg.org/css-writing-modes/#orthogonal-flows
         assert_eq!(
-            containing_block.mode, containing_block_for_children.mode,
+            containing_block.mode.0, containing_block_for_children.mode.0,
             "Mixed writing modes are not supported yet"
         );
         let dummy_tree_rank = 0;
@@ -299,10 +328,53 @@ impl<'a> AbsolutelyPositionedFragment<'a> {
             border,
             margin,
             block_margins_collapsed_with_children: CollapsedBlockMargins::zero(),
+            // Out of flow
+            last_baseline: None,
         })
     }
 }
 
+/// Absolutely-positioned descendants of a box laid out by a table, flex, or grid container
+/// have it as their containing block if it is relatively positioned. Otherwise their static
+/// position is made relative to the content box of the container,
+/// where `content_start` is that of the fragment.
+pub(super) fn place_absolutely_positioned_fragments<'a>(
+    fragment: &mut BoxFragment,
+    mut nested_abspos: Vec<AbsolutelyPositionedFragment<'a>>,
+    content_start: Vec2<Length>,
+    containing_block_mode: (WritingMode, Direction),
+    percentage_basis: Length,
+    absolutely_positioned_fragments: &mut Vec<AbsolutelyPositionedFragment<'a>>,
+) {
+    if fragment.style.box_.position.is_relatively_positioned() {
+        let mode = fragment.style.writing_mode();
+        AbsolutelyPositionedFragment::in_positioned_containing_block(
+            &nested_abspos,
+            &mut fragment.children,
+            &fragment
+                .content_rect
+                .size
+                .size_to_physical(containing_block_mode)
+                .size_to_flow_relative(mode),
+            &fragment
+                .style
+                .padding(mode)
+                .percentages_relative_to(percentage_basis),
+            mode,
+        );
+        return;
+    }
+    for abspos in &mut nested_abspos {
+        if let AbsoluteBoxOffsets::StaticStart { start } = &mut abspos.inline_start {
+            *start += content_start.inline
+        }
+        if let AbsoluteBoxOffsets::StaticStart { start } = &mut abspos.block_start {
+            *start += content_start.block
+        }
+    }
+    absolutely_positioned_fragments.append(&mut nested_abspos)
+}
+
 pub(super) fn adjust_static_positions(
     absolutely_positioned_fragments: &mut [AbsolutelyPositionedFragment],
     child_fragments: &mut [Fragment],
@@ -0,0 +1,122 @@
+//! https://drafts.csswg.org/css-sizing/
+
+use super::*;
+use std::sync::Mutex;
+
+/// https://drafts.csswg.org/css-sizing/#intrinsic-sizes
+#[derive(Clone, Copy, Debug)]
+pub(super) struct ContentSizes {
+    pub min_content: Length,
+    pub max_content: Length,
+}
+
+impl ContentSizes {
+    pub fn zero() -> Self {
+        Self {
+            min_content: Length::zero(),
+            max_content: Length::zero(),
+        }
+    }
+
+    pub fn max(self, other: Self) -> Self {
+        Self {
+            min_content: self.min_content.max(other.min_content),
+            max_content: self.max_content.max(other.max_content),
+        }
+    }
+
+    /// https://drafts.csswg.org/css2/visudet.html#shrink-to-fit-float
+    pub fn shrink_to_fit(&self, available_size: Length) -> Length {
+        available_size.max(self.min_content).min(self.max_content)
+    }
+}
+
+/// Content sizes of a box, computed the first time they are needed during layout
+#[derive(Debug, Default)]
+pub(super) struct ContentSizesCache(Mutex<Option<ContentSizes>>);
+
+impl ContentSizesCache {
+    pub fn get_or_compute(&self, compute: impl FnOnce() -> ContentSizes) -> ContentSizes {
+        if let Some(sizes) = *self.0.lock().unwrap() {
+            return sizes;
+        }
+        // Not holding the lock, since computing may take a while
+        let sizes = compute();
+        *self.0.lock().unwrap() = Some(sizes);
+        sizes
+    }
+}
+
+/// The used value of `width` or `height` in the inline axis of the containing block,
+/// or `Auto` which depends on the kind of box.
+///
+/// `available` is the inline size of the containing block minus margins, borders, and padding,
+/// that `fit-content` is limited to.
+pub(super) fn inline_size(
+    size: Size,
+    containing_block_inline_size: Length,
+    available: Length,
+    content_sizes: impl FnOnce() -> ContentSizes,
+) -> LengthOrAuto {
+    LengthOrAuto::Length(match size {
+        Size::Length(length) => length,
+        Size::Percentage(percentage) => containing_block_inline_size * percentage,
+        Size::Auto => return LengthOrAuto::Auto,
+        Size::MinContent => content_sizes().min_content,
+        Size::MaxContent => content_sizes().max_content,
+        Size::FitContent => content_sizes().shrink_to_fit(available),
+    })
+}
+
+/// The used value of `width` or `height` in the block axis of the containing block,
+/// where intrinsic size keywords behave as `auto` for block containers.
+///
+/// https://drafts.csswg.org/css-sizing-3/#valdef-width-min-content
+pub(super) fn block_size(size: Size, containing_block_block_size: LengthOrAuto) -> LengthOrAuto {
+    match size {
+        Size::Length(length) => LengthOrAuto::Length(length),
+        Size::Percentage(percentage) => containing_block_block_size.map(|size| size * percentage),
+        Size::Auto | Size::MinContent | Size::MaxContent | Size::FitContent => LengthOrAuto::Auto,
+    }
+}
+
+/// The outer content sizes of a box, which are its contribution to those of its parent.
+/// `inner` are the content sizes of its contents.
+///
+/// Percentages of the containing block, whose size is not known yet, are treated as zero.
+///
+/// https://drafts.csswg.org/css-sizing/#intrinsic-contribution
+pub(super) fn outer_inline_content_sizes(
+    style: &ComputedValues,
+    containing_block_mode: (WritingMode, Direction),
+    inner: impl FnOnce() -> ContentSizes,
+) -> ContentSizes {
+    let fixed = |length| ContentSizes {
+        min_content: length,
+        max_content: length,
+    };
+    // FIXME: orthogonal flows contribute their block size
+    let mut sizes = match style.box_size(containing_block_mode).inline {
+        Size::Length(length) => fixed(length),
+        Size::MinContent => fixed(inner().min_content),
+        Size::MaxContent => fixed(inner().max_content),
+        Size::Percentage(_) | Size::Auto | Size::FitContent => inner(),
+    };
+    let zero = Length::zero();
+    let pbm = style
+        .padding(containing_block_mode)
+        .percentages_relative_to(zero)
+        .inline_sum()
+        + style
+            .border_width(containing_block_mode)
+            .percentages_relative_to(zero)
+            .inline_sum()
+        + style
+            .margin(containing_block_mode)
+            .percentages_relative_to(zero)
+            .auto_is(Length::zero)
+            .inline_sum();
+    sizes.min_content += pbm;
+    sizes.max_content += pbm;
+    sizes
+}
@@ -0,0 +1,507 @@
+use super::*;
+use html5ever::LocalName;
+
+impl TableFormattingContext {
+    /// `style` is that of the table box, see `ComputedValues::table_wrapper_and_box`
+    pub fn construct<'a>(
+        context: &'a Context<'a>,
+        style: &Arc<ComputedValues>,
+        contents: NonReplacedContents,
+    ) -> Self {
+        let mut builder = TableBuilder::new(context, style.clone());
+        contents.traverse(style, context, &mut builder);
+        builder.finish()
+    }
+}
+
+/// A builder for a table box.
+///
+/// Captions, columns, and row groups are handled here. Consecutive other children
+/// are wrapped in an anonymous row group, which wraps non-rows in an anonymous row,
+/// which wraps non-cells in an anonymous cell.
+///
+/// https://drafts.csswg.org/css2/tables.html#anonymous-boxes
+pub(in crate::layout) struct TableBuilder<'a> {
+    context: &'a Context<'a>,
+    style: Arc<ComputedValues>,
+    captions: Vec<Caption>,
+    column_groups: Vec<ColumnBox>,
+    columns: Vec<ColumnBox>,
+    /// The number of columns created by column and column group boxes so far
+    declared_column_count: usize,
+    row_groups: Vec<RowGroup>,
+    ongoing_anonymous_row_group: Option<RowGroupBuilder<'a>>,
+}
+
+struct RowGroupBuilder<'a> {
+    context: &'a Context<'a>,
+    style: Arc<ComputedValues>,
+    kind: RowGroupKind,
+    rows: Vec<Row>,
+    ongoing_anonymous_row: Option<RowBuilder<'a>>,
+}
+
+struct RowBuilder<'a> {
+    context: &'a Context<'a>,
+    style: Arc<ComputedValues>,
+    cells: Vec<Cell>,
+    /// The contents of an anonymous cell can come from several sibling nodes
+    ongoing_anonymous_cell: Option<(Arc<ComputedValues>, BlockContainerBuilder<'a>)>,
+}
+
+/// Columns in a column group
+struct ColumnGroupBuilder<'a> {
+    context: &'a Context<'a>,
+    next_column: usize,
+    columns: Vec<ColumnBox>,
+}
+
+impl<'a> TableBuilder<'a> {
+    fn new(context: &'a Context<'a>, style: Arc<ComputedValues>) -> Self {
+        TableBuilder {
+            context,
+            style,
+            captions: Vec::new(),
+            column_groups: Vec::new(),
+            columns: Vec::new(),
+            declared_column_count: 0,
+            row_groups: Vec::new(),
+            ongoing_anonymous_row_group: None,
+        }
+    }
+
+    /// For table-internal boxes whose parent is not a table.
+    /// Also returns the style of the table wrapper box.
+    pub(in crate::layout) fn new_anonymous(
+        context: &'a Context<'a>,
+        parent_style: &ComputedValues,
+        inline: bool,
+    ) -> (Arc<ComputedValues>, Self) {
+        let style = ComputedValues::anonymous_table_inheriting_from(parent_style, inline);
+        let (wrapper_style, table_style) = style.table_wrapper_and_box();
+        (wrapper_style, TableBuilder::new(context, table_style))
+    }
+
+    pub(in crate::layout) fn finish(mut self) -> TableFormattingContext {
+        self.end_ongoing_anonymous_row_group();
+        let mut row_groups = self.row_groups;
+        // Only the first header and footer groups are moved,
+        // any other is displayed like a body group.
+        // https://drafts.csswg.org/css2/tables.html#table-display
+        if let Some(index) = row_groups
+            .iter()
+            .position(|group| group.kind == RowGroupKind::Header)
+        {
+            let header = row_groups.remove(index);
+            row_groups.insert(0, header)
+        }
+        if let Some(index) = row_groups
+            .iter()
+            .position(|group| group.kind == RowGroupKind::Footer)
+        {
+            let footer = row_groups.remove(index);
+            row_groups.push(footer)
+        }
+        let column_count = row_groups
+            .iter()
+            .flat_map(|group| &group.rows)
+            .flat_map(|row| &row.cells)
+            .map(|cell| cell.column + cell.column_span)
+            .fold(self.declared_column_count, usize::max);
+        TableFormattingContext {
+            style: self.style,
+            captions: self.captions,
+            column_groups: self.column_groups,
+            columns: self.columns,
+            row_groups,
+            column_count,
+            content_sizes: ContentSizesCache::default(),
+        }
+    }
+
+    fn end_ongoing_anonymous_row_group(&mut self) {
+        if let Some(row_group) = self.ongoing_anonymous_row_group.take() {
+            self.row_groups.push(row_group.finish())
+        }
+    }
+}
+
+impl<'a> TraversalHandler<'a> for TableBuilder<'a> {
+    fn handle_element(
+        &mut self,
+        style: &Arc<ComputedValues>,
+        display: DisplayGeneratingBox,
+        contents: Contents,
+        box_slot: BoxSlot<'a>,
+    ) {
+        let context = self.context;
+        let internal = match display {
+            DisplayGeneratingBox::LayoutInternal(internal) => internal,
+            DisplayGeneratingBox::OutsideInside { .. } => {
+                return self
+                    .anonymous_row_group()
+                    .handle_element(style, display, contents, box_slot)
+            }
+        };
+        match internal {
+            DisplayLayoutInternal::TableCaption => {
+                self.end_ongoing_anonymous_row_group();
+                let mut style = style.clone();
+                let contents = IndependentFormattingContext::construct(
+                    context,
+                    &mut style,
+                    DisplayInside::FlowRoot,
+                    contents,
+                );
+                self.captions.push(Caption { style, contents })
+            }
+            DisplayLayoutInternal::TableColumnGroup => {
+                self.end_ongoing_anonymous_row_group();
+                let start = self.declared_column_count;
+                let span = column_span_attribute(context, &contents);
+                let mut builder = ColumnGroupBuilder {
+                    context,
+                    next_column: start,
+                    columns: Vec::new(),
+                };
+                non_replaced(contents).traverse(style, context, &mut builder);
+                // A column group without columns spans as many columns as its `span` attribute
+                let end = if builder.columns.is_empty() {
+                    start + span
+                } else {
+                    builder.next_column
+                };
+                self.columns.extend(builder.columns);
+                self.column_groups.push(ColumnBox {
+                    style: style.clone(),
+                    columns: start..end,
+                });
+                self.declared_column_count = end
+            }
+            DisplayLayoutInternal::TableColumn => {
+                self.end_ongoing_anonymous_row_group();
+                let start = self.declared_column_count;
+                let end = start + column_span_attribute(context, &contents);
+                self.columns.push(ColumnBox {
+                    style: style.clone(),
+                    columns: start..end,
+                });
+                self.declared_column_count = end
+            }
+            DisplayLayoutInternal::TableRowGroup
+            | DisplayLayoutInternal::TableHeaderGroup
+            | DisplayLayoutInternal::TableFooterGroup => {
+                self.end_ongoing_anonymous_row_group();
+                let kind = match internal {
+                    DisplayLayoutInternal::TableHeaderGroup => RowGroupKind::Header,
+                    DisplayLayoutInternal::TableFooterGroup => RowGroupKind::Footer,
+                    _ => RowGroupKind::Body,
+                };
+                let mut builder = RowGroupBuilder::new(context, style.clone(), kind);
+                non_replaced(contents).traverse(style, context, &mut builder);
+                self.row_groups.push(builder.finish())
+            }
+            DisplayLayoutInternal::TableRow | DisplayLayoutInternal::TableCell => {
+                return self
+                    .anonymous_row_group()
+                    .handle_element(style, display, contents, box_slot)
+            }
+        }
+        box_slot.set(LayoutBox::TableInternal)
+    }
+
+    fn handle_text(&mut self, text: &str, parent_style: &Arc<ComputedValues>) {
+        if self.ongoing_anonymous_row_group.is_some() || !is_white_space_only(text) {
+            self.anonymous_row_group().handle_text(text, parent_style)
+        }
+    }
+}
+
+impl<'a> TableBuilder<'a> {
+    fn anonymous_row_group(&mut self) -> &mut RowGroupBuilder<'a> {
+        let (context, style) = (self.context, &self.style);
+        self.ongoing_anonymous_row_group.get_or_insert_with(|| {
+            let style = ComputedValues::anonymous_inheriting_from(Some(style));
+            RowGroupBuilder::new(context, style, RowGroupKind::Body)
+        })
+    }
+}
+
+impl<'a> RowGroupBuilder<'a> {
+    fn new(context: &'a Context<'a>, style: Arc<ComputedValues>, kind: RowGroupKind) -> Self {
+        RowGroupBuilder {
+            context,
+            style,
+            kind,
+            rows: Vec::new(),
+            ongoing_anonymous_row: None,
+        }
+    }
+
+    fn end_ongoing_anonymous_row(&mut self) {
+        if let Some(row) = self.ongoing_anonymous_row.take() {
+            self.rows.push(row.finish())
+        }
+    }
+
+    fn anonymous_row(&mut self) -> &mut RowBuilder<'a> {
+        let (context, style) = (self.context, &self.style);
+        self.ongoing_anonymous_row.get_or_insert_with(|| {
+            let style = ComputedValues::anonymous_inheriting_from(Some(style));
+            RowBuilder::new(context, style)
+        })
+    }
+
+    /// Assigns cells to slots of the grid, skipping those taken by cells of previous rows
+    /// that span several rows.
+    ///
+    /// https://html.spec.whatwg.org/multipage/tables.html#forming-a-table
+    fn finish(mut self) -> RowGroup {
+        self.end_ongoing_anonymous_row();
+        let row_count = self.rows.len();
+        // For each column, how many more rows are covered by a cell from a previous row
+        let mut covered_rows: Vec<usize> = Vec::new();
+        for (row_index, row) in self.rows.iter_mut().enumerate() {
+            let remaining_rows = row_count - row_index;
+            let mut column = 0;
+            for cell in &mut row.cells {
+                while covered_rows.get(column).map_or(false, |&rows| rows > 0) {
+                    column += 1
+                }
+                cell.column = column;
+                // `rowspan="0"` extends to the end of the row group, and so do cells
+                // that would span more rows than there are.
+                if cell.row_span == 0 || cell.row_span > remaining_rows {
+                    cell.row_span = remaining_rows
+                }
+                let end = column + cell.column_span;
+                if covered_rows.len() < end {
+                    covered_rows.resize(end, 0)
+                }
+                for rows in &mut covered_rows[column..end] {
+                    *rows = cell.row_span
+                }
+                column = end
+            }
+            for rows in &mut covered_rows {
+                *rows = rows.saturating_sub(1)
+            }
+        }
+        RowGroup {
+            style: self.style,
+            kind: self.kind,
+            rows: self.rows,
+        }
+    }
+}
+
+impl<'a> TraversalHandler<'a> for RowGroupBuilder<'a> {
+    fn handle_element(
+        &mut self,
+        style: &Arc<ComputedValues>,
+        display: DisplayGeneratingBox,
+        contents: Contents,
+        box_slot: BoxSlot<'a>,
+    ) {
+        if let DisplayGeneratingBox::LayoutInternal(DisplayLayoutInternal::TableRow) = display {
+            self.end_ongoing_anonymous_row();
+            let mut builder = RowBuilder::new(self.context, style.clone());
+            non_replaced(contents).traverse(style, self.context, &mut builder);
+            self.rows.push(builder.finish());
+            box_slot.set(LayoutBox::TableInternal)
+        } else {
+            self.anonymous_row()
+                .handle_element(style, display, contents, box_slot)
+        }
+    }
+
+    fn handle_text(&mut self, text: &str, parent_style: &Arc<ComputedValues>) {
+        if self.ongoing_anonymous_row.is_some() || !is_white_space_only(text) {
+            self.anonymous_row().handle_text(text, parent_style)
+        }
+    }
+}
+
+impl<'a> RowBuilder<'a> {
+    fn new(context: &'a Context<'a>, style: Arc<ComputedValues>) -> Self {
+        RowBuilder {
+            context,
+            style,
+            cells: Vec::new(),
+            ongoing_anonymous_cell: None,
+        }
+    }
+
+    fn end_ongoing_anonymous_cell(&mut self) {
+        if let Some((style, builder)) = self.ongoing_anonymous_cell.take() {
+            let (contents, contains_floats) = builder.finish();
+            let contents = IndependentFormattingContext::Flow(BlockFormattingContext {
+                contents,
+                contains_floats: contains_floats == ContainsFloats::Yes,
+                content_sizes: ContentSizesCache::default(),
+            });
+            self.cells.push(Cell::new(style, contents, 1, 1))
+        }
+    }
+
+    fn anonymous_cell(&mut self) -> &mut BlockContainerBuilder<'a> {
+        let (context, style) = (self.context, &self.style);
+        let (_, builder) = self.ongoing_anonymous_cell.get_or_insert_with(|| {
+            let style = ComputedValues::anonymous_inheriting_from(Some(style));
+            let builder = BlockContainerBuilder::new(context, style.clone());
+            (style, builder)
+        });
+        builder
+    }
+
+    fn finish(mut self) -> Row {
+        self.end_ongoing_anonymous_cell();
+        Row {
+            style: self.style,
+            cells: self.cells,
+        }
+    }
+}
+
+impl<'a> TraversalHandler<'a> for RowBuilder<'a> {
+    fn handle_element(
+        &mut self,
+        style: &Arc<ComputedValues>,
+        display: DisplayGeneratingBox,
+        contents: Contents,
+        box_slot: BoxSlot<'a>,
+    ) {
+        if let DisplayGeneratingBox::LayoutInternal(DisplayLayoutInternal::TableCell) = display {
+            self.end_ongoing_anonymous_cell();
+            let context = self.context;
+            let cell_elements = [local_name!("td"), local_name!("th")];
+            // https://html.spec.whatwg.org/multipage/tables.html#attr-tdth-colspan
+            let column_span =
+                span_attribute(context, &contents, &cell_elements, &local_name!("colspan"))
+                    .map_or(1, |span| span.max(1).min(1000));
+            let row_span =
+                span_attribute(context, &contents, &cell_elements, &local_name!("rowspan"))
+                    .map_or(1, |span| span.min(65534));
+            let mut style = style.clone();
+            let contents = IndependentFormattingContext::construct(
+                context,
+                &mut style,
+                DisplayInside::FlowRoot,
+                contents,
+            );
+            self.cells
+                .push(Cell::new(style, contents, column_span, row_span));
+            box_slot.set(LayoutBox::TableInternal)
+        } else {
+            self.anonymous_cell()
+                .handle_element(style, display, contents, box_slot)
+        }
+    }
+
+    fn handle_text(&mut self, text: &str, parent_style: &Arc<ComputedValues>) {
+        if self.ongoing_anonymous_cell.is_some() || !is_white_space_only(text) {
+            self.anonymous_cell().handle_text(text, parent_style)
+        }
+    }
+}
+
+impl<'a> TraversalHandler<'a> for ColumnGroupBuilder<'a> {
+    fn handle_element(
+        &mut self,
+        style: &Arc<ComputedValues>,
+        display: DisplayGeneratingBox,
+        contents: Contents,
+        box_slot: BoxSlot<'a>,
+    ) {
+        // Other children of a column group are treated as if they had `display: none`
+        if let DisplayGeneratingBox::LayoutInternal(DisplayLayoutInternal::TableColumn) = display {
+            let start = self.next_column;
+            self.next_column += column_span_attribute(self.context, &contents);
+            self.columns.push(ColumnBox {
+                style: style.clone(),
+                columns: start..self.next_column,
+            })
+        }
+        box_slot.set(LayoutBox::TableInternal)
+    }
+
+    fn handle_text(&mut self, _text: &str, _parent_style: &Arc<ComputedValues>) {}
+}
+
+impl Cell {
+    fn new(
+        style: Arc<ComputedValues>,
+        contents: IndependentFormattingContext,
+        column_span: usize,
+        row_span: usize,
+    ) -> Self {
+        Cell {
+            style,
+            contents,
+            column_span,
+            row_span,
+            // Assigned in `RowGroupBuilder::finish`
+            column: 0,
+        }
+    }
+}
+
+/// Children of tables that are replaced elements are not supported yet
+fn non_replaced(contents: Contents) -> NonReplacedContents {
+    match contents.try_into() {
+        Ok(non_replaced) => non_replaced,
+        Err(replaced) => match replaced {},
+    }
+}
+
+/// The number of columns created by a column or column group element
+///
+/// https://html.spec.whatwg.org/multipage/tables.html#attr-col-span
+fn column_span_attribute(context: &Context, contents: &Contents) -> usize {
+    let elements = [local_name!("col"), local_name!("colgroup")];
+    span_attribute(context, contents, &elements, &local_name!("span"))
+        .map_or(1, |span| span.max(1).min(1000))
+}
+
+/// The value of a `colspan`, `rowspan`, or `span` attribute,
+/// if `contents` are of an HTML element with one of these local names.
+fn span_attribute(
+    context: &Context,
+    contents: &Contents,
+    elements: &[LocalName],
+    attribute: &LocalName,
+) -> Option<usize> {
+    let element = match contents {
+        Contents::OfElement(element) => *element,
+        Contents::Replaced(_) | Contents::OfPseudoElement(_) => return None,
+    };
+    if !elements
+        .iter()
+        .any(|name| context.is_html_element(element, name))
+    {
+        return None;
+    }
+    let value = context.document[element]
+        .as_element()
+        .unwrap()
+        .get_attr(attribute)?;
+    parse_non_negative_integer(value)
+}
+
+/// https://html.spec.whatwg.org/multipage/common-microsyntaxes.html#rules-for-parsing-non-negative-integers
+fn parse_non_negative_integer(value: &str) -> Option<usize> {
+    let value = value.trim_start_matches(|ch: char| ch.is_ascii_whitespace());
+    let value = value.strip_prefix('+').unwrap_or(value);
+    let digits = &value[..value
+        .find(|ch: char| !ch.is_ascii_digit())
+        .unwrap_or(value.len())];
+    if digits.is_empty() {
+        return None;
+    }
+    // Saturating, since callers clamp the result anyway
+    Some(digits.bytes().fold(0_usize, |value, digit| {
+        value
+            .saturating_mul(10)
+            .saturating_add(usize::from(digit - b'0'))
+    }))
+}
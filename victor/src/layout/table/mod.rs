@@ -0,0 +1,917 @@
+//! Table layout
+//!
+//! https://drafts.csswg.org/css2/tables.html
+
+use super::*;
+use rayon::prelude::*;
+use std::ops::Range;
+
+mod construct;
+
+pub(super) use construct::*;
+
+/// The contents of a table wrapper box: a table box and its captions
+///
+/// https://drafts.csswg.org/css2/tables.html#model
+#[derive(Debug)]
+pub(super) struct TableFormattingContext {
+    /// Of the table box, rather than of the wrapper
+    style: Arc<ComputedValues>,
+    captions: Vec<Caption>,
+    column_groups: Vec<ColumnBox>,
+    columns: Vec<ColumnBox>,
+    /// The first header group comes first and the first footer group last
+    row_groups: Vec<RowGroup>,
+    column_count: usize,
+    content_sizes: ContentSizesCache,
+}
+
+#[derive(Debug)]
+struct Caption {
+    style: Arc<ComputedValues>,
+    contents: IndependentFormattingContext,
+}
+
+/// A column or column group
+#[derive(Debug)]
+struct ColumnBox {
+    style: Arc<ComputedValues>,
+    columns: Range<usize>,
+}
+
+#[derive(Debug)]
+struct RowGroup {
+    style: Arc<ComputedValues>,
+    kind: RowGroupKind,
+    rows: Vec<Row>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum RowGroupKind {
+    Header,
+    Body,
+    Footer,
+}
+
+#[derive(Debug)]
+struct Row {
+    style: Arc<ComputedValues>,
+    cells: Vec<Cell>,
+}
+
+#[derive(Debug)]
+struct Cell {
+    style: Arc<ComputedValues>,
+    contents: IndependentFormattingContext,
+    /// The first column that this cell spans
+    column: usize,
+    column_span: usize,
+    /// At least one, and not past the end of the row group
+    row_span: usize,
+}
+
+/// The rows and cells of a table, with their used borders and spacing
+struct Grid<'a> {
+    /// Of the table
+    mode: (WritingMode, Direction),
+    /// Rows of all row groups, in order
+    rows: Vec<&'a Row>,
+    /// Cells in tree order, with the index of their first row
+    cells: Vec<(usize, &'a Cell)>,
+    /// Of each cell, in the same order
+    cell_borders: Vec<Sides<Length>>,
+    cell_padding: Vec<Sides<Length>>,
+    table_border: Sides<Length>,
+    table_padding: Sides<Length>,
+    /// `border-spacing` in the inline and block axes of the table
+    spacing: Vec2<Length>,
+}
+
+/// The layout of the contents of a cell, before it is positioned
+struct CellContents<'a> {
+    flow_children: FlowChildren,
+    absolutely_positioned_fragments: Vec<AbsolutelyPositionedFragment<'a>>,
+    /// Of the content box
+    inline_size: Length,
+    block_size: Length,
+}
+
+impl TableFormattingContext {
+    /// The content sizes of the border box of the table
+    pub(super) fn inline_content_sizes(&self) -> ContentSizes {
+        self.content_sizes.get_or_compute(|| {
+            let grid = Grid::new(self, Length::zero());
+            let extra = grid.table_padding.inline_sum()
+                + grid.table_border.inline_sum()
+                + grid.spacing.inline * spacing_count(self.column_count);
+            let mut sizes = self.column_content_sizes(&grid).iter().fold(
+                ContentSizes {
+                    min_content: extra,
+                    max_content: extra,
+                },
+                |sizes, column| ContentSizes {
+                    min_content: sizes.min_content + column.min_content,
+                    max_content: sizes.max_content + column.max_content,
+                },
+            );
+            // The table is at least as wide as its captions
+            for caption in &self.captions {
+                let caption_min =
+                    sizing::outer_inline_content_sizes(&caption.style, grid.mode, || {
+                        caption.contents.inline_content_sizes(&caption.style)
+                    })
+                    .min_content;
+                sizes.min_content.max_assign(caption_min);
+                sizes.max_content.max_assign(caption_min);
+            }
+            sizes
+        })
+    }
+
+    /// Lays out the table box and its captions in the content box of the table wrapper box,
+    /// whose inline size is that of the table box unless the table does not fit.
+    ///
+    /// FIXME: header and footer groups are not repeated on each page,
+    /// since documents are laid out on a single page for now.
+    /// Repeat them once layout is fragmented across pages.
+    pub(super) fn layout<'a>(
+        &'a self,
+        containing_block: &ContainingBlock,
+        tree_rank: usize,
+        absolutely_positioned_fragments: &mut Vec<AbsolutelyPositionedFragment<'a>>,
+    ) -> FlowChildren {
+        let style = &self.style;
+        let cbis = containing_block.inline_size;
+        let grid = Grid::new(self, cbis);
+        let mode = grid.mode;
+        let table_pb = &grid.table_padding + &grid.table_border;
+
+        // Columns
+        let inline_spacing = grid.spacing.inline * spacing_count(self.column_count);
+        let fixed = style.table.table_layout == TableLayout::Fixed
+            && !matches!(style.box_size(mode).inline, Size::Auto);
+        let column_sizes = if fixed {
+            let available = cbis - table_pb.inline_sum() - inline_spacing;
+            self.fixed_column_sizes(&grid, available.max(Length::zero()))
+        } else {
+            let columns = self.column_content_sizes(&grid);
+            let min_content = columns
+                .iter()
+                .fold(Length::zero(), |sum, column| sum + column.min_content);
+            let available = (cbis - table_pb.inline_sum() - inline_spacing).max(min_content);
+            distribute_column_sizes(&columns, available)
+        };
+        let mut column_starts = Vec::with_capacity(self.column_count);
+        let mut grid_inline_size = grid.spacing.inline;
+        for size in &column_sizes {
+            column_starts.push(grid_inline_size);
+            grid_inline_size += *size + grid.spacing.inline
+        }
+        if self.column_count == 0 {
+            grid_inline_size = Length::zero()
+        }
+        let columns_size = |columns: Range<usize>| {
+            let span = columns.len();
+            column_sizes[columns]
+                .iter()
+                .fold(Length::zero(), |sum, size| sum + *size)
+                + grid.spacing.inline * span.saturating_sub(1) as f32
+        };
+
+        // Cell contents
+        let cell_contents: Vec<_> = grid
+            .cells
+            .par_iter()
+            .enumerate()
+            .map(|(index, &(_, cell))| {
+                let pb = &grid.cell_padding[index] + &grid.cell_borders[index];
+                let inline_size = (columns_size(cell.column..cell.column + cell.column_span)
+                    - pb.inline_sum())
+                .max(Length::zero());
+                // FIXME: orthogonal flows
+                let containing_block_for_children = ContainingBlock {
+                    inline_size,
+                    block_size: LengthOrAuto::Auto,
+                    mode: cell.style.writing_mode(),
+                    style: &cell.style,
+                };
+                let mut absolutely_positioned_fragments = Vec::new();
+                let flow_children = cell.contents.layout(
+                    &containing_block_for_children,
+                    tree_rank,
+                    &mut absolutely_positioned_fragments,
+                );
+                // The `height` of a cell is a minimum
+                let block_size = match cell.style.box_size(mode).block {
+                    Size::Length(length) => length.max(flow_children.block_size),
+                    _ => flow_children.block_size,
+                };
+                CellContents {
+                    flow_children,
+                    absolutely_positioned_fragments,
+                    inline_size,
+                    block_size,
+                }
+            })
+            .collect();
+
+        // Rows
+        let row_count = grid.rows.len();
+        let mut row_sizes: Vec<Length> = grid
+            .rows
+            .iter()
+            .map(|row| match row.style.box_size(mode).block {
+                Size::Length(length) => length,
+                _ => Length::zero(),
+            })
+            .collect();
+        // For baseline-aligned cells, how far their baseline is from the start of their row
+        let mut row_baselines = vec![None; row_count];
+        let mut row_below_baselines = vec![Length::zero(); row_count];
+        for (index, &(row, cell)) in grid.cells.iter().enumerate() {
+            if let Some(above) = grid.cell_baseline(index, &cell_contents[index]) {
+                let baseline = row_baselines[row].get_or_insert(above);
+                baseline.max_assign(above);
+                if cell.row_span == 1 {
+                    let below = grid.cell_block_size(index, &cell_contents[index]) - above;
+                    row_below_baselines[row].max_assign(below)
+                }
+            }
+        }
+        for (row, baseline) in row_baselines.iter().enumerate() {
+            if let Some(above) = baseline {
+                row_sizes[row].max_assign(*above + row_below_baselines[row])
+            }
+        }
+        for (index, &(row, cell)) in grid.cells.iter().enumerate() {
+            if cell.row_span == 1 {
+                row_sizes[row].max_assign(grid.cell_block_size(index, &cell_contents[index]))
+            }
+        }
+        // FIXME: distribute the extra size of cells that span several rows
+        // rather than giving it to their last row
+        for (index, &(row, cell)) in grid.cells.iter().enumerate() {
+            if cell.row_span > 1 {
+                let rows = row..row + cell.row_span;
+                let size = grid.cell_block_size(index, &cell_contents[index]);
+                let spanned = rows_size(&row_sizes, rows.clone(), grid.spacing.block);
+                if size > spanned {
+                    row_sizes[rows.end - 1] += size - spanned
+                }
+            }
+        }
+        let block_spacing = grid.spacing.block * spacing_count(row_count);
+        let mut grid_block_size = row_sizes
+            .iter()
+            .fold(block_spacing, |sum, size| sum + *size);
+        // The `height` of the table is that of its border box, and a minimum
+        if let LengthOrAuto::Length(block_size) =
+            sizing::block_size(style.box_size(mode).block, containing_block.block_size)
+        {
+            let extra = block_size - table_pb.block_sum() - grid_block_size;
+            if extra > Length::zero() {
+                grid_block_size += extra;
+                let rows_sum = grid_block_size - extra - block_spacing;
+                for size in &mut row_sizes {
+                    *size += if rows_sum > Length::zero() {
+                        extra * (size.px / rows_sum.px)
+                    } else {
+                        extra / row_count as f32
+                    }
+                }
+            }
+        }
+        let mut row_starts = Vec::with_capacity(row_count);
+        let mut position = grid.spacing.block;
+        for size in &row_sizes {
+            row_starts.push(position);
+            position += *size + grid.spacing.block
+        }
+
+        // Fragments of the table box, in painting order:
+        // column groups, columns, row groups, rows, and cells
+        let grid_rect = |columns: Range<usize>, rows: Range<usize>| Rect {
+            start_corner: Vec2 {
+                inline: column_starts
+                    .get(columns.start)
+                    .copied()
+                    .unwrap_or(Length::zero()),
+                block: row_starts
+                    .get(rows.start)
+                    .copied()
+                    .unwrap_or(Length::zero()),
+            },
+            size: Vec2 {
+                inline: columns_size(columns),
+                block: rows_size(&row_sizes, rows, grid.spacing.block),
+            },
+        };
+        let mut table_children = Vec::new();
+        let all_rows = 0..row_count;
+        for column in self.column_groups.iter().chain(&self.columns) {
+            let rect = grid_rect(column.columns.clone(), all_rows.clone());
+            table_children.push(Fragment::Box(BoxFragment::background(&column.style, rect)))
+        }
+        let all_columns = 0..self.column_count;
+        let mut first_row = 0;
+        for group in &self.row_groups {
+            let rows = first_row..first_row + group.rows.len();
+            if !rows.is_empty() {
+                let rect = grid_rect(all_columns.clone(), rows.clone());
+                table_children.push(Fragment::Box(BoxFragment::background(&group.style, rect)))
+            }
+            first_row = rows.end
+        }
+        for (row, row_box) in grid.rows.iter().enumerate() {
+            let rect = grid_rect(all_columns.clone(), row..row + 1);
+            table_children.push(Fragment::Box(BoxFragment::background(&row_box.style, rect)))
+        }
+
+        let captions_before = self
+            .captions
+            .iter()
+            .filter(|caption| caption.style.inherited_table.caption_side == CaptionSide::Top);
+        let captions_after = self
+            .captions
+            .iter()
+            .filter(|caption| caption.style.inherited_table.caption_side == CaptionSide::Bottom);
+        let table_inline_size = grid_inline_size + table_pb.inline_sum();
+        let mut fragments = Vec::new();
+        let mut block_position = Length::zero();
+        for caption in captions_before {
+            let fragment = caption.layout(
+                table_inline_size,
+                &mut block_position,
+                tree_rank,
+                absolutely_positioned_fragments,
+            );
+            fragments.push(Fragment::Box(fragment))
+        }
+        let table_content_start = Vec2 {
+            inline: table_pb.inline_start,
+            block: block_position + table_pb.block_start,
+        };
+
+        let mut table_baseline = None;
+        for ((index, &(row, cell)), contents) in grid.cells.iter().enumerate().zip(cell_contents) {
+            let columns = cell.column..cell.column + cell.column_span;
+            let border_rect = grid_rect(columns, row..row + cell.row_span);
+            let padding = grid.cell_padding[index].clone();
+            let border = grid.cell_borders[index].clone();
+            let pb = &padding + &border;
+            let content_block_size = border_rect.size.block - pb.block_sum();
+            let free_space = content_block_size - contents.flow_children.block_size;
+            let offset = match cell.style.text.vertical_align {
+                VerticalAlign::Top => Length::zero(),
+                VerticalAlign::Middle => free_space / 2.,
+                VerticalAlign::Bottom => free_space,
+                // Other values behave like `baseline`
+                _ => match grid.cell_baseline(index, &contents) {
+                    Some(above) => row_baselines[row].unwrap_or(above) - above,
+                    None => Length::zero(),
+                },
+            };
+            let mut start_corner = Vec2 {
+                inline: border_rect.start_corner.inline + pb.inline_start,
+                block: border_rect.start_corner.block + pb.block_start,
+            };
+            let content_size = Vec2 {
+                inline: contents.inline_size,
+                block: content_block_size,
+            };
+            let last_baseline = contents
+                .flow_children
+                .last_baseline
+                .map(|baseline| baseline + offset);
+            if row == 0 && table_baseline.is_none() {
+                table_baseline = last_baseline.map(|baseline| start_corner.block + baseline)
+            }
+            let mut children = contents.flow_children.fragments;
+            if offset != Length::zero() {
+                let cell_mode = cell.style.writing_mode();
+                children = vec![Fragment::Anonymous(AnonymousFragment {
+                    rect: Rect {
+                        start_corner: Vec2 {
+                            inline: Length::zero(),
+                            block: offset,
+                        },
+                        size: Vec2 {
+                            inline: contents.inline_size,
+                            block: contents.flow_children.block_size,
+                        },
+                    },
+                    children,
+                    mode: cell_mode,
+                })]
+            }
+            let content_start_in_wrapper = &table_content_start
+                + &Vec2 {
+                    inline: start_corner.inline,
+                    block: start_corner.block + offset,
+                };
+            start_corner += &relative_adjustement(&cell.style, cbis, LengthOrAuto::Auto, mode);
+            let mut fragment = BoxFragment {
+                style: cell.style.clone(),
+                children,
+                content_rect: Rect {
+                    start_corner,
+                    size: content_size,
+                },
+                padding,
+                border,
+                margin: Sides::zero(),
+                block_margins_collapsed_with_children: CollapsedBlockMargins::zero(),
+                last_baseline,
+            };
+            place_absolutely_positioned_fragments(
+                &mut fragment,
+                contents.absolutely_positioned_fragments,
+                content_start_in_wrapper,
+                mode,
+                cbis,
+                absolutely_positioned_fragments,
+            );
+            table_children.push(Fragment::Box(fragment))
+        }
+
+        fragments.push(Fragment::Box(BoxFragment {
+            style: style.clone(),
+            children: table_children,
+            content_rect: Rect {
+                start_corner: table_content_start.clone(),
+                size: Vec2 {
+                    inline: grid_inline_size,
+                    block: grid_block_size,
+                },
+            },
+            padding: grid.table_padding,
+            border: grid.table_border,
+            margin: Sides::zero(),
+            block_margins_collapsed_with_children: CollapsedBlockMargins::zero(),
+            last_baseline: table_baseline,
+        }));
+        block_position += grid_block_size + table_pb.block_sum();
+        for caption in captions_after {
+            let fragment = caption.layout(
+                table_inline_size,
+                &mut block_position,
+                tree_rank,
+                absolutely_positioned_fragments,
+            );
+            fragments.push(Fragment::Box(fragment))
+        }
+
+        FlowChildren {
+            fragments,
+            block_size: block_position,
+            collapsible_margins_in_children: CollapsedBlockMargins::zero(),
+            // “The baseline of an 'inline-table' is the baseline of the first row of the table.”
+            // https://drafts.csswg.org/css2/visudet.html#propdef-vertical-align
+            last_baseline: table_baseline.map(|baseline| table_content_start.block + baseline),
+        }
+    }
+
+    /// The minimum and maximum widths of the border box of each cell, spread over its columns
+    ///
+    /// https://drafts.csswg.org/css2/tables.html#auto-table-layout
+    fn column_content_sizes(&self, grid: &Grid) -> Vec<ContentSizes> {
+        let mode = grid.mode;
+        let mut columns = vec![ContentSizes::zero(); self.column_count];
+        for column in self.column_groups.iter().chain(&self.columns) {
+            if let Size::Length(size) = column.style.box_size(mode).inline {
+                for sizes in &mut columns[column.columns.clone()] {
+                    sizes.min_content.max_assign(size);
+                    sizes.max_content.max_assign(size);
+                }
+            }
+        }
+        let cell_sizes: Vec<_> = grid
+            .cells
+            .par_iter()
+            .enumerate()
+            .map(|(index, &(_, cell))| {
+                let mut sizes = cell.contents.inline_content_sizes(&cell.style);
+                // A specified width is used unless the contents do not fit
+                // FIXME: percentages
+                if let Size::Length(size) = cell.style.box_size(mode).inline {
+                    let size = size.max(sizes.min_content);
+                    sizes = ContentSizes {
+                        min_content: size,
+                        max_content: size,
+                    }
+                }
+                let pb =
+                    grid.cell_padding[index].inline_sum() + grid.cell_borders[index].inline_sum();
+                sizes.min_content += pb;
+                sizes.max_content += pb;
+                sizes
+            })
+            .collect();
+        // Cells that span several columns are taken into account last,
+        // growing their columns when they are not wide enough
+        let mut spanning = Vec::new();
+        for (&(_, cell), sizes) in grid.cells.iter().zip(cell_sizes) {
+            if cell.column_span == 1 {
+                columns[cell.column] = columns[cell.column].max(sizes)
+            } else {
+                spanning.push((cell, sizes))
+            }
+        }
+        spanning.sort_by_key(|(cell, _)| cell.column_span);
+        for (cell, sizes) in spanning {
+            let spanned = &mut columns[cell.column..cell.column + cell.column_span];
+            grow_spanned_columns(spanned, grid.spacing.inline, sizes)
+        }
+        for sizes in &mut columns {
+            sizes.max_content.max_assign(sizes.min_content)
+        }
+        columns
+    }
+
+    /// Column widths from columns and from cells of the first row, ignoring contents.
+    /// `available` is the inline size of the table minus borders, padding, and spacing.
+    ///
+    /// https://drafts.csswg.org/css2/tables.html#fixed-table-layout
+    fn fixed_column_sizes(&self, grid: &Grid, available: Length) -> Vec<Length> {
+        let mode = grid.mode;
+        let mut sizes = vec![None; self.column_count];
+        for column in &self.columns {
+            if let Size::Length(size) = column.style.box_size(mode).inline {
+                for column_size in &mut sizes[column.columns.clone()] {
+                    *column_size = Some(size)
+                }
+            }
+        }
+        let first_row = grid
+            .cells
+            .iter()
+            .enumerate()
+            .take_while(|(_, (row, _))| *row == 0);
+        for (index, &(_, cell)) in first_row {
+            if let Size::Length(size) = cell.style.box_size(mode).inline {
+                let pb =
+                    grid.cell_padding[index].inline_sum() + grid.cell_borders[index].inline_sum();
+                let span = cell.column_span as f32;
+                let size = (size + pb - grid.spacing.inline * (span - 1.)) / span;
+                for column_size in &mut sizes[cell.column..cell.column + cell.column_span] {
+                    column_size.get_or_insert(size);
+                }
+            }
+        }
+        let specified = sizes
+            .iter()
+            .flatten()
+            .fold(Length::zero(), |sum, size| sum + *size);
+        let remaining = (available - specified).max(Length::zero());
+        let auto_count = sizes.iter().filter(|size| size.is_none()).count();
+        // The remaining space goes to columns without a width if any, or else to all columns
+        if auto_count > 0 {
+            let share = remaining / auto_count as f32;
+            sizes
+                .into_iter()
+                .map(|size| size.unwrap_or(share))
+                .collect()
+        } else {
+            let share = remaining / self.column_count.max(1) as f32;
+            sizes
+                .into_iter()
+                .map(|size| size.unwrap() + share)
+                .collect()
+        }
+    }
+}
+
+impl<'a> Grid<'a> {
+    /// Percentages of padding and borders are relative to `percentage_basis`
+    fn new(table: &'a TableFormattingContext, percentage_basis: Length) -> Self {
+        let style = &table.style;
+        let mode = style.writing_mode();
+        let rows: Vec<_> = table
+            .row_groups
+            .iter()
+            .flat_map(|group| &group.rows)
+            .collect();
+        let cells: Vec<_> = rows
+            .iter()
+            .enumerate()
+            .flat_map(|(index, row)| row.cells.iter().map(move |cell| (index, cell)))
+            .collect();
+        let border = |style: &ComputedValues| {
+            style
+                .border_width(mode)
+                .percentages_relative_to(percentage_basis)
+        };
+        let cell_padding = cells
+            .iter()
+            .map(|(_, cell)| {
+                cell.style
+                    .padding(mode)
+                    .percentages_relative_to(percentage_basis)
+            })
+            .collect();
+        let cell_borders = cells.iter().map(|(_, cell)| border(&cell.style)).collect();
+        let mut grid = Grid {
+            mode,
+            rows,
+            cells,
+            cell_borders,
+            cell_padding,
+            table_border: border(style),
+            table_padding: style
+                .padding(mode)
+                .percentages_relative_to(percentage_basis),
+            spacing: Vec2::zero(),
+        };
+        match style.inherited_table.border_collapse {
+            BorderCollapse::Separate => {
+                let spacing = style.inherited_table.border_spacing;
+                grid.spacing = if mode.0.is_vertical() {
+                    Vec2 {
+                        inline: spacing.vertical,
+                        block: spacing.horizontal,
+                    }
+                } else {
+                    Vec2 {
+                        inline: spacing.horizontal,
+                        block: spacing.vertical,
+                    }
+                }
+            }
+            BorderCollapse::Collapse => {
+                // “In this model, a table does not have padding”
+                grid.table_padding = Sides::zero();
+                grid.collapse_borders(table.column_count)
+            }
+        }
+        grid
+    }
+
+    /// Each cell gets half of the widest border on each of its edges, and the table gets half
+    /// of the widest border at its block-start and block-end edges and at the ends of the first row.
+    ///
+    /// FIXME: borders of rows, columns, and their groups
+    ///
+    /// https://drafts.csswg.org/css2/tables.html#collapsing-borders
+    fn collapse_borders(&mut self, column_count: usize) {
+        let row_count = self.rows.len();
+        let table = self.table_border.clone();
+        if row_count == 0 || column_count == 0 {
+            self.table_border = table.map(|width| *width / 2.);
+            return;
+        }
+        // The widest border between two rows (or a row and the table) for each column,
+        // and between two columns for each row
+        let mut block_edges = vec![vec![Length::zero(); column_count]; row_count + 1];
+        let mut inline_edges = vec![vec![Length::zero(); column_count + 1]; row_count];
+        for width in &mut block_edges[0] {
+            *width = table.block_start
+        }
+        for width in &mut block_edges[row_count] {
+            *width = table.block_end
+        }
+        for row in &mut inline_edges {
+            row[0] = table.inline_start;
+            row[column_count] = table.inline_end
+        }
+        for (&(row, cell), border) in self.cells.iter().zip(&self.cell_borders) {
+            let columns = cell.column..cell.column + cell.column_span;
+            for column in columns.clone() {
+                block_edges[row][column].max_assign(border.block_start);
+                block_edges[row + cell.row_span][column].max_assign(border.block_end);
+            }
+            for edges in &mut inline_edges[row..row + cell.row_span] {
+                edges[columns.start].max_assign(border.inline_start);
+                edges[columns.end].max_assign(border.inline_end);
+            }
+        }
+        let half_widest = |widths: &mut dyn Iterator<Item = Length>| {
+            widths.fold(Length::zero(), Length::max) / 2.
+        };
+        self.cell_borders = self
+            .cells
+            .iter()
+            .map(|&(row, cell)| {
+                let columns = cell.column..cell.column + cell.column_span;
+                let rows = row..row + cell.row_span;
+                Sides {
+                    block_start: half_widest(
+                        &mut block_edges[row][columns.clone()].iter().copied(),
+                    ),
+                    block_end: half_widest(
+                        &mut block_edges[rows.end][columns.clone()].iter().copied(),
+                    ),
+                    inline_start: half_widest(
+                        &mut inline_edges[rows.clone()]
+                            .iter()
+                            .map(|row| row[columns.start]),
+                    ),
+                    inline_end: half_widest(
+                        &mut inline_edges[rows].iter().map(|row| row[columns.end]),
+                    ),
+                }
+            })
+            .collect();
+        self.table_border = Sides {
+            block_start: half_widest(&mut block_edges[0].iter().copied()),
+            block_end: half_widest(&mut block_edges[row_count].iter().copied()),
+            inline_start: inline_edges[0][0] / 2.,
+            inline_end: inline_edges[0][column_count] / 2.,
+        }
+    }
+
+    /// The block size of the border box of a cell, before rows are sized
+    fn cell_block_size(&self, index: usize, contents: &CellContents) -> Length {
+        contents.block_size
+            + self.cell_padding[index].block_sum()
+            + self.cell_borders[index].block_sum()
+    }
+
+    /// For a baseline-aligned cell, how far from the start of its border box its baseline is:
+    /// that of its first line box or else the end of its content box.
+    ///
+    /// FIXME: use the first line box rather than the last
+    ///
+    /// https://drafts.csswg.org/css2/tables.html#height-layout
+    fn cell_baseline(&self, index: usize, contents: &CellContents) -> Option<Length> {
+        let (_, cell) = self.cells[index];
+        match cell.style.text.vertical_align {
+            VerticalAlign::Top | VerticalAlign::Middle | VerticalAlign::Bottom => None,
+            _ => Some(
+                self.cell_padding[index].block_start
+                    + self.cell_borders[index].block_start
+                    + contents
+                        .flow_children
+                        .last_baseline
+                        .unwrap_or(contents.block_size),
+            ),
+        }
+    }
+}
+
+impl Caption {
+    /// Lays out this caption with the inline size of the table, starting at `block_position`
+    /// which is then moved to its end.
+    fn layout<'a>(
+        &'a self,
+        table_inline_size: Length,
+        block_position: &mut Length,
+        tree_rank: usize,
+        absolutely_positioned_fragments: &mut Vec<AbsolutelyPositionedFragment<'a>>,
+    ) -> BoxFragment {
+        let style = &self.style;
+        let mode = style.writing_mode();
+        let padding = style
+            .padding(mode)
+            .percentages_relative_to(table_inline_size);
+        let border = style
+            .border_width(mode)
+            .percentages_relative_to(table_inline_size);
+        let margin = style
+            .margin(mode)
+            .percentages_relative_to(table_inline_size)
+            .auto_is(Length::zero);
+        let pbm = &(&padding + &border) + &margin;
+        let containing_block_for_children = ContainingBlock {
+            inline_size: (table_inline_size - pbm.inline_sum()).max(Length::zero()),
+            block_size: sizing::block_size(style.box_size(mode).block, LengthOrAuto::Auto),
+            mode,
+            style,
+        };
+        let mut nested_abspos = Vec::new();
+        let flow_children = self.contents.layout(
+            &containing_block_for_children,
+            tree_rank,
+            &mut nested_abspos,
+        );
+        let content_rect = Rect {
+            start_corner: Vec2 {
+                inline: pbm.inline_start,
+                block: *block_position + pbm.block_start,
+            },
+            size: Vec2 {
+                inline: containing_block_for_children.inline_size,
+                block: containing_block_for_children
+                    .block_size
+                    .auto_is(|| flow_children.block_size),
+            },
+        };
+        *block_position += content_rect.size.block + pbm.block_sum();
+        let content_start = content_rect.start_corner.clone();
+        let mut fragment = BoxFragment {
+            style: style.clone(),
+            children: flow_children.fragments,
+            content_rect,
+            padding,
+            border,
+            margin,
+            block_margins_collapsed_with_children: CollapsedBlockMargins::zero(),
+            last_baseline: None,
+        };
+        fragment.content_rect.start_corner +=
+            &relative_adjustement(style, table_inline_size, LengthOrAuto::Auto, mode);
+        place_absolutely_positioned_fragments(
+            &mut fragment,
+            nested_abspos,
+            content_start,
+            mode,
+            table_inline_size,
+            absolutely_positioned_fragments,
+        );
+        fragment
+    }
+}
+
+impl BoxFragment {
+    /// The fragment of a column, row, or one of their groups, which only paints a background
+    fn background(style: &Arc<ComputedValues>, content_rect: Rect<Length>) -> Self {
+        BoxFragment {
+            style: style.clone(),
+            children: Vec::new(),
+            content_rect,
+            padding: Sides::zero(),
+            border: Sides::zero(),
+            margin: Sides::zero(),
+            block_margins_collapsed_with_children: CollapsedBlockMargins::zero(),
+            last_baseline: None,
+        }
+    }
+}
+
+/// Spacing goes between columns and around them, if there are any. Same for rows.
+fn spacing_count(count: usize) -> f32 {
+    if count == 0 {
+        0.
+    } else {
+        (count + 1) as f32
+    }
+}
+
+/// The block size of these rows and of the spacing between them
+fn rows_size(row_sizes: &[Length], rows: Range<usize>, spacing: Length) -> Length {
+    let span = rows.len();
+    row_sizes[rows]
+        .iter()
+        .fold(Length::zero(), |sum, size| sum + *size)
+        + spacing * span.saturating_sub(1) as f32
+}
+
+/// Grows the spanned columns so that together with the spacing between them
+/// they are at least as wide as a cell that spans them, in proportion to their
+/// max-content widths, or equally if those are zero.
+fn grow_spanned_columns(columns: &mut [ContentSizes], spacing: Length, cell: ContentSizes) {
+    let spacing = spacing * (columns.len() - 1) as f32;
+    let total = columns
+        .iter()
+        .fold(ContentSizes::zero(), |sum, column| ContentSizes {
+            min_content: sum.min_content + column.min_content,
+            max_content: sum.max_content + column.max_content,
+        });
+    let column_count = columns.len() as f32;
+    let extra_min = cell.min_content - spacing - total.min_content;
+    let extra_max = cell.max_content - spacing - total.max_content;
+    for column in columns {
+        let weight = if total.max_content > Length::zero() {
+            column.max_content.px / total.max_content.px
+        } else {
+            1. / column_count
+        };
+        if extra_min > Length::zero() {
+            column.min_content += extra_min * weight
+        }
+        if extra_max > Length::zero() {
+            column.max_content += extra_max * weight
+        }
+    }
+}
+
+/// Column widths that add up to `available`, or to the sum of minimum widths if larger:
+/// between the minimum and maximum widths if possible, or else larger than the maximum
+/// in proportion to it.
+fn distribute_column_sizes(columns: &[ContentSizes], available: Length) -> Vec<Length> {
+    let total = columns
+        .iter()
+        .fold(ContentSizes::zero(), |sum, column| ContentSizes {
+            min_content: sum.min_content + column.min_content,
+            max_content: sum.max_content + column.max_content,
+        });
+    if available <= total.min_content {
+        columns.iter().map(|column| column.min_content).collect()
+    } else if available <= total.max_content {
+        let ratio = (available - total.min_content).px / (total.max_content - total.min_content).px;
+        columns
+            .iter()
+            .map(|column| column.min_content + (column.max_content - column.min_content) * ratio)
+            .collect()
+    } else if total.max_content > Length::zero() {
+        let extra = available - total.max_content;
+        columns
+            .iter()
+            .map(|column| {
+                column.max_content + extra * (column.max_content.px / total.max_content.px)
+            })
+            .collect()
+    } else {
+        let share = available / columns.len() as f32;
+        columns.iter().map(|_| share).collect()
+    }
+}
@@ -2,6 +2,7 @@ pub use euclid;
 
 pub mod dom;
 pub mod fonts;
+pub mod hyphenation;
 pub mod pdf;
 pub mod primitives;
 pub mod text;
@@ -1,8 +1,12 @@
+use crate::fonts::Font;
 use crate::geom::physical::{Rect, Vec2};
 use crate::geom::Length;
-use crate::layout::{BoxFragment, Fragment};
+use crate::layout::{BoxFragment, Fragment, TextFragment};
 use crate::pdf::Page;
-use crate::primitives::{CssPx, Size, TextRun};
+use crate::primitives::{point, CssPx, PathSegment, Size, TextRun};
+use crate::style::values::{Direction, TextDecorationStyle, TextDecorationThickness, WritingMode};
+use crate::style::ComputedValues;
+use std::sync::Arc;
 
 impl crate::dom::Document {
     pub fn to_pdf_bytes(&self) -> Vec<u8> {
@@ -23,10 +27,12 @@ impl crate::dom::Document {
                 },
             },
         };
+        // Pages are horizontal, the fragment tree starts with the principal writing mode
+        let mode = (WritingMode::HorizontalTb, Direction::Ltr);
         {
             let mut page = doc.add_page(page_size);
             for fragment in fragments {
-                fragment.paint_onto(&mut page, &containing_block)
+                fragment.paint_onto(&mut page, &containing_block, mode, &[])
             }
         }
         doc.write_to_pdf_bytes()
@@ -34,57 +40,305 @@ impl crate::dom::Document {
 }
 
 impl Fragment {
-    fn paint_onto(&self, page: &mut Page, containing_block: &Rect<Length>) {
+    /// The fragment’s own geometry is in the writing mode of its containing block.
+    ///
+    /// `decorating_boxes` are ancestors with `text-decoration-line`,
+    /// whose decorations are propagated to descendant text.
+    fn paint_onto(
+        &self,
+        page: &mut Page,
+        containing_block: &Rect<Length>,
+        containing_block_mode: (WritingMode, Direction),
+        decorating_boxes: &[DecoratingBox],
+    ) {
         match self {
-            Fragment::Box(b) => b.paint_onto(page, containing_block),
+            Fragment::Box(b) => b.paint_onto(
+                page,
+                containing_block,
+                containing_block_mode,
+                decorating_boxes,
+            ),
             Fragment::Anonymous(a) => {
                 let rect = a
                     .rect
-                    .to_physical(a.mode, containing_block)
+                    .to_physical(containing_block_mode, containing_block)
                     .translate(&containing_block.top_left);
                 for child in &a.children {
-                    child.paint_onto(page, &rect)
+                    child.paint_onto(page, &rect, a.mode, decorating_boxes)
                 }
             }
             Fragment::Text(t) => {
-                let mut origin = t
+                let rect = t
                     .content_rect
-                    .to_physical(t.parent_style.writing_mode(), containing_block)
-                    .translate(&containing_block.top_left)
-                    .top_left;
-                // Distance from top edge to baseline
-                let ascender = t.parent_style.font.font_size * t.text.font.ascender();
-                origin.y += ascender;
+                    .to_physical(containing_block_mode, containing_block)
+                    .translate(&containing_block.top_left);
+                let mut origin = rect.top_left.clone();
+                if t.text.vertical {
+                    // Glyphs are centered on the line
+                    origin.x += rect.size.x / 2.;
+                } else {
+                    // Distance from top edge to baseline
+                    let ascender = t.parent_style.font.font_size * t.text.font.ascender();
+                    origin.y += ascender;
+                }
+                // Underlines and overlines are painted below the text, line-throughs above.
+                // https://drafts.csswg.org/css-text-decor-3/#painting-order
+                for decorating_box in decorating_boxes {
+                    let line = decorating_box.style.text.text_decoration_line;
+                    if line.underline {
+                        t.paint_decoration(page, &rect, &origin, decorating_box, Decoration::Under)
+                    }
+                    if line.overline {
+                        t.paint_decoration(page, &rect, &origin, decorating_box, Decoration::Over)
+                    }
+                }
                 page.set_color(&t.parent_style.color.color.into());
                 page.show_text(&TextRun {
                     segment: &t.text,
-                    font_size: t.parent_style.font.font_size.0.into(),
-                    origin: origin.into(),
+                    font_size: t.font_size.0.into(),
+                    origin: origin.clone().into(),
                 })
                 .unwrap();
+                for decorating_box in decorating_boxes {
+                    if decorating_box.style.text.text_decoration_line.line_through {
+                        t.paint_decoration(
+                            page,
+                            &rect,
+                            &origin,
+                            decorating_box,
+                            Decoration::Through,
+                        )
+                    }
+                }
             }
         }
     }
 }
 
 impl BoxFragment {
-    fn paint_onto(&self, page: &mut Page, containing_block: &Rect<Length>) {
+    fn paint_onto(
+        &self,
+        page: &mut Page,
+        containing_block: &Rect<Length>,
+        containing_block_mode: (WritingMode, Direction),
+        decorating_boxes: &[DecoratingBox],
+    ) {
         let background_color = self.style.to_rgba(self.style.background.background_color);
         if background_color.alpha > 0 {
             page.set_color(&background_color.into());
             let rect = self
                 .border_rect()
-                .to_physical(self.style.writing_mode(), containing_block)
+                .to_physical(containing_block_mode, containing_block)
                 .translate(&containing_block.top_left)
                 .into();
             page.paint_rectangle(&rect);
         }
         let content_rect = self
             .content_rect
-            .to_physical(self.style.writing_mode(), containing_block)
+            .to_physical(containing_block_mode, containing_block)
             .translate(&containing_block.top_left);
+        // Decorations are not propagated to the contents of atomic inlines or floats
+        let propagates_decorations =
+            !self.is_atomic_inline() && !self.style.box_.float.is_floating();
+        let mut decorating_boxes = if propagates_decorations {
+            decorating_boxes.to_vec()
+        } else {
+            Vec::new()
+        };
+        if !self.style.text.text_decoration_line.is_none() {
+            let font = self.style.font();
+            let baseline = if !self.is_inline_box() {
+                None
+            } else if self.style.writing_mode().0.is_vertical() {
+                Some(content_rect.top_left.x + content_rect.size.x / 2.)
+            } else {
+                Some(content_rect.top_left.y + self.style.font.font_size * font.ascender())
+            };
+            decorating_boxes.push(DecoratingBox {
+                style: &self.style,
+                font,
+                baseline,
+            })
+        }
         for child in &self.children {
-            child.paint_onto(page, &content_rect)
+            child.paint_onto(
+                page,
+                &content_rect,
+                self.style.writing_mode(),
+                &decorating_boxes,
+            )
+        }
+    }
+}
+
+/// An ancestor with `text-decoration-line`
+#[derive(Clone)]
+struct DecoratingBox<'a> {
+    style: &'a ComputedValues,
+    font: Arc<Font>,
+
+    /// For an inline box, the physical position of its baseline along the block axis:
+    /// the alphabetic baseline for horizontal text, the central one for vertical text.
+    /// For a block container, decorations are at the baseline of each line instead.
+    baseline: Option<Length>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Decoration {
+    Under,
+    Over,
+    Through,
+}
+
+impl TextFragment {
+    /// `rect` is the physical content area, `origin` the start of the baseline:
+    /// the alphabetic baseline for horizontal text, the central one for vertical text.
+    ///
+    /// https://drafts.csswg.org/css-text-decor-3/#line-decoration
+    fn paint_decoration(
+        &self,
+        page: &mut Page,
+        rect: &Rect<Length>,
+        origin: &Vec2<Length>,
+        decorating_box: &DecoratingBox,
+        decoration: Decoration,
+    ) {
+        // The font, size, and baseline are those of the decorating box,
+        // for consistency across its descendants.
+        // FIXME: for a block container, this is the baseline of the text’s own inline box,
+        // not of the line, when descendants are shifted by `vertical-align`.
+        let font = &decorating_box.font;
+        let vertical = self.text.vertical;
+        let baseline = match decorating_box.baseline {
+            Some(baseline) => baseline,
+            None if vertical => origin.x,
+            None => origin.y,
+        };
+        let decorating_box = decorating_box.style;
+        let font_size = decorating_box.font.font_size;
+        let (position, font_thickness) = match decoration {
+            Decoration::Under => (font.underline_position(), font.underline_thickness()),
+            Decoration::Over => (font.ascender(), font.underline_thickness()),
+            Decoration::Through => (font.strikeout_position(), font.strikeout_thickness()),
+        };
+        let thickness = match decorating_box.text.text_decoration_thickness {
+            TextDecorationThickness::Auto | TextDecorationThickness::FromFont => {
+                font_size * font_thickness
+            }
+            TextDecorationThickness::LengthOrPercentage(thickness) => {
+                thickness.percentage_relative_to(font_size.0)
+            }
+        };
+        // Distances above the baseline of the top edge of each line
+        let top = font_size * position;
+        let style = decorating_box.text.text_decoration_style;
+        let tops = if style == TextDecorationStyle::Double {
+            // The second line is further away from the text
+            match decoration {
+                Decoration::Under => vec![top, top - thickness * 2.],
+                Decoration::Over => vec![top + thickness * 2., top],
+                Decoration::Through => vec![top + thickness, top - thickness],
+            }
+        } else {
+            vec![top]
+        };
+
+        let inline_size = if vertical { rect.size.y } else { rect.size.x };
+        // From line-relative coordinates: along the inline axis from the start of the text,
+        // and along the block axis upwards from the baseline (to the right in vertical text).
+        let to_physical = |inline: Length, above_baseline: Length| {
+            if vertical {
+                point(
+                    (baseline + above_baseline).px,
+                    (rect.top_left.y + inline).px,
+                )
+            } else {
+                point(
+                    (rect.top_left.x + inline).px,
+                    (baseline - above_baseline).px,
+                )
+            }
+        };
+        let segment = |start: Length, end: Length, top: Length| {
+            let a = to_physical(start, top);
+            let b = to_physical(end, top - thickness);
+            crate::primitives::rect(
+                a.x.min(b.x),
+                a.y.min(b.y),
+                (b.x - a.x).abs(),
+                (b.y - a.y).abs(),
+            )
+        };
+
+        page.set_color(
+            &decorating_box
+                .to_rgba(decorating_box.text.text_decoration_color)
+                .into(),
+        );
+        for top in tops {
+            match style {
+                TextDecorationStyle::Solid | TextDecorationStyle::Double => {
+                    page.paint_rectangle(&segment(Length::zero(), inline_size, top));
+                }
+                TextDecorationStyle::Dotted | TextDecorationStyle::Dashed => {
+                    let (dash, gap) = if style == TextDecorationStyle::Dotted {
+                        (thickness, thickness)
+                    } else {
+                        (thickness * 3., thickness * 2.)
+                    };
+                    if dash <= Length::zero() {
+                        continue;
+                    }
+                    let mut start = Length::zero();
+                    while start < inline_size {
+                        let end = (start + dash).min(inline_size);
+                        page.paint_rectangle(&segment(start, end, top));
+                        start = end + gap;
+                    }
+                }
+                TextDecorationStyle::Wavy => {
+                    // Alternating half-periods, with an amplitude of one thickness
+                    let half_period = thickness * 2.;
+                    if half_period <= Length::zero() {
+                        continue;
+                    }
+                    let middle = top - thickness / 2.;
+                    let peak = |i: usize| {
+                        if i % 2 == 0 {
+                            middle + thickness
+                        } else {
+                            middle - thickness
+                        }
+                    };
+                    let count = (inline_size.px / half_period.px).ceil() as usize;
+                    let mut path = vec![PathSegment::MoveTo(to_physical(Length::zero(), peak(0)))];
+                    // The top edge of the wave forward, then the bottom edge backward
+                    for i in 0..count {
+                        let start = half_period * i as f32;
+                        let end = start + half_period;
+                        let control = start + half_period / 2.;
+                        path.push(PathSegment::CurveTo(
+                            to_physical(control, peak(i)),
+                            to_physical(control, peak(i + 1)),
+                            to_physical(end, peak(i + 1)),
+                        ))
+                    }
+                    path.push(PathSegment::LineTo(to_physical(
+                        half_period * count as f32,
+                        peak(count) - thickness,
+                    )));
+                    for i in (0..count).rev() {
+                        let start = half_period * i as f32;
+                        let control = start + half_period / 2.;
+                        path.push(PathSegment::CurveTo(
+                            to_physical(control, peak(i + 1) - thickness),
+                            to_physical(control, peak(i) - thickness),
+                            to_physical(start, peak(i) - thickness),
+                        ))
+                    }
+                    page.fill_path(&path);
+                }
+            }
         }
     }
 }
@@ -1,6 +1,9 @@
-use crate::fonts::{Font, FontError, GlyphId};
+use crate::fonts::{
+    ColorGlyph, EmbeddingPermissions, Font, FontError, GlyphBitmap, GlyphId, OutlineFormat,
+};
 use crate::pdf::object::{Dictionary, Object};
-use crate::pdf::syntax::{BasicObjects, PdfFile, PAGE_TREE_ID};
+use crate::pdf::png;
+use crate::pdf::syntax::{BasicObjects, IndirectObjectId, PdfFile, PAGE_TREE_ID};
 use crate::primitives::*;
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
@@ -21,7 +24,13 @@ pub(crate) struct InProgressDoc {
     extended_graphics_states: Vec<(Vec<u8>, Object<'static>)>,
     font_resources: Vec<(Vec<u8>, Object<'static>)>,
     alpha_states: HashMap<u16, String>,
-    fonts: HashMap<ByAddress<Arc<Font>>, String>,
+    /// Keyed by font and whether it is used for vertical writing
+    fonts: HashMap<(ByAddress<Arc<Font>>, bool), String>,
+    /// Font descriptor and ToUnicode CMap, shared by horizontal and vertical fonts
+    font_descriptors: HashMap<ByAddress<Arc<Font>>, (IndirectObjectId, IndirectObjectId)>,
+    image_resources: Vec<(Vec<u8>, Object<'static>)>,
+    /// Bitmaps of colour glyphs, `None` if the image could not be decoded
+    glyph_images: HashMap<(ByAddress<Arc<Font>>, GlyphId), Option<String>>,
 }
 
 impl InProgressDoc {
@@ -33,18 +42,28 @@ impl InProgressDoc {
             font_resources: Vec::new(),
             alpha_states: HashMap::new(),
             fonts: HashMap::new(),
+            font_descriptors: HashMap::new(),
+            image_resources: Vec::new(),
+            glyph_images: HashMap::new(),
         }
     }
 
     pub(crate) fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let images = dictionary! {
+            "XObject" => Object::DictionaryWithOwnedKeys(&self.image_resources),
+        };
         self.pdf.write(w, &BasicObjects {
             page_tree: dictionary! {
                 "Type" => "Pages",
                 "Count" => self.page_ids.len(),
                 "Kids" => &*self.page_ids,
-                "Resources" => dictionary! {
-                    "Font" => Object::DictionaryWithOwnedKeys(&self.font_resources),
-                    "ExtGState" => Object::DictionaryWithOwnedKeys(&self.extended_graphics_states),
+                "Resources" => Dictionary {
+                    // Only for documents with colour bitmap glyphs
+                    prev: if self.image_resources.is_empty() { None } else { Some(&images) },
+                    pairs: key_value_pairs! {
+                        "Font" => Object::DictionaryWithOwnedKeys(&self.font_resources),
+                        "ExtGState" => Object::DictionaryWithOwnedKeys(&self.extended_graphics_states),
+                    },
                 },
             },
             catalog: dictionary! {
@@ -119,9 +138,30 @@ impl<'a> Drop for InProgressPage<'a> {
     }
 }
 
+#[derive(Copy, Clone)]
 struct GraphicsState {
     non_stroking_color_rgb: (f32, f32, f32),
     alpha: f32,
+    character_spacing: f32,
+}
+
+/// Extra advance after glyphs, in em
+#[derive(Copy, Clone)]
+struct GlyphSpacing {
+    /// After every glyph
+    letter: f32,
+    /// After the given space glyph
+    word: Option<(GlyphId, f32)>,
+    vertical: bool,
+}
+
+impl GlyphSpacing {
+    /// For showing a single glyph
+    const NONE: Self = GlyphSpacing {
+        letter: 0.,
+        word: None,
+        vertical: false,
+    };
 }
 
 macro_rules! op {
@@ -150,6 +190,7 @@ impl<'a> InProgressPage<'a> {
             graphics_state: GraphicsState {
                 non_stroking_color_rgb: (0., 0., 0.), // Black
                 alpha: 1.,                            // Fully opaque
+                character_spacing: 0.,
             },
         };
         op!(
@@ -185,24 +226,119 @@ impl<'a> InProgressPage<'a> {
         op!(self, FILL);
     }
 
+    pub(crate) fn fill_path(&mut self, path: &[PathSegment]) {
+        for segment in path {
+            match segment {
+                PathSegment::MoveTo(p) => op!(self, MOVE_TO, p.x, p.y),
+                PathSegment::LineTo(p) => op!(self, LINE_TO, p.x, p.y),
+                PathSegment::CurveTo(c1, c2, p) => {
+                    op!(self, CURVE_TO, c1.x, c1.y, c2.x, c2.y, p.x, p.y)
+                }
+            }
+        }
+        op!(self, CLOSE_PATH);
+        op!(self, FILL);
+    }
+
     pub(crate) fn show_text(&mut self, text: &TextRun) -> Result<(), FontError> {
         let TextRun {
             ref segment,
             ref font_size,
             ref origin,
         } = *text;
-        let font_key = self.add_font(&segment.font)?;
+        let font = &segment.font;
+        let font_key = self.add_font(font, segment.vertical)?;
+        let spacing = GlyphSpacing {
+            letter: segment.letter_spacing.get(),
+            word: if segment.word_spacing.get() != 0. {
+                segment
+                    .space_glyph()?
+                    .map(|space| (space, segment.word_spacing.get()))
+            } else {
+                None
+            },
+            vertical: segment.vertical,
+        };
+        let show_glyphs = |this: &mut Self, glyphs: &[GlyphId], origin| {
+            this.show_glyphs(&font_key, glyphs, *font_size, origin, spacing)
+        };
+        if !font.has_color_glyphs() {
+            show_glyphs(self, &segment.glyphs, *origin);
+            return Ok(());
+        }
+
+        // Show runs of glyphs without a colour version at once,
+        // and paint colour glyphs one by one at the pen position.
+        let mut run_start = 0;
+        let mut run_origin = *origin;
+        let mut pen = *origin;
+        for (i, &glyph_id) in segment.glyphs.iter().enumerate() {
+            let color_glyph = font.color_glyph(glyph_id)?;
+            if let Some(color_glyph) = &color_glyph {
+                let run = &segment.glyphs[run_start..i];
+                if !run.is_empty() {
+                    show_glyphs(self, run, run_origin)
+                }
+                self.paint_color_glyph(font, &font_key, glyph_id, color_glyph, text, pen)?;
+            }
+            let mut advance = if segment.vertical {
+                font.glyph_height(glyph_id)?.get()
+            } else {
+                font.glyph_width(glyph_id)?.get()
+            };
+            advance += spacing.letter;
+            if let Some((space, extra)) = spacing.word {
+                if glyph_id == space {
+                    advance += extra
+                }
+            }
+            if segment.vertical {
+                pen.y += advance * font_size.0
+            } else {
+                pen.x += advance * font_size.0
+            }
+            if color_glyph.is_some() {
+                run_start = i + 1;
+                run_origin = pen;
+            }
+        }
+        let run = &segment.glyphs[run_start..];
+        if !run.is_empty() {
+            show_glyphs(self, run, run_origin)
+        }
+        Ok(())
+    }
+
+    fn show_glyphs(
+        &mut self,
+        font_key: &str,
+        glyphs: &[GlyphId],
+        font_size: Length<CssPx>,
+        origin: Point<CssPx>,
+        spacing: GlyphSpacing,
+    ) {
         // flip the Y axis in to compensate the same flip at the page level.
         let x_scale = font_size.0;
         let y_scale = -font_size.0;
-        let mut glyph_codes = Vec::with_capacity(segment.glyphs.len() * 2);
-        for &GlyphId(id) in &segment.glyphs {
-            // Big-endian
-            glyph_codes.push((id >> 8) as u8);
-            glyph_codes.push(id as u8);
+        // Strings of big-endian glyph codes, split after each space with extra word spacing
+        let mut strings = vec![Vec::with_capacity(glyphs.len() * 2)];
+        for &glyph_id in glyphs {
+            let GlyphId(id) = glyph_id;
+            let codes = strings.last_mut().unwrap();
+            codes.push((id >> 8) as u8);
+            codes.push(id as u8);
+            if spacing.word.map_or(false, |(space, _)| glyph_id == space) {
+                strings.push(Vec::new())
+            }
         }
+        // Spacing is in unscaled text space units, which are em with a font size of 1.
+        // Vertical glyph displacement is downwards, towards negative y.
+        let direction = if spacing.vertical { -1. } else { 1. };
+        // Character spacing applies to every glyph. Word spacing with `Tw` only applies
+        // to the single-byte code 32, which the Identity-H encoding does not have.
+        self.set_character_spacing(spacing.letter * direction);
         op!(self, BEGIN_TEXT);
-        op!(self, TEXT_FONT_AND_SIZE, &*font_key, 1);
+        op!(self, TEXT_FONT_AND_SIZE, font_key, 1);
         op!(
             self,
             TEXT_MATRIX,
@@ -213,7 +349,21 @@ impl<'a> InProgressPage<'a> {
             origin.x,
             origin.y
         );
-        op!(self, SHOW_TEXT, Object::HexString(&glyph_codes));
+        match spacing.word {
+            Some((_, extra)) if strings.len() > 1 => {
+                // Adjustments are in thousandths of text space units, subtracted from the position.
+                let adjustment = -extra * direction * 1000.;
+                let mut array = Vec::with_capacity(strings.len() * 2);
+                for (i, codes) in strings.iter().enumerate() {
+                    if i > 0 {
+                        array.push(Object::Float(adjustment))
+                    }
+                    array.push(Object::HexString(codes))
+                }
+                op!(self, SHOW_TEXT_WITH_POSITIONING, Object::Array(&array));
+            }
+            _ => op!(self, SHOW_TEXT, Object::HexString(&strings[0])),
+        }
         op!(self, END_TEXT);
 
         // https://www.adobe.com/content/dam/acom/en/devnet/pdf/PDF32000_2008.pdf#G8.1910927
@@ -233,12 +383,91 @@ impl<'a> InProgressPage<'a> {
         //   (identifying this subset) and "+"
 
         // Probably won’t use:
-        // Word spacing = character spacing for ASCII space 0x20 single-byte code
         // Leading = height between consecutive baselines
+    }
 
+    /// Paint the layers or bitmap of a colour glyph whose origin is at `pen`.
+    ///
+    /// The glyph itself is shown with invisible text, so that the text can still be extracted.
+    fn paint_color_glyph(
+        &mut self,
+        font: &Arc<Font>,
+        font_key: &str,
+        glyph_id: GlyphId,
+        color_glyph: &ColorGlyph,
+        text: &TextRun,
+        pen: Point<CssPx>,
+    ) -> Result<(), FontError> {
+        let graphics_state = self.graphics_state;
+        op!(self, SAVE_GRAPHICS_STATE);
+        op!(self, TEXT_RENDERING_MODE, INVISIBLE_TEXT);
+        self.show_glyphs(
+            font_key,
+            &[glyph_id],
+            text.font_size,
+            pen,
+            GlyphSpacing::NONE,
+        );
+        op!(self, TEXT_RENDERING_MODE, FILL_TEXT);
+        match color_glyph {
+            ColorGlyph::Layers(layers) => {
+                let (r, g, b) = graphics_state.non_stroking_color_rgb;
+                let text_color = RGBA(r, g, b, graphics_state.alpha);
+                for layer in layers {
+                    self.set_color(&match layer.color {
+                        Some(RGBA(r, g, b, a)) => RGBA(r, g, b, a * graphics_state.alpha),
+                        None => text_color,
+                    });
+                    self.show_glyphs(
+                        font_key,
+                        &[layer.glyph_id],
+                        text.font_size,
+                        pen,
+                        GlyphSpacing::NONE,
+                    )
+                }
+            }
+            ColorGlyph::Bitmap(bitmap) => {
+                if let Some(image_key) = self.add_glyph_image(font, glyph_id, bitmap) {
+                    let font_size = text.font_size.0;
+                    // The horizontal origin of the glyph, in CSS coordinates
+                    let origin = if text.segment.vertical {
+                        point(
+                            pen.x - font.glyph_width(glyph_id)?.get() / 2. * font_size,
+                            pen.y + font.ascender().get() * font_size,
+                        )
+                    } else {
+                        pen
+                    };
+                    // Map the unit square to the image’s bounds, with its first row at the top
+                    let bounds = &bitmap.bounds;
+                    op!(
+                        self,
+                        CURRENT_TRANSFORMATION_MATRIX,
+                        bounds.size.width * font_size,
+                        0,
+                        0,
+                        -bounds.size.height * font_size,
+                        origin.x + bounds.origin.x * font_size,
+                        origin.y - bounds.origin.y * font_size
+                    );
+                    op!(self, PAINT_XOBJECT, &*image_key);
+                }
+            }
+        }
+        op!(self, RESTORE_GRAPHICS_STATE);
+        self.graphics_state = graphics_state;
         Ok(())
     }
 
+    /// The `Tc` text state parameter, in unscaled text space units
+    fn set_character_spacing(&mut self, spacing: f32) {
+        if spacing != self.graphics_state.character_spacing {
+            self.graphics_state.character_spacing = spacing;
+            op!(self, CHARACTER_SPACING, spacing);
+        }
+    }
+
     fn set_alpha(&mut self, alpha: f32) {
         let alpha = alpha.max(0.).min(1.);
         if alpha != self.graphics_state.alpha {
@@ -265,95 +494,79 @@ impl<'a> InProgressPage<'a> {
         }
     }
 
-    fn add_font(&mut self, font: &Arc<Font>) -> Result<String, FontError> {
+    fn add_glyph_image(
+        &mut self,
+        font: &Arc<Font>,
+        glyph_id: GlyphId,
+        bitmap: &GlyphBitmap,
+    ) -> Option<String> {
+        let next_id = self.doc.image_resources.len();
+        let pdf = &mut self.doc.pdf;
+        let resources = &mut self.doc.image_resources;
+        self.doc
+            .glyph_images
+            .entry((ByAddress(font.clone()), glyph_id))
+            .or_insert_with(|| {
+                // Image Dictionaries
+                // https://www.adobe.com/content/dam/acom/en/devnet/pdf/PDF32000_2008.pdf#G7.3801570
+                let image = png::decode(bitmap.png)?;
+                let soft_mask_id = image.alpha.as_ref().map(|alpha| {
+                    pdf.add_stream(
+                        dictionary! {
+                            "Type" => "XObject",
+                            "Subtype" => "Image",
+                            "Width" => image.width as usize,
+                            "Height" => image.height as usize,
+                            "ColorSpace" => "DeviceGray",
+                            "BitsPerComponent" => 8,
+                            "Filter" => "FlateDecode",
+                        },
+                        deflate(alpha).into(),
+                    )
+                });
+                let soft_mask_pairs: Vec<(&[u8], Object)> = soft_mask_id
+                    .map(|id| (&b"SMask"[..], Object::from(id)))
+                    .into_iter()
+                    .collect();
+                let soft_mask = Dictionary {
+                    prev: None,
+                    pairs: &soft_mask_pairs,
+                };
+                let image_id = pdf.add_stream(
+                    Dictionary {
+                        prev: Some(&soft_mask),
+                        pairs: key_value_pairs! {
+                            "Type" => "XObject",
+                            "Subtype" => "Image",
+                            "Width" => image.width as usize,
+                            "Height" => image.height as usize,
+                            "ColorSpace" => "DeviceRGB",
+                            "BitsPerComponent" => 8,
+                            "Filter" => "FlateDecode",
+                        },
+                    },
+                    deflate(&image.rgb).into(),
+                );
+                let pdf_key = format!("im{}", next_id);
+                resources.push((pdf_key.clone().into_bytes(), image_id.into()));
+                Some(pdf_key)
+            })
+            .clone()
+    }
+
+    fn add_font(&mut self, font: &Arc<Font>, vertical: bool) -> Result<String, FontError> {
         let next_id = self.doc.fonts.len();
-        let vacant_entry = match self.doc.fonts.entry(ByAddress(font.clone())) {
+        let vacant_entry = match self.doc.fonts.entry((ByAddress(font.clone()), vertical)) {
             Entry::Occupied(entry) => return Ok(entry.get().clone()),
             Entry::Vacant(entry) => entry,
         };
-        let font_bytes = font.bytes();
-        let truetype_id = self.doc.pdf.add_stream(
-            dictionary! {
-                "Length1" => font_bytes.len(),
-            },
-            font_bytes.into(),
-        );
-        let font_descriptor_id = self.doc.pdf.add_dictionary(dictionary! {
-            "Type" => "FontDescriptor",
-            "FontName" => font.postscript_name(),
-            "FontBBox" => array![
-                font.min_x(),
-                font.min_y(),
-                font.max_x(),
-                font.max_y(),
-            ],
-            "Ascent" => font.ascender(),
-            "Descent" => font.descender(),
-            "FontFile2" => truetype_id,
-
-            // These seem somewhat arbitrary, they’re copied from cairo:
-            "ItalicAngle" => 0,
-            "Flags" => 4,
-            "CapHeight" => font.max_y(),
-            "StemV" => 80,
-            "StemH" => 80,
-        });
-        // Boilerplate based on a PDF generated by cairo
-        let mut to_unicode_cmap = b"\
-            /CIDInit /ProcSet findresource begin\n\
-            12 dict begin\n\
-            begincmap\n\
-            /CIDSystemInfo\n\
-            << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
-            /CMapName /Adobe-Identity-UCS def\n\
-            /CMapType 2 def\n\
-            1 begincodespacerange\n\
-            <0000> <ffff>\n\
-            endcodespacerange\n\
-        "
-        .to_vec();
-        {
-            let mut write_bfchar = |chars: &[char], glyph_ids: &[u16]| {
-                write!(to_unicode_cmap, "{} beginbfchar\n", chars.len()).unwrap();
-                for (ch, glyph_id) in chars.iter().zip(glyph_ids) {
-                    write!(to_unicode_cmap, "<{:04x}> <", glyph_id).unwrap();
-                    for code_unit in ch.encode_utf16(&mut [0, 0]) {
-                        write!(to_unicode_cmap, "{:04x}", code_unit).unwrap()
-                    }
-                    to_unicode_cmap.extend(b">\n");
+        let (font_descriptor_id, to_unicode_id) =
+            match self.doc.font_descriptors.entry(ByAddress(font.clone())) {
+                Entry::Occupied(entry) => *entry.get(),
+                Entry::Vacant(entry) => {
+                    *entry.insert(add_font_descriptor_and_to_unicode(&mut self.doc.pdf, font)?)
                 }
-                to_unicode_cmap.extend(b"endbfchar\n");
             };
-            // Max 100 entries per beginbfchar operator
-            let mut chars = ['\0'; 100];
-            let mut glyph_ids = [0_u16; 100];
-            let mut i = 0;
-            font.each_code_point(|ch, GlyphId(glyph_id)| {
-                if i >= 100 {
-                    write_bfchar(&chars, &glyph_ids);
-                    i = 0
-                }
-                chars[i] = ch;
-                glyph_ids[i] = glyph_id;
-                i += 1;
-            })?;
-            if i > 0 {
-                write_bfchar(&chars[..i], &glyph_ids[..i])
-            }
-        }
-        to_unicode_cmap.extend(
-            b"\
-            endcmap\n\
-            CMapName currentdict /CMap defineresource pop\n\
-            end\n\
-            end\
-        "
-            .as_ref(),
-        );
-        let to_unicode_id = self
-            .doc
-            .pdf
-            .add_stream(dictionary! {}, to_unicode_cmap.into());
         // Type 0 Font Dictionaries
         // https://www.adobe.com/content/dam/acom/en/devnet/pdf/PDF32000_2008.pdf#G8.1859105
 
@@ -362,29 +575,55 @@ impl<'a> InProgressPage<'a> {
         for i in 0..font.glyph_count() {
             glyph_widths.push(Object::from(font.glyph_width(GlyphId(i))?));
         }
+        let mut vertical_metrics = Vec::new();
+        if vertical {
+            // https://www.adobe.com/content/dam/acom/en/devnet/pdf/PDF32000_2008.pdf#G8.1906458
+            // For each glyph: vertical displacement, then the position vector from
+            // the horizontal origin to the vertical origin (centered, at the ascender).
+            vertical_metrics.reserve(font.glyph_count() as usize * 3);
+            for i in 0..font.glyph_count() {
+                let glyph_id = GlyphId(i);
+                vertical_metrics.push(Object::from(-font.glyph_height(glyph_id)?));
+                vertical_metrics.push(Object::from(font.glyph_width(glyph_id)? / 2.));
+                vertical_metrics.push(Object::from(font.ascender()));
+            }
+        }
+        let vertical_metrics = [
+            Object::from(0), // start CID
+            Object::from(&*vertical_metrics),
+        ];
+        let vertical_metrics = dictionary! {
+            "W2" => &vertical_metrics[..],
+        };
         let font_dict_id = self.doc.pdf.add_dictionary(dictionary! {
             "Type" => "Font",
             "Subtype" => "Type0",
             "BaseFont" => font.postscript_name(),
             "ToUnicode" => to_unicode_id,
 
-            // 2-bytes big-endian char codes, horizontal writing mode:
-            "Encoding" => "Identity-H",
-
-            "DescendantFonts" => array![dictionary! {
-                "Type" => "Font",
-                "Subtype" => "CIDFontType2",
-                "BaseFont" => font.postscript_name(),
-                "CIDSystemInfo" => dictionary! {
-                    "Registry" => Object::LiteralString(b"Adobe"),
-                    "Ordering" => Object::LiteralString(b"Identity"),
-                    "Supplement" => 0,
+            // 2-bytes big-endian char codes, horizontal or vertical writing mode:
+            "Encoding" => if vertical { "Identity-V" } else { "Identity-H" },
+
+            "DescendantFonts" => array![Dictionary {
+                prev: if vertical { Some(&vertical_metrics) } else { None },
+                pairs: key_value_pairs! {
+                    "Type" => "Font",
+                    "Subtype" => match font.outline_format() {
+                        OutlineFormat::TrueType => "CIDFontType2",
+                        OutlineFormat::CompactFontFormat => "CIDFontType0",
+                    },
+                    "BaseFont" => font.postscript_name(),
+                    "CIDSystemInfo" => dictionary! {
+                        "Registry" => Object::LiteralString(b"Adobe"),
+                        "Ordering" => Object::LiteralString(b"Identity"),
+                        "Supplement" => 0,
+                    },
+                    "FontDescriptor" => font_descriptor_id,
+                    "W" => array![
+                        0,  // start CID
+                        &*glyph_widths,
+                    ],
                 },
-                "FontDescriptor" => font_descriptor_id,
-                "W" => array![
-                    0,  // start CID
-                    &*glyph_widths,
-                ],
             }],
         });
 
@@ -397,6 +636,128 @@ impl<'a> InProgressPage<'a> {
     }
 }
 
+fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn stem_width(weight_class: u16) -> i32 {
+    let ratio = f32::from(weight_class) / 65.;
+    50 + (ratio * ratio).round() as i32
+}
+
+fn add_font_descriptor_and_to_unicode(
+    pdf: &mut PdfFile,
+    font: &Font,
+) -> Result<(IndirectObjectId, IndirectObjectId), FontError> {
+    // Embedded Font Programs
+    // https://www.adobe.com/content/dam/acom/en/devnet/pdf/PDF32000_2008.pdf#G8.1876221
+    if font.embedding_permissions() == EmbeddingPermissions::Restricted {
+        return Err(FontError::EmbeddingRestricted);
+    }
+    let font_bytes = font.sfnt_bytes()?;
+    let font_length = font_bytes.len();
+    let (font_file_key, font_file_id) = match font.outline_format() {
+        OutlineFormat::TrueType => (
+            "FontFile2",
+            pdf.add_stream(
+                dictionary! {
+                    "Length1" => font_length,
+                },
+                font_bytes,
+            ),
+        ),
+        OutlineFormat::CompactFontFormat => (
+            "FontFile3",
+            pdf.add_stream(
+                dictionary! {
+                    "Subtype" => "OpenType",
+                },
+                font_bytes,
+            ),
+        ),
+    };
+    let font_descriptor_id = pdf.add_dictionary(dictionary! {
+        "Type" => "FontDescriptor",
+        "FontName" => font.postscript_name(),
+        "FontBBox" => array![
+            font.min_x(),
+            font.min_y(),
+            font.max_x(),
+            font.max_y(),
+        ],
+        "Ascent" => font.ascender(),
+        "Descent" => font.descender(),
+        "Leading" => font.ascender() - font.descender() + font.line_gap(),
+        "CapHeight" => font.cap_height(),
+        "XHeight" => font.x_height(),
+        // Fonts don’t record stem widths, estimate them from the weight
+        // like other PDF producers: 88 for normal and 166 for bold.
+        "StemV" => stem_width(font.weight_class()),
+        font_file_key => font_file_id,
+
+        // These seem somewhat arbitrary, they’re copied from cairo:
+        "ItalicAngle" => 0,
+        "Flags" => 4,
+        "StemH" => 80,
+    });
+    // Boilerplate based on a PDF generated by cairo
+    let mut to_unicode_cmap = b"\
+            /CIDInit /ProcSet findresource begin\n\
+            12 dict begin\n\
+            begincmap\n\
+            /CIDSystemInfo\n\
+            << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+            /CMapName /Adobe-Identity-UCS def\n\
+            /CMapType 2 def\n\
+            1 begincodespacerange\n\
+            <0000> <ffff>\n\
+            endcodespacerange\n\
+        "
+    .to_vec();
+    {
+        let mut write_bfchar = |chars: &[char], glyph_ids: &[u16]| {
+            write!(to_unicode_cmap, "{} beginbfchar\n", chars.len()).unwrap();
+            for (ch, glyph_id) in chars.iter().zip(glyph_ids) {
+                write!(to_unicode_cmap, "<{:04x}> <", glyph_id).unwrap();
+                for code_unit in ch.encode_utf16(&mut [0, 0]) {
+                    write!(to_unicode_cmap, "{:04x}", code_unit).unwrap()
+                }
+                to_unicode_cmap.extend(b">\n");
+            }
+            to_unicode_cmap.extend(b"endbfchar\n");
+        };
+        // Max 100 entries per beginbfchar operator
+        let mut chars = ['\0'; 100];
+        let mut glyph_ids = [0_u16; 100];
+        let mut i = 0;
+        font.each_code_point(|ch, GlyphId(glyph_id)| {
+            if i >= 100 {
+                write_bfchar(&chars, &glyph_ids);
+                i = 0
+            }
+            chars[i] = ch;
+            glyph_ids[i] = glyph_id;
+            i += 1;
+        })?;
+        if i > 0 {
+            write_bfchar(&chars[..i], &glyph_ids[..i])
+        }
+    }
+    to_unicode_cmap.extend(
+        b"\
+            endcmap\n\
+            CMapName currentdict /CMap defineresource pop\n\
+            end\n\
+            end\
+        "
+        .as_ref(),
+    );
+    let to_unicode_id = pdf.add_stream(dictionary! {}, to_unicode_cmap.into());
+    Ok((font_descriptor_id, to_unicode_id))
+}
+
 macro_rules! operators {
     ($( $name: ident = $value: expr, )+) => {
         $(
@@ -410,11 +771,17 @@ macro_rules! operators {
 operators! {
     // Graphics State Operators
     // https://www.adobe.com/content/dam/acom/en/devnet/pdf/PDF32000_2008.pdf#G7.3793795
+    SAVE_GRAPHICS_STATE = "q",
+    RESTORE_GRAPHICS_STATE = "Q",
     CURRENT_TRANSFORMATION_MATRIX = "cm",
     EXTENDED_GRAPHICS_STATE = "gs",
 
     // Path Construction and Painting
     // https://www.adobe.com/content/dam/acom/en/devnet/pdf/PDF32000_2008.pdf#G7.1849957
+    MOVE_TO = "m",
+    LINE_TO = "l",
+    CURVE_TO = "c",
+    CLOSE_PATH = "h",
     RECTANGLE = "re",
     FILL = "f",
 
@@ -426,7 +793,19 @@ operators! {
     // https://www.adobe.com/content/dam/acom/en/devnet/pdf/PDF32000_2008.pdf#G8.1910927
     BEGIN_TEXT = "BT",
     END_TEXT = "ET",
+    CHARACTER_SPACING = "Tc",
     TEXT_FONT_AND_SIZE = "Tf",
     TEXT_MATRIX = "Tm",
     SHOW_TEXT = "Tj",
+    SHOW_TEXT_WITH_POSITIONING = "TJ",
+    TEXT_RENDERING_MODE = "Tr",
+
+    // External Objects
+    // https://www.adobe.com/content/dam/acom/en/devnet/pdf/PDF32000_2008.pdf#G7.3852627
+    PAINT_XOBJECT = "Do",
 }
+
+// Text Rendering Modes
+// https://www.adobe.com/content/dam/acom/en/devnet/pdf/PDF32000_2008.pdf#G8.1694856
+const FILL_TEXT: i32 = 0;
+const INVISIBLE_TEXT: i32 = 3;
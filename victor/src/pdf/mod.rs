@@ -1,6 +1,6 @@
 use self::convert::{InProgressDoc, InProgressPage};
 use crate::fonts::FontError;
-use crate::primitives::{CssPx, Rect, Size, TextRun, RGBA};
+use crate::primitives::{CssPx, PathSegment, Rect, Size, TextRun, RGBA};
 use std::fs;
 use std::io::{self, Write};
 use std::path;
@@ -8,6 +8,7 @@ use std::path;
 #[macro_use]
 mod object;
 mod convert;
+mod png;
 mod syntax;
 
 pub struct Document {
@@ -67,6 +68,12 @@ impl<'doc> Page<'doc> {
         self
     }
 
+    /// Close the path and fill it with the nonzero winding rule
+    pub fn fill_path(&mut self, path: &[PathSegment]) -> &mut Self {
+        self.in_progress.fill_path(path);
+        self
+    }
+
     pub fn show_text(&mut self, text: &TextRun) -> Result<&mut Self, FontError> {
         self.in_progress.show_text(text)?;
         Ok(self)
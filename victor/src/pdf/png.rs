@@ -0,0 +1,218 @@
+//! Decoding PNG images into samples that can be embedded in PDF
+//! https://www.w3.org/TR/PNG/
+//!
+//! Interlaced images are not supported.
+
+use std::io::Read;
+
+pub(crate) struct DecodedImage {
+    pub width: u32,
+    pub height: u32,
+
+    /// Red, green, and blue samples, 8 bits each
+    pub rgb: Vec<u8>,
+
+    /// Opacity samples, 8 bits each, or `None` if the image is fully opaque
+    pub alpha: Option<Vec<u8>>,
+}
+
+const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1A\n";
+
+// Color types
+const GRAYSCALE: u8 = 0;
+const TRUECOLOR: u8 = 2;
+const INDEXED: u8 = 3;
+const GRAYSCALE_ALPHA: u8 = 4;
+const TRUECOLOR_ALPHA: u8 = 6;
+
+/// Returns `None` for invalid or unsupported images.
+pub(crate) fn decode(png: &[u8]) -> Option<DecodedImage> {
+    if !png.starts_with(SIGNATURE) {
+        return None;
+    }
+    let mut header = None;
+    let mut palette: &[u8] = &[];
+    let mut transparency: &[u8] = &[];
+    let mut compressed = Vec::new();
+    let mut rest = &png[SIGNATURE.len()..];
+    while rest.len() >= 12 {
+        let length = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+        let chunk_type = &rest[4..8];
+        let data = rest.get(8..8 + length)?;
+        match chunk_type {
+            b"IHDR" => header = Some(data),
+            b"PLTE" => palette = data,
+            b"tRNS" => transparency = data,
+            b"IDAT" => compressed.extend_from_slice(data),
+            b"IEND" => break,
+            _ => {}
+        }
+        // Skip the CRC
+        rest = rest.get(8 + length + 4..)?;
+    }
+
+    let header = header?;
+    if header.len() < 13 {
+        return None;
+    }
+    let width = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+    let height = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+    let bit_depth = header[8];
+    let color_type = header[9];
+    let interlaced = header[12] != 0;
+    let channels = match color_type {
+        GRAYSCALE | INDEXED => 1,
+        GRAYSCALE_ALPHA => 2,
+        TRUECOLOR => 3,
+        TRUECOLOR_ALPHA => 4,
+        _ => return None,
+    };
+    if interlaced || !matches!(bit_depth, 1 | 2 | 4 | 8 | 16) {
+        return None;
+    }
+
+    let bits_per_pixel = channels * bit_depth as usize;
+    let bytes_per_row = (width as usize * bits_per_pixel + 7) / 8;
+    // Filters compare with the corresponding byte of the previous pixel, or of the first one
+    let filter_distance = (bits_per_pixel / 8).max(1);
+    let mut filtered = Vec::new();
+    flate2::read::ZlibDecoder::new(&*compressed)
+        .read_to_end(&mut filtered)
+        .ok()?;
+    if filtered.len() < (bytes_per_row + 1) * height as usize {
+        return None;
+    }
+
+    let pixel_count = width as usize * height as usize;
+    let mut rgb = Vec::with_capacity(pixel_count * 3);
+    let mut alpha = Vec::with_capacity(pixel_count);
+    let mut previous_row = vec![0; bytes_per_row];
+    let mut row = vec![0; bytes_per_row];
+    for filtered_row in filtered.chunks(bytes_per_row + 1).take(height as usize) {
+        unfilter(
+            filtered_row[0],
+            &filtered_row[1..],
+            &previous_row,
+            &mut row,
+            filter_distance,
+        )?;
+
+        for x in 0..width as usize {
+            // Samples at their original bit depth
+            let sample = |channel: usize| -> u16 {
+                let index = x * channels + channel;
+                match bit_depth {
+                    16 => u16::from_be_bytes([row[index * 2], row[index * 2 + 1]]),
+                    8 => u16::from(row[index]),
+                    _ => {
+                        let bit = index * bit_depth as usize;
+                        let shift = 8 - bit_depth as usize - bit % 8;
+                        u16::from(row[bit / 8] >> shift) & ((1 << bit_depth) - 1)
+                    }
+                }
+            };
+            // Scaled to 8 bits
+            let scale = |value: u16| -> u8 {
+                match bit_depth {
+                    16 => (value >> 8) as u8,
+                    _ => (u32::from(value) * 255 / ((1 << bit_depth) - 1)) as u8,
+                }
+            };
+            // For grayscale and truecolor images, `tRNS` contains one transparent color
+            let is_transparent_color = |samples: &[u16]| {
+                transparency.len() >= samples.len() * 2
+                    && samples.iter().enumerate().all(|(i, &sample)| {
+                        u16::from_be_bytes([transparency[i * 2], transparency[i * 2 + 1]]) == sample
+                    })
+            };
+            match color_type {
+                GRAYSCALE => {
+                    let gray = sample(0);
+                    rgb.extend_from_slice(&[scale(gray); 3]);
+                    alpha.push(if is_transparent_color(&[gray]) {
+                        0
+                    } else {
+                        255
+                    })
+                }
+                GRAYSCALE_ALPHA => {
+                    rgb.extend_from_slice(&[scale(sample(0)); 3]);
+                    alpha.push(scale(sample(1)))
+                }
+                TRUECOLOR => {
+                    let samples = [sample(0), sample(1), sample(2)];
+                    rgb.extend(samples.iter().map(|&sample| scale(sample)));
+                    alpha.push(if is_transparent_color(&samples) {
+                        0
+                    } else {
+                        255
+                    })
+                }
+                TRUECOLOR_ALPHA => {
+                    rgb.extend((0..3).map(|channel| scale(sample(channel))));
+                    alpha.push(scale(sample(3)))
+                }
+                _ => {
+                    let index = sample(0) as usize;
+                    rgb.extend_from_slice(palette.get(index * 3..index * 3 + 3)?);
+                    alpha.push(transparency.get(index).cloned().unwrap_or(255))
+                }
+            }
+        }
+        std::mem::swap(&mut row, &mut previous_row);
+    }
+
+    Some(DecodedImage {
+        width,
+        height,
+        rgb,
+        alpha: if alpha.iter().all(|&a| a == 255) {
+            None
+        } else {
+            Some(alpha)
+        },
+    })
+}
+
+/// https://www.w3.org/TR/PNG/#9Filters
+fn unfilter(
+    filter_type: u8,
+    filtered: &[u8],
+    previous: &[u8],
+    row: &mut [u8],
+    distance: usize,
+) -> Option<()> {
+    for i in 0..row.len() {
+        let a = if i >= distance { row[i - distance] } else { 0 };
+        let b = previous[i];
+        let c = if i >= distance {
+            previous[i - distance]
+        } else {
+            0
+        };
+        let predictor = match filter_type {
+            0 => 0,
+            1 => a,
+            2 => b,
+            3 => ((u16::from(a) + u16::from(b)) / 2) as u8,
+            4 => paeth(a, b, c),
+            _ => return None,
+        };
+        row[i] = filtered[i].wrapping_add(predictor)
+    }
+    Some(())
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = i16::from(a) + i16::from(b) - i16::from(c);
+    let pa = (p - i16::from(a)).abs();
+    let pb = (p - i16::from(b)).abs();
+    let pc = (p - i16::from(c)).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
@@ -21,6 +21,14 @@ pub struct TextRun<'a> {
     pub origin: Point<CssPx>,
 }
 
+/// A closed path starts with `MoveTo`
+pub enum PathSegment {
+    MoveTo(Point<CssPx>),
+    LineTo(Point<CssPx>),
+    /// Cubic Bézier curve with two control points, to the last point
+    CurveTo(Point<CssPx>, Point<CssPx>, Point<CssPx>),
+}
+
 impl From<cssparser::RGBA> for RGBA {
     fn from(c: cssparser::RGBA) -> Self {
         RGBA(c.red_f32(), c.green_f32(), c.blue_f32(), c.alpha_f32())
@@ -104,5 +104,14 @@ pub(crate) fn style_for_element(
             matching.author.push(&style_attr_block);
         }
     }
-    ComputedValues::new(parent_style, Some(&matching))
+    let mut style = ComputedValues::new(parent_style, Some(&matching));
+    if let Some(lang) = element.get_attr(&local_name!("lang")) {
+        // An empty value means the language is unknown
+        Arc::make_mut(&mut style).lang = if lang.is_empty() {
+            None
+        } else {
+            Some(lang.into())
+        }
+    }
+    style
 }
@@ -6,6 +6,39 @@ properties! {
 
     inherited struct font {
         @early font_size { "font-size", FontSize, initial = Length { px: 16. } }
+        font_weight { "font-weight", FontWeight, initial = FontWeight::NORMAL }
+        font_stretch { "font-stretch", FontStretch, initial = FontStretch::NORMAL }
+        font_variation_settings {
+            "font-variation-settings", FontVariationSettings, initial = FontVariationSettings::NORMAL
+        }
+        font_variant_caps { "font-variant-caps", FontVariantCaps, initial = FontVariantCaps::Normal }
+        line_height { "line-height", LineHeight, initial = LineHeight::Normal }
+    }
+
+    inherited struct inherited_box {
+        direction { "direction", Direction, initial = Direction::Ltr }
+        writing_mode { "writing-mode", WritingMode, initial = WritingMode::HorizontalTb }
+    }
+
+    inherited struct inherited_text {
+        white_space { "white-space", WhiteSpace, initial = WhiteSpace::Normal }
+        tab_size { "tab-size", TabSize, initial = TabSize::Spaces(8.) }
+        line_break { "line-break", LineBreak, initial = LineBreak::Auto }
+        word_break { "word-break", WordBreak, initial = WordBreak::Normal }
+        overflow_wrap { "overflow-wrap", OverflowWrap, initial = OverflowWrap::Normal }
+        hyphens { "hyphens", Hyphens, initial = Hyphens::Manual }
+        text_align { "text-align", TextAlign, initial = TextAlign::Start }
+        text_align_last { "text-align-last", TextAlignLast, initial = TextAlignLast::Auto }
+        text_indent { "text-indent", TextIndent, initial = TextIndent::INITIAL }
+        letter_spacing { "letter-spacing", Spacing, initial = Spacing::Normal }
+        word_spacing { "word-spacing", Spacing, initial = Spacing::Normal }
+        text_transform { "text-transform", TextTransform, initial = TextTransform::NONE }
+    }
+
+    inherited struct inherited_table {
+        border_collapse { "border-collapse", BorderCollapse, initial = BorderCollapse::Separate }
+        border_spacing { "border-spacing", BorderSpacing, initial = BorderSpacing::ZERO }
+        caption_side { "caption-side", CaptionSide, initial = CaptionSide::Top }
     }
 
     inherited struct color {
@@ -16,13 +49,61 @@ properties! {
     reset struct box_ {
         position { "position", Position, initial = Position::Static }
         float { "float", Float, initial = Float::None }
+        clear { "clear", Clear, initial = Clear::None }
         display { "display", Display, initial = Display::INITIAL }
         top { "top", LengthOrPercentageOrAuto, initial = LengthOrPercentageOrAuto::Auto }
         left { "left", LengthOrPercentageOrAuto, initial = LengthOrPercentageOrAuto::Auto }
         bottom { "bottom", LengthOrPercentageOrAuto, initial = LengthOrPercentageOrAuto::Auto }
         right { "right", LengthOrPercentageOrAuto, initial = LengthOrPercentageOrAuto::Auto }
-        width { "width", LengthOrPercentageOrAuto, initial = LengthOrPercentageOrAuto::Auto }
-        height { "height", LengthOrPercentageOrAuto, initial = LengthOrPercentageOrAuto::Auto }
+        width { "width", Size, initial = Size::Auto }
+        height { "height", Size, initial = Size::Auto }
+    }
+
+    reset struct table {
+        table_layout { "table-layout", TableLayout, initial = TableLayout::Auto }
+    }
+
+    reset struct flex {
+        flex_direction { "flex-direction", FlexDirection, initial = FlexDirection::Row }
+        flex_wrap { "flex-wrap", FlexWrap, initial = FlexWrap::Nowrap }
+        flex_grow { "flex-grow", FlexFactor, initial = FlexFactor(0.) }
+        flex_shrink { "flex-shrink", FlexFactor, initial = FlexFactor(1.) }
+        flex_basis { "flex-basis", FlexBasis, initial = FlexBasis::Size(Size::Auto) }
+        order { "order", Order, initial = Order(0) }
+    }
+
+    reset struct align {
+        justify_content {
+            "justify-content", ContentDistribution, initial = ContentDistribution::Normal
+        }
+        align_content {
+            "align-content", ContentDistribution, initial = ContentDistribution::Normal
+        }
+        align_items { "align-items", ItemAlignment, initial = ItemAlignment::Normal }
+        align_self { "align-self", SelfAlignment, initial = SelfAlignment::Auto }
+        justify_items { "justify-items", ItemAlignment, initial = ItemAlignment::Normal }
+        justify_self { "justify-self", SelfAlignment, initial = SelfAlignment::Auto }
+        row_gap { "row-gap", Gap, initial = Gap::Normal }
+        column_gap { "column-gap", Gap, initial = Gap::Normal }
+    }
+
+    reset struct grid {
+        grid_template_rows { "grid-template-rows", GridTemplate, initial = GridTemplate(None) }
+        grid_template_columns {
+            "grid-template-columns", GridTemplate, initial = GridTemplate(None)
+        }
+        grid_template_areas {
+            "grid-template-areas", GridTemplateAreas, initial = GridTemplateAreas(None)
+        }
+        grid_auto_rows { "grid-auto-rows", GridAutoTracks, initial = GridAutoTracks::auto() }
+        grid_auto_columns {
+            "grid-auto-columns", GridAutoTracks, initial = GridAutoTracks::auto()
+        }
+        grid_auto_flow { "grid-auto-flow", GridAutoFlow, initial = GridAutoFlow::ROW }
+        grid_row_start { "grid-row-start", GridLine, initial = GridLine::Auto }
+        grid_row_end { "grid-row-end", GridLine, initial = GridLine::Auto }
+        grid_column_start { "grid-column-start", GridLine, initial = GridLine::Auto }
+        grid_column_end { "grid-column-end", GridLine, initial = GridLine::Auto }
     }
 
     reset struct margin {
@@ -56,6 +137,24 @@ properties! {
         border_right_width { "border-right-width", LineWidth, initial = LineWidth::MEDIUM }
     }
 
+    reset struct text {
+        unicode_bidi { "unicode-bidi", UnicodeBidi, initial = UnicodeBidi::Normal }
+        vertical_align { "vertical-align", VerticalAlign, initial = VerticalAlign::Baseline }
+        text_decoration_line {
+            "text-decoration-line", TextDecorationLine, initial = TextDecorationLine::NONE
+        }
+        text_decoration_style {
+            "text-decoration-style", TextDecorationStyle, initial = TextDecorationStyle::Solid
+        }
+        text_decoration_color {
+            "text-decoration-color", Color, initial = Color::CurrentColor
+        }
+        text_decoration_thickness {
+            "text-decoration-thickness", TextDecorationThickness,
+            initial = TextDecorationThickness::Auto
+        }
+    }
+
     reset struct background {
         background_color { "background-color", Color, initial = Color::RGBA(RGBA::transparent()) }
     }
@@ -96,8 +195,47 @@ properties! {
             color: border_top_color,
             width: border_top_width,
         }
+        "flex-flow" => FlexFlow {
+            direction: flex_direction,
+            wrap: flex_wrap,
+        }
+        "flex" => Flex {
+            grow: flex_grow,
+            shrink: flex_shrink,
+            basis: flex_basis,
+        }
+        "gap" => Gaps {
+            row: row_gap,
+            column: column_gap,
+        }
+        "grid-row" => GridLines {
+            start: grid_row_start,
+            end: grid_row_end,
+        }
+        "grid-column" => GridLines {
+            start: grid_column_start,
+            end: grid_column_end,
+        }
+        "grid-area" => GridArea {
+            row_start: grid_row_start,
+            column_start: grid_column_start,
+            row_end: grid_row_end,
+            column_end: grid_column_end,
+        }
         "background" => Background {
             color: background_color,
         }
+        "font-variant" => FontVariant {
+            caps: font_variant_caps,
+        }
+        "word-wrap" => WordWrap {
+            overflow_wrap: overflow_wrap,
+        }
+        "text-decoration" => TextDecoration {
+            line: text_decoration_line,
+            style: text_decoration_style,
+            color: text_decoration_color,
+            thickness: text_decoration_thickness,
+        }
     }
 }
@@ -167,6 +167,8 @@ macro_rules! properties {
                 pub(crate) $struct_name: Arc<style_structs::$struct_name>,
             )+
             pub(crate) specified_display: Display,
+            /// From the `lang` attribute of this element or its nearest ancestor that has one
+            pub(crate) lang: Option<Arc<str>>,
         }
 
         impl std::fmt::Debug for ComputedValues {
@@ -205,6 +207,7 @@ macro_rules! properties {
                             ),
                         )+
                         specified_display: Display::INITIAL,
+                        lang: None,
                     };
                 }
                 let inherited = inherited.unwrap_or(&*INITIAL_VALUES);
@@ -217,6 +220,7 @@ macro_rules! properties {
                         $struct_name: Arc::clone(&select!($inherited).$struct_name),
                     )+
                     specified_display: Display::INITIAL,
+                    lang: inherited.lang.clone(),
                 };
                 if let Some(matching) = matching {
                     matching.cascade(&mut crate::style::values::EarlyCascadeContext {
@@ -228,7 +232,7 @@ macro_rules! properties {
                         this: ComputedValuesForLateCascade(&mut computed)
                     });
                 }
-                computed.post_cascade_fixups();
+                computed.post_cascade_fixups(inherited);
                 Arc::new(computed)
             }
         }
@@ -2,6 +2,7 @@ pub(crate) use self::definitions::ComputedValues;
 use self::definitions::LonghandId;
 pub(super) use self::definitions::{property_data_by_name, LonghandDeclaration};
 pub(super) use self::definitions::{ComputedValuesForEarlyCascade, ComputedValuesForLateCascade};
+use crate::fonts::{Font, BITSTREAM_VERA_SANS};
 use crate::geom::{flow_relative, physical};
 use crate::style::errors::PropertyParseError;
 use crate::style::values::{self, CssWideKeyword, Direction, Display, WritingMode};
@@ -19,67 +20,163 @@ impl ComputedValues {
         Self::new(parent_style, None)
     }
 
-    pub(super) fn post_cascade_fixups(&mut self) {
+    /// The style of an anonymous table generated around table-internal boxes
+    /// whose parent is not a table
+    ///
+    /// https://drafts.csswg.org/css2/tables.html#anonymous-boxes
+    pub(crate) fn anonymous_table_inheriting_from(parent_style: &Self, inline: bool) -> Arc<Self> {
+        let mut style = Self::new(Some(parent_style), None);
+        let outside = if inline {
+            values::DisplayOutside::Inline
+        } else {
+            values::DisplayOutside::Block
+        };
+        let display = values::DisplayGeneratingBox::OutsideInside {
+            outside,
+            inside: values::DisplayInside::Table,
+        };
+        Arc::make_mut(&mut Arc::make_mut(&mut style).box_).display =
+            Display::GeneratingBox(display);
+        style
+    }
+
+    pub(super) fn post_cascade_fixups(&mut self, parent_style: &Self) {
         let b = Arc::make_mut(&mut self.border);
         b.border_top_width.fixup(b.border_top_style);
         b.border_left_width.fixup(b.border_left_style);
         b.border_bottom_width.fixup(b.border_bottom_style);
         b.border_right_width.fixup(b.border_right_style);
 
-        Display::fixup(self);
+        Display::fixup(self, parent_style);
+    }
+
+    /// The styles of the table wrapper box and of the table box generated by a table element.
+    /// Properties that position the table among its siblings apply to the wrapper,
+    /// the others to the table box.
+    ///
+    /// https://drafts.csswg.org/css2/tables.html#model
+    pub(crate) fn table_wrapper_and_box(&self) -> (Arc<Self>, Arc<Self>) {
+        let initial = Self::anonymous_inheriting_from(None);
+        let mut wrapper = self.clone();
+        wrapper.padding = initial.padding.clone();
+        wrapper.border = initial.border.clone();
+        wrapper.background = initial.background.clone();
+        // The wrapper has the size of the table’s border box (as if with `box-sizing: border-box`)
+        // in the inline axis, where an auto size is shrink-to-fit.
+        // The table grows past a specified size that is smaller than its contents.
+        let inline_size = match self.box_size(self.writing_mode()).inline {
+            values::Size::Auto => values::Size::FitContent,
+            size => size,
+        };
+        let box_ = Arc::make_mut(&mut wrapper.box_);
+        if self.inherited_box.writing_mode.is_vertical() {
+            box_.width = initial.box_.width;
+            box_.height = inline_size;
+        } else {
+            box_.width = inline_size;
+            box_.height = initial.box_.height;
+        }
+        let mut table = self.clone();
+        table.margin = initial.margin.clone();
+        table.text = initial.text.clone();
+        let box_ = Arc::make_mut(&mut table.box_);
+        box_.position = initial.box_.position;
+        box_.float = initial.box_.float;
+        box_.clear = initial.box_.clear;
+        (Arc::new(wrapper), Arc::new(table))
     }
 
     pub(crate) fn writing_mode(&self) -> (WritingMode, Direction) {
-        // FIXME: For now, this is the only supported mode
-        (WritingMode::HorizontalTb, Direction::Ltr)
+        (
+            self.inherited_box.writing_mode,
+            self.inherited_box.direction,
+        )
     }
 
-    pub(crate) fn box_offsets(&self) -> flow_relative::Sides<values::LengthOrPercentageOrAuto> {
+    // Box offsets, size, padding, border, and margin are mapped to flow-relative sides
+    // in the writing mode of the containing block,
+    // since that is where the box’s fragment is positioned.
+    pub(crate) fn box_offsets(
+        &self,
+        containing_block_mode: (WritingMode, Direction),
+    ) -> flow_relative::Sides<values::LengthOrPercentageOrAuto> {
         physical::Sides {
             top: self.box_.top,
             left: self.box_.left,
             bottom: self.box_.bottom,
             right: self.box_.right,
         }
-        .to_flow_relative(self.writing_mode())
+        .to_flow_relative(containing_block_mode)
     }
 
-    pub(crate) fn box_size(&self) -> flow_relative::Vec2<values::LengthOrPercentageOrAuto> {
+    pub(crate) fn box_size(
+        &self,
+        containing_block_mode: (WritingMode, Direction),
+    ) -> flow_relative::Vec2<values::Size> {
         physical::Vec2 {
             x: self.box_.width,
             y: self.box_.height,
         }
-        .size_to_flow_relative(self.writing_mode())
+        .size_to_flow_relative(containing_block_mode)
     }
 
-    pub(crate) fn padding(&self) -> flow_relative::Sides<values::LengthOrPercentage> {
+    pub(crate) fn padding(
+        &self,
+        containing_block_mode: (WritingMode, Direction),
+    ) -> flow_relative::Sides<values::LengthOrPercentage> {
         physical::Sides {
             top: self.padding.padding_top,
             left: self.padding.padding_left,
             bottom: self.padding.padding_bottom,
             right: self.padding.padding_right,
         }
-        .to_flow_relative(self.writing_mode())
+        .to_flow_relative(containing_block_mode)
     }
 
-    pub(crate) fn border_width(&self) -> flow_relative::Sides<values::LengthOrPercentage> {
+    pub(crate) fn border_width(
+        &self,
+        containing_block_mode: (WritingMode, Direction),
+    ) -> flow_relative::Sides<values::LengthOrPercentage> {
         physical::Sides {
             top: self.border.border_top_width.0,
             left: self.border.border_left_width.0,
             bottom: self.border.border_bottom_width.0,
             right: self.border.border_right_width.0,
         }
-        .to_flow_relative(self.writing_mode())
+        .to_flow_relative(containing_block_mode)
     }
 
-    pub(crate) fn margin(&self) -> flow_relative::Sides<values::LengthOrPercentageOrAuto> {
+    pub(crate) fn margin(
+        &self,
+        containing_block_mode: (WritingMode, Direction),
+    ) -> flow_relative::Sides<values::LengthOrPercentageOrAuto> {
         physical::Sides {
             top: self.margin.margin_top,
             left: self.margin.margin_left,
             bottom: self.margin.margin_bottom,
             right: self.margin.margin_right,
         }
-        .to_flow_relative(self.writing_mode())
+        .to_flow_relative(containing_block_mode)
+    }
+
+    /// Axis values for `Font::instantiate`, with later settings taking precedence
+    ///
+    /// https://drafts.csswg.org/css-fonts-4/#font-feature-variation-resolution
+    fn font_variations(&self) -> Vec<([u8; 4], f32)> {
+        let mut variations = vec![
+            (*b"wght", self.font.font_weight.0),
+            (*b"wdth", self.font.font_stretch.0),
+        ];
+        variations.extend_from_slice(&self.font.font_variation_settings.0);
+        variations
+    }
+
+    /// The font for text in this style, at the position in its design space from
+    /// `font_variations`, or at the default position if that instance cannot be created.
+    pub(crate) fn font(&self) -> Arc<Font> {
+        BITSTREAM_VERA_SANS
+            .instantiate(&self.font_variations())
+            .unwrap_or_else(|_| BITSTREAM_VERA_SANS.clone())
     }
 
     pub(crate) fn to_rgba(&self, color: Color) -> RGBA {
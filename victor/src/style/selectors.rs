@@ -1,10 +1,11 @@
 use crate::dom::{Document, Node, NodeId};
 use crate::style::errors::RuleParseErrorKind;
-use cssparser::ToCss;
+use cssparser::{CowRcStr, ParseError, SourceLocation, ToCss};
 use html5ever::{LocalName, Namespace, Prefix};
 use selectors::attr::{AttrSelectorOperation, CaseSensitivity, NamespaceConstraint};
 use selectors::context::{MatchingContext, MatchingMode, QuirksMode};
 use selectors::matching::{matches_selector, ElementSelectorFlags};
+use selectors::parser::SelectorParseErrorKind;
 use std::fmt;
 
 pub(super) type SelectorList = selectors::SelectorList<Impl>;
@@ -33,12 +34,17 @@ pub(super) struct Parser;
 pub(super) enum PseudoElement {}
 
 #[derive(Clone, PartialEq, Eq)]
-pub(super) enum PseudoClass {}
+pub(super) enum PseudoClass {
+    /// https://html.spec.whatwg.org/multipage/semantics-other.html#selector-link
+    Link,
+    /// There is no browsing history, so this never matches
+    Visited,
+}
 
 impl selectors::parser::NonTSPseudoClass for PseudoClass {
     type Impl = Impl;
     fn is_active_or_hover(&self) -> bool {
-        match *self {}
+        false
     }
 }
 
@@ -59,6 +65,23 @@ impl selectors::parser::SelectorImpl for Impl {
 impl<'i> selectors::parser::Parser<'i> for Parser {
     type Impl = Impl;
     type Error = RuleParseErrorKind<'i>;
+
+    fn parse_non_ts_pseudo_class(
+        &self,
+        location: SourceLocation,
+        name: CowRcStr<'i>,
+    ) -> Result<PseudoClass, ParseError<'i, Self::Error>> {
+        match_ignore_ascii_case! { &name,
+            "link" => return Ok(PseudoClass::Link),
+            "visited" => return Ok(PseudoClass::Visited),
+            _ => {}
+        }
+        Err(
+            location.new_custom_error(SelectorParseErrorKind::UnsupportedPseudoClassOrElement(
+                name,
+            )),
+        )
+    }
 }
 
 impl selectors::parser::PseudoElement for PseudoElement {
@@ -75,11 +98,14 @@ impl ToCss for PseudoElement {
 }
 
 impl ToCss for PseudoClass {
-    fn to_css<W>(&self, _dest: &mut W) -> fmt::Result
+    fn to_css<W>(&self, dest: &mut W) -> fmt::Result
     where
         W: fmt::Write,
     {
-        match *self {}
+        dest.write_str(match *self {
+            PseudoClass::Link => ":link",
+            PseudoClass::Visited => ":visited",
+        })
     }
 }
 
@@ -195,7 +221,10 @@ impl<'a> selectors::Element for NodeRef<'a> {
     where
         F: FnMut(&Self, ElementSelectorFlags),
     {
-        match *pseudo_class {}
+        match *pseudo_class {
+            PseudoClass::Link => self.is_link(),
+            PseudoClass::Visited => false,
+        }
     }
 
     fn match_pseudo_element(
@@ -0,0 +1,100 @@
+use super::length::{Length, Percentage, SpecifiedLength};
+use crate::style::errors::PropertyParseError;
+use cssparser::Parser;
+
+/// The value of `justify-content` or `align-content`
+///
+/// https://drafts.csswg.org/css-align-3/#align-justify-content
+#[derive(Copy, Clone, Debug, Eq, Parse, PartialEq, SpecifiedAsComputed)]
+pub(crate) enum ContentDistribution {
+    Normal,
+    Start,
+    End,
+    FlexStart,
+    FlexEnd,
+    Center,
+    SpaceBetween,
+    SpaceAround,
+    SpaceEvenly,
+    Stretch,
+}
+
+/// The value of `align-items` or `justify-items`
+///
+/// https://drafts.csswg.org/css-align-3/#align-items-property
+#[derive(Copy, Clone, Debug, Eq, Parse, PartialEq, SpecifiedAsComputed)]
+pub(crate) enum ItemAlignment {
+    Normal,
+    Stretch,
+    Start,
+    End,
+    FlexStart,
+    FlexEnd,
+    SelfStart,
+    SelfEnd,
+    Center,
+    Baseline,
+}
+
+/// The value of `align-self` or `justify-self`
+///
+/// https://drafts.csswg.org/css-align-3/#align-self-property
+#[derive(Copy, Clone, Debug, Eq, Parse, PartialEq, SpecifiedAsComputed)]
+pub(crate) enum SelfAlignment {
+    Auto,
+    Item(ItemAlignment),
+}
+
+/// The value of `row-gap` or `column-gap`
+///
+/// https://drafts.csswg.org/css-align-3/#column-row-gap
+#[derive(Copy, Clone, Debug, FromSpecified, FromVariants)]
+pub(crate) enum Gap {
+    Normal,
+    Length(Length),
+    Percentage(Percentage),
+}
+
+#[derive(Clone, Parse, FromVariants)]
+pub(in crate::style) enum SpecifiedGap {
+    Normal,
+    Length(SpecifiedLength),
+    Percentage(Percentage),
+}
+
+/// https://drafts.csswg.org/css-align-3/#gap-shorthand
+pub(in crate::style) struct Gaps {
+    pub row: SpecifiedGap,
+    pub column: SpecifiedGap,
+}
+
+impl SelfAlignment {
+    /// `auto` behaves as the `align-items` or `justify-items` of the parent box
+    pub fn or_items(self, items: ItemAlignment) -> ItemAlignment {
+        match self {
+            SelfAlignment::Auto => items,
+            SelfAlignment::Item(alignment) => alignment,
+        }
+    }
+}
+
+impl Gap {
+    /// “normal” is zero in flex and grid layout, the only ones supported
+    pub fn percentage_relative_to(&self, reference: Length) -> Length {
+        match *self {
+            Gap::Normal => Length::zero(),
+            Gap::Length(length) => length,
+            Gap::Percentage(percentage) => reference * percentage,
+        }
+    }
+}
+
+impl super::Parse for Gaps {
+    fn parse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Self, PropertyParseError<'i>> {
+        let row: SpecifiedGap = super::Parse::parse(parser)?;
+        let column = parser
+            .r#try(super::Parse::parse)
+            .unwrap_or_else(|_| row.clone());
+        Ok(Gaps { row, column })
+    }
+}
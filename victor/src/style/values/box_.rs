@@ -23,8 +23,7 @@ pub(crate) enum DisplayGeneratingBox {
         inside: DisplayInside,
         // list_item: bool,
     },
-    // Layout-internal display types go here:
-    // https://drafts.csswg.org/css-display-3/#layout-specific-display
+    LayoutInternal(DisplayLayoutInternal),
 }
 
 /// https://drafts.csswg.org/css-display-3/#outer-role
@@ -39,6 +38,22 @@ pub(crate) enum DisplayOutside {
 pub(crate) enum DisplayInside {
     Flow,
     FlowRoot,
+    Table,
+    Flex,
+    Grid,
+}
+
+/// https://drafts.csswg.org/css-display-3/#layout-specific-display
+#[derive(Copy, Clone, Eq, Parse, PartialEq)]
+pub(crate) enum DisplayLayoutInternal {
+    TableRowGroup,
+    TableHeaderGroup,
+    TableFooterGroup,
+    TableRow,
+    TableCell,
+    TableColumnGroup,
+    TableColumn,
+    TableCaption,
 }
 
 impl Display {
@@ -57,16 +72,39 @@ impl Display {
                         inside,
                     }
                 }
-                // other => other,
+                // “If a layout-internal box is blockified,
+                //  its inner display type converts to flow”
+                DisplayGeneratingBox::LayoutInternal(_) => DisplayGeneratingBox::OutsideInside {
+                    outside: DisplayOutside::Block,
+                    inside: DisplayInside::Flow,
+                },
             }),
             other => other,
         }
     }
 
+    fn is_flex_or_grid_container(&self) -> bool {
+        matches!(
+            self,
+            Display::GeneratingBox(DisplayGeneratingBox::OutsideInside {
+                inside: DisplayInside::Flex,
+                ..
+            }) | Display::GeneratingBox(DisplayGeneratingBox::OutsideInside {
+                inside: DisplayInside::Grid,
+                ..
+            })
+        )
+    }
+
     /// https://drafts.csswg.org/css2/visuren.html#dis-pos-flo
-    pub fn fixup(style: &mut ComputedValues) {
+    /// https://drafts.csswg.org/css-flexbox-1/#flex-items
+    /// https://drafts.csswg.org/css-grid-1/#grid-item-display
+    pub fn fixup(style: &mut ComputedValues, parent_style: &ComputedValues) {
         style.specified_display = style.box_.display;
-        if style.box_.position.is_absolutely_positioned() || style.box_.float.is_floating() {
+        if style.box_.position.is_absolutely_positioned()
+            || style.box_.float.is_floating()
+            || parent_style.box_.display.is_flex_or_grid_container()
+        {
             let display = style.box_.display.blockify();
             if display != style.box_.display {
                 Arc::make_mut(&mut style.box_).display = display
@@ -77,33 +115,81 @@ impl Display {
 
 impl super::Parse for Display {
     fn parse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Self, PropertyParseError<'i>> {
-        let ident = parser.expect_ident()?;
-        match &**ident {
-            "none" => Ok(Display::None),
-            "contents" => Ok(Display::Contents),
-            "block" => Ok(Display::GeneratingBox(
-                DisplayGeneratingBox::OutsideInside {
-                    outside: DisplayOutside::Block,
-                    inside: DisplayInside::Flow,
-                },
-            )),
-            "flow-root" => Ok(Display::GeneratingBox(
-                DisplayGeneratingBox::OutsideInside {
-                    outside: DisplayOutside::Block,
-                    inside: DisplayInside::FlowRoot,
-                },
-            )),
-            "inline" => Ok(Display::GeneratingBox(
-                DisplayGeneratingBox::OutsideInside {
-                    outside: DisplayOutside::Inline,
-                    inside: DisplayInside::Flow,
-                },
-            )),
-            _ => {
-                let token = cssparser::Token::Ident(ident.clone());
-                Err(parser.new_unexpected_token_error(token))
+        if let Ok(internal) = parser.r#try(<DisplayLayoutInternal as super::Parse>::parse) {
+            return Ok(Display::GeneratingBox(
+                DisplayGeneratingBox::LayoutInternal(internal),
+            ));
+        }
+        let mut location = parser.current_source_location();
+        let mut ident = parser.expect_ident()?.clone();
+        match &*ident {
+            "none" => return Ok(Display::None),
+            "contents" => return Ok(Display::Contents),
+            "inline-block" => {
+                return Ok(Display::GeneratingBox(
+                    DisplayGeneratingBox::OutsideInside {
+                        outside: DisplayOutside::Inline,
+                        inside: DisplayInside::FlowRoot,
+                    },
+                ))
+            }
+            "inline-table" => {
+                return Ok(Display::GeneratingBox(
+                    DisplayGeneratingBox::OutsideInside {
+                        outside: DisplayOutside::Inline,
+                        inside: DisplayInside::Table,
+                    },
+                ))
+            }
+            "inline-flex" => {
+                return Ok(Display::GeneratingBox(
+                    DisplayGeneratingBox::OutsideInside {
+                        outside: DisplayOutside::Inline,
+                        inside: DisplayInside::Flex,
+                    },
+                ))
+            }
+            "inline-grid" => {
+                return Ok(Display::GeneratingBox(
+                    DisplayGeneratingBox::OutsideInside {
+                        outside: DisplayOutside::Inline,
+                        inside: DisplayInside::Grid,
+                    },
+                ))
+            }
+            _ => {}
+        }
+        // One or two keywords, in any order
+        // https://drafts.csswg.org/css-display-3/#display-value-summary
+        let mut outside = None;
+        let mut inside = None;
+        loop {
+            match &*ident {
+                "block" if outside.is_none() => outside = Some(DisplayOutside::Block),
+                "inline" if outside.is_none() => outside = Some(DisplayOutside::Inline),
+                "flow" if inside.is_none() => inside = Some(DisplayInside::Flow),
+                "flow-root" if inside.is_none() => inside = Some(DisplayInside::FlowRoot),
+                "table" if inside.is_none() => inside = Some(DisplayInside::Table),
+                "flex" if inside.is_none() => inside = Some(DisplayInside::Flex),
+                "grid" if inside.is_none() => inside = Some(DisplayInside::Grid),
+                _ => {
+                    let token = cssparser::Token::Ident(ident.clone());
+                    return Err(location.new_unexpected_token_error(token));
+                }
+            }
+            location = parser.current_source_location();
+            match parser.r#try(|parser| parser.expect_ident().cloned()) {
+                Ok(next) => ident = next,
+                Err(_) => break,
             }
         }
+        Ok(Display::GeneratingBox(
+            DisplayGeneratingBox::OutsideInside {
+                // `flow-root` alone is block-level
+                outside: outside.unwrap_or(DisplayOutside::Block),
+                inside: inside.unwrap_or(DisplayInside::Flow),
+            },
+        ))
     }
 }
 
@@ -124,6 +210,15 @@ impl Float {
     }
 }
 
+/// https://drafts.csswg.org/css2/visuren.html#propdef-clear
+#[derive(Copy, Clone, Eq, Parse, PartialEq, SpecifiedAsComputed)]
+pub(crate) enum Clear {
+    None,
+    Left,
+    Right,
+    Both,
+}
+
 /// https://drafts.csswg.org/css-position-3/#position-property
 #[derive(Copy, Clone, Eq, Parse, PartialEq, SpecifiedAsComputed)]
 pub(crate) enum Position {
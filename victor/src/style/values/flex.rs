@@ -0,0 +1,160 @@
+use super::length::{Length, Size, SpecifiedLength, SpecifiedSize};
+use crate::style::errors::PropertyParseError;
+use cssparser::{Parser, Token};
+
+/// https://drafts.csswg.org/css-flexbox-1/#flex-direction-property
+#[derive(Copy, Clone, Debug, Eq, Parse, PartialEq, SpecifiedAsComputed)]
+pub(crate) enum FlexDirection {
+    Row,
+    RowReverse,
+    Column,
+    ColumnReverse,
+}
+
+/// https://drafts.csswg.org/css-flexbox-1/#flex-wrap-property
+#[derive(Copy, Clone, Debug, Eq, Parse, PartialEq, SpecifiedAsComputed)]
+pub(crate) enum FlexWrap {
+    Nowrap,
+    Wrap,
+    WrapReverse,
+}
+
+/// The value of `flex-grow` or `flex-shrink`, a non-negative number
+///
+/// https://drafts.csswg.org/css-flexbox-1/#flex-grow-property
+#[derive(Copy, Clone, Debug, SpecifiedAsComputed)]
+pub(crate) struct FlexFactor(pub f32);
+
+/// https://drafts.csswg.org/css-flexbox-1/#flex-basis-property
+#[derive(Copy, Clone, Debug, FromSpecified)]
+pub(crate) enum FlexBasis {
+    /// https://drafts.csswg.org/css-flexbox-1/#valdef-flex-basis-content
+    Content,
+    /// With `auto`, the value of `width` or `height` in the main axis is used instead
+    Size(Size),
+}
+
+#[derive(Clone, Parse)]
+pub(in crate::style) enum SpecifiedFlexBasis {
+    Content,
+    Size(SpecifiedSize),
+}
+
+/// https://drafts.csswg.org/css-display-3/#order-property
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd, SpecifiedAsComputed)]
+pub(crate) struct Order(pub i32);
+
+/// https://drafts.csswg.org/css-flexbox-1/#flex-flow-property
+pub(in crate::style) struct FlexFlow {
+    pub direction: Option<FlexDirection>,
+    pub wrap: Option<FlexWrap>,
+}
+
+/// https://drafts.csswg.org/css-flexbox-1/#flex-property
+///
+/// Components omitted from the shorthand are not set to their initial values,
+/// so all fields are set.
+pub(in crate::style) struct Flex {
+    pub grow: FlexFactor,
+    pub shrink: FlexFactor,
+    pub basis: SpecifiedFlexBasis,
+}
+
+impl super::Parse for FlexFactor {
+    fn parse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Self, PropertyParseError<'i>> {
+        match parser.next()? {
+            Token::Number { value, .. } if *value >= 0. => Ok(FlexFactor(*value)),
+            token => {
+                let t = token.clone();
+                Err(parser.new_unexpected_token_error(t))
+            }
+        }
+    }
+}
+
+impl super::Parse for Order {
+    fn parse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Self, PropertyParseError<'i>> {
+        match parser.next()? {
+            Token::Number {
+                int_value: Some(value),
+                ..
+            } => Ok(Order(*value)),
+            token => {
+                let t = token.clone();
+                Err(parser.new_unexpected_token_error(t))
+            }
+        }
+    }
+}
+
+impl super::Parse for FlexFlow {
+    fn parse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Self, PropertyParseError<'i>> {
+        let mut direction = None;
+        let mut wrap = None;
+        loop {
+            if direction.is_none() {
+                if let Ok(value) = parser.r#try(super::Parse::parse) {
+                    direction = Some(value);
+                    continue;
+                }
+            }
+            if wrap.is_none() {
+                if let Ok(value) = parser.r#try(super::Parse::parse) {
+                    wrap = Some(value);
+                    continue;
+                }
+            }
+            break;
+        }
+        if direction.is_none() && wrap.is_none() {
+            return Err(parser.new_error_for_next_token());
+        }
+        Ok(FlexFlow { direction, wrap })
+    }
+}
+
+impl super::Parse for Flex {
+    fn parse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Self, PropertyParseError<'i>> {
+        if parser.r#try(|p| p.expect_ident_matching("none")).is_ok() {
+            return Ok(Flex {
+                grow: FlexFactor(0.),
+                shrink: FlexFactor(0.),
+                basis: SpecifiedFlexBasis::Size(SpecifiedSize::Auto),
+            });
+        }
+        let mut factors = None;
+        let mut basis = None;
+        loop {
+            if factors.is_none() {
+                if let Ok(grow) = parser.r#try(<FlexFactor as super::Parse>::parse) {
+                    let shrink = parser.r#try(<FlexFactor as super::Parse>::parse).ok();
+                    factors = Some((grow, shrink));
+                    continue;
+                }
+            }
+            if basis.is_none() {
+                if let Ok(value) = parser.r#try(super::Parse::parse) {
+                    basis = Some(value);
+                    continue;
+                }
+            }
+            break;
+        }
+        if factors.is_none() && basis.is_none() {
+            return Err(parser.new_error_for_next_token());
+        }
+        // “When omitted from the flex shorthand, [flex-grow and flex-shrink’s]
+        //  specified value is 1. […] When omitted from the flex shorthand,
+        //  [flex-basis’s] specified value is 0.”
+        let (grow, shrink) = factors.unwrap_or((FlexFactor(1.), None));
+        Ok(Flex {
+            grow,
+            shrink: shrink.unwrap_or(FlexFactor(1.)),
+            basis: basis.unwrap_or_else(|| {
+                SpecifiedFlexBasis::Size(SpecifiedSize::Length(SpecifiedLength::Absolute(
+                    Length::zero(),
+                )))
+            }),
+        })
+    }
+}
@@ -1,4 +1,8 @@
-use super::{EarlyCascadeContext, EarlyFromSpecified, Length, SpecifiedLength, SpecifiedValue};
+use super::SpecifiedValue;
+use super::{CascadeContext, EarlyCascadeContext, EarlyFromSpecified, FromSpecified, Parse};
+use super::{Length, LengthOrPercentage, SpecifiedLength, SpecifiedLengthOrPercentage};
+use crate::style::errors::PropertyParseError;
+use cssparser::{Parser, Token};
 
 #[derive(Copy, Clone)]
 pub(crate) struct FontSize(pub Length);
@@ -38,3 +42,233 @@ impl std::ops::Div<FontSize> for Length {
         Em::new(self.px / other.0.px)
     }
 }
+
+/// https://drafts.csswg.org/css-fonts-4/#font-weight-prop
+#[derive(Copy, Clone, PartialEq)]
+pub(crate) struct FontWeight(pub f32);
+
+#[derive(Clone)]
+pub(in crate::style) enum SpecifiedFontWeight {
+    Absolute(FontWeight),
+    Bolder,
+    Lighter,
+}
+
+impl FontWeight {
+    pub(in crate::style) const NORMAL: Self = FontWeight(400.);
+    const BOLD: Self = FontWeight(700.);
+}
+
+impl SpecifiedValue for FontWeight {
+    type SpecifiedValue = SpecifiedFontWeight;
+}
+
+impl Parse for SpecifiedFontWeight {
+    fn parse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Self, PropertyParseError<'i>> {
+        match parser.next()? {
+            Token::Number { value, .. } if 1. <= *value && *value <= 1000. => {
+                Ok(SpecifiedFontWeight::Absolute(FontWeight(*value)))
+            }
+            Token::Ident(ident) => match_ignore_ascii_case!(ident,
+                "normal" => Ok(SpecifiedFontWeight::Absolute(FontWeight::NORMAL)),
+                "bold" => Ok(SpecifiedFontWeight::Absolute(FontWeight::BOLD)),
+                "bolder" => Ok(SpecifiedFontWeight::Bolder),
+                "lighter" => Ok(SpecifiedFontWeight::Lighter),
+                _ => {
+                    let t = Token::Ident(ident.clone());
+                    Err(parser.new_unexpected_token_error(t))
+                }
+            ),
+            token => {
+                let t = token.clone();
+                Err(parser.new_unexpected_token_error(t))
+            }
+        }
+    }
+}
+
+impl FromSpecified for FontWeight {
+    /// https://drafts.csswg.org/css-fonts-4/#relative-weights
+    fn from_specified(s: &SpecifiedFontWeight, context: &CascadeContext) -> Self {
+        let inherited = context.inherited.font.font_weight.0;
+        FontWeight(match s {
+            SpecifiedFontWeight::Absolute(weight) => return *weight,
+            SpecifiedFontWeight::Bolder if inherited < 350. => 400.,
+            SpecifiedFontWeight::Bolder if inherited < 550. => 700.,
+            SpecifiedFontWeight::Bolder => inherited.max(900.),
+            SpecifiedFontWeight::Lighter if inherited < 100. => inherited,
+            SpecifiedFontWeight::Lighter if inherited < 550. => 100.,
+            SpecifiedFontWeight::Lighter if inherited < 750. => 400.,
+            SpecifiedFontWeight::Lighter => 700.,
+        })
+    }
+}
+
+/// https://drafts.csswg.org/css-fonts-4/#font-stretch-prop
+///
+/// As a percentage of the normal width
+#[derive(Copy, Clone, PartialEq, SpecifiedAsComputed)]
+pub(crate) struct FontStretch(pub f32);
+
+impl FontStretch {
+    pub(in crate::style) const NORMAL: Self = FontStretch(100.);
+}
+
+impl Parse for FontStretch {
+    fn parse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Self, PropertyParseError<'i>> {
+        match parser.next()? {
+            Token::Percentage { unit_value, .. } if *unit_value >= 0. => {
+                Ok(FontStretch(*unit_value * 100.))
+            }
+            Token::Ident(ident) => match_ignore_ascii_case!(ident,
+                "normal" => Ok(FontStretch::NORMAL),
+                "ultra-condensed" => Ok(FontStretch(50.)),
+                "extra-condensed" => Ok(FontStretch(62.5)),
+                "condensed" => Ok(FontStretch(75.)),
+                "semi-condensed" => Ok(FontStretch(87.5)),
+                "semi-expanded" => Ok(FontStretch(112.5)),
+                "expanded" => Ok(FontStretch(125.)),
+                "extra-expanded" => Ok(FontStretch(150.)),
+                "ultra-expanded" => Ok(FontStretch(200.)),
+                _ => {
+                    let t = Token::Ident(ident.clone());
+                    Err(parser.new_unexpected_token_error(t))
+                }
+            ),
+            token => {
+                let t = token.clone();
+                Err(parser.new_unexpected_token_error(t))
+            }
+        }
+    }
+}
+
+/// https://drafts.csswg.org/css-fonts-4/#font-variation-settings-def
+///
+/// Empty for `normal`
+#[derive(Clone, SpecifiedAsComputed)]
+pub(crate) struct FontVariationSettings(pub Vec<([u8; 4], f32)>);
+
+impl FontVariationSettings {
+    pub(in crate::style) const NORMAL: Self = FontVariationSettings(Vec::new());
+}
+
+impl Parse for FontVariationSettings {
+    fn parse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Self, PropertyParseError<'i>> {
+        if parser
+            .r#try(|parser| parser.expect_ident_matching("normal"))
+            .is_ok()
+        {
+            return Ok(FontVariationSettings::NORMAL);
+        }
+        parser
+            .parse_comma_separated(|parser| {
+                let location = parser.current_source_location();
+                let tag = parser.expect_string()?.clone();
+                // Exactly four printable ASCII characters
+                let bytes = tag.as_bytes();
+                if bytes.len() != 4 || bytes.iter().any(|b| !(b' '..=b'~').contains(b)) {
+                    return Err(location.new_unexpected_token_error(Token::QuotedString(tag)));
+                }
+                let value = parser.expect_number()?;
+                Ok(([bytes[0], bytes[1], bytes[2], bytes[3]], value))
+            })
+            .map(FontVariationSettings)
+    }
+}
+
+/// https://drafts.csswg.org/css-fonts-4/#font-variant-caps-prop
+#[derive(Debug, Copy, Clone, Eq, PartialEq, SpecifiedAsComputed)]
+pub(crate) enum FontVariantCaps {
+    Normal,
+    SmallCaps,
+    AllSmallCaps,
+}
+
+impl Parse for FontVariantCaps {
+    fn parse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Self, PropertyParseError<'i>> {
+        let location = parser.current_source_location();
+        let ident = parser.expect_ident()?;
+        match_ignore_ascii_case!(ident,
+            "normal" => Ok(FontVariantCaps::Normal),
+            "small-caps" => Ok(FontVariantCaps::SmallCaps),
+            "all-small-caps" => Ok(FontVariantCaps::AllSmallCaps),
+            _ => {
+                let t = Token::Ident(ident.clone());
+                Err(location.new_unexpected_token_error(t))
+            }
+        )
+    }
+}
+
+/// The `font-variant` shorthand, limited to the values of `font-variant-caps`
+pub(in crate::style) struct FontVariant {
+    pub caps: FontVariantCaps,
+}
+
+impl Parse for FontVariant {
+    fn parse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Self, PropertyParseError<'i>> {
+        Ok(FontVariant {
+            caps: FontVariantCaps::parse(parser)?,
+        })
+    }
+}
+
+/// https://drafts.csswg.org/css2/visudet.html#propdef-line-height
+#[derive(Copy, Clone)]
+pub(crate) enum LineHeight {
+    Normal,
+    /// Multiplied by the font size of each element, so it is inherited as a number
+    Number(f32),
+    Length(Length),
+}
+
+#[derive(Clone)]
+pub(in crate::style) enum SpecifiedLineHeight {
+    Normal,
+    Number(f32),
+    LengthOrPercentage(SpecifiedLengthOrPercentage),
+}
+
+impl SpecifiedValue for LineHeight {
+    type SpecifiedValue = SpecifiedLineHeight;
+}
+
+impl Parse for SpecifiedLineHeight {
+    fn parse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Self, PropertyParseError<'i>> {
+        if parser
+            .r#try(|parser| parser.expect_ident_matching("normal"))
+            .is_ok()
+        {
+            return Ok(SpecifiedLineHeight::Normal);
+        }
+        let number = parser.r#try(|parser| -> Result<_, PropertyParseError<'i>> {
+            match parser.next()? {
+                Token::Number { value, .. } if *value >= 0. => Ok(*value),
+                token => {
+                    let t = token.clone();
+                    Err(parser.new_unexpected_token_error(t))
+                }
+            }
+        });
+        if let Ok(number) = number {
+            return Ok(SpecifiedLineHeight::Number(number));
+        }
+        Ok(SpecifiedLineHeight::LengthOrPercentage(Parse::parse(
+            parser,
+        )?))
+    }
+}
+
+impl FromSpecified for LineHeight {
+    fn from_specified(s: &SpecifiedLineHeight, context: &CascadeContext) -> Self {
+        match s {
+            SpecifiedLineHeight::Normal => LineHeight::Normal,
+            SpecifiedLineHeight::Number(number) => LineHeight::Number(*number),
+            SpecifiedLineHeight::LengthOrPercentage(l) => LineHeight::Length(
+                LengthOrPercentage::from_specified(l, context)
+                    .percentage_relative_to(context.this.font_size().0),
+            ),
+        }
+    }
+}
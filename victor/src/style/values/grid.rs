@@ -0,0 +1,679 @@
+use super::length::{Length, Percentage, SpecifiedLength};
+use super::{CascadeContext, SpecifiedValue};
+use crate::style::errors::PropertyParseError;
+use cssparser::{Parser, Token};
+use std::ops::Range;
+use std::sync::Arc;
+
+/// https://drafts.csswg.org/css-grid-1/#typedef-track-breadth
+#[derive(Copy, Clone, Debug, FromSpecified)]
+pub(crate) enum TrackBreadth {
+    Length(Length),
+    Percentage(Percentage),
+    /// Only as the maximum of a track size
+    Flex(Fr),
+    MinContent,
+    MaxContent,
+    Auto,
+}
+
+#[derive(Clone, Parse)]
+pub(in crate::style) enum SpecifiedTrackBreadth {
+    Length(SpecifiedLength),
+    Percentage(Percentage),
+    Flex(Fr),
+    MinContent,
+    MaxContent,
+    Auto,
+}
+
+/// A flexible length, a fraction of the leftover space in the grid container
+///
+/// https://drafts.csswg.org/css-grid-1/#fr-unit
+#[derive(Copy, Clone, Debug, SpecifiedAsComputed)]
+pub(crate) struct Fr(pub f32);
+
+/// A single breadth `b` is `minmax(b, b)`, or `minmax(auto, b)` for flexible lengths.
+///
+/// https://drafts.csswg.org/css-grid-1/#typedef-track-size
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct TrackSize {
+    pub min: TrackBreadth,
+    pub max: TrackBreadth,
+}
+
+#[derive(Clone)]
+pub(in crate::style) struct SpecifiedTrackSize {
+    min: SpecifiedTrackBreadth,
+    max: SpecifiedTrackBreadth,
+}
+
+/// Consecutive tracks and the names of the lines around them
+#[derive(Clone, Debug)]
+pub(crate) struct Tracks<Size = TrackSize> {
+    /// Names of the line before each track, and of the one after the last track
+    pub line_names: Vec<Vec<Arc<str>>>,
+    pub sizes: Vec<Size>,
+}
+
+/// `repeat()` with a number of repetitions that depends on the size of the grid container
+///
+/// https://drafts.csswg.org/css-grid-1/#auto-repeat
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum AutoRepeat {
+    Fill,
+    /// Like `auto-fill`, except that repeated tracks without items collapse
+    Fit,
+}
+
+/// Integer repetitions are expanded when parsing.
+///
+/// https://drafts.csswg.org/css-grid-1/#track-sizing
+#[derive(Clone, Debug)]
+pub(crate) struct TrackList<Size = TrackSize> {
+    pub before: Tracks<Size>,
+    /// Repeated tracks, followed by `after`
+    pub auto_repeat: Option<(AutoRepeat, Tracks<Size>)>,
+    pub after: Tracks<Size>,
+}
+
+/// The value of `grid-template-rows` or `grid-template-columns`, `None` for `none`
+#[derive(Clone, Debug)]
+pub(crate) struct GridTemplate(pub Option<Arc<TrackList>>);
+
+#[derive(Clone)]
+pub(in crate::style) struct SpecifiedGridTemplate(Option<Arc<TrackList<SpecifiedTrackSize>>>);
+
+/// The value of `grid-auto-rows` or `grid-auto-columns`
+///
+/// https://drafts.csswg.org/css-grid-1/#auto-tracks
+#[derive(Clone, Debug)]
+pub(crate) struct GridAutoTracks(pub Arc<[TrackSize]>);
+
+#[derive(Clone)]
+pub(in crate::style) struct SpecifiedGridAutoTracks(Vec<SpecifiedTrackSize>);
+
+/// https://drafts.csswg.org/css-grid-1/#grid-template-areas-property
+#[derive(Clone, Debug, SpecifiedAsComputed)]
+pub(crate) struct GridTemplateAreas(pub Option<Arc<NamedAreas>>);
+
+#[derive(Debug)]
+pub(crate) struct NamedAreas {
+    pub rows: usize,
+    pub columns: usize,
+    pub areas: Vec<NamedArea>,
+}
+
+/// Ranges are of the tracks that the area covers
+#[derive(Debug)]
+pub(crate) struct NamedArea {
+    pub name: Arc<str>,
+    pub rows: Range<usize>,
+    pub columns: Range<usize>,
+}
+
+/// https://drafts.csswg.org/css-grid-1/#grid-auto-flow-property
+#[derive(Copy, Clone, Debug, SpecifiedAsComputed)]
+pub(crate) struct GridAutoFlow {
+    pub column: bool,
+    pub dense: bool,
+}
+
+/// The value of `grid-row-start`, `grid-column-end`, etc.
+///
+/// https://drafts.csswg.org/css-grid-1/#typedef-grid-row-start-grid-line
+#[derive(Clone, Debug, SpecifiedAsComputed)]
+pub(crate) enum GridLine {
+    Auto,
+    /// The nth line, or the nth line with this name. Negative numbers count from the end.
+    Line(i32, Option<Arc<str>>),
+    /// The area spans this many lines, or this many lines with this name
+    Span(u32, Option<Arc<str>>),
+    /// The edge of a named area, or else the first line with this name
+    Ident(Arc<str>),
+}
+
+/// https://drafts.csswg.org/css-grid-1/#propdef-grid-row
+pub(in crate::style) struct GridLines {
+    pub start: GridLine,
+    pub end: GridLine,
+}
+
+/// https://drafts.csswg.org/css-grid-1/#propdef-grid-area
+pub(in crate::style) struct GridArea {
+    pub row_start: GridLine,
+    pub column_start: GridLine,
+    pub row_end: GridLine,
+    pub column_end: GridLine,
+}
+
+impl TrackSize {
+    pub const AUTO: Self = TrackSize {
+        min: TrackBreadth::Auto,
+        max: TrackBreadth::Auto,
+    };
+}
+
+impl<Size: Clone> Tracks<Size> {
+    fn new() -> Self {
+        Tracks {
+            line_names: vec![Vec::new()],
+            sizes: Vec::new(),
+        }
+    }
+
+    /// Appends other tracks, whose first line is the last one of these tracks
+    fn append(&mut self, other: &Self) {
+        self.line_names
+            .last_mut()
+            .unwrap()
+            .extend(other.line_names[0].iter().cloned());
+        self.line_names
+            .extend(other.line_names[1..].iter().cloned());
+        self.sizes.extend(other.sizes.iter().cloned())
+    }
+
+    fn map<U>(&self, f: impl Fn(&Size) -> U) -> Tracks<U> {
+        Tracks {
+            line_names: self.line_names.clone(),
+            sizes: self.sizes.iter().map(f).collect(),
+        }
+    }
+}
+
+impl TrackList {
+    /// The tracks of the explicit grid, with automatic repetitions repeated this many times
+    pub fn expand(&self, repetitions: usize) -> Tracks {
+        let mut tracks = self.before.clone();
+        if let Some((_, repeated)) = &self.auto_repeat {
+            for _ in 0..repetitions {
+                tracks.append(repeated)
+            }
+        }
+        tracks.append(&self.after);
+        tracks
+    }
+}
+
+impl GridAutoTracks {
+    pub fn auto() -> Self {
+        GridAutoTracks(Arc::new([TrackSize::AUTO]))
+    }
+}
+
+impl GridAutoFlow {
+    pub const ROW: Self = GridAutoFlow {
+        column: false,
+        dense: false,
+    };
+}
+
+impl SpecifiedValue for TrackSize {
+    type SpecifiedValue = SpecifiedTrackSize;
+}
+
+impl super::FromSpecified for TrackSize {
+    fn from_specified(s: &SpecifiedTrackSize, context: &CascadeContext) -> Self {
+        TrackSize {
+            min: super::FromSpecified::from_specified(&s.min, context),
+            max: super::FromSpecified::from_specified(&s.max, context),
+        }
+    }
+}
+
+impl SpecifiedValue for GridTemplate {
+    type SpecifiedValue = SpecifiedGridTemplate;
+}
+
+impl super::FromSpecified for GridTemplate {
+    fn from_specified(s: &SpecifiedGridTemplate, context: &CascadeContext) -> Self {
+        let size = |size: &SpecifiedTrackSize| {
+            <TrackSize as super::FromSpecified>::from_specified(size, context)
+        };
+        GridTemplate(s.0.as_ref().map(|list| {
+            Arc::new(TrackList {
+                before: list.before.map(size),
+                auto_repeat: list
+                    .auto_repeat
+                    .as_ref()
+                    .map(|(kind, repeated)| (*kind, repeated.map(size))),
+                after: list.after.map(size),
+            })
+        }))
+    }
+}
+
+impl SpecifiedValue for GridAutoTracks {
+    type SpecifiedValue = SpecifiedGridAutoTracks;
+}
+
+impl super::FromSpecified for GridAutoTracks {
+    fn from_specified(s: &SpecifiedGridAutoTracks, context: &CascadeContext) -> Self {
+        GridAutoTracks(
+            s.0.iter()
+                .map(|size| <TrackSize as super::FromSpecified>::from_specified(size, context))
+                .collect(),
+        )
+    }
+}
+
+impl super::Parse for Fr {
+    fn parse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Self, PropertyParseError<'i>> {
+        match parser.next()? {
+            Token::Dimension { value, unit, .. }
+                if *value >= 0. && unit.eq_ignore_ascii_case("fr") =>
+            {
+                Ok(Fr(*value))
+            }
+            token => {
+                let t = token.clone();
+                Err(parser.new_unexpected_token_error(t))
+            }
+        }
+    }
+}
+
+impl super::Parse for SpecifiedTrackSize {
+    fn parse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Self, PropertyParseError<'i>> {
+        if parser
+            .r#try(|parser| parser.expect_function_matching("minmax"))
+            .is_ok()
+        {
+            return parser.parse_nested_block(|parser| {
+                let min = super::Parse::parse(parser)?;
+                if let SpecifiedTrackBreadth::Flex(_) = min {
+                    return Err(parser.new_error_for_next_token());
+                }
+                parser.expect_comma()?;
+                let max = super::Parse::parse(parser)?;
+                Ok(SpecifiedTrackSize { min, max })
+            });
+        }
+        let breadth: SpecifiedTrackBreadth = super::Parse::parse(parser)?;
+        let min = match breadth {
+            SpecifiedTrackBreadth::Flex(_) => SpecifiedTrackBreadth::Auto,
+            _ => breadth.clone(),
+        };
+        Ok(SpecifiedTrackSize { min, max: breadth })
+    }
+}
+
+impl super::Parse for SpecifiedGridTemplate {
+    fn parse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Self, PropertyParseError<'i>> {
+        if parser
+            .r#try(|parser| parser.expect_ident_matching("none"))
+            .is_ok()
+        {
+            return Ok(SpecifiedGridTemplate(None));
+        }
+        let mut before = Tracks::new();
+        let mut auto_repeat = None;
+        let mut after = Tracks::new();
+        loop {
+            let tracks = if auto_repeat.is_some() {
+                &mut after
+            } else {
+                &mut before
+            };
+            let names = parse_line_names(parser)?;
+            tracks.line_names.last_mut().unwrap().extend(names);
+            if let Ok(size) = parser.r#try(super::Parse::parse) {
+                tracks.sizes.push(size);
+                tracks.line_names.push(Vec::new());
+                continue;
+            }
+            if parser
+                .r#try(|parser| parser.expect_function_matching("repeat"))
+                .is_err()
+            {
+                break;
+            }
+            let (count, repeated) = parser.parse_nested_block(parse_repeat)?;
+            match count {
+                Ok(count) => {
+                    for _ in 0..count {
+                        tracks.append(&repeated)
+                    }
+                }
+                // Only one automatic repetition is allowed
+                Err(_) if auto_repeat.is_some() => return Err(parser.new_error_for_next_token()),
+                Err(kind) => auto_repeat = Some((kind, repeated)),
+            }
+        }
+        if before.sizes.is_empty() && auto_repeat.is_none() {
+            return Err(parser.new_error_for_next_token());
+        }
+        Ok(SpecifiedGridTemplate(Some(Arc::new(TrackList {
+            before,
+            auto_repeat,
+            after,
+        }))))
+    }
+}
+
+/// The arguments of `repeat()`: a number of repetitions or an automatic repetition,
+/// and the repeated tracks
+///
+/// https://drafts.csswg.org/css-grid-1/#repeat-syntax
+fn parse_repeat<'i, 't>(
+    parser: &mut Parser<'i, 't>,
+) -> Result<(Result<u32, AutoRepeat>, Tracks<SpecifiedTrackSize>), PropertyParseError<'i>> {
+    let count = if let Ok(count) = parser.r#try(|parser| parser.expect_integer()) {
+        if count < 1 {
+            return Err(parser.new_error_for_next_token());
+        }
+        // Avoid creating an unreasonable number of tracks
+        Ok((count as u32).min(10_000))
+    } else {
+        let location = parser.current_source_location();
+        let ident = parser.expect_ident()?.clone();
+        match_ignore_ascii_case! { &ident,
+            "auto-fill" => Err(AutoRepeat::Fill),
+            "auto-fit" => Err(AutoRepeat::Fit),
+            _ => return Err(location.new_unexpected_token_error(Token::Ident(ident))),
+        }
+    };
+    parser.expect_comma()?;
+    let mut tracks = Tracks::new();
+    loop {
+        let names = parse_line_names(parser)?;
+        tracks.line_names.last_mut().unwrap().extend(names);
+        match parser.r#try(super::Parse::parse) {
+            Ok(size) => {
+                tracks.sizes.push(size);
+                tracks.line_names.push(Vec::new())
+            }
+            Err(_) => break,
+        }
+    }
+    if tracks.sizes.is_empty() {
+        return Err(parser.new_error_for_next_token());
+    }
+    Ok((count, tracks))
+}
+
+/// Bracketed line names, if any
+///
+/// https://drafts.csswg.org/css-grid-1/#named-lines
+fn parse_line_names<'i, 't>(
+    parser: &mut Parser<'i, 't>,
+) -> Result<Vec<Arc<str>>, PropertyParseError<'i>> {
+    if parser
+        .r#try(|parser| parser.expect_square_bracket_block())
+        .is_err()
+    {
+        return Ok(Vec::new());
+    }
+    parser.parse_nested_block(|parser| {
+        let mut names = Vec::new();
+        while let Ok(name) = parser.r#try(parse_custom_ident) {
+            names.push(name)
+        }
+        Ok(names)
+    })
+}
+
+/// A line or area name, which cannot be a keyword of `grid-row` and similar properties
+///
+/// https://drafts.csswg.org/css-values-4/#custom-idents
+fn parse_custom_ident<'i, 't>(
+    parser: &mut Parser<'i, 't>,
+) -> Result<Arc<str>, PropertyParseError<'i>> {
+    let location = parser.current_source_location();
+    let ident = parser.expect_ident()?.clone();
+    match_ignore_ascii_case! { &ident,
+        "span" | "auto" | "inherit" | "initial" | "unset" | "default" => {
+            Err(location.new_unexpected_token_error(Token::Ident(ident)))
+        }
+        _ => Ok(Arc::from(&*ident)),
+    }
+}
+
+impl super::Parse for SpecifiedGridAutoTracks {
+    fn parse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Self, PropertyParseError<'i>> {
+        let mut sizes = vec![super::Parse::parse(parser)?];
+        while let Ok(size) = parser.r#try(super::Parse::parse) {
+            sizes.push(size)
+        }
+        Ok(SpecifiedGridAutoTracks(sizes))
+    }
+}
+
+impl super::Parse for GridTemplateAreas {
+    fn parse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Self, PropertyParseError<'i>> {
+        if parser
+            .r#try(|parser| parser.expect_ident_matching("none"))
+            .is_ok()
+        {
+            return Ok(GridTemplateAreas(None));
+        }
+        // Cell names of each row, `None` for null cells
+        let mut rows: Vec<Vec<Option<&str>>> = Vec::new();
+        let mut strings = Vec::new();
+        while let Ok(string) = parser.r#try(|parser| parser.expect_string().cloned()) {
+            strings.push((parser.current_source_location(), string))
+        }
+        for (location, string) in &strings {
+            let invalid =
+                || location.new_unexpected_token_error(Token::QuotedString(string.clone()));
+            let mut row = Vec::new();
+            let mut rest = &**string;
+            // https://drafts.csswg.org/css-grid-1/#valdef-grid-template-areas-string
+            loop {
+                rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace());
+                let is_name_code_point =
+                    |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_' || !c.is_ascii();
+                let token_length = if rest.starts_with('.') {
+                    rest.find(|c| c != '.').unwrap_or(rest.len())
+                } else {
+                    rest.find(|c| !is_name_code_point(c)).unwrap_or(rest.len())
+                };
+                if token_length == 0 {
+                    if rest.is_empty() {
+                        break;
+                    }
+                    // A trash token makes the declaration invalid
+                    return Err(invalid());
+                }
+                let (token, remaining) = rest.split_at(token_length);
+                row.push(if token.starts_with('.') {
+                    None
+                } else {
+                    Some(token)
+                });
+                rest = remaining
+            }
+            if row.is_empty() || rows.first().map_or(false, |first| first.len() != row.len()) {
+                return Err(invalid());
+            }
+            rows.push(row)
+        }
+        if rows.is_empty() {
+            return Err(parser.new_error_for_next_token());
+        }
+
+        // Each name must cover a rectangle of cells
+        let mut areas: Vec<NamedArea> = Vec::new();
+        for (row_index, row) in rows.iter().enumerate() {
+            for (column_index, name) in row.iter().enumerate() {
+                let name = match name {
+                    Some(name) => *name,
+                    None => continue,
+                };
+                if areas.iter().any(|area| &*area.name == name) {
+                    continue;
+                }
+                let columns = column_index
+                    ..row[column_index..]
+                        .iter()
+                        .position(|cell| *cell != Some(name))
+                        .map_or(row.len(), |end| column_index + end);
+                let rows_end = rows[row_index..]
+                    .iter()
+                    .position(|row| row[column_index] != Some(name))
+                    .map_or(rows.len(), |end| row_index + end);
+                areas.push(NamedArea {
+                    name: Arc::from(name),
+                    rows: row_index..rows_end,
+                    columns,
+                })
+            }
+        }
+        for (row_index, row) in rows.iter().enumerate() {
+            for (column_index, name) in row.iter().enumerate() {
+                let covering = areas.iter().find(|area| {
+                    area.rows.contains(&row_index) && area.columns.contains(&column_index)
+                });
+                if covering.map(|area| &*area.name) != *name {
+                    let (location, string) = &strings[row_index];
+                    return Err(
+                        location.new_unexpected_token_error(Token::QuotedString(string.clone()))
+                    );
+                }
+            }
+        }
+        Ok(GridTemplateAreas(Some(Arc::new(NamedAreas {
+            rows: rows.len(),
+            columns: rows[0].len(),
+            areas,
+        }))))
+    }
+}
+
+impl super::Parse for GridAutoFlow {
+    fn parse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Self, PropertyParseError<'i>> {
+        let mut column = None;
+        let mut dense = false;
+        loop {
+            if column.is_none() {
+                if parser
+                    .r#try(|parser| parser.expect_ident_matching("row"))
+                    .is_ok()
+                {
+                    column = Some(false);
+                    continue;
+                }
+                if parser
+                    .r#try(|parser| parser.expect_ident_matching("column"))
+                    .is_ok()
+                {
+                    column = Some(true);
+                    continue;
+                }
+            }
+            if !dense
+                && parser
+                    .r#try(|parser| parser.expect_ident_matching("dense"))
+                    .is_ok()
+            {
+                dense = true;
+                continue;
+            }
+            break;
+        }
+        if column.is_none() && !dense {
+            return Err(parser.new_error_for_next_token());
+        }
+        Ok(GridAutoFlow {
+            column: column.unwrap_or(false),
+            dense,
+        })
+    }
+}
+
+impl super::Parse for GridLine {
+    fn parse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Self, PropertyParseError<'i>> {
+        if parser
+            .r#try(|parser| parser.expect_ident_matching("auto"))
+            .is_ok()
+        {
+            return Ok(GridLine::Auto);
+        }
+        // `span`, an integer, and a name, in any order
+        let mut span = false;
+        let mut integer = None;
+        let mut name = None;
+        loop {
+            if !span
+                && parser
+                    .r#try(|parser| parser.expect_ident_matching("span"))
+                    .is_ok()
+            {
+                span = true;
+                continue;
+            }
+            if integer.is_none() {
+                if let Ok(value) = parser.r#try(|parser| parser.expect_integer()) {
+                    integer = Some(value);
+                    continue;
+                }
+            }
+            if name.is_none() {
+                if let Ok(value) = parser.r#try(parse_custom_ident) {
+                    name = Some(value);
+                    continue;
+                }
+            }
+            break;
+        }
+        match (span, integer, name) {
+            (true, Some(count), name) if count > 0 => Ok(GridLine::Span(count as u32, name)),
+            (true, None, name @ Some(_)) => Ok(GridLine::Span(1, name)),
+            (false, Some(number), name) if number != 0 => Ok(GridLine::Line(number, name)),
+            (false, None, Some(name)) => Ok(GridLine::Ident(name)),
+            _ => Err(parser.new_error_for_next_token()),
+        }
+    }
+}
+
+impl GridLine {
+    /// The value of an end longhand omitted from a shorthand
+    ///
+    /// https://drafts.csswg.org/css-grid-1/#placement-shorthands
+    fn omitted_end(&self) -> Self {
+        match self {
+            GridLine::Ident(name) => GridLine::Ident(name.clone()),
+            _ => GridLine::Auto,
+        }
+    }
+}
+
+/// A grid line, or the next one after a slash
+fn parse_after_slash<'i, 't>(
+    parser: &mut Parser<'i, 't>,
+) -> Result<Option<GridLine>, PropertyParseError<'i>> {
+    if parser.r#try(|parser| parser.expect_delim('/')).is_err() {
+        return Ok(None);
+    }
+    super::Parse::parse(parser).map(Some)
+}
+
+impl super::Parse for GridLines {
+    fn parse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Self, PropertyParseError<'i>> {
+        let start: GridLine = super::Parse::parse(parser)?;
+        let end = parse_after_slash(parser)?.unwrap_or_else(|| start.omitted_end());
+        Ok(GridLines { start, end })
+    }
+}
+
+impl super::Parse for GridArea {
+    fn parse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Self, PropertyParseError<'i>> {
+        let row_start: GridLine = super::Parse::parse(parser)?;
+        let column_start = parse_after_slash(parser)?;
+        let row_end = match column_start {
+            Some(_) => parse_after_slash(parser)?,
+            None => None,
+        };
+        let column_end = match row_end {
+            Some(_) => parse_after_slash(parser)?,
+            None => None,
+        };
+        let column_start = column_start.unwrap_or_else(|| row_start.omitted_end());
+        Ok(GridArea {
+            row_end: row_end.unwrap_or_else(|| row_start.omitted_end()),
+            column_end: column_end.unwrap_or_else(|| column_start.omitted_end()),
+            row_start,
+            column_start,
+        })
+    }
+}
@@ -50,6 +50,32 @@ pub(crate) enum LengthOrPercentageOrAuto {
     Auto,
 }
 
+/// The value of `width` or `height`
+///
+/// https://drafts.csswg.org/css-sizing-3/#preferred-size-properties
+#[derive(Clone, Parse, FromVariants)]
+pub(in crate::style) enum SpecifiedSize {
+    Length(SpecifiedLength),
+    Percentage(Percentage),
+    Auto,
+    MinContent,
+    MaxContent,
+    FitContent,
+}
+
+#[derive(Debug, Copy, Clone, FromSpecified, FromVariants)]
+pub(crate) enum Size {
+    Length(Length),
+    Percentage(Percentage),
+    Auto,
+    /// https://drafts.csswg.org/css-sizing-3/#valdef-width-min-content
+    MinContent,
+    /// https://drafts.csswg.org/css-sizing-3/#valdef-width-max-content
+    MaxContent,
+    /// https://drafts.csswg.org/css-sizing-3/#valdef-width-fit-content-length-percentage
+    FitContent,
+}
+
 #[derive(Copy, Clone, Debug, FromVariants, PartialEq)]
 pub(crate) enum LengthOrAuto {
     Length(Length),
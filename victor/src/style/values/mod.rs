@@ -3,17 +3,26 @@ use crate::style::properties::ComputedValues;
 use crate::style::properties::{ComputedValuesForEarlyCascade, ComputedValuesForLateCascade};
 use cssparser::Parser;
 
+mod align;
 mod background;
 mod border;
 mod box_;
 mod color;
+mod flex;
 mod fonts;
 mod generic;
+mod grid;
 mod length;
+mod table;
+mod text;
+mod text_decoration;
 mod writing_modes;
 
 pub(super) use self::{background::*, generic::*};
-pub(crate) use self::{border::*, box_::*, color::*, fonts::*, length::*, writing_modes::*};
+pub(crate) use self::{
+    align::*, border::*, box_::*, color::*, flex::*, fonts::*, grid::*, length::*, table::*,
+    text::*, text_decoration::*, writing_modes::*,
+};
 
 pub(super) trait Parse: Sized {
     fn parse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Self, PropertyParseError<'i>>;
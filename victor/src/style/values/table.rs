@@ -0,0 +1,72 @@
+use super::length::{Length, SpecifiedLength};
+use super::{CascadeContext, FromSpecified, SpecifiedValue};
+use crate::style::errors::PropertyParseError;
+use cssparser::Parser;
+
+/// https://drafts.csswg.org/css2/tables.html#propdef-border-collapse
+#[derive(Copy, Clone, Eq, Parse, PartialEq, SpecifiedAsComputed)]
+pub(crate) enum BorderCollapse {
+    Separate,
+    Collapse,
+}
+
+/// https://drafts.csswg.org/css2/tables.html#propdef-table-layout
+#[derive(Copy, Clone, Eq, Parse, PartialEq, SpecifiedAsComputed)]
+pub(crate) enum TableLayout {
+    Auto,
+    Fixed,
+}
+
+/// https://drafts.csswg.org/css2/tables.html#propdef-caption-side
+#[derive(Copy, Clone, Eq, Parse, PartialEq, SpecifiedAsComputed)]
+pub(crate) enum CaptionSide {
+    Top,
+    Bottom,
+}
+
+/// https://drafts.csswg.org/css2/tables.html#propdef-border-spacing
+#[derive(Copy, Clone)]
+pub(crate) struct BorderSpacing {
+    pub horizontal: Length,
+    pub vertical: Length,
+}
+
+#[derive(Clone)]
+pub(in crate::style) struct SpecifiedBorderSpacing {
+    horizontal: SpecifiedLength,
+    vertical: SpecifiedLength,
+}
+
+impl BorderSpacing {
+    pub const ZERO: Self = BorderSpacing {
+        horizontal: Length { px: 0. },
+        vertical: Length { px: 0. },
+    };
+}
+
+impl SpecifiedValue for BorderSpacing {
+    type SpecifiedValue = SpecifiedBorderSpacing;
+}
+
+impl super::Parse for SpecifiedBorderSpacing {
+    fn parse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Self, PropertyParseError<'i>> {
+        let horizontal: SpecifiedLength = super::Parse::parse(parser)?;
+        // “If one length is specified, it gives both the horizontal and vertical spacing.”
+        let vertical = parser
+            .r#try(super::Parse::parse)
+            .unwrap_or_else(|_| horizontal.clone());
+        Ok(SpecifiedBorderSpacing {
+            horizontal,
+            vertical,
+        })
+    }
+}
+
+impl FromSpecified for BorderSpacing {
+    fn from_specified(s: &SpecifiedBorderSpacing, context: &CascadeContext) -> Self {
+        BorderSpacing {
+            horizontal: FromSpecified::from_specified(&s.horizontal, context),
+            vertical: FromSpecified::from_specified(&s.vertical, context),
+        }
+    }
+}
@@ -0,0 +1,335 @@
+use super::length::{Length, LengthOrPercentage, SpecifiedLength, SpecifiedLengthOrPercentage};
+use super::{CascadeContext, SpecifiedValue};
+use crate::style::errors::PropertyParseError;
+use cssparser::{Parser, Token};
+
+/// https://drafts.csswg.org/css-text-3/#white-space-property
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Parse, SpecifiedAsComputed)]
+pub(crate) enum WhiteSpace {
+    Normal,
+    Pre,
+    Nowrap,
+    PreWrap,
+    PreLine,
+}
+
+impl WhiteSpace {
+    /// Whether spaces and tabs are kept as-is rather than collapsed
+    pub(crate) fn preserves_spaces(self) -> bool {
+        matches!(self, WhiteSpace::Pre | WhiteSpace::PreWrap)
+    }
+
+    /// Whether newlines are kept as forced line breaks rather than collapsed
+    pub(crate) fn preserves_segment_breaks(self) -> bool {
+        self.preserves_spaces() || self == WhiteSpace::PreLine
+    }
+
+    /// Whether lines may break at soft wrap opportunities
+    pub(crate) fn allows_wrapping(self) -> bool {
+        !matches!(self, WhiteSpace::Pre | WhiteSpace::Nowrap)
+    }
+}
+
+/// https://drafts.csswg.org/css-text-3/#tab-size-property
+#[derive(Copy, Clone)]
+pub(crate) enum TabSize {
+    /// A multiple of the advance width of the space character
+    Spaces(f32),
+    Length(Length),
+}
+
+#[derive(Clone)]
+pub(in crate::style) enum SpecifiedTabSize {
+    Spaces(f32),
+    Length(SpecifiedLength),
+}
+
+impl SpecifiedValue for TabSize {
+    type SpecifiedValue = SpecifiedTabSize;
+}
+
+impl super::Parse for SpecifiedTabSize {
+    fn parse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Self, PropertyParseError<'i>> {
+        let spaces = parser.r#try(|parser| -> Result<_, PropertyParseError<'i>> {
+            match parser.next()? {
+                Token::Number { value, .. } if *value >= 0. => Ok(*value),
+                token => {
+                    let t = token.clone();
+                    Err(parser.new_unexpected_token_error(t))
+                }
+            }
+        });
+        if let Ok(spaces) = spaces {
+            return Ok(SpecifiedTabSize::Spaces(spaces));
+        }
+        Ok(SpecifiedTabSize::Length(super::Parse::parse(parser)?))
+    }
+}
+
+impl super::FromSpecified for TabSize {
+    fn from_specified(s: &SpecifiedTabSize, context: &CascadeContext) -> Self {
+        match s {
+            SpecifiedTabSize::Spaces(spaces) => TabSize::Spaces(*spaces),
+            SpecifiedTabSize::Length(l) => {
+                TabSize::Length(super::FromSpecified::from_specified(l, context))
+            }
+        }
+    }
+}
+
+/// https://drafts.csswg.org/css-text-3/#line-break-property
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Parse, SpecifiedAsComputed)]
+pub(crate) enum LineBreak {
+    Auto,
+    Loose,
+    Normal,
+    Strict,
+    Anywhere,
+}
+
+/// https://drafts.csswg.org/css-text-3/#word-break-property
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Parse, SpecifiedAsComputed)]
+pub(crate) enum WordBreak {
+    Normal,
+    BreakAll,
+    KeepAll,
+    BreakWord,
+}
+
+/// https://drafts.csswg.org/css-text-3/#overflow-wrap-property
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Parse, SpecifiedAsComputed)]
+pub(crate) enum OverflowWrap {
+    Normal,
+    BreakWord,
+    Anywhere,
+}
+
+/// `word-wrap` is a legacy name alias of `overflow-wrap`
+pub(in crate::style) struct WordWrap {
+    pub overflow_wrap: OverflowWrap,
+}
+
+impl super::Parse for WordWrap {
+    fn parse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Self, PropertyParseError<'i>> {
+        Ok(WordWrap {
+            overflow_wrap: OverflowWrap::parse(parser)?,
+        })
+    }
+}
+
+/// https://drafts.csswg.org/css-text-3/#hyphens-property
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Parse, SpecifiedAsComputed)]
+pub(crate) enum Hyphens {
+    None,
+    Manual,
+    Auto,
+}
+
+/// https://drafts.csswg.org/css-text-3/#text-align-property
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Parse, SpecifiedAsComputed)]
+pub(crate) enum TextAlign {
+    Start,
+    End,
+    Left,
+    Right,
+    Center,
+    Justify,
+}
+
+/// https://drafts.csswg.org/css-text-3/#text-align-last-property
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Parse, SpecifiedAsComputed)]
+pub(crate) enum TextAlignLast {
+    Auto,
+    Start,
+    End,
+    Left,
+    Right,
+    Center,
+    Justify,
+}
+
+/// https://drafts.csswg.org/css-text-3/#text-indent-property
+#[derive(Copy, Clone)]
+pub(crate) struct TextIndent {
+    /// Percentages refer to the inline size of the block container
+    pub length: LengthOrPercentage,
+    /// Indent all lines except the first
+    pub hanging: bool,
+    /// Also indent lines after a forced line break
+    pub each_line: bool,
+}
+
+#[derive(Clone)]
+pub(in crate::style) struct SpecifiedTextIndent {
+    length: SpecifiedLengthOrPercentage,
+    hanging: bool,
+    each_line: bool,
+}
+
+impl TextIndent {
+    pub const INITIAL: Self = TextIndent {
+        length: LengthOrPercentage::Length(Length { px: 0. }),
+        hanging: false,
+        each_line: false,
+    };
+}
+
+impl SpecifiedValue for TextIndent {
+    type SpecifiedValue = SpecifiedTextIndent;
+}
+
+impl super::Parse for SpecifiedTextIndent {
+    fn parse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Self, PropertyParseError<'i>> {
+        let mut length = None;
+        let mut hanging = false;
+        let mut each_line = false;
+        loop {
+            if length.is_none() {
+                if let Ok(value) = parser.r#try(super::Parse::parse) {
+                    length = Some(value);
+                    continue;
+                }
+            }
+            if !hanging && parser.r#try(|p| p.expect_ident_matching("hanging")).is_ok() {
+                hanging = true;
+                continue;
+            }
+            if !each_line
+                && parser
+                    .r#try(|p| p.expect_ident_matching("each-line"))
+                    .is_ok()
+            {
+                each_line = true;
+                continue;
+            }
+            break;
+        }
+        match length {
+            Some(length) => Ok(SpecifiedTextIndent {
+                length,
+                hanging,
+                each_line,
+            }),
+            None => Err(parser.new_error_for_next_token()),
+        }
+    }
+}
+
+impl super::FromSpecified for TextIndent {
+    fn from_specified(s: &SpecifiedTextIndent, context: &CascadeContext) -> Self {
+        TextIndent {
+            length: super::FromSpecified::from_specified(&s.length, context),
+            hanging: s.hanging,
+            each_line: s.each_line,
+        }
+    }
+}
+
+/// `normal` or a length, for `letter-spacing` and `word-spacing`
+///
+/// https://drafts.csswg.org/css-text-3/#spacing
+#[derive(Copy, Clone, FromSpecified)]
+pub(crate) enum Spacing {
+    Normal,
+    Length(Length),
+}
+
+#[derive(Clone, Parse)]
+pub(in crate::style) enum SpecifiedSpacing {
+    Normal,
+    Length(SpecifiedLength),
+}
+
+impl Spacing {
+    /// Extra space, in addition to what the font specifies
+    pub(crate) fn length(self) -> Length {
+        match self {
+            Spacing::Normal => Length::zero(),
+            Spacing::Length(length) => length,
+        }
+    }
+}
+
+/// https://drafts.csswg.org/css-text-3/#text-transform-property
+#[derive(Copy, Clone, SpecifiedAsComputed)]
+pub(crate) struct TextTransform {
+    pub case: Option<TextTransformCase>,
+    pub full_width: bool,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Parse)]
+pub(crate) enum TextTransformCase {
+    Uppercase,
+    Lowercase,
+    Capitalize,
+}
+
+impl TextTransform {
+    pub const NONE: Self = TextTransform {
+        case: None,
+        full_width: false,
+    };
+
+    pub(crate) fn is_none(self) -> bool {
+        self.case.is_none() && !self.full_width
+    }
+}
+
+impl super::Parse for TextTransform {
+    fn parse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Self, PropertyParseError<'i>> {
+        if parser.r#try(|p| p.expect_ident_matching("none")).is_ok() {
+            return Ok(TextTransform::NONE);
+        }
+        let mut transform = TextTransform::NONE;
+        loop {
+            if transform.case.is_none() {
+                if let Ok(value) = parser.r#try(super::Parse::parse) {
+                    transform.case = Some(value);
+                    continue;
+                }
+            }
+            if !transform.full_width
+                && parser
+                    .r#try(|p| p.expect_ident_matching("full-width"))
+                    .is_ok()
+            {
+                transform.full_width = true;
+                continue;
+            }
+            break;
+        }
+        if transform.is_none() {
+            Err(parser.new_error_for_next_token())
+        } else {
+            Ok(transform)
+        }
+    }
+}
+
+/// https://drafts.csswg.org/css2/visudet.html#propdef-vertical-align
+#[derive(Copy, Clone, FromSpecified)]
+pub(crate) enum VerticalAlign {
+    Baseline,
+    Sub,
+    Super,
+    TextTop,
+    TextBottom,
+    Middle,
+    Top,
+    Bottom,
+    /// Raise by this amount, percentages refer to the `line-height` of the element itself
+    LengthOrPercentage(LengthOrPercentage),
+}
+
+#[derive(Clone, Parse)]
+pub(in crate::style) enum SpecifiedVerticalAlign {
+    Baseline,
+    Sub,
+    Super,
+    TextTop,
+    TextBottom,
+    Middle,
+    Top,
+    Bottom,
+    LengthOrPercentage(SpecifiedLengthOrPercentage),
+}
@@ -0,0 +1,135 @@
+use super::length::{LengthOrPercentage, SpecifiedLengthOrPercentage};
+use crate::style::errors::PropertyParseError;
+use cssparser::{Color, Parser};
+
+/// https://drafts.csswg.org/css-text-decor-3/#text-decoration-line-property
+#[derive(Copy, Clone, SpecifiedAsComputed)]
+pub(crate) struct TextDecorationLine {
+    pub underline: bool,
+    pub overline: bool,
+    pub line_through: bool,
+}
+
+impl TextDecorationLine {
+    pub const NONE: Self = TextDecorationLine {
+        underline: false,
+        overline: false,
+        line_through: false,
+    };
+
+    pub(crate) fn is_none(self) -> bool {
+        !(self.underline || self.overline || self.line_through)
+    }
+}
+
+impl super::Parse for TextDecorationLine {
+    fn parse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Self, PropertyParseError<'i>> {
+        if parser.r#try(|p| p.expect_ident_matching("none")).is_ok() {
+            return Ok(TextDecorationLine::NONE);
+        }
+        let mut line = TextDecorationLine::NONE;
+        while let Ok(keyword) = parser.r#try(<LineKeyword as super::Parse>::parse) {
+            let value = match keyword {
+                LineKeyword::Underline => &mut line.underline,
+                LineKeyword::Overline => &mut line.overline,
+                LineKeyword::LineThrough => &mut line.line_through,
+            };
+            // Each keyword at most once
+            if *value {
+                return Err(parser.new_error_for_next_token());
+            }
+            *value = true
+        }
+        if line.is_none() {
+            Err(parser.new_error_for_next_token())
+        } else {
+            Ok(line)
+        }
+    }
+}
+
+#[derive(Parse)]
+enum LineKeyword {
+    Underline,
+    Overline,
+    LineThrough,
+}
+
+/// https://drafts.csswg.org/css-text-decor-3/#text-decoration-style-property
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Parse, SpecifiedAsComputed)]
+pub(crate) enum TextDecorationStyle {
+    Solid,
+    Double,
+    Dotted,
+    Dashed,
+    Wavy,
+}
+
+/// https://drafts.csswg.org/css-text-decor-4/#text-decoration-thickness-property
+#[derive(Copy, Clone, FromSpecified)]
+pub(crate) enum TextDecorationThickness {
+    Auto,
+    /// From the `post` table of the first available font
+    FromFont,
+    /// Percentages refer to 1em
+    LengthOrPercentage(LengthOrPercentage),
+}
+
+#[derive(Clone, Parse)]
+pub(in crate::style) enum SpecifiedTextDecorationThickness {
+    Auto,
+    FromFont,
+    LengthOrPercentage(SpecifiedLengthOrPercentage),
+}
+
+/// The `text-decoration` shorthand
+#[derive(Default)]
+pub(in crate::style) struct TextDecoration {
+    pub line: Option<TextDecorationLine>,
+    pub style: Option<TextDecorationStyle>,
+    pub color: Option<Color>,
+    pub thickness: Option<SpecifiedTextDecorationThickness>,
+}
+
+impl super::Parse for TextDecoration {
+    fn parse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Self, PropertyParseError<'i>> {
+        let mut values = TextDecoration::default();
+        let mut any = false;
+        loop {
+            if values.line.is_none() {
+                if let Ok(value) = parser.r#try(super::Parse::parse) {
+                    values.line = Some(value);
+                    any = true;
+                    continue;
+                }
+            }
+            if values.style.is_none() {
+                if let Ok(value) = parser.r#try(super::Parse::parse) {
+                    values.style = Some(value);
+                    any = true;
+                    continue;
+                }
+            }
+            if values.color.is_none() {
+                if let Ok(value) = parser.r#try(super::Parse::parse) {
+                    values.color = Some(value);
+                    any = true;
+                    continue;
+                }
+            }
+            if values.thickness.is_none() {
+                if let Ok(value) = parser.r#try(super::Parse::parse) {
+                    values.thickness = Some(value);
+                    any = true;
+                    continue;
+                }
+            }
+            break;
+        }
+        if any {
+            Ok(values)
+        } else {
+            Err(parser.new_error_for_next_token())
+        }
+    }
+}
@@ -1,10 +1,12 @@
-#[derive(Debug, Copy, Clone, PartialEq, Parse)]
+/// https://drafts.csswg.org/css-writing-modes/#direction
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Parse, SpecifiedAsComputed)]
 pub(crate) enum Direction {
     Ltr,
     Rtl,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Parse)]
+/// https://drafts.csswg.org/css-writing-modes/#block-flow
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Parse, SpecifiedAsComputed)]
 pub(crate) enum WritingMode {
     HorizontalTb,
     VerticalRl,
@@ -12,3 +14,21 @@ pub(crate) enum WritingMode {
     SidewaysRl,
     SidewaysLr,
 }
+
+impl WritingMode {
+    /// Whether the inline axis is vertical
+    pub(crate) fn is_vertical(self) -> bool {
+        self != WritingMode::HorizontalTb
+    }
+}
+
+/// https://drafts.csswg.org/css-writing-modes/#unicode-bidi
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Parse, SpecifiedAsComputed)]
+pub(crate) enum UnicodeBidi {
+    Normal,
+    Embed,
+    Isolate,
+    BidiOverride,
+    IsolateOverride,
+    Plaintext,
+}
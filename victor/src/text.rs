@@ -6,12 +6,33 @@ use std::sync::Arc;
 pub struct ShapedSegment {
     pub(crate) font: Arc<Font>,
     pub(crate) glyphs: Vec<GlyphId>,
+
+    /// For vertical text, this is the sum of vertical advances
     pub(crate) advance_width: Length<Em>,
+
+    /// Upright glyphs set top to bottom, with vertical metrics and alternates
+    pub(crate) vertical: bool,
+
+    /// Lowercase letters, and uppercase ones with `capitals_to_small_caps`,
+    /// use the font’s small capital glyphs
+    pub(crate) small_caps: bool,
+    pub(crate) capitals_to_small_caps: bool,
+
+    /// Extra advance after each glyph, included in `advance_width`
+    pub(crate) letter_spacing: Length<Em>,
+
+    /// Extra advance after each U+0020 SPACE glyph, included in `advance_width`
+    pub(crate) word_spacing: Length<Em>,
+
+    /// The character of the last glyph, which a variation selector may apply to
+    previous_char: Option<char>,
 }
 
+#[derive(Clone)]
 pub struct ShapedSegmentState {
     glyphs: usize,
-    advance_width: Length<Em>,
+    pub(crate) advance_width: Length<Em>,
+    previous_char: Option<char>,
 }
 
 impl std::fmt::Debug for ShapedSegment {
@@ -27,6 +48,8 @@ impl ShapedSegment {
     /// * No support for complex scripts
     /// * No ligatures
     /// * No kerning
+    ///
+    /// Variation selectors are supported through the font’s `cmap` format 14 subtable.
     pub fn naive_shape(text: &str, font: Arc<Font>) -> Result<Self, FontError> {
         let mut s = Self::new_with_naive_shaping(font);
         s.append(text.chars())?;
@@ -38,6 +61,19 @@ impl ShapedSegment {
             font,
             glyphs: Vec::new(),
             advance_width: Length::new(0.),
+            vertical: false,
+            small_caps: false,
+            capitals_to_small_caps: false,
+            letter_spacing: Length::new(0.),
+            word_spacing: Length::new(0.),
+            previous_char: None,
+        }
+    }
+
+    pub fn new_with_naive_vertical_shaping(font: Arc<Font>) -> Self {
+        Self {
+            vertical: true,
+            ..Self::new_with_naive_shaping(font)
         }
     }
 
@@ -46,21 +82,141 @@ impl ShapedSegment {
     }
 
     pub fn append_char(&mut self, ch: char) -> Result<(), FontError> {
+        if is_variation_selector(ch) {
+            // Variation selectors are never rendered by themselves,
+            // they may select an alternate glyph for the previous character.
+            if let Some(previous_char) = self.previous_char.take() {
+                if let Some(id) = self.font.variation_glyph_id(previous_char, ch)? {
+                    let previous_id = self.glyphs.pop().unwrap();
+                    self.advance_width -= self.spaced_advance(previous_id)?;
+                    self.push_glyph(id)?;
+                }
+            }
+            return Ok(());
+        }
         let id = self.font.glyph_id(ch)?;
-        self.advance_width += self.font.glyph_width(id)?;
+        self.push_glyph(id)?;
+        self.previous_char = Some(ch);
+        Ok(())
+    }
+
+    fn push_glyph(&mut self, mut id: GlyphId) -> Result<(), FontError> {
+        if self.small_caps {
+            id = self.font.small_caps_glyph(id).unwrap_or(id)
+        }
+        if self.capitals_to_small_caps {
+            id = self.font.capital_to_small_caps_glyph(id).unwrap_or(id)
+        }
+        if self.vertical {
+            id = self.font.vertical_glyph(id);
+        }
+        self.advance_width += self.spaced_advance(id)?;
         self.glyphs.push(id);
         Ok(())
     }
 
+    /// The advance of a character on its own, in this segment’s font and direction
+    pub(crate) fn char_advance(&self, ch: char) -> Result<Length<Em>, FontError> {
+        let mut id = self.font.glyph_id(ch)?;
+        if self.vertical {
+            id = self.font.vertical_glyph(id);
+        }
+        self.advance(id)
+    }
+
+    fn advance(&self, id: GlyphId) -> Result<Length<Em>, FontError> {
+        if self.vertical {
+            self.font.glyph_height(id)
+        } else {
+            self.font.glyph_width(id)
+        }
+    }
+
+    /// Including letter and word spacing
+    fn spaced_advance(&self, id: GlyphId) -> Result<Length<Em>, FontError> {
+        let mut advance = self.advance(id)? + self.letter_spacing;
+        if self.word_spacing != Length::new(0.) && self.space_glyph()? == Some(id) {
+            advance += self.word_spacing
+        }
+        Ok(advance)
+    }
+
+    /// The glyph of U+0020 SPACE, if the font has one
+    pub(crate) fn space_glyph(&self) -> Result<Option<GlyphId>, FontError> {
+        let id = self.font.glyph_id(' ')?;
+        Ok(if id == GlyphId(0) { None } else { Some(id) })
+    }
+
+    /// U+2010 HYPHEN if the font has it, or U+002D HYPHEN-MINUS
+    pub(crate) fn hyphen_char(&self) -> Result<char, FontError> {
+        Ok(if self.font.glyph_id('\u{2010}')? == GlyphId(0) {
+            '-'
+        } else {
+            '\u{2010}'
+        })
+    }
+
+    pub(crate) fn count_spaces(&self) -> Result<usize, FontError> {
+        Ok(match self.space_glyph()? {
+            Some(space) => self.glyphs.iter().filter(|&&id| id == space).count(),
+            None => 0,
+        })
+    }
+
+    /// Adds to the advance of each space, for justification
+    pub(crate) fn add_word_spacing(&mut self, extra: Length<Em>) -> Result<(), FontError> {
+        let spaces = self.count_spaces()? as f32;
+        self.word_spacing += extra;
+        self.advance_width += Length::new(extra.get() * spaces);
+        Ok(())
+    }
+
+    /// Removes spaces at the end of the text, or at the start if `reversed`
+    /// for right-to-left text, and returns their advance.
+    pub(crate) fn trim_trailing_spaces(&mut self, reversed: bool) -> Result<Length<Em>, FontError> {
+        let space = match self.space_glyph()? {
+            Some(space) => space,
+            None => return Ok(Length::new(0.)),
+        };
+        let count = if reversed {
+            self.glyphs.iter().take_while(|&&id| id == space).count()
+        } else {
+            self.glyphs
+                .iter()
+                .rev()
+                .take_while(|&&id| id == space)
+                .count()
+        };
+        if reversed {
+            self.glyphs.drain(..count);
+        } else {
+            self.glyphs.truncate(self.glyphs.len() - count);
+        }
+        let trimmed = Length::new(self.spaced_advance(space)?.get() * count as f32);
+        self.advance_width -= trimmed;
+        Ok(trimmed)
+    }
+
     pub fn save(&self) -> ShapedSegmentState {
         ShapedSegmentState {
             glyphs: self.glyphs.len(),
             advance_width: self.advance_width,
+            previous_char: self.previous_char,
         }
     }
 
     pub fn restore(&mut self, state: &ShapedSegmentState) {
         self.glyphs.truncate(state.glyphs);
         self.advance_width = state.advance_width;
+        self.previous_char = state.previous_char;
     }
 }
+
+/// Standardized variation selectors, ideographic variation selectors,
+/// and Mongolian free variation selectors
+fn is_variation_selector(ch: char) -> bool {
+    matches!(
+        ch,
+        '\u{FE00}'..='\u{FE0F}' | '\u{E0100}'..='\u{E01EF}' | '\u{180B}'..='\u{180D}' | '\u{180F}'
+    )
+}